@@ -3,7 +3,7 @@ use egui::FontData;
 use egui_ash::{
     raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _},
     winit::{self, window::Theme},
-    App, AppCreator, AshRenderState, CreationContext, ExitSignal, RunOption,
+    App, AppCreator, AshRenderState, CreationContext, ExitSignal, FrameInfo, RunOption,
 };
 use gpu_allocator::vulkan::*;
 use std::{
@@ -31,7 +31,7 @@ struct MyApp {
     exit_signal: ExitSignal,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         let esc_press = ctx.input(|i| i.key_down(egui::Key::Escape));
         if esc_press {
             self.exit_signal.send(ExitCode::SUCCESS);
@@ -242,13 +242,6 @@ impl MyAppCreator {
         ash::khr::surface::Instance::new(entry, instance)
     }
 
-    fn create_swapchain_loader(
-        instance: &Instance,
-        device: &Device,
-    ) -> ash::khr::swapchain::Device {
-        ash::khr::swapchain::Device::new(instance, device)
-    }
-
     fn create_surface(
         entry: &Entry,
         instance: &Instance,
@@ -441,7 +434,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             queue_family_index,
             &cc.required_device_extensions,
         );
-        let swapchain_loader = Self::create_swapchain_loader(&instance, &device);
         let command_pool = Self::create_command_pool(&device, queue_family_index);
 
         // create allocator
@@ -450,7 +442,7 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
                 instance: instance.clone(),
                 device: device.clone(),
                 physical_device,
-                debug_settings: Default::default(),
+                debug_settings: cc.allocator_debug.into(),
                 buffer_device_address: false,
                 allocation_sizes: Default::default(),
             })
@@ -495,18 +487,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             exit_signal: cc.exit_signal,
         };
 
-        let ash_render_state = AshRenderState {
-            entry: app.entry.clone(),
-            instance: app.instance.clone(),
+        let ash_render_state = AshRenderState::new(
+            app.entry.clone(),
+            app.instance.clone(),
             physical_device,
-            device: app.device.clone(),
-            surface_loader: app.surface_loader.clone(),
-            swapchain_loader: swapchain_loader.clone(),
+            app.device.clone(),
             queue,
             queue_family_index,
-            command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         (app, ash_render_state)
     }
@@ -1,6 +1,7 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
 use egui_ash::{
-    winit::window::Theme, App, AppCreator, AshRenderState, CreationContext, HandleRedraw, RunOption,
+    winit::window::Theme, App, AppCreator, AshRenderState, CreationContext, FrameInfo,
+    HandleRedraw, RunOption,
 };
 use gpu_allocator::vulkan::*;
 use std::{
@@ -23,7 +24,6 @@ struct MyApp {
     debug_messenger: vk::DebugUtilsMessengerEXT,
     physical_device: vk::PhysicalDevice,
     surface_loader: Arc<ash::khr::surface::Instance>,
-    swapchain_loader: Arc<ash::khr::swapchain::Device>,
     surface: vk::SurfaceKHR,
     queue: vk::Queue,
     command_pool: vk::CommandPool,
@@ -36,7 +36,7 @@ struct MyApp {
     rotate_y: f32,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::SidePanel::left("my_side_panel").show(ctx, |ui| {
             ui.heading("Hello");
             ui.label("Hello egui!");
@@ -198,7 +198,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             debug_messenger,
             physical_device,
             surface_loader,
-            swapchain_loader,
             surface,
             queue,
             command_pool,
@@ -214,18 +213,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             text: String::from("Hello text!"),
             rotate_y: 0.0,
         };
-        let ash_render_state = AshRenderState {
-            entry: app.entry.clone(),
-            instance: app.instance.clone(),
-            physical_device: app.physical_device,
-            device: (*app.device).clone(),
-            surface_loader: (*app.surface_loader).clone(),
-            swapchain_loader: (*app.swapchain_loader).clone(),
-            queue: app.queue,
+        let ash_render_state = AshRenderState::new(
+            app.entry.clone(),
+            app.instance.clone(),
+            app.physical_device,
+            (*app.device).clone(),
+            app.queue,
             queue_family_index,
-            command_pool: app.command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         (app, ash_render_state)
     }
@@ -1,5 +1,5 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
-use egui_ash::{App, AppCreator, AshRenderState, CreationContext, RunOption};
+use egui_ash::{App, AppCreator, AshRenderState, CreationContext, FrameInfo, RunOption};
 use gpu_allocator::vulkan::*;
 use std::{
     mem::ManuallyDrop,
@@ -18,14 +18,13 @@ struct MyApp {
     debug_messenger: vk::DebugUtilsMessengerEXT,
     physical_device: vk::PhysicalDevice,
     surface_loader: ash::khr::surface::Instance,
-    swapchain_loader: ash::khr::swapchain::Device,
     surface: vk::SurfaceKHR,
     queue: vk::Queue,
     command_pool: vk::CommandPool,
     allocator: ManuallyDrop<Arc<Mutex<Allocator>>>,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::ScrollArea::both().show(ui, |ui| {
                 ui.add(
@@ -78,7 +77,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             queue_family_index,
             &cc.required_device_extensions,
         );
-        let swapchain_loader = create_swapchain_loader(&instance, &device);
         let command_pool = create_command_pool(&device, queue_family_index);
 
         // create allocator
@@ -107,24 +105,20 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             debug_messenger,
             physical_device,
             surface_loader,
-            swapchain_loader,
             surface,
             queue,
             command_pool,
             allocator: ManuallyDrop::new(allocator.clone()),
         };
-        let ash_render_state = AshRenderState {
-            entry: app.entry.clone(),
-            instance: app.instance.clone(),
-            physical_device: app.physical_device,
-            device: app.device.clone(),
-            surface_loader: app.surface_loader.clone(),
-            swapchain_loader: app.swapchain_loader.clone(),
-            queue: app.queue,
+        let ash_render_state = AshRenderState::new(
+            app.entry.clone(),
+            app.instance.clone(),
+            app.physical_device,
+            app.device.clone(),
+            app.queue,
             queue_family_index,
-            command_pool: app.command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         (app, ash_render_state)
     }
@@ -1,7 +1,7 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
 use egui_ash::{
-    event, winit::window::Theme, App, AppCreator, AshRenderState, CreationContext, HandleRedraw,
-    RunOption,
+    event, winit::window::Theme, App, AppCreator, AshRenderState, CreationContext, FrameInfo,
+    HandleRedraw, RunOption,
 };
 use gpu_allocator::vulkan::*;
 use std::{
@@ -46,7 +46,7 @@ struct MyApp {
     rotate_y: Arc<Mutex<f32>>,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::SidePanel::left("my_side_panel").show(ctx, |ui| {
             ui.heading("Multi viewports");
             ui.label("Hello egui multi viewports!");
@@ -308,18 +308,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
         // setup context
         cc.context.set_visuals(egui::style::Visuals::dark());
 
-        let ash_render_state = AshRenderState {
-            entry: entry.clone(),
-            instance: instance.clone(),
+        let ash_render_state = AshRenderState::new(
+            entry.clone(),
+            instance.clone(),
             physical_device,
-            device: device.clone(),
-            surface_loader: surface_loader.clone(),
-            swapchain_loader: swapchain_loader.clone(),
+            device.clone(),
             queue,
             queue_family_index,
-            command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         let device = Arc::new(device);
         let surface_loader = Arc::new(surface_loader);
@@ -1,7 +1,8 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
 use egui_ash::{
     raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _},
-    winit::{self, window::Theme}, App, AppCreator, AshRenderState, CreationContext, RunOption,
+    winit::{self, window::Theme},
+    App, AppCreator, AshRenderState, CreationContext, FrameInfo, RunOption,
 };
 use gpu_allocator::vulkan::*;
 use std::{
@@ -19,7 +20,6 @@ struct MyApp {
     debug_messenger: vk::DebugUtilsMessengerEXT,
     physical_device: vk::PhysicalDevice,
     surface_loader: ash::khr::surface::Instance,
-    swapchain_loader: ash::khr::swapchain::Device,
     surface: vk::SurfaceKHR,
     queue: vk::Queue,
     command_pool: vk::CommandPool,
@@ -34,7 +34,7 @@ struct MyApp {
     sampler: vk::Sampler,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Hello");
             ui.label("Hello native ash image texture!");
@@ -198,13 +198,6 @@ impl MyAppCreator {
         ash::khr::surface::Instance::new(entry, instance)
     }
 
-    fn create_swapchain_loader(
-        instance: &Instance,
-        device: &Device,
-    ) -> ash::khr::swapchain::Device {
-        ash::khr::swapchain::Device::new(instance, device)
-    }
-
     fn create_surface(
         entry: &Entry,
         instance: &Instance,
@@ -653,7 +646,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             queue_family_index,
             &cc.required_device_extensions,
         );
-        let swapchain_loader = Self::create_swapchain_loader(&instance, &device);
         let command_pool = Self::create_command_pool(&device, queue_family_index);
 
         // create allocator
@@ -685,7 +677,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             debug_messenger,
             physical_device,
             surface_loader,
-            swapchain_loader,
             surface,
             queue,
             command_pool,
@@ -698,18 +689,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             image_allocation: Some(image_allocation),
             sampler,
         };
-        let ash_render_state = AshRenderState {
-            entry: app.entry.clone(),
-            instance: app.instance.clone(),
-            physical_device: app.physical_device,
-            device: app.device.clone(),
-            surface_loader: app.surface_loader.clone(),
-            swapchain_loader: app.swapchain_loader.clone(),
-            queue: app.queue,
+        let ash_render_state = AshRenderState::new(
+            app.entry.clone(),
+            app.instance.clone(),
+            app.physical_device,
+            app.device.clone(),
+            app.queue,
             queue_family_index,
-            command_pool: app.command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         (app, ash_render_state)
     }
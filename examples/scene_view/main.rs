@@ -1,5 +1,7 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
-use egui_ash::{App, AppCreator, AshRenderState, CreationContext, HandleRedraw, RunOption};
+use egui_ash::{
+    App, AppCreator, AshRenderState, CreationContext, FrameInfo, HandleRedraw, RunOption,
+};
 use gpu_allocator::vulkan::*;
 use std::{
     mem::ManuallyDrop,
@@ -26,7 +28,7 @@ struct MyApp {
     scene_view: SceneView,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Scene View");
             ui.label("Hello scene view!");
@@ -97,7 +99,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             queue_family_index,
             &cc.required_device_extensions,
         );
-        let swapchain_loader = create_swapchain_loader(&instance, &device);
         let command_pool = create_command_pool(&device, queue_family_index);
 
         // create allocator
@@ -117,18 +118,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
         // setup context
         cc.context.set_visuals(egui::style::Visuals::dark());
 
-        let ash_render_state = AshRenderState {
-            entry: entry.clone(),
-            instance: instance.clone(),
+        let ash_render_state = AshRenderState::new(
+            entry.clone(),
+            instance.clone(),
             physical_device,
-            device: device.clone(),
-            surface_loader: surface_loader.clone(),
-            swapchain_loader: swapchain_loader.clone(),
+            device.clone(),
             queue,
             queue_family_index,
-            command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         let device = Arc::new(device);
         let surface_loader = Arc::new(surface_loader);
@@ -1,5 +1,7 @@
 use ash::{ext::debug_utils, vk, Device, Entry, Instance};
-use egui_ash::{App, AppCreator, AshRenderState, CreationContext, HandleRedraw, RunOption};
+use egui_ash::{
+    App, AppCreator, AshRenderState, CreationContext, FrameInfo, HandleRedraw, RunOption,
+};
 use gpu_allocator::vulkan::*;
 use std::{
     mem::ManuallyDrop,
@@ -32,7 +34,7 @@ struct MyApp {
     tree_behavior: TreeBehavior,
 }
 impl App for MyApp {
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, _frame: &FrameInfo) {
         egui::CentralPanel::default().show(ctx, |ui| {
             let behavior = &mut self.tree_behavior;
             self.tree.ui(behavior, ui);
@@ -88,7 +90,6 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
             queue_family_index,
             &cc.required_device_extensions,
         );
-        let swapchain_loader = create_swapchain_loader(&instance, &device);
         let command_pool = create_command_pool(&device, queue_family_index);
 
         // create allocator
@@ -108,18 +109,15 @@ impl AppCreator<Arc<Mutex<Allocator>>> for MyAppCreator {
         // setup context
         cc.context.set_visuals(egui::style::Visuals::dark());
 
-        let ash_render_state = AshRenderState {
-            entry: entry.clone(),
-            instance: instance.clone(),
+        let ash_render_state = AshRenderState::new(
+            entry.clone(),
+            instance.clone(),
             physical_device,
-            device: device.clone(),
-            surface_loader: surface_loader.clone(),
-            swapchain_loader,
+            device.clone(),
             queue,
             queue_family_index,
-            command_pool,
-            allocator: allocator.clone(),
-        };
+            allocator.clone(),
+        );
 
         let device = Arc::new(device);
         let surface_loader = Arc::new(surface_loader);
@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+
+/// app-wide keyboard shortcuts, declared once by the app.
+///
+/// Key presses matching a shortcut are taken out of the egui input of the focused viewport
+/// and delivered as [`crate::event::Event::AcceleratorTriggered`], regardless of the egui focus.
+/// With the `menu` feature, a native menu action with the same id shows the shortcut.
+/// On macOS the native menu then handles the key press and delivers
+/// [`crate::event::Event::MenuActivated`] instead.
+#[derive(Clone, Default)]
+pub struct Accelerators {
+    inner: Arc<Mutex<Vec<(String, egui::KeyboardShortcut)>>>,
+}
+impl Accelerators {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// register the shortcut of `id`, replacing the previous one.
+    pub fn register(&self, id: impl Into<String>, shortcut: egui::KeyboardShortcut) {
+        let id = id.into();
+        let mut inner = self.inner.lock().unwrap();
+        match inner.iter_mut().find(|(other, _)| *other == id) {
+            Some((_, old_shortcut)) => *old_shortcut = shortcut,
+            None => inner.push((id, shortcut)),
+        }
+    }
+
+    /// unregister the shortcut of `id`.
+    pub fn unregister(&self, id: &str) {
+        self.inner.lock().unwrap().retain(|(other, _)| other != id);
+    }
+
+    /// shortcut of `id`.
+    pub fn shortcut(&self, id: &str) -> Option<egui::KeyboardShortcut> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .iter()
+            .find(|(other, _)| other == id)
+            .map(|(_, shortcut)| *shortcut)
+    }
+
+    /// shortcut of `id` as text for the current platform, e.g. `Ctrl+S` or `⌘S`, for hints in the UI.
+    pub fn format(&self, ctx: &egui::Context, id: &str) -> Option<String> {
+        self.shortcut(id)
+            .map(|shortcut| ctx.format_shortcut(&shortcut))
+    }
+
+    /// all registered ids and shortcuts in registration order.
+    pub fn all(&self) -> Vec<(String, egui::KeyboardShortcut)> {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// take key presses of the shortcuts out of `events[start..]` and return the triggered ids.
+    pub(crate) fn take_triggered(
+        &self,
+        events: &mut Vec<egui::Event>,
+        start: usize,
+    ) -> Vec<String> {
+        take_shortcuts(&self.inner.lock().unwrap(), events, start)
+    }
+}
+impl std::fmt::Debug for Accelerators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Accelerators").finish()
+    }
+}
+
+/// take key presses matching `shortcuts` out of `events[start..]` and return their ids.
+pub(crate) fn take_shortcuts(
+    shortcuts: &[(String, egui::KeyboardShortcut)],
+    events: &mut Vec<egui::Event>,
+    start: usize,
+) -> Vec<String> {
+    if shortcuts.is_empty() {
+        return vec![];
+    }
+
+    let mut taken = vec![];
+    let mut index = start;
+    while index < events.len() {
+        let egui::Event::Key {
+            key,
+            pressed: true,
+            modifiers,
+            ..
+        } = &events[index]
+        else {
+            index += 1;
+            continue;
+        };
+        let matched = shortcuts.iter().find(|(_, shortcut)| {
+            shortcut.logical_key == *key && modifiers.matches_exact(shortcut.modifiers)
+        });
+        match matched {
+            Some((id, _)) => {
+                taken.push(id.clone());
+                events.remove(index);
+            }
+            None => index += 1,
+        }
+    }
+    taken
+}
@@ -0,0 +1,148 @@
+//! snapshot of the AccessKit tree of a viewport, to assert on roles, labels and focus order in tests
+//! without a screen reader.
+//!
+//! The tree is recorded from the AccessKit update of the last pass of each viewport, so it is only
+//! there while AccessKit is active: when an assistive technology is listening or after
+//! `egui::Context::enable_accesskit`. With the `serde` feature the tree is serializable, e.g. to keep
+//! it as a JSON snapshot next to the tests.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use egui::accesskit;
+
+/// node of an [`AccessTree`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessNode {
+    /// AccessKit id, stable across the frames like the `egui::Id` it is derived from.
+    pub id: u64,
+    /// role, e.g. "Button" or "TextInput".
+    pub role: String,
+    pub label: Option<String>,
+    pub value: Option<String>,
+    /// bounds as reported to AccessKit.
+    pub bounds: Option<egui::Rect>,
+    pub focusable: bool,
+    pub focused: bool,
+    pub disabled: bool,
+    pub children: Vec<AccessNode>,
+}
+impl AccessNode {
+    /// this node and its descendants, depth-first.
+    pub fn iter(&self) -> impl Iterator<Item = &AccessNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+/// AccessKit tree of a viewport.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessTree {
+    pub root: AccessNode,
+    /// id of the focused node.
+    pub focus: u64,
+}
+impl AccessTree {
+    /// build the tree of a full AccessKit update, like the ones egui outputs each pass.
+    pub fn from_update(update: &accesskit::TreeUpdate) -> Option<Self> {
+        let nodes: HashMap<accesskit::NodeId, &accesskit::Node> =
+            update.nodes.iter().map(|(id, node)| (*id, node)).collect();
+        let root = match &update.tree {
+            Some(tree) => tree.root,
+            // the node that is no child of another one.
+            None => {
+                let children: HashSet<_> = nodes
+                    .values()
+                    .flat_map(|node| node.children().iter().copied())
+                    .collect();
+                update
+                    .nodes
+                    .iter()
+                    .map(|(id, _)| *id)
+                    .find(|id| !children.contains(id))?
+            }
+        };
+        Some(Self {
+            root: build_node(&nodes, root, update.focus)?,
+            focus: update.focus.0,
+        })
+    }
+
+    /// nodes depth-first.
+    pub fn iter(&self) -> impl Iterator<Item = &AccessNode> {
+        self.root.iter()
+    }
+
+    /// first node with `label`.
+    pub fn find_by_label(&self, label: &str) -> Option<&AccessNode> {
+        self.iter()
+            .find(|node| node.label.as_deref() == Some(label))
+    }
+
+    /// the focusable nodes in tree order, which is the order egui moves the focus in with Tab.
+    pub fn focus_order(&self) -> Vec<&AccessNode> {
+        self.iter().filter(|node| node.focusable).collect()
+    }
+
+    /// the focused node, unless the focus is on the root.
+    pub fn focused(&self) -> Option<&AccessNode> {
+        self.iter()
+            .find(|node| node.focused && node.id != self.root.id)
+    }
+}
+
+fn build_node(
+    nodes: &HashMap<accesskit::NodeId, &accesskit::Node>,
+    id: accesskit::NodeId,
+    focus: accesskit::NodeId,
+) -> Option<AccessNode> {
+    let node = nodes.get(&id)?;
+    Some(AccessNode {
+        id: id.0,
+        role: format!("{:?}", node.role()),
+        label: node.label().map(str::to_owned),
+        value: node.value().map(str::to_owned),
+        bounds: node.bounds().map(|bounds| {
+            egui::Rect::from_min_max(
+                egui::pos2(bounds.x0 as f32, bounds.y0 as f32),
+                egui::pos2(bounds.x1 as f32, bounds.y1 as f32),
+            )
+        }),
+        focusable: node.supports_action(accesskit::Action::Focus),
+        focused: id == focus,
+        disabled: node.is_disabled(),
+        children: node
+            .children()
+            .iter()
+            .filter_map(|child| build_node(nodes, *child, focus))
+            .collect(),
+    })
+}
+
+fn id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_access_tree").with(viewport_id)
+}
+
+/// keep the AccessKit update of the last pass of the viewport for [`access_tree`].
+pub(crate) fn store(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    update: &accesskit::TreeUpdate,
+) {
+    let update = Arc::new(update.clone());
+    ctx.data_mut(|data| data.insert_temp(id(viewport_id), update));
+}
+
+/// the AccessKit tree of the last pass of the viewport, `None` while AccessKit is inactive.
+pub fn access_tree(ctx: &egui::Context, viewport_id: egui::ViewportId) -> Option<AccessTree> {
+    let update = ctx.data(|data| data.get_temp::<Arc<accesskit::TreeUpdate>>(id(viewport_id)))?;
+    AccessTree::from_update(&update)
+}
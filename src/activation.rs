@@ -0,0 +1,123 @@
+//! XDG activation tokens, for windows to get the focus on Wayland and X11.
+//!
+//! Wayland compositors focus a new window only with a token of the launcher or of a focused window
+//! of the app. The root window takes the token the app was launched with, e.g. by a `.desktop`
+//! file, and the focused viewport requests one for the next viewport the app opens.
+
+use egui_winit::winit::{
+    event_loop::ActiveEventLoop,
+    window::{ActivationToken, Window, WindowAttributes},
+};
+
+fn token_id() -> egui::Id {
+    egui::Id::new("egui_ash_activation_token")
+}
+
+/// keep the token of `WindowEvent::ActivationTokenDone` for the next viewport.
+pub(crate) fn store_token(ctx: &egui::Context, token: ActivationToken) {
+    ctx.data_mut(|data| data.insert_temp(token_id(), token));
+}
+
+/// the attributes of a viewport window with the token of the focused viewport, if there is one.
+pub(crate) fn apply_token(
+    ctx: &egui::Context,
+    window_attributes: WindowAttributes,
+) -> WindowAttributes {
+    let token = ctx.data_mut(|data| {
+        let token = data.get_temp::<ActivationToken>(token_id());
+        data.remove::<ActivationToken>(token_id());
+        token
+    });
+    match token {
+        Some(token) => platform::with_token(window_attributes, token),
+        None => window_attributes,
+    }
+}
+
+/// the attributes of the root window with the activation token of the launch.
+pub(crate) fn apply_startup_token(
+    window_attributes: WindowAttributes,
+    event_loop: &ActiveEventLoop,
+) -> WindowAttributes {
+    match platform::take_startup_token(event_loop) {
+        Some(token) => platform::with_token(window_attributes, token),
+        None => window_attributes,
+    }
+}
+
+/// request a token for the next viewport from `window`, which was just focused.
+pub(crate) fn request_token(window: &Window) {
+    platform::request_token(window);
+}
+
+#[cfg(all(
+    any(feature = "wayland", feature = "x11"),
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+))]
+mod platform {
+    use egui_winit::winit::{
+        event_loop::ActiveEventLoop,
+        platform::startup_notify::{
+            self, EventLoopExtStartupNotify as _, WindowAttributesExtStartupNotify as _,
+            WindowExtStartupNotify as _,
+        },
+        window::{ActivationToken, Window, WindowAttributes},
+    };
+
+    pub(super) fn with_token(
+        window_attributes: WindowAttributes,
+        token: ActivationToken,
+    ) -> WindowAttributes {
+        window_attributes.with_activation_token(token)
+    }
+
+    pub(super) fn take_startup_token(event_loop: &ActiveEventLoop) -> Option<ActivationToken> {
+        let token = event_loop.read_token_from_env()?;
+        // the token is used up, the processes launched by the app must not inherit it.
+        startup_notify::reset_activation_token_env();
+        Some(token)
+    }
+
+    pub(super) fn request_token(window: &Window) {
+        if let Err(err) = window.request_activation_token() {
+            debug!("Failed to request an activation token: {err}");
+        }
+    }
+}
+
+// the other platforms focus new windows without a token.
+#[cfg(not(all(
+    any(feature = "wayland", feature = "x11"),
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )
+)))]
+mod platform {
+    use egui_winit::winit::{
+        event_loop::ActiveEventLoop,
+        window::{ActivationToken, Window, WindowAttributes},
+    };
+
+    pub(super) fn with_token(
+        window_attributes: WindowAttributes,
+        _token: ActivationToken,
+    ) -> WindowAttributes {
+        window_attributes
+    }
+
+    pub(super) fn take_startup_token(_event_loop: &ActiveEventLoop) -> Option<ActivationToken> {
+        None
+    }
+
+    pub(super) fn request_token(_window: &Window) {}
+}
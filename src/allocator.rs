@@ -2,7 +2,10 @@ use std::ffi::c_void;
 use std::ptr::NonNull;
 
 use anyhow::Result;
-use ash::vk::{DeviceMemory, MemoryRequirements};
+use ash::{
+    vk::{self, DeviceMemory, MemoryRequirements},
+    Instance,
+};
 
 /// Represents a memory allocation.
 pub trait Allocation: Send + Sync {
@@ -39,6 +42,104 @@ pub trait AllocationCreateInfo {
     ) -> Self;
 }
 
+/// memory usage of a memory heap, see [`AllocatorReport`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapUsage {
+    /// index of the heap in `vk::PhysicalDeviceMemoryProperties::memory_heaps`.
+    pub heap_index: u32,
+    /// bytes of the live allocations in the heap.
+    pub allocated: u64,
+    /// bytes of device memory the allocator reserved from the heap.
+    pub reserved: u64,
+}
+
+/// live allocation of an [`AllocatorReport`], named after [`AllocationCreateInfo::new`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamedAllocation {
+    /// name the allocation was created with, e.g. `"egui-ash vertex buffer"`.
+    pub name: String,
+    /// bytes of the allocation.
+    pub size: u64,
+}
+
+/// memory usage of an [`Allocator`], returned by [`Allocator::report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllocatorReport {
+    /// number of live allocations.
+    pub allocation_count: usize,
+    /// bytes of the live allocations.
+    pub total_allocated: u64,
+    /// bytes of device memory the allocator reserved, including free space in its blocks.
+    pub total_reserved: u64,
+    /// usage per memory heap. Empty if the allocator does not track the heaps.
+    pub heaps: Vec<HeapUsage>,
+    /// the live allocations with their names. Empty if the allocator does not list them.
+    pub allocations: Vec<NamedAllocation>,
+}
+
+/// budget of a memory heap, returned by [`Allocator::budget`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// index of the heap in `vk::PhysicalDeviceMemoryProperties::memory_heaps`.
+    pub heap_index: u32,
+    /// bytes of the heap used by the whole process.
+    pub usage: u64,
+    /// bytes of the heap the process can use before allocations may fail or degrade performance.
+    pub budget: u64,
+}
+impl MemoryBudget {
+    /// fraction of the budget in use, e.g. to evict textures above `0.9`.
+    pub fn pressure(&self) -> f32 {
+        if self.budget == 0 {
+            return 0.0;
+        }
+        self.usage as f32 / self.budget as f32
+    }
+}
+
+/// budget of the memory heaps of `physical_device` from `VK_EXT_memory_budget`, e.g. to implement
+/// [`Allocator::budget`] for an allocator which does not query it.
+///
+/// The device must be created with `VK_EXT_memory_budget` enabled.
+pub fn query_memory_budget(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> Vec<MemoryBudget> {
+    let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+    let mut properties =
+        vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget_properties);
+    unsafe { instance.get_physical_device_memory_properties2(physical_device, &mut properties) };
+    let heap_count = properties.memory_properties.memory_heap_count as usize;
+    (0..heap_count)
+        .map(|i| MemoryBudget {
+            heap_index: i as u32,
+            usage: budget_properties.heap_usage[i],
+            budget: budget_properties.heap_budget[i],
+        })
+        .collect()
+}
+
+/// debug options of the allocator, see [`crate::RunOption::allocator_debug`].
+///
+/// With the `gpu-allocator` feature they convert into `gpu_allocator::AllocatorDebugSettings`
+/// to create the allocator with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocatorDebugOptions {
+    /// log the allocations of egui-ash still alive when the integration is destroyed, using
+    /// the names of [`AllocatorReport::allocations`].
+    pub log_leaks_on_shutdown: bool,
+    /// store a stack trace of each allocation, to tell where leaked allocations were made.
+    pub store_stack_traces: bool,
+}
+impl Default for AllocatorDebugOptions {
+    fn default() -> Self {
+        Self {
+            log_leaks_on_shutdown: true,
+            store_stack_traces: false,
+        }
+    }
+}
+
 /// trait for GPU memory allocator.
 pub trait Allocator: Clone + Send + Sync {
     type Allocation: Allocation;
@@ -49,4 +150,15 @@ pub trait Allocator: Clone + Send + Sync {
 
     /// Free a memory region.
     fn free(&self, allocation: Self::Allocation) -> Result<()>;
+
+    /// current memory usage, e.g. to watch for leaked textures. `None` if the allocator does not report it.
+    fn report(&self) -> Option<AllocatorReport> {
+        None
+    }
+
+    /// budget of each memory heap from `VK_EXT_memory_budget`, e.g. to evict textures under memory
+    /// pressure. `None` if the allocator does not query it.
+    fn budget(&self) -> Option<Vec<MemoryBudget>> {
+        None
+    }
 }
@@ -0,0 +1,80 @@
+//! screen reader announcements, independent of the focused widget.
+//!
+//! The announcements are the labels of two AccessKit live regions of the root viewport, one polite and
+//! one assertive. Screen readers read a live region when its label changes.
+
+use std::collections::VecDeque;
+
+use egui::accesskit;
+
+/// how urgently a screen reader reads an announcement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AnnouncePriority {
+    /// read once the screen reader is idle, e.g. "Export finished".
+    #[default]
+    Polite,
+    /// interrupts the speech of the screen reader, e.g. for errors.
+    Assertive,
+}
+
+#[derive(Clone, Default)]
+struct LiveRegion {
+    label: String,
+    queue: VecDeque<String>,
+}
+
+fn region_id(priority: AnnouncePriority) -> egui::Id {
+    egui::Id::new("egui_ash_announce").with(priority)
+}
+
+/// announce `text` to screen readers, from any thread.
+///
+/// Announcements are queued and made one per frame of the root viewport. They are dropped while no
+/// assistive technology is active.
+pub fn announce(ctx: &egui::Context, text: impl Into<String>, priority: AnnouncePriority) {
+    ctx.data_mut(|data| {
+        data.get_temp_mut_or_default::<LiveRegion>(region_id(priority))
+            .queue
+            .push_back(text.into());
+    });
+    ctx.request_repaint_of(egui::ViewportId::ROOT);
+}
+
+/// add the live regions to the AccessKit tree of the pass, with the next announcements.
+pub(crate) fn update_live_regions(ctx: &egui::Context) {
+    if ctx.viewport_id() != egui::ViewportId::ROOT {
+        return;
+    }
+    for priority in [AnnouncePriority::Polite, AnnouncePriority::Assertive] {
+        let id = region_id(priority);
+        let (label, announced) = ctx.data_mut(|data| {
+            let region = data.get_temp_mut_or_default::<LiveRegion>(id);
+            let announced = region.queue.front().is_some();
+            if let Some(next) = region.queue.front() {
+                // the same text again is only read after a pass without it.
+                if *next == region.label {
+                    region.label.clear();
+                } else {
+                    region.label = region.queue.pop_front().unwrap();
+                }
+            }
+            (region.label.clone(), announced)
+        });
+        if announced {
+            ctx.request_repaint();
+        }
+        let added = ctx.accesskit_node_builder(id, |node| {
+            let (role, live) = match priority {
+                AnnouncePriority::Polite => (accesskit::Role::Status, accesskit::Live::Polite),
+                AnnouncePriority::Assertive => (accesskit::Role::Alert, accesskit::Live::Assertive),
+            };
+            node.set_role(role);
+            node.set_live(live);
+            node.set_label(label);
+        });
+        if added.is_none() {
+            // no assistive technology is listening.
+            ctx.data_mut(|data| data.remove_temp::<LiveRegion>(id));
+        }
+    }
+}
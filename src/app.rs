@@ -5,6 +5,9 @@ use std::ffi::CString;
 #[cfg(feature = "persistence")]
 use crate::storage;
 use crate::{
+    accelerator::Accelerators,
+    cursor::CustomCursors,
+    documents::DocumentId,
     event,
     renderer::{EguiCommand, ImageRegistry},
     Allocator, ExitSignal,
@@ -19,14 +22,99 @@ pub enum HandleRedraw {
     Handle(RedrawHandler),
 }
 
+/// return type of [`App::filter_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFilter {
+    /// pass the event to egui and then to [`App::handle_event`].
+    Pass,
+    /// the app consumed the event. egui and [`App::handle_event`] do not receive it.
+    Consumed,
+}
+
+/// return type of [`App::on_close_requested`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseResponse {
+    /// close the viewport. Closing the root viewport exits the app.
+    Allow,
+    /// keep the viewport open, e.g. to ask about unsaved changes first.
+    Deny,
+}
+
+/// information about the current frame, passed to [`App::ui`].
+#[derive(Debug, Clone, Copy)]
+pub struct FrameInfo {
+    /// time since the previous frame. Zero on the first frame.
+    pub delta_time: std::time::Duration,
+    /// index of the frame, starting at 0.
+    pub frame_index: u64,
+    /// viewport of the frame, `egui::ViewportId::ROOT` for [`App::ui`]. Immediate and deferred
+    /// viewports build their ui in their own callbacks.
+    pub viewport_id: egui::ViewportId,
+    /// estimated time the frame appears on screen, from the refresh rate of the monitor.
+    /// `None` if the refresh rate is unknown.
+    pub predicted_present_time: Option<std::time::Instant>,
+    /// window size in physical pixels.
+    pub window_size: winit::dpi::PhysicalSize<u32>,
+    /// see [`crate::fixed_update_alpha`].
+    pub fixed_update_alpha: f32,
+}
+
 /// main egui-ash app trait.
 pub trait App {
     /// egui entry point
-    fn ui(&mut self, ctx: &egui::Context);
+    fn ui(&mut self, ctx: &egui::Context, frame: &FrameInfo);
+
+    /// ui of a document opened with [`crate::documents::Documents::open`], in the context of the
+    /// document.
+    fn document_ui(&mut self, _document: DocumentId, _ctx: &egui::Context, _frame: &FrameInfo) {}
+
+    /// called when a document is closed, by [`crate::documents::Documents::close`], its window or
+    /// `egui::ViewportCommand::Close`.
+    fn on_document_closed(&mut self, _document: DocumentId) {}
+
+    /// advance the simulation by `dt`, called every [`crate::RunOption::fixed_timestep`] before the frames.
+    ///
+    /// See [`crate::fixed_update_alpha`] to interpolate between the steps in [`Self::ui`].
+    fn fixed_update(&mut self, _dt: std::time::Duration) {}
 
     /// handle events of the app.
     fn handle_event(&mut self, _event: event::Event) {}
 
+    /// intercept input events before egui receives them.
+    ///
+    /// Called for keyboard, mouse, touch and IME events of all viewports of [`crate::run`].
+    /// Return [`EventFilter::Consumed`] to bypass egui, e.g. while a 3D view has the focus.
+    /// If a key or button press is passed but its release is consumed, egui sees the key as held down.
+    fn filter_event(
+        &mut self,
+        _viewport_id: egui::ViewportId,
+        _event: &winit::event::WindowEvent,
+    ) -> EventFilter {
+        EventFilter::Pass
+    }
+
+    /// inspect or rewrite the input of a pass of the viewport before egui runs it, e.g. to log the
+    /// input or drop events.
+    ///
+    /// Called with the input the integration built from the window events, after
+    /// [`Self::filter_event`]. Not called for immediate viewports, they run inside the pass of
+    /// their parent.
+    fn on_raw_input(&mut self, _viewport_id: egui::ViewportId, _raw_input: &mut egui::RawInput) {}
+
+    /// inspect the output of a pass of the viewport before the integration handles it, e.g. for
+    /// analytics or to composite the shapes with another renderer.
+    ///
+    /// Not called for immediate viewports, like [`Self::on_raw_input`].
+    fn on_full_output(&mut self, _viewport_id: egui::ViewportId, _full_output: &egui::FullOutput) {}
+
+    /// decide whether the viewport closes when the user closes its window.
+    ///
+    /// Deny and close it later with [`egui::ViewportCommand::Close`] or [`crate::ExitSignal`],
+    /// e.g. after a dialog about unsaved changes.
+    fn on_close_requested(&mut self, _viewport_id: egui::ViewportId) -> CloseResponse {
+        CloseResponse::Allow
+    }
+
     /// redraw the app.
     ///
     /// If you want to draw only egui, return [`HandleRedraw::Auto`].
@@ -39,13 +127,20 @@ pub trait App {
         HandleRedraw::Auto
     }
 
-    /// time interval for call [`Self::save`].
+    /// free resources of the app on [`event::AppEvent::MemoryWarning`].
+    ///
+    /// Called after the egui image caches are freed. `unused_user_textures` are the user textures
+    /// not drawn in the last second, least recently used first.
+    /// Unregister the ones you destroy from [`ImageRegistry`].
+    fn trim_memory(&mut self, _unused_user_textures: &[egui::TextureId]) {}
+
+    /// time interval for call [`Self::save`] while the app runs.
     #[cfg(feature = "persistence")]
     fn auto_save_interval(&self) -> std::time::Duration {
         std::time::Duration::from_secs(30)
     }
 
-    /// save app state.
+    /// save app state. Read it back from [`CreationContext::storage`] on the next run.
     #[cfg(feature = "persistence")]
     fn save(&mut self, _storage: &mut storage::Storage) {}
 }
@@ -69,10 +164,89 @@ pub struct CreationContext<'a> {
 
     /// exit signal sender
     pub exit_signal: ExitSignal,
+
+    /// repaint requests from other threads
+    pub repaint_signal: crate::RepaintSignal,
+
+    /// windows with their own egui context
+    pub documents: crate::documents::Documents,
+
+    /// persisted app state, to restore the state saved in [`App::save`]
+    #[cfg(feature = "persistence")]
+    pub storage: &'a storage::Storage,
+
+    /// named window layouts
+    #[cfg(feature = "persistence")]
+    pub layout_profiles: crate::layout::LayoutProfiles,
+
+    /// custom cursor images
+    pub custom_cursors: CustomCursors,
+
+    /// fonts registered on top of the default fonts
+    pub fonts: crate::Fonts,
+
+    /// app-wide keyboard shortcuts
+    pub accelerators: Accelerators,
+
+    /// dock badge and attention requests of the app icon
+    pub taskbar: crate::Taskbar,
+
+    /// mirrors of viewports on second windows, e.g. for presenter views and projectors
+    pub mirrors: crate::Mirrors,
+
+    /// native drag source (Windows/macOS only)
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    pub drag_source: crate::drag::DragSource,
+
+    /// native file dialogs that do not block the event loop
+    #[cfg(feature = "file-dialog")]
+    pub file_dialogs: crate::file_dialog::FileDialogs,
+
+    /// OS-level global hotkeys
+    #[cfg(feature = "global-hotkey")]
+    pub global_hotkeys: crate::hotkey::GlobalHotkeys,
+
+    /// native menu bar of the main window
+    #[cfg(feature = "menu")]
+    pub native_menu: crate::menu::NativeMenu,
+
+    /// native alert and confirm dialogs that do not block the event loop
+    #[cfg(feature = "message-dialog")]
+    pub message_dialogs: crate::message_dialog::MessageDialogs,
+
+    /// clipboard with rich text formats
+    #[cfg(feature = "rich-clipboard")]
+    pub rich_clipboard: crate::rich_clipboard::RichClipboard,
+
+    /// system tray icon
+    #[cfg(feature = "tray")]
+    pub tray: crate::tray::Tray,
+
+    /// splash window opened with [`crate::RunOption::splash`], to report the progress of the creation
+    #[cfg(feature = "splash")]
+    pub splash: Option<crate::splash::SplashHandle>,
+
+    /// frame rate cap
+    pub frame_limiter: crate::FrameLimiter,
+
+    /// manual frame stepping
+    pub frame_stepper: crate::FrameStepper,
+
+    /// metrics of the last frames
+    pub frame_stats: crate::FrameStatistics,
+
+    /// callbacks around the queue submissions of egui-ash and the final pass of its frames
+    pub submit_hooks: crate::SubmitHooks,
+
+    /// debug options to create the allocator with
+    pub allocator_debug: crate::AllocatorDebugOptions,
 }
 
 /// vulkan objects required for drawing ash.
-/// You should return this struct from [`AppCreator::create()`].
+/// You should return this struct from [`AppCreator::create()`], created with
+/// [`AshRenderState::new`].
+#[derive(Clone)]
+#[non_exhaustive]
 pub struct AshRenderState<A: Allocator + 'static> {
     pub entry: Entry,
     pub instance: Instance,
@@ -82,9 +256,47 @@ pub struct AshRenderState<A: Allocator + 'static> {
     pub swapchain_loader: ash::khr::swapchain::Device,
     pub queue: vk::Queue,
     pub queue_family_index: u32,
+    /// other queue families using the textures, render targets and vertex buffers of egui-ash,
+    /// e.g. when the command buffers of [`HandleRedraw::Handle`] are submitted to a queue of
+    /// another family. They are created with `vk::SharingMode::CONCURRENT` if not empty.
+    ///
+    /// Empty by default.
+    pub concurrent_queue_family_indices: Vec<u32>,
+    /// not used by egui-ash, null by default.
     pub command_pool: vk::CommandPool,
     pub allocator: A,
 }
+impl<A: Allocator + 'static> AshRenderState<A> {
+    /// render state drawing with `queue` of `queue_family_index` on `device`.
+    ///
+    /// The surface and swapchain loaders are loaded from `entry`, `instance` and `device`. Set
+    /// the other fields after, e.g. [`Self::concurrent_queue_family_indices`].
+    pub fn new(
+        entry: Entry,
+        instance: Instance,
+        physical_device: vk::PhysicalDevice,
+        device: Device,
+        queue: vk::Queue,
+        queue_family_index: u32,
+        allocator: A,
+    ) -> Self {
+        let surface_loader = ash::khr::surface::Instance::new(&entry, &instance);
+        let swapchain_loader = ash::khr::swapchain::Device::new(&instance, &device);
+        Self {
+            entry,
+            instance,
+            physical_device,
+            device,
+            surface_loader,
+            swapchain_loader,
+            queue,
+            queue_family_index,
+            concurrent_queue_family_indices: vec![],
+            command_pool: vk::CommandPool::null(),
+            allocator,
+        }
+    }
+}
 
 /// egui-ash app creator trait.
 pub trait AppCreator<A: Allocator + 'static> {
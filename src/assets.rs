@@ -0,0 +1,156 @@
+//! images decoded on worker threads and uploaded in the background.
+//!
+//! Queue image files or encoded bytes with an [`AssetLoader`] from any thread. Each load returns a
+//! [`PendingTexture`] that is polled from the ui, or a channel that receives the [`LoadedTexture`].
+//! The images are packed into the atlas pages of the renderer with
+//! [`ImageRegistry::register_atlas_image`] and the context is repainted when one arrives.
+
+use anyhow::Result;
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+};
+
+use crate::ImageRegistry;
+
+/// an image uploaded by an [`AssetLoader`].
+///
+/// The texture belongs to the app, free it with [`ImageRegistry::unregister_user_texture`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadedTexture {
+    /// id to draw the texture with.
+    pub id: egui::TextureId,
+    /// size of the image in pixels.
+    pub size: [usize; 2],
+}
+impl LoadedTexture {
+    /// the texture at the size of the image, for `egui::Image::new`.
+    pub fn sized_texture(&self) -> egui::load::SizedTexture {
+        egui::load::SizedTexture::new(
+            self.id,
+            egui::vec2(self.size[0] as f32, self.size[1] as f32),
+        )
+    }
+}
+
+/// a queued load of an [`AssetLoader`].
+#[derive(Debug)]
+pub struct PendingTexture {
+    receiver: mpsc::Receiver<Result<LoadedTexture>>,
+    result: Option<Result<LoadedTexture>>,
+}
+impl PendingTexture {
+    /// the result once the image is uploaded, `None` before.
+    pub fn poll(&mut self) -> Option<&Result<LoadedTexture>> {
+        if self.result.is_none() {
+            self.result = match self.receiver.try_recv() {
+                Ok(result) => Some(result),
+                Err(mpsc::TryRecvError::Empty) => None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    Some(Err(anyhow::anyhow!("The asset loader stopped")))
+                }
+            };
+        }
+        self.result.as_ref()
+    }
+
+    /// the channel receiving the result, e.g. to wait for it on another thread.
+    pub fn into_receiver(self) -> mpsc::Receiver<Result<LoadedTexture>> {
+        self.receiver
+    }
+}
+
+enum Source {
+    Path(PathBuf),
+    Bytes(Arc<[u8]>),
+}
+
+struct Job {
+    source: Source,
+    sender: mpsc::Sender<Result<LoadedTexture>>,
+}
+
+/// decodes and uploads images on worker threads.
+///
+/// Clones share the workers, which stop when the last clone is dropped.
+#[derive(Clone)]
+pub struct AssetLoader {
+    jobs: mpsc::Sender<Job>,
+}
+impl AssetLoader {
+    /// start `threads` workers uploading with `image_registry` and repainting `ctx`.
+    pub fn new(ctx: &egui::Context, image_registry: &ImageRegistry, threads: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            let ctx = ctx.clone();
+            let image_registry = image_registry.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("egui-ash assets {index}"))
+                .spawn(move || worker(&receiver, &ctx, &image_registry));
+            if let Err(err) = spawned {
+                error!("Failed to start an asset loader thread: {err}");
+            }
+        }
+        Self { jobs }
+    }
+
+    /// load the image file at `path`.
+    pub fn load_path(&self, path: impl Into<PathBuf>) -> PendingTexture {
+        self.queue(Source::Path(path.into()))
+    }
+
+    /// load an image from the bytes of an encoded file, e.g. PNG or JPEG.
+    pub fn load_bytes(&self, bytes: impl Into<Arc<[u8]>>) -> PendingTexture {
+        self.queue(Source::Bytes(bytes.into()))
+    }
+
+    fn queue(&self, source: Source) -> PendingTexture {
+        let (sender, receiver) = mpsc::channel();
+        // without workers the sender is dropped and the load fails when polled.
+        let _ = self.jobs.send(Job { source, sender });
+        PendingTexture {
+            receiver,
+            result: None,
+        }
+    }
+}
+
+fn worker(receiver: &Mutex<mpsc::Receiver<Job>>, ctx: &egui::Context, registry: &ImageRegistry) {
+    loop {
+        let Ok(job) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        profile_scope!("load_asset");
+        let image = match job.source {
+            Source::Path(path) => std::fs::read(&path)
+                .map_err(|err| anyhow::anyhow!("Failed to read image {path:?}: {err}"))
+                .and_then(|bytes| decode(&bytes)),
+            Source::Bytes(bytes) => decode(&bytes),
+        };
+        let result = image.map(|image| LoadedTexture {
+            size: image.size,
+            id: registry.register_atlas_image(image),
+        });
+        match job.sender.send(result) {
+            Ok(()) => ctx.request_repaint(),
+            // the pending texture was dropped while loading.
+            Err(mpsc::SendError(Ok(texture))) => registry.unregister_user_texture(texture.id),
+            Err(_) => {}
+        }
+    }
+}
+
+/// decode an encoded image into a color image.
+pub(crate) fn decode(bytes: &[u8]) -> Result<egui::ColorImage> {
+    profile_function!();
+    let image = image::load_from_memory(bytes)
+        .map_err(|err| anyhow::anyhow!("Failed to decode image: {err}"))?
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_flat_samples().as_slice(),
+    ))
+}
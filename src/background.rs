@@ -0,0 +1,59 @@
+use egui_winit::winit;
+
+/// platform effect behind the transparent parts of a window.
+///
+/// The viewport must be transparent, see `egui::ViewportBuilder::with_transparent`,
+/// and the panels must leave the background visible, e.g. with `egui::Frame::NONE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BackgroundEffect {
+    /// show what is behind the window as is.
+    #[default]
+    None,
+    /// blur what is behind the window. (macOS, Wayland with the KDE blur protocol, Windows 11 as acrylic)
+    Blur,
+    /// Windows 11 acrylic, falls back to [`BackgroundEffect::Blur`] on other platforms.
+    Acrylic,
+    /// Windows 11 mica, tinted by the desktop wallpaper. Falls back to [`BackgroundEffect::Blur`] on other platforms.
+    Mica,
+    /// Windows 11 mica for tabbed windows. Falls back to [`BackgroundEffect::Blur`] on other platforms.
+    MicaAlt,
+}
+
+fn pending_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_background_effect").with(viewport_id)
+}
+
+/// set the [`BackgroundEffect`] of the window of `viewport_id`, applied after the current frame.
+///
+/// Call it once, e.g. when creating the app or when the viewport is shown for the first time.
+pub fn set_background_effect(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    effect: BackgroundEffect,
+) {
+    ctx.data_mut(|data| data.insert_temp(pending_id(viewport_id), effect));
+    ctx.request_repaint_of(viewport_id);
+}
+
+/// the effect set by [`set_background_effect`] for `viewport_id` since the last call.
+pub(crate) fn take_background_effect(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+) -> Option<BackgroundEffect> {
+    ctx.data_mut(|data| data.remove_temp(pending_id(viewport_id)))
+}
+
+pub(crate) fn apply_background_effect(window: &winit::window::Window, effect: BackgroundEffect) {
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::{BackdropType, WindowExtWindows as _};
+        window.set_system_backdrop(match effect {
+            BackgroundEffect::None => BackdropType::None,
+            BackgroundEffect::Blur | BackgroundEffect::Acrylic => BackdropType::TransientWindow,
+            BackgroundEffect::Mica => BackdropType::MainWindow,
+            BackgroundEffect::MicaAlt => BackdropType::TabbedWindow,
+        });
+    }
+    #[cfg(not(target_os = "windows"))]
+    window.set_blur(effect != BackgroundEffect::None);
+}
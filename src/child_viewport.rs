@@ -0,0 +1,69 @@
+use egui_winit::winit;
+use raw_window_handle::RawWindowHandle;
+
+fn rect_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_child_viewport").with(viewport_id)
+}
+
+/// embed the window of the deferred viewport `viewport_id` in the window of its parent viewport at
+/// `rect`, in points of the parent, e.g. to host an egui panel in a region of a native layout.
+///
+/// The viewport gets a native child window with its own swapchain, clipped to the parent window.
+/// Call it from the ui of the parent in each pass, the child window follows the changes of `rect`.
+/// Call it before the viewport is shown for the first time, an existing top-level window is not
+/// turned into a child window. (Windows, X11; a window floating above the parent on macOS)
+pub fn set_child_viewport(ctx: &egui::Context, viewport_id: egui::ViewportId, rect: egui::Rect) {
+    ctx.data_mut(|data| data.insert_temp(rect_id(viewport_id), rect));
+}
+
+pub(crate) fn child_rect(ctx: &egui::Context, viewport_id: egui::ViewportId) -> Option<egui::Rect> {
+    ctx.data(|data| data.get_temp(rect_id(viewport_id)))
+}
+
+fn position(ctx: &egui::Context, rect: egui::Rect) -> winit::dpi::LogicalPosition<f32> {
+    let min = rect.min * ctx.zoom_factor();
+    winit::dpi::LogicalPosition::new(min.x, min.y)
+}
+
+fn size(ctx: &egui::Context, rect: egui::Rect) -> winit::dpi::LogicalSize<f32> {
+    let size = (rect.size() * ctx.zoom_factor()).max(egui::vec2(1.0, 1.0));
+    winit::dpi::LogicalSize::new(size.x, size.y)
+}
+
+/// the window attributes of a child window at `rect` in `parent`.
+pub(crate) fn apply_child_window(
+    window_attributes: winit::window::WindowAttributes,
+    ctx: &egui::Context,
+    rect: egui::Rect,
+    parent: RawWindowHandle,
+) -> winit::window::WindowAttributes {
+    let window_attributes = window_attributes
+        .with_position(position(ctx, rect))
+        .with_inner_size(size(ctx, rect))
+        .with_decorations(false)
+        .with_resizable(false);
+    // SAFETY: the parent window outlives its child viewports, they are closed with it.
+    unsafe { window_attributes.with_parent_window(Some(parent)) }
+}
+
+/// move and resize the child window to the rect set in the last pass.
+pub(crate) fn update_child_window(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    window: &winit::window::Window,
+    applied: &mut Option<egui::Rect>,
+) {
+    let (Some(rect), Some(applied)) = (child_rect(ctx, viewport_id), applied.as_mut()) else {
+        return;
+    };
+    if *applied == rect {
+        return;
+    }
+    if applied.min != rect.min {
+        window.set_outer_position(position(ctx, rect));
+    }
+    if applied.size() != rect.size() {
+        let _ = window.request_inner_size(size(ctx, rect));
+    }
+    *applied = rect;
+}
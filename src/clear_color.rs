@@ -0,0 +1,53 @@
+fn default_id() -> egui::Id {
+    egui::Id::new("egui_ash_clear_color")
+}
+
+fn viewport_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_clear_color").with(viewport_id)
+}
+
+fn transparent_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_clear_color_transparent").with(viewport_id)
+}
+
+/// set the color the window of `viewport_id` is cleared to before egui paints, e.g. to animate
+/// the background behind the panels. Applies from the current frame on, nothing is recreated.
+///
+/// The color is premultiplied and in gamma space, like `egui::Color32::to_normalized_gamma_f32`.
+/// Its alpha shows what is behind transparent windows.
+pub fn set_clear_color(ctx: &egui::Context, viewport_id: egui::ViewportId, color: [f32; 4]) {
+    ctx.data_mut(|data| data.insert_temp(self::viewport_id(viewport_id), color));
+    ctx.request_repaint_of(viewport_id);
+}
+
+/// the clear color of `viewport_id`, set by [`set_clear_color`] or else
+/// [`crate::RunOption::clear_color`]. Transparent windows without their own color are cleared to
+/// transparent.
+///
+/// Use it to clear the swapchain image in [`crate::HandleRedraw::Handle`].
+pub fn clear_color(ctx: &egui::Context, viewport_id: egui::ViewportId) -> [f32; 4] {
+    ctx.data(|data| {
+        data.get_temp(self::viewport_id(viewport_id)).or_else(|| {
+            if data.get_temp(transparent_id(viewport_id)).unwrap_or(false) {
+                Some([0.0; 4])
+            } else {
+                data.get_temp(default_id())
+            }
+        })
+    })
+    .unwrap_or([0.0, 0.0, 0.0, 1.0])
+}
+
+/// set the clear color of the viewports without their own clear color.
+pub(crate) fn set_default_clear_color(ctx: &egui::Context, color: [f32; 4]) {
+    ctx.data_mut(|data| data.insert_temp(default_id(), color));
+}
+
+/// mark the window of `viewport_id` transparent, so that it is cleared to transparent by default.
+pub(crate) fn set_transparent(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    transparent: bool,
+) {
+    ctx.data_mut(|data| data.insert_temp(transparent_id(viewport_id), transparent));
+}
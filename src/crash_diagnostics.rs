@@ -0,0 +1,253 @@
+use anyhow::Result;
+use ash::{vk, Device, Instance};
+use std::{
+    collections::VecDeque,
+    ffi::{c_void, CStr},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
+
+// number of frames whose description is kept to name the markers of a device lost.
+const KEPT_FRAMES: usize = 64;
+
+/// device extension writing the breadcrumbs of [`crate::RunOption::gpu_crash_diagnostics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuCrashDiagnostics {
+    /// `VK_NV_device_diagnostic_checkpoints`, checkpoints before and after each egui frame.
+    NvDiagnosticCheckpoints,
+    /// `VK_AMD_buffer_marker`, markers written to a host visible buffer when each egui frame
+    /// starts and finishes on the GPU.
+    AmdBufferMarker,
+}
+impl GpuCrashDiagnostics {
+    /// the device extension to enable.
+    pub fn device_extension(self) -> &'static CStr {
+        match self {
+            Self::NvDiagnosticCheckpoints => ash::nv::device_diagnostic_checkpoints::NAME,
+            Self::AmdBufferMarker => ash::amd::buffer_marker::NAME,
+        }
+    }
+
+    /// the first diagnostics the physical device supports.
+    pub fn supported(instance: &Instance, physical_device: vk::PhysicalDevice) -> Option<Self> {
+        let extensions =
+            unsafe { instance.enumerate_device_extension_properties(physical_device) }.ok()?;
+        [Self::NvDiagnosticCheckpoints, Self::AmdBufferMarker]
+            .into_iter()
+            .find(|diagnostics| {
+                extensions.iter().any(|extension| {
+                    extension.extension_name_as_c_str() == Ok(diagnostics.device_extension())
+                })
+            })
+    }
+}
+
+enum Writer {
+    Nv(ash::nv::device_diagnostic_checkpoints::Device),
+    Amd {
+        loader: ash::amd::buffer_marker::Device,
+        buffer: vk::Buffer,
+        memory: vk::DeviceMemory,
+        // the markers of the last frame started at offset 0 and finished at offset 4.
+        mapped: *const u32,
+    },
+}
+
+/// markers around the egui frames on the GPU, dumped when the device is lost.
+pub(crate) struct Breadcrumbs {
+    device: Device,
+    writer: Writer,
+    next_marker: AtomicU32,
+    frames: Mutex<VecDeque<(u32, egui::ViewportId)>>,
+}
+// the mapped memory is only read after the device is lost.
+unsafe impl Send for Breadcrumbs {}
+unsafe impl Sync for Breadcrumbs {}
+impl Breadcrumbs {
+    pub(crate) fn new(
+        instance: &Instance,
+        physical_device: vk::PhysicalDevice,
+        device: Device,
+        diagnostics: GpuCrashDiagnostics,
+    ) -> Result<Self> {
+        let writer = match diagnostics {
+            GpuCrashDiagnostics::NvDiagnosticCheckpoints => Writer::Nv(
+                ash::nv::device_diagnostic_checkpoints::Device::new(instance, &device),
+            ),
+            GpuCrashDiagnostics::AmdBufferMarker => {
+                let loader = ash::amd::buffer_marker::Device::new(instance, &device);
+                let buffer = unsafe {
+                    device.create_buffer(
+                        &vk::BufferCreateInfo::default()
+                            .size(8)
+                            .usage(vk::BufferUsageFlags::TRANSFER_DST)
+                            .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                        None,
+                    )?
+                };
+                let requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
+                let properties =
+                    unsafe { instance.get_physical_device_memory_properties(physical_device) };
+                let flags =
+                    vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+                let Some(memory_type_index) = (0..properties.memory_type_count).find(|&i| {
+                    requirements.memory_type_bits & (1 << i) != 0
+                        && properties.memory_types[i as usize]
+                            .property_flags
+                            .contains(flags)
+                }) else {
+                    unsafe { device.destroy_buffer(buffer, None) };
+                    anyhow::bail!("no host visible memory for the buffer markers");
+                };
+                let memory = unsafe {
+                    device.allocate_memory(
+                        &vk::MemoryAllocateInfo::default()
+                            .allocation_size(requirements.size)
+                            .memory_type_index(memory_type_index),
+                        None,
+                    )?
+                };
+                let mapped = unsafe {
+                    device.bind_buffer_memory(buffer, memory, 0)?;
+                    let mapped =
+                        device.map_memory(memory, 0, 8, vk::MemoryMapFlags::empty())? as *mut u32;
+                    mapped.write_bytes(0, 2);
+                    mapped
+                };
+                Writer::Amd {
+                    loader,
+                    buffer,
+                    memory,
+                    mapped,
+                }
+            }
+        };
+        Ok(Self {
+            device,
+            writer,
+            next_marker: AtomicU32::new(1),
+            frames: Mutex::new(VecDeque::with_capacity(KEPT_FRAMES)),
+        })
+    }
+
+    /// marker of the next frame of `viewport_id`.
+    pub(crate) fn next_marker(&self, viewport_id: egui::ViewportId) -> u32 {
+        let marker = self.next_marker.fetch_add(1, Ordering::Relaxed);
+        let mut frames = self.frames.lock().unwrap();
+        if frames.len() == KEPT_FRAMES {
+            frames.pop_front();
+        }
+        frames.push_back((marker, viewport_id));
+        marker
+    }
+
+    /// mark the start of the frame `marker` in `cmd`.
+    pub(crate) fn begin(&self, cmd: vk::CommandBuffer, marker: u32) {
+        match &self.writer {
+            Writer::Nv(loader) => unsafe {
+                loader.cmd_set_checkpoint(cmd, checkpoint(marker, false));
+            },
+            Writer::Amd { loader, buffer, .. } => unsafe {
+                loader.cmd_write_buffer_marker(
+                    cmd,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    *buffer,
+                    0,
+                    marker,
+                );
+            },
+        }
+    }
+
+    /// mark the end of the frame `marker` in `cmd`.
+    pub(crate) fn end(&self, cmd: vk::CommandBuffer, marker: u32) {
+        match &self.writer {
+            Writer::Nv(loader) => unsafe {
+                loader.cmd_set_checkpoint(cmd, checkpoint(marker, true));
+            },
+            Writer::Amd { loader, buffer, .. } => unsafe {
+                loader.cmd_write_buffer_marker(
+                    cmd,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    *buffer,
+                    4,
+                    marker,
+                );
+            },
+        }
+    }
+
+    fn describe(&self, marker: u32) -> String {
+        let frames = self.frames.lock().unwrap();
+        match frames
+            .iter()
+            .find(|(frame_marker, _)| *frame_marker == marker)
+        {
+            Some((_, viewport_id)) => format!("frame {marker} of {viewport_id:?}"),
+            None => format!("frame {marker}"),
+        }
+    }
+
+    /// log the last markers the GPU reached, after the device was lost or a frame hung.
+    pub(crate) fn dump(&self, queue: vk::Queue) {
+        match &self.writer {
+            Writer::Nv(loader) => {
+                let len = unsafe { loader.get_queue_checkpoint_data_len(queue) };
+                let mut checkpoints = vec![vk::CheckpointDataNV::default(); len];
+                unsafe { loader.get_queue_checkpoint_data(queue, &mut checkpoints) };
+                if checkpoints.is_empty() {
+                    error!("No egui frame reached a checkpoint");
+                }
+                for data in checkpoints {
+                    let value = data.p_checkpoint_marker as usize;
+                    let marker = (value >> 1) as u32;
+                    let position = if value & 1 == 1 { "end" } else { "start" };
+                    error!(
+                        "Checkpoint at the {} of {} reached {:?}",
+                        position,
+                        self.describe(marker),
+                        data.stage
+                    );
+                }
+            }
+            Writer::Amd { mapped, .. } => {
+                let (started, finished) =
+                    unsafe { (mapped.read_volatile(), mapped.add(1).read_volatile()) };
+                if started == finished {
+                    error!(
+                        "The GPU is outside of the egui frames, the last one finished was {}",
+                        self.describe(finished)
+                    );
+                } else {
+                    error!(
+                        "The GPU stopped in {}, the last one finished was {}",
+                        self.describe(started),
+                        self.describe(finished)
+                    );
+                }
+            }
+        }
+    }
+
+    pub(crate) fn destroy(&self) {
+        if let Writer::Amd { buffer, memory, .. } = &self.writer {
+            unsafe {
+                self.device.destroy_buffer(*buffer, None);
+                self.device.unmap_memory(*memory);
+                self.device.free_memory(*memory, None);
+            }
+        }
+    }
+}
+
+// checkpoint markers are pointer sized values, the lowest bit tells the end of the frame.
+fn checkpoint(marker: u32, end: bool) -> *const c_void {
+    (((marker as usize) << 1) | usize::from(end)) as *const c_void
+}
+
+/// whether `err` is `vk::Result::ERROR_DEVICE_LOST`.
+pub(crate) fn is_device_lost(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<vk::Result>() == Some(&vk::Result::ERROR_DEVICE_LOST)
+}
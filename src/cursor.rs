@@ -0,0 +1,161 @@
+use anyhow::Result;
+use egui_winit::winit::{self, event_loop::ActiveEventLoop};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// id of a custom cursor registered with [`CustomCursors::register`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomCursorId(u64);
+
+enum CustomCursorEntry {
+    Source(winit::window::CustomCursorSource),
+    Cursor(winit::window::CustomCursor),
+}
+
+#[derive(Default)]
+struct CustomCursorsInner {
+    counter: u64,
+    cursors: HashMap<CustomCursorId, CustomCursorEntry>,
+    current: Option<CustomCursorId>,
+}
+
+/// custom cursor images for egui-ash windows.
+///
+/// While a custom cursor is set, it overrides the cursor icon requested by egui.
+#[derive(Clone, Default)]
+pub struct CustomCursors {
+    inner: Arc<Mutex<CustomCursorsInner>>,
+}
+impl CustomCursors {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// register a custom cursor from RGBA pixels. `rgba.len()` must be `width * height * 4`.
+    pub fn register(
+        &self,
+        rgba: Vec<u8>,
+        width: u16,
+        height: u16,
+        hotspot_x: u16,
+        hotspot_y: u16,
+    ) -> Result<CustomCursorId> {
+        let source =
+            winit::window::CustomCursor::from_rgba(rgba, width, height, hotspot_x, hotspot_y)?;
+        let mut inner = self.inner.lock().unwrap();
+        let id = CustomCursorId(inner.counter);
+        inner.counter += 1;
+        inner.cursors.insert(id, CustomCursorEntry::Source(source));
+        Ok(id)
+    }
+
+    /// unregister a custom cursor.
+    pub fn unregister(&self, id: CustomCursorId) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.cursors.remove(&id);
+        if inner.current == Some(id) {
+            inner.current = None;
+        }
+    }
+
+    /// set the custom cursor. `None` restores the cursor icon requested by egui.
+    pub fn set(&self, id: Option<CustomCursorId>) {
+        self.inner.lock().unwrap().current = id;
+    }
+
+    /// call after `egui_winit::State::handle_platform_output`.
+    pub(crate) fn apply(
+        &self,
+        event_loop: &ActiveEventLoop,
+        window: &winit::window::Window,
+        custom_cursor_applied: &mut bool,
+        cursor_icon: egui::CursorIcon,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        let cursor = inner.current.and_then(|id| {
+            let entry = inner.cursors.remove(&id)?;
+            let cursor = match entry {
+                CustomCursorEntry::Source(source) => event_loop.create_custom_cursor(source),
+                CustomCursorEntry::Cursor(cursor) => cursor,
+            };
+            inner
+                .cursors
+                .insert(id, CustomCursorEntry::Cursor(cursor.clone()));
+            Some(cursor)
+        });
+
+        match cursor {
+            Some(cursor) => {
+                // egui-winit may have set its own cursor icon this frame, so always set it again.
+                window.set_cursor_visible(true);
+                window.set_cursor(cursor);
+                *custom_cursor_applied = true;
+            }
+            None if *custom_cursor_applied => {
+                // egui-winit caches its cursor icon, so restore it here.
+                match translate_cursor(cursor_icon) {
+                    Some(icon) => {
+                        window.set_cursor_visible(true);
+                        window.set_cursor(icon);
+                    }
+                    None => window.set_cursor_visible(false),
+                }
+                *custom_cursor_applied = false;
+            }
+            None => {}
+        }
+    }
+}
+impl std::fmt::Debug for CustomCursors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomCursors").finish()
+    }
+}
+
+pub(crate) fn translate_cursor(cursor_icon: egui::CursorIcon) -> Option<winit::window::CursorIcon> {
+    use winit::window::CursorIcon;
+
+    match cursor_icon {
+        egui::CursorIcon::None => None,
+
+        egui::CursorIcon::Alias => Some(CursorIcon::Alias),
+        egui::CursorIcon::AllScroll => Some(CursorIcon::AllScroll),
+        egui::CursorIcon::Cell => Some(CursorIcon::Cell),
+        egui::CursorIcon::ContextMenu => Some(CursorIcon::ContextMenu),
+        egui::CursorIcon::Copy => Some(CursorIcon::Copy),
+        egui::CursorIcon::Crosshair => Some(CursorIcon::Crosshair),
+        egui::CursorIcon::Default => Some(CursorIcon::Default),
+        egui::CursorIcon::Grab => Some(CursorIcon::Grab),
+        egui::CursorIcon::Grabbing => Some(CursorIcon::Grabbing),
+        egui::CursorIcon::Help => Some(CursorIcon::Help),
+        egui::CursorIcon::Move => Some(CursorIcon::Move),
+        egui::CursorIcon::NoDrop => Some(CursorIcon::NoDrop),
+        egui::CursorIcon::NotAllowed => Some(CursorIcon::NotAllowed),
+        egui::CursorIcon::PointingHand => Some(CursorIcon::Pointer),
+        egui::CursorIcon::Progress => Some(CursorIcon::Progress),
+
+        egui::CursorIcon::ResizeHorizontal => Some(CursorIcon::EwResize),
+        egui::CursorIcon::ResizeNeSw => Some(CursorIcon::NeswResize),
+        egui::CursorIcon::ResizeNwSe => Some(CursorIcon::NwseResize),
+        egui::CursorIcon::ResizeVertical => Some(CursorIcon::NsResize),
+
+        egui::CursorIcon::ResizeEast => Some(CursorIcon::EResize),
+        egui::CursorIcon::ResizeSouthEast => Some(CursorIcon::SeResize),
+        egui::CursorIcon::ResizeSouth => Some(CursorIcon::SResize),
+        egui::CursorIcon::ResizeSouthWest => Some(CursorIcon::SwResize),
+        egui::CursorIcon::ResizeWest => Some(CursorIcon::WResize),
+        egui::CursorIcon::ResizeNorthWest => Some(CursorIcon::NwResize),
+        egui::CursorIcon::ResizeNorth => Some(CursorIcon::NResize),
+        egui::CursorIcon::ResizeNorthEast => Some(CursorIcon::NeResize),
+        egui::CursorIcon::ResizeColumn => Some(CursorIcon::ColResize),
+        egui::CursorIcon::ResizeRow => Some(CursorIcon::RowResize),
+
+        egui::CursorIcon::Text => Some(CursorIcon::Text),
+        egui::CursorIcon::VerticalText => Some(CursorIcon::VerticalText),
+        egui::CursorIcon::Wait => Some(CursorIcon::Wait),
+        egui::CursorIcon::ZoomIn => Some(CursorIcon::ZoomIn),
+        egui::CursorIcon::ZoomOut => Some(CursorIcon::ZoomOut),
+    }
+}
@@ -0,0 +1,628 @@
+//! Physical device selection from the Vulkan features the app requires or can make use of, for the
+//! device created in [`crate::AppCreator::create`].
+//!
+//! Declare the features in [`DeviceRequirements`], pick a device with [`select_physical_device`]
+//! and create it with [`DeviceSelection::create_device`]. [`DeviceSelection::is_enabled`] then
+//! tells which optional features the renderer of the app can use. [`enumerate_gpus`] lists the
+//! devices to let the user choose one.
+//!
+//! egui-ash itself runs on Vulkan 1.0 devices, and uses the features of newer devices created with
+//! [`DeviceSelection::create_device`].
+
+use anyhow::{anyhow, Result};
+use ash::{khr, prelude::VkResult, vk, Device, Instance};
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    sync::{Mutex, OnceLock},
+};
+
+// capabilities of the devices created with `DeviceSelection::create_device`, by device handle.
+static DEVICE_CAPABILITIES: OnceLock<Mutex<HashMap<vk::Device, DeviceCapabilities>>> =
+    OnceLock::new();
+
+/// Vulkan feature negotiated by [`select_physical_device`].
+///
+/// Features promoted to core after [`DeviceRequirements::api_version`] are enabled through their
+/// extensions, which needs Vulkan 1.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceFeature {
+    /// `samplerAnisotropy`.
+    SamplerAnisotropy,
+    /// `fillModeNonSolid`, e.g. for wireframe pipelines.
+    FillModeNonSolid,
+    /// `wideLines`.
+    WideLines,
+    /// `dynamicRendering`, core in Vulkan 1.3 or `VK_KHR_dynamic_rendering`.
+    DynamicRendering,
+    /// `synchronization2`, core in Vulkan 1.3 or `VK_KHR_synchronization2`.
+    Synchronization2,
+    /// `timelineSemaphore`, core in Vulkan 1.2 or `VK_KHR_timeline_semaphore`.
+    TimelineSemaphore,
+    /// `bufferDeviceAddress`, core in Vulkan 1.2 or `VK_KHR_buffer_device_address`.
+    BufferDeviceAddress,
+    /// bindless sampled images: `runtimeDescriptorArray`, `descriptorBindingPartiallyBound`,
+    /// `descriptorBindingVariableDescriptorCount`, `descriptorBindingSampledImageUpdateAfterBind`
+    /// and `shaderSampledImageArrayNonUniformIndexing`, core in Vulkan 1.2 or
+    /// `VK_EXT_descriptor_indexing`.
+    DescriptorIndexing,
+}
+impl DeviceFeature {
+    const ALL: [Self; 8] = [
+        Self::SamplerAnisotropy,
+        Self::FillModeNonSolid,
+        Self::WideLines,
+        Self::DynamicRendering,
+        Self::Synchronization2,
+        Self::TimelineSemaphore,
+        Self::BufferDeviceAddress,
+        Self::DescriptorIndexing,
+    ];
+
+    // device extensions providing the feature at `api_version`. `None` if it needs a later version.
+    fn extensions(self, api_version: u32) -> Option<&'static [&'static CStr]> {
+        let (core_version, extensions): (u32, &'static [&'static CStr]) = match self {
+            Self::SamplerAnisotropy | Self::FillModeNonSolid | Self::WideLines => {
+                (vk::API_VERSION_1_0, &[])
+            }
+            Self::DynamicRendering => (
+                vk::API_VERSION_1_3,
+                &[
+                    khr::dynamic_rendering::NAME,
+                    khr::depth_stencil_resolve::NAME,
+                    khr::create_renderpass2::NAME,
+                ],
+            ),
+            Self::Synchronization2 => (vk::API_VERSION_1_3, &[khr::synchronization2::NAME]),
+            Self::TimelineSemaphore => (vk::API_VERSION_1_2, &[khr::timeline_semaphore::NAME]),
+            Self::BufferDeviceAddress => (vk::API_VERSION_1_2, &[khr::buffer_device_address::NAME]),
+            Self::DescriptorIndexing => {
+                (vk::API_VERSION_1_2, &[ash::ext::descriptor_indexing::NAME])
+            }
+        };
+        if api_version >= core_version {
+            Some(&[])
+        } else if api_version >= vk::API_VERSION_1_1 {
+            Some(extensions)
+        } else {
+            None
+        }
+    }
+}
+
+/// kind of GPU preferred by [`select_physical_device`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuPreference {
+    /// discrete GPUs first, then integrated ones.
+    #[default]
+    HighPerformance,
+    /// integrated GPUs first, e.g. to save battery.
+    LowPower,
+    /// software rasterizers first, like lavapipe and SwiftShader, e.g. for reproducible CI runs on
+    /// machines with a GPU.
+    Software,
+}
+impl GpuPreference {
+    // higher ranks are selected first.
+    fn rank(self, device_type: vk::PhysicalDeviceType) -> u32 {
+        match (device_type, self) {
+            (vk::PhysicalDeviceType::DISCRETE_GPU, Self::HighPerformance) => 4,
+            (vk::PhysicalDeviceType::INTEGRATED_GPU, Self::HighPerformance) => 3,
+            (vk::PhysicalDeviceType::INTEGRATED_GPU, Self::LowPower) => 4,
+            (vk::PhysicalDeviceType::DISCRETE_GPU, Self::LowPower) => 3,
+            (vk::PhysicalDeviceType::CPU, Self::Software) => 4,
+            (vk::PhysicalDeviceType::VIRTUAL_GPU, _) => 2,
+            (vk::PhysicalDeviceType::CPU, _) => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// physical device listed by [`enumerate_gpus`], e.g. for a "Select GPU" dropdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuInfo {
+    pub physical_device: vk::PhysicalDevice,
+    /// `deviceName` of the device, to store the choice as [`DeviceRequirements::device_name`].
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    /// highest Vulkan version the device supports.
+    pub api_version: u32,
+}
+impl GpuInfo {
+    fn new(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let name = properties
+            .device_name_as_c_str()
+            .unwrap_or(c"unknown device")
+            .to_string_lossy()
+            .into_owned();
+        Self {
+            physical_device,
+            name,
+            device_type: properties.device_type,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            api_version: properties.api_version,
+        }
+    }
+
+    /// whether the device is a software rasterizer, like lavapipe and SwiftShader.
+    pub fn is_software(&self) -> bool {
+        self.device_type == vk::PhysicalDeviceType::CPU
+    }
+
+    /// whether the name of the device contains `filter`, ignoring case.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.name.to_lowercase().contains(&filter.to_lowercase())
+    }
+}
+
+/// the physical devices of `instance`.
+///
+/// The device is chosen when the render state is created, so a GPU selected in the UI is
+/// typically stored as [`DeviceRequirements::device_name`] and applied on the next start.
+pub fn enumerate_gpus(instance: &Instance) -> VkResult<Vec<GpuInfo>> {
+    let physical_devices = unsafe { instance.enumerate_physical_devices()? };
+    Ok(physical_devices
+        .into_iter()
+        .map(|physical_device| GpuInfo::new(instance, physical_device))
+        .collect())
+}
+
+/// features and extensions passed to [`select_physical_device`].
+#[derive(Debug, Clone)]
+pub struct DeviceRequirements {
+    /// `vk::ApplicationInfo::api_version` of the instance.
+    pub api_version: u32,
+    /// lowest Vulkan version the device must support, e.g. `vk::API_VERSION_1_0` to run on old
+    /// GPUs and use the features of newer ones through [`Self::optional_features`].
+    pub min_api_version: u32,
+    /// features the device must support.
+    pub features: Vec<DeviceFeature>,
+    /// features enabled if the device supports them.
+    pub optional_features: Vec<DeviceFeature>,
+    /// device extensions the device must support, e.g.
+    /// [`crate::CreationContext::required_device_extensions`].
+    pub extensions: Vec<CString>,
+    /// device extensions enabled if the device supports them.
+    pub optional_extensions: Vec<CString>,
+    /// kind of GPU selected when several devices meet the requirements.
+    pub gpu_preference: GpuPreference,
+    /// only select a device whose name contains this, ignoring case, see [`GpuInfo::matches`].
+    pub device_name: Option<String>,
+}
+impl Default for DeviceRequirements {
+    fn default() -> Self {
+        Self {
+            api_version: vk::API_VERSION_1_0,
+            min_api_version: vk::API_VERSION_1_0,
+            features: vec![],
+            optional_features: vec![],
+            extensions: vec![],
+            optional_extensions: vec![],
+            gpu_preference: GpuPreference::default(),
+            device_name: None,
+        }
+    }
+}
+impl DeviceRequirements {
+    /// these requirements for software rasterizers, used with [`crate::RunOption::software`].
+    ///
+    /// Software devices are preferred and the required features become optional, so that a
+    /// feature lavapipe or SwiftShader lack is skipped instead of failing the selection. Check
+    /// [`DeviceSelection::is_enabled`] before using one. Required extensions stay required.
+    pub fn software(mut self) -> Self {
+        for feature in std::mem::take(&mut self.features) {
+            if !self.optional_features.contains(&feature) {
+                self.optional_features.push(feature);
+            }
+        }
+        self.gpu_preference = GpuPreference::Software;
+        self
+    }
+}
+
+/// physical device chosen by [`select_physical_device`].
+#[derive(Debug, Clone)]
+pub struct DeviceSelection {
+    pub physical_device: vk::PhysicalDevice,
+    /// queue family supporting graphics and presenting to the surface.
+    pub queue_family_index: u32,
+    /// required features and the supported optional features.
+    pub enabled_features: Vec<DeviceFeature>,
+    /// required extensions, the supported optional extensions and the extensions of the enabled
+    /// features.
+    pub enabled_extensions: Vec<CString>,
+    api_version: u32,
+}
+impl DeviceSelection {
+    /// whether `feature` is enabled on the device, e.g. to take a faster path in the renderer.
+    pub fn is_enabled(&self, feature: DeviceFeature) -> bool {
+        self.enabled_features.contains(&feature)
+    }
+
+    /// whether the device extension `name` is enabled.
+    pub fn is_extension_enabled(&self, name: &CStr) -> bool {
+        self.enabled_extensions
+            .iter()
+            .any(|extension| extension.as_c_str() == name)
+    }
+
+    /// Vulkan version the device is used with, the lower of the instance and device versions.
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            api_version: self.api_version,
+            enabled_features: self.enabled_features.clone(),
+        }
+    }
+
+    /// create the device with the enabled features and extensions and one queue of
+    /// [`Self::queue_family_index`].
+    ///
+    /// egui-ash uses the enabled features of the device, e.g. [`DeviceFeature::Synchronization2`].
+    pub fn create_device(&self, instance: &Instance) -> VkResult<Device> {
+        let device = self.create_device_with_features(instance)?;
+        DEVICE_CAPABILITIES
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap()
+            .insert(device.handle(), self.capabilities());
+        Ok(device)
+    }
+
+    fn create_device_with_features(&self, instance: &Instance) -> VkResult<Device> {
+        let mut features = vk::PhysicalDeviceFeatures::default();
+        let mut extension_features = ExtensionFeatures::default();
+        for &feature in &self.enabled_features {
+            extension_features.enable(&mut features, feature);
+        }
+        let extension_names = self
+            .enabled_extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect::<Vec<_>>();
+        let queue_priorities = [1.0];
+        let queue_create_info = vk::DeviceQueueCreateInfo::default()
+            .queue_family_index(self.queue_family_index)
+            .queue_priorities(&queue_priorities);
+        let create_info = vk::DeviceCreateInfo::default()
+            .queue_create_infos(std::slice::from_ref(&queue_create_info))
+            .enabled_extension_names(&extension_names);
+        if self.api_version >= vk::API_VERSION_1_1 {
+            let mut features2 = extension_features.chain(features, &self.enabled_features);
+            let create_info = create_info.push_next(&mut features2);
+            unsafe { instance.create_device(self.physical_device, &create_info, None) }
+        } else {
+            let create_info = create_info.enabled_features(&features);
+            unsafe { instance.create_device(self.physical_device, &create_info, None) }
+        }
+    }
+}
+
+/// Vulkan version and enabled features of a device.
+///
+/// egui-ash records its frames with `vkCmdPipelineBarrier2` and `vkQueueSubmit2` when
+/// [`DeviceFeature::Synchronization2`] is enabled, and with the Vulkan 1.0 commands otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DeviceCapabilities {
+    pub(crate) api_version: u32,
+    pub(crate) enabled_features: Vec<DeviceFeature>,
+}
+impl DeviceCapabilities {
+    /// capabilities of `device` if it was created with [`DeviceSelection::create_device`], of a
+    /// Vulkan 1.0 device without optional features otherwise.
+    pub(crate) fn of(device: &Device) -> Self {
+        DEVICE_CAPABILITIES
+            .get()
+            .and_then(|capabilities| capabilities.lock().unwrap().get(&device.handle()).cloned())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn is_enabled(&self, feature: DeviceFeature) -> bool {
+        self.enabled_features.contains(&feature)
+    }
+}
+// a Vulkan 1.0 device without optional features.
+impl Default for DeviceCapabilities {
+    fn default() -> Self {
+        Self {
+            api_version: vk::API_VERSION_1_0,
+            enabled_features: vec![],
+        }
+    }
+}
+
+/// select the physical device with a queue family presenting to `surface` which supports all
+/// required features and extensions.
+///
+/// The kind of [`DeviceRequirements::gpu_preference`] is preferred, then the device with the most
+/// optional features and extensions. The reasons to skip a device are logged at debug level.
+pub fn select_physical_device(
+    instance: &Instance,
+    surface_loader: &khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+    requirements: &DeviceRequirements,
+) -> Result<DeviceSelection> {
+    if requirements.api_version < requirements.min_api_version {
+        return Err(anyhow!(
+            "The instance uses Vulkan {}, below the minimum version {}",
+            version_string(requirements.api_version),
+            version_string(requirements.min_api_version)
+        ));
+    }
+    let physical_devices = unsafe { instance.enumerate_physical_devices()? };
+    physical_devices
+        .into_iter()
+        .filter_map(|physical_device| {
+            candidate(
+                instance,
+                surface_loader,
+                surface,
+                requirements,
+                physical_device,
+            )
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, selection)| selection)
+        .ok_or_else(|| anyhow!("No physical device supports the required features"))
+}
+
+// the selection of `physical_device` and its score, `None` if it misses a requirement.
+fn candidate(
+    instance: &Instance,
+    surface_loader: &khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+    requirements: &DeviceRequirements,
+    physical_device: vk::PhysicalDevice,
+) -> Option<((u32, usize), DeviceSelection)> {
+    let info = GpuInfo::new(instance, physical_device);
+    let name = &info.name;
+    if let Some(filter) = &requirements.device_name {
+        if !info.matches(filter) {
+            debug!("Skipping {}: the name does not match {:?}", name, filter);
+            return None;
+        }
+    }
+    if info.api_version < requirements.min_api_version {
+        debug!(
+            "Skipping {}: Vulkan {} is below the minimum version {}",
+            name,
+            version_string(info.api_version),
+            version_string(requirements.min_api_version)
+        );
+        return None;
+    }
+    let api_version = info.api_version.min(requirements.api_version);
+
+    let queue_families =
+        unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+    let Some(queue_family_index) = (0..queue_families.len() as u32).find(|&i| {
+        queue_families[i as usize]
+            .queue_flags
+            .contains(vk::QueueFlags::GRAPHICS)
+            && unsafe {
+                surface_loader.get_physical_device_surface_support(physical_device, i, surface)
+            }
+            .unwrap_or(false)
+    }) else {
+        debug!("Skipping {}: no queue family presents to the surface", name);
+        return None;
+    };
+
+    let extension_properties =
+        unsafe { instance.enumerate_device_extension_properties(physical_device) }.ok()?;
+    let supports_extension = |name: &CStr| {
+        extension_properties
+            .iter()
+            .any(|extension| extension.extension_name_as_c_str() == Ok(name))
+    };
+    if let Some(missing) = requirements
+        .extensions
+        .iter()
+        .find(|extension| !supports_extension(extension.as_c_str()))
+    {
+        debug!("Skipping {}: {:?} is not supported", name, missing);
+        return None;
+    }
+
+    let supported = supported_features(instance, physical_device, api_version, &supports_extension);
+    if let Some(missing) = requirements
+        .features
+        .iter()
+        .find(|feature| !supported.contains(feature))
+    {
+        debug!("Skipping {}: {:?} is not supported", name, missing);
+        return None;
+    }
+
+    let mut enabled_features = requirements.features.clone();
+    for &feature in &requirements.optional_features {
+        if supported.contains(&feature) && !enabled_features.contains(&feature) {
+            enabled_features.push(feature);
+        }
+    }
+    let mut enabled_extensions = requirements.extensions.clone();
+    let optional_extensions = requirements
+        .optional_extensions
+        .iter()
+        .filter(|extension| supports_extension(extension.as_c_str()))
+        .cloned();
+    let feature_extensions = enabled_features
+        .iter()
+        .flat_map(|feature| feature.extensions(api_version).unwrap_or_default())
+        .map(|&extension| extension.to_owned());
+    for extension in optional_extensions.chain(feature_extensions) {
+        if !enabled_extensions.contains(&extension) {
+            enabled_extensions.push(extension);
+        }
+    }
+
+    let rank = requirements.gpu_preference.rank(info.device_type);
+    let optional = enabled_features.len() + enabled_extensions.len()
+        - requirements.features.len()
+        - requirements.extensions.len();
+    Some((
+        (rank, optional),
+        DeviceSelection {
+            physical_device,
+            queue_family_index,
+            enabled_features,
+            enabled_extensions,
+            api_version,
+        },
+    ))
+}
+
+/// the highest sample count of `supported` not above `requested`, e.g. with
+/// `vk::PhysicalDeviceLimits::framebuffer_color_sample_counts` to create MSAA render targets on
+/// devices which support fewer samples.
+pub fn clamp_sample_count(
+    requested: vk::SampleCountFlags,
+    supported: vk::SampleCountFlags,
+) -> vk::SampleCountFlags {
+    [
+        vk::SampleCountFlags::TYPE_64,
+        vk::SampleCountFlags::TYPE_32,
+        vk::SampleCountFlags::TYPE_16,
+        vk::SampleCountFlags::TYPE_8,
+        vk::SampleCountFlags::TYPE_4,
+        vk::SampleCountFlags::TYPE_2,
+    ]
+    .into_iter()
+    .find(|&samples| samples.as_raw() <= requested.as_raw() && supported.contains(samples))
+    .unwrap_or(vk::SampleCountFlags::TYPE_1)
+}
+
+// `major.minor` of a Vulkan version.
+fn version_string(api_version: u32) -> String {
+    format!(
+        "{}.{}",
+        vk::api_version_major(api_version),
+        vk::api_version_minor(api_version)
+    )
+}
+
+// features of `physical_device` whose extensions are supported.
+fn supported_features(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    api_version: u32,
+    supports_extension: &dyn Fn(&CStr) -> bool,
+) -> Vec<DeviceFeature> {
+    let available = DeviceFeature::ALL
+        .into_iter()
+        .filter(|feature| {
+            feature.extensions(api_version).is_some_and(|extensions| {
+                extensions
+                    .iter()
+                    .all(|&extension| supports_extension(extension))
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut extension_features = ExtensionFeatures::default();
+    let features = if api_version >= vk::API_VERSION_1_1 {
+        let mut features2 =
+            extension_features.chain(vk::PhysicalDeviceFeatures::default(), &available);
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features2) };
+        features2.features
+    } else {
+        unsafe { instance.get_physical_device_features(physical_device) }
+    };
+    available
+        .into_iter()
+        .filter(|&feature| extension_features.supports(&features, feature))
+        .collect()
+}
+
+// the feature structs of the features promoted to core after Vulkan 1.0.
+#[derive(Default)]
+struct ExtensionFeatures {
+    dynamic_rendering: vk::PhysicalDeviceDynamicRenderingFeatures<'static>,
+    synchronization2: vk::PhysicalDeviceSynchronization2Features<'static>,
+    timeline_semaphore: vk::PhysicalDeviceTimelineSemaphoreFeatures<'static>,
+    buffer_device_address: vk::PhysicalDeviceBufferDeviceAddressFeatures<'static>,
+    descriptor_indexing: vk::PhysicalDeviceDescriptorIndexingFeatures<'static>,
+}
+impl ExtensionFeatures {
+    // `features` with the structs of `chained` in its chain.
+    fn chain<'a>(
+        &'a mut self,
+        features: vk::PhysicalDeviceFeatures,
+        chained: &[DeviceFeature],
+    ) -> vk::PhysicalDeviceFeatures2<'a> {
+        let mut features2 = vk::PhysicalDeviceFeatures2::default().features(features);
+        if chained.contains(&DeviceFeature::DynamicRendering) {
+            features2 = features2.push_next(&mut self.dynamic_rendering);
+        }
+        if chained.contains(&DeviceFeature::Synchronization2) {
+            features2 = features2.push_next(&mut self.synchronization2);
+        }
+        if chained.contains(&DeviceFeature::TimelineSemaphore) {
+            features2 = features2.push_next(&mut self.timeline_semaphore);
+        }
+        if chained.contains(&DeviceFeature::BufferDeviceAddress) {
+            features2 = features2.push_next(&mut self.buffer_device_address);
+        }
+        if chained.contains(&DeviceFeature::DescriptorIndexing) {
+            features2 = features2.push_next(&mut self.descriptor_indexing);
+        }
+        features2
+    }
+
+    fn supports(&self, features: &vk::PhysicalDeviceFeatures, feature: DeviceFeature) -> bool {
+        let indexing = &self.descriptor_indexing;
+        let bits = match feature {
+            DeviceFeature::SamplerAnisotropy => vec![features.sampler_anisotropy],
+            DeviceFeature::FillModeNonSolid => vec![features.fill_mode_non_solid],
+            DeviceFeature::WideLines => vec![features.wide_lines],
+            DeviceFeature::DynamicRendering => vec![self.dynamic_rendering.dynamic_rendering],
+            DeviceFeature::Synchronization2 => vec![self.synchronization2.synchronization2],
+            DeviceFeature::TimelineSemaphore => vec![self.timeline_semaphore.timeline_semaphore],
+            DeviceFeature::BufferDeviceAddress => {
+                vec![self.buffer_device_address.buffer_device_address]
+            }
+            DeviceFeature::DescriptorIndexing => vec![
+                indexing.runtime_descriptor_array,
+                indexing.descriptor_binding_partially_bound,
+                indexing.descriptor_binding_variable_descriptor_count,
+                indexing.descriptor_binding_sampled_image_update_after_bind,
+                indexing.shader_sampled_image_array_non_uniform_indexing,
+            ],
+        };
+        bits.into_iter().all(|bit| bit == vk::TRUE)
+    }
+
+    fn enable(&mut self, features: &mut vk::PhysicalDeviceFeatures, feature: DeviceFeature) {
+        match feature {
+            DeviceFeature::SamplerAnisotropy => features.sampler_anisotropy = vk::TRUE,
+            DeviceFeature::FillModeNonSolid => features.fill_mode_non_solid = vk::TRUE,
+            DeviceFeature::WideLines => features.wide_lines = vk::TRUE,
+            DeviceFeature::DynamicRendering => {
+                self.dynamic_rendering.dynamic_rendering = vk::TRUE;
+            }
+            DeviceFeature::Synchronization2 => {
+                self.synchronization2.synchronization2 = vk::TRUE;
+            }
+            DeviceFeature::TimelineSemaphore => {
+                self.timeline_semaphore.timeline_semaphore = vk::TRUE;
+            }
+            DeviceFeature::BufferDeviceAddress => {
+                self.buffer_device_address.buffer_device_address = vk::TRUE;
+            }
+            DeviceFeature::DescriptorIndexing => {
+                let indexing = &mut self.descriptor_indexing;
+                indexing.runtime_descriptor_array = vk::TRUE;
+                indexing.descriptor_binding_partially_bound = vk::TRUE;
+                indexing.descriptor_binding_variable_descriptor_count = vk::TRUE;
+                indexing.descriptor_binding_sampled_image_update_after_bind = vk::TRUE;
+                indexing.shader_sampled_image_array_non_uniform_indexing = vk::TRUE;
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+//! windows with their own egui context, for multi-document apps.
+//!
+//! The viewports of [`crate::run`] share one `egui::Context`, so they share its memory: styles,
+//! open panels, focus, scroll positions. A document opened with [`Documents::open`] gets a window
+//! with a context of its own instead, its ui is built by [`App::document_ui`]. The viewports a
+//! document shows are embedded in its window.
+//!
+//! Each document renders with its own renderer, register the user textures it draws in the
+//! [`ImageRegistry`] of the document.
+
+use ash::vk;
+use egui_winit::winit::{
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::{Window, WindowId},
+};
+use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    integration::IntegrationEvent,
+    raw::{RawIntegration, RawOptions, RawWindow},
+    renderer::{ImageRegistry, ImageRegistryReceiver},
+    Allocator, App, AshRenderState, FrameInfo,
+};
+
+/// id of a document opened with [`Documents::open`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocumentId(u64);
+
+struct Document {
+    context: egui::Context,
+    image_registry: ImageRegistry,
+}
+
+struct PendingDocument {
+    id: DocumentId,
+    viewport_builder: egui::ViewportBuilder,
+    image_registry: ImageRegistry,
+    receiver: ImageRegistryReceiver,
+    repaint_at: Arc<Mutex<Option<Instant>>>,
+}
+
+#[derive(Default)]
+struct DocumentsInner {
+    next_id: u64,
+    documents: HashMap<DocumentId, Document>,
+    // documents whose window is created on the next iteration of the event loop.
+    pending: Vec<PendingDocument>,
+    closing: Vec<DocumentId>,
+}
+
+/// opens and closes the documents of the app, see the [module docs](self).
+#[derive(Clone)]
+pub struct Documents {
+    inner: Arc<Mutex<DocumentsInner>>,
+    event_loop_proxy: EventLoopProxy<IntegrationEvent>,
+}
+impl Documents {
+    pub(crate) fn new(event_loop_proxy: EventLoopProxy<IntegrationEvent>) -> Self {
+        Self {
+            inner: Arc::default(),
+            event_loop_proxy,
+        }
+    }
+
+    /// open a window for a new document with the title, size etc. of `viewport_builder`.
+    ///
+    /// The context of the document exists right away, e.g. to set its fonts and style with
+    /// [`Documents::context`]. The window is created on the next iteration of the event loop.
+    pub fn open(&self, viewport_builder: egui::ViewportBuilder) -> DocumentId {
+        let mut inner = self.inner.lock().unwrap();
+        let id = DocumentId(inner.next_id);
+        inner.next_id += 1;
+
+        let context = egui::Context::default();
+        let repaint_at = Arc::new(Mutex::new(Some(Instant::now())));
+        context.set_request_repaint_callback({
+            let repaint_at = repaint_at.clone();
+            let event_loop_proxy = self.event_loop_proxy.clone();
+            move |info| {
+                let deadline = Instant::now() + info.delay;
+                let mut repaint_at = repaint_at.lock().unwrap();
+                *repaint_at = Some(repaint_at.map_or(deadline, |at| at.min(deadline)));
+                drop(repaint_at);
+                let _ = event_loop_proxy.send_event(IntegrationEvent::RequestRepaint);
+            }
+        });
+        let (image_registry, receiver) = ImageRegistry::new();
+        inner.documents.insert(
+            id,
+            Document {
+                context,
+                image_registry: image_registry.clone(),
+            },
+        );
+        inner.pending.push(PendingDocument {
+            id,
+            viewport_builder,
+            image_registry,
+            receiver,
+            repaint_at,
+        });
+        drop(inner);
+        let _ = self
+            .event_loop_proxy
+            .send_event(IntegrationEvent::RequestRepaint);
+        id
+    }
+
+    /// close the window of a document and drop its context.
+    pub fn close(&self, id: DocumentId) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.documents.remove(&id).is_some() {
+            inner.closing.push(id);
+            drop(inner);
+            let _ = self
+                .event_loop_proxy
+                .send_event(IntegrationEvent::RequestRepaint);
+        }
+    }
+
+    /// the context of a document, `None` once it is closed.
+    pub fn context(&self, id: DocumentId) -> Option<egui::Context> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .documents
+            .get(&id)
+            .map(|document| document.context.clone())
+    }
+
+    /// the image registry for the user textures of a document, `None` once it is closed.
+    pub fn image_registry(&self, id: DocumentId) -> Option<ImageRegistry> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .documents
+            .get(&id)
+            .map(|document| document.image_registry.clone())
+    }
+
+    /// the open documents, in the order they were opened.
+    pub fn ids(&self) -> Vec<DocumentId> {
+        let inner = self.inner.lock().unwrap();
+        let mut ids = inner.documents.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+}
+
+// runs the app as the ui of one document.
+struct DocumentApp<'a, T: App> {
+    app: &'a mut T,
+    id: DocumentId,
+}
+impl<T: App> App for DocumentApp<'_, T> {
+    fn ui(&mut self, ctx: &egui::Context, frame: &FrameInfo) {
+        self.app.document_ui(self.id, ctx, frame);
+    }
+}
+
+struct DocumentWindow<A: Allocator + 'static> {
+    id: DocumentId,
+    window: Window,
+    integration: RawIntegration<A>,
+    state: egui_winit::State,
+    info: egui::ViewportInfo,
+    repaint_at: Arc<Mutex<Option<Instant>>>,
+}
+
+/// the windows of the documents, driven by the event loop of [`crate::run`].
+pub(crate) struct DocumentWindows<A: Allocator + 'static> {
+    documents: Documents,
+    render_state: AshRenderState<A>,
+    present_mode: vk::PresentModeKHR,
+    max_swapchain_images: Option<u32>,
+    windows: HashMap<WindowId, DocumentWindow<A>>,
+}
+impl<A: Allocator + 'static> DocumentWindows<A> {
+    pub(crate) fn new(
+        documents: Documents,
+        render_state: AshRenderState<A>,
+        present_mode: vk::PresentModeKHR,
+        max_swapchain_images: Option<u32>,
+    ) -> Self {
+        Self {
+            documents,
+            render_state,
+            present_mode,
+            max_swapchain_images,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// create the windows of the opened documents and destroy the ones of the closed documents.
+    pub(crate) fn update(&mut self, event_loop: &ActiveEventLoop, app: &mut impl App) {
+        let (pending, closing) = {
+            let mut inner = self.documents.inner.lock().unwrap();
+            (
+                std::mem::take(&mut inner.pending),
+                std::mem::take(&mut inner.closing),
+            )
+        };
+        for id in closing {
+            let window_id = self
+                .windows
+                .iter()
+                .find_map(|(window_id, window)| (window.id == id).then_some(*window_id));
+            if let Some(window) = window_id.and_then(|window_id| self.windows.remove(&window_id)) {
+                window.integration.destroy();
+            }
+            app.on_document_closed(id);
+        }
+        for pending in pending {
+            let Some(context) = self.documents.context(pending.id) else {
+                // closed before its window was created.
+                continue;
+            };
+            match self.create_window(event_loop, context, pending) {
+                Ok(window) => {
+                    self.windows.insert(window.window.id(), window);
+                }
+                Err(err) => error!("Failed to create the window of a document: {err}"),
+            }
+        }
+    }
+
+    fn create_window(
+        &self,
+        event_loop: &ActiveEventLoop,
+        context: egui::Context,
+        pending: PendingDocument,
+    ) -> anyhow::Result<DocumentWindow<A>> {
+        let window = egui_winit::create_window(&context, event_loop, &pending.viewport_builder)?;
+        egui_winit::apply_viewport_builder_to_window(&context, &window, &pending.viewport_builder);
+        let native_pixels_per_point = window.scale_factor() as f32;
+        let mut options = RawOptions::new(self.present_mode);
+        options.presenter.max_swapchain_images = self.max_swapchain_images;
+        // SAFETY: the window is dropped after the integration is destroyed.
+        let mut integration = unsafe {
+            RawIntegration::from_parts(
+                context.clone(),
+                RawWindow {
+                    display_handle: window.display_handle()?.as_raw(),
+                    window_handle: window.window_handle()?.as_raw(),
+                },
+                window.inner_size(),
+                native_pixels_per_point,
+                self.render_state.clone(),
+                (pending.image_registry, pending.receiver),
+                options,
+            )
+        };
+        let max_texture_side = integration.egui_input_mut().max_texture_side;
+        let state = egui_winit::State::new(
+            context,
+            egui::ViewportId::ROOT,
+            &window,
+            Some(native_pixels_per_point),
+            window.theme(),
+            max_texture_side,
+        );
+        Ok(DocumentWindow {
+            id: pending.id,
+            window,
+            integration,
+            state,
+            info: egui::ViewportInfo::default(),
+            repaint_at: pending.repaint_at,
+        })
+    }
+
+    /// handle an event of a document window. Returns `false` if the window is not a document's.
+    pub(crate) fn handle_window_event(&mut self, window_id: WindowId, event: &WindowEvent) -> bool {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return false;
+        };
+        match event {
+            WindowEvent::CloseRequested => {
+                self.documents.close(window.id);
+                return true;
+            }
+            WindowEvent::Resized(size) => {
+                window
+                    .integration
+                    .resize(*size, window.window.scale_factor() as f32);
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                let size = window.window.inner_size();
+                window.integration.resize(size, *scale_factor as f32);
+            }
+            _ => {}
+        }
+        let response = window.state.on_window_event(&window.window, event);
+        if response.repaint || matches!(event, WindowEvent::RedrawRequested) {
+            *window.repaint_at.lock().unwrap() = Some(Instant::now());
+        }
+        true
+    }
+
+    /// paint the documents egui repaints. Returns when the next document is due.
+    pub(crate) fn paint(&mut self, app: &mut impl App) -> Option<Instant> {
+        let mut next_repaint = None::<Instant>;
+        for window in self.windows.values_mut() {
+            let now = Instant::now();
+            let due = window.repaint_at.lock().unwrap().filter(|at| *at <= now);
+            if due.is_some() {
+                *window.repaint_at.lock().unwrap() = None;
+                let raw_input = window.state.take_egui_input(&window.window);
+                let input = window.integration.egui_input_mut();
+                let events = std::mem::take(&mut input.events);
+                *input = raw_input;
+                input.events.splice(0..0, events);
+                let mut output = window.integration.run(&mut DocumentApp {
+                    app: &mut *app,
+                    id: window.id,
+                });
+                window.state.handle_platform_output(
+                    &window.window,
+                    std::mem::take(&mut output.platform_output),
+                );
+                let context = window.integration.context().clone();
+                if output
+                    .viewport_commands
+                    .contains(&egui::ViewportCommand::Close)
+                {
+                    self.documents.close(window.id);
+                }
+                let mut _actions = Vec::new();
+                egui_winit::process_viewport_commands(
+                    &context,
+                    &mut window.info,
+                    output.viewport_commands,
+                    &window.window,
+                    &mut _actions,
+                );
+                if let Some(deadline) = now.checked_add(output.repaint_delay) {
+                    let mut repaint_at = window.repaint_at.lock().unwrap();
+                    *repaint_at = Some(repaint_at.map_or(deadline, |at| at.min(deadline)));
+                }
+            }
+            if let Some(at) = *window.repaint_at.lock().unwrap() {
+                next_repaint = Some(next_repaint.map_or(at, |next| next.min(at)));
+            }
+        }
+        next_repaint
+    }
+
+    /// destroy the integrations and windows of all documents.
+    pub(crate) fn destroy(&mut self) {
+        for (_, window) in self.windows.drain() {
+            window.integration.destroy();
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! native drag source for dragging files or text out of the app.
+//!
+//! Only available on Windows and macOS. On other platforms the `drag` feature has no effect
+//! and [`crate::CreationContext::drag_source`] does not exist.
+
+use egui_winit::winit;
+use raw_window_handle::{HandleError, HasWindowHandle, RawWindowHandle, WindowHandle};
+use std::{path::PathBuf, sync::mpsc};
+
+/// payload of a native drag operation.
+#[derive(Debug, Clone)]
+pub enum DragPayload {
+    /// absolute paths of files to drag.
+    Files(Vec<PathBuf>),
+    /// plain text to drag. (macOS only)
+    Text(String),
+}
+
+/// result of a native drag operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragResult {
+    Dropped,
+    Cancelled,
+}
+
+pub(crate) struct DragRequest {
+    payload: DragPayload,
+    preview_image: Vec<u8>,
+}
+
+pub(crate) type DragRequestReceiver = mpsc::Receiver<DragRequest>;
+
+/// native drag source for dragging data out of the app.
+///
+/// The drag starts from the focused window after the current frame.
+/// The result is delivered to [`crate::App::handle_event`] as [`crate::event::Event::DragFinished`].
+#[derive(Debug, Clone)]
+pub struct DragSource {
+    tx: mpsc::Sender<DragRequest>,
+}
+impl DragSource {
+    pub(crate) fn new() -> (Self, DragRequestReceiver) {
+        let (tx, rx) = mpsc::channel();
+        (Self { tx }, rx)
+    }
+
+    /// start a native drag operation.
+    ///
+    /// `preview_image` is an encoded image (png, etc.) shown under the cursor while dragging.
+    pub fn start_drag(&self, payload: DragPayload, preview_image: Vec<u8>) {
+        let _ = self.tx.send(DragRequest {
+            payload,
+            preview_image,
+        });
+    }
+}
+
+// the window handle is copied so that no lock is held while the platform drag loop runs.
+struct DragWindow(RawWindowHandle);
+impl HasWindowHandle for DragWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: the window is alive until the drag operation ends.
+        Ok(unsafe { WindowHandle::borrow_raw(self.0) })
+    }
+}
+
+pub(crate) fn window_handle(window: &winit::window::Window) -> Option<RawWindowHandle> {
+    window.window_handle().ok().map(|handle| handle.as_raw())
+}
+
+pub(crate) fn start_drag(
+    window_handle: RawWindowHandle,
+    request: DragRequest,
+    on_finished: impl Fn(DragResult) + Send + 'static,
+) -> anyhow::Result<()> {
+    let item = match request.payload {
+        DragPayload::Files(paths) => drag::DragItem::Files(paths),
+        DragPayload::Text(text) => {
+            if cfg!(target_os = "windows") {
+                anyhow::bail!("Dragging text is not supported on this platform");
+            }
+            const PLAIN_TEXT: &str = "public.utf8-plain-text";
+            drag::DragItem::Data {
+                provider: Box::new(move |ty| (ty == PLAIN_TEXT).then(|| text.as_bytes().to_vec())),
+                types: vec![PLAIN_TEXT.to_owned()],
+            }
+        }
+    };
+    drag::start_drag(
+        &DragWindow(window_handle),
+        item,
+        drag::Image::Raw(request.preview_image),
+        move |result, _cursor_position| {
+            on_finished(match result {
+                drag::DragResult::Dropped => DragResult::Dropped,
+                drag::DragResult::Cancel => DragResult::Cancelled,
+            })
+        },
+        drag::Options::default(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! egui-ash in a window embedded into a parent window of the host, e.g. the editor of a VST3 or CLAP
+//! plugin, which gets the HWND, NSView or X11 window of the host instead of creating its own window.
+//!
+//! [`EmbeddedWindow`] creates a child window of the parent and renders into it with
+//! [`RawIntegration`]. There is no event loop to run: call [`EmbeddedWindow::idle`] from the timer
+//! or idle callback of the host, which handles the pending window events and paints when egui
+//! repaints. The host resizes the editor with [`EmbeddedWindow::set_size`] and moves it to another
+//! parent with [`EmbeddedWindow::reparent`].
+//!
+//! The child windows of a thread share one winit event loop, which is pumped without blocking, so
+//! several editors can be open at the same time.
+
+use anyhow::Result;
+use ash::vk;
+use egui_winit::winit::{
+    self,
+    application::ApplicationHandler,
+    event::{StartCause, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    platform::pump_events::EventLoopExtPumpEvents,
+    window::{Window, WindowAttributes, WindowId},
+};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CString,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    raw::{self, RawIntegration, RawOutput},
+    Allocator, App, AshRenderState,
+};
+
+struct SharedEventLoop {
+    event_loop: EventLoop<()>,
+    // events of all child windows, taken by the window they belong to.
+    events: HashMap<WindowId, Vec<WindowEvent>>,
+}
+
+thread_local! {
+    static SHARED: RefCell<Option<SharedEventLoop>> = const { RefCell::new(None) };
+}
+
+struct Pump<'a> {
+    attributes: Option<WindowAttributes>,
+    window: Option<Result<Window, winit::error::OsError>>,
+    events: &'a mut HashMap<WindowId, Vec<WindowEvent>>,
+}
+impl Pump<'_> {
+    fn create_window(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(attributes) = self.attributes.take() {
+            self.window = Some(event_loop.create_window(attributes));
+        }
+    }
+}
+impl ApplicationHandler for Pump<'_> {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, _cause: StartCause) {
+        self.create_window(event_loop);
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.create_window(event_loop);
+    }
+
+    fn window_event(&mut self, _: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+        self.events.entry(window_id).or_default().push(event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.create_window(event_loop);
+    }
+}
+
+// the event loop of the thread, created on first use.
+fn with_shared<R>(f: impl FnOnce(&mut SharedEventLoop) -> Result<R>) -> Result<R> {
+    SHARED.with_borrow_mut(|shared| {
+        if shared.is_none() {
+            *shared = Some(SharedEventLoop {
+                event_loop: EventLoop::new()?,
+                events: HashMap::new(),
+            });
+        }
+        f(shared.as_mut().unwrap())
+    })
+}
+
+// pump the event loop of the thread once, creating a window with `attributes`.
+fn pump(attributes: Option<WindowAttributes>) -> Result<Option<Window>> {
+    with_shared(|shared| {
+        let mut pump = Pump {
+            attributes,
+            window: None,
+            events: &mut shared.events,
+        };
+        let _ = shared
+            .event_loop
+            .pump_app_events(Some(Duration::ZERO), &mut pump);
+        Ok(pump.window.transpose()?)
+    })
+}
+
+fn take_events(window_id: WindowId) -> Vec<WindowEvent> {
+    SHARED.with_borrow_mut(|shared| {
+        shared
+            .as_mut()
+            .and_then(|shared| shared.events.remove(&window_id))
+            .unwrap_or_default()
+    })
+}
+
+/// get required instance extensions for the child windows of this thread.
+pub fn required_instance_extensions() -> Result<Vec<CString>> {
+    let display_handle = with_shared(|shared| Ok(shared.event_loop.display_handle()?.as_raw()))?;
+    Ok(raw::required_instance_extensions(display_handle))
+}
+
+fn create_child(parent: RawWindowHandle, size: winit::dpi::PhysicalSize<u32>) -> Result<Window> {
+    // SAFETY: the parent is valid, guaranteed by the callers of `open` and `reparent`.
+    let attributes = unsafe {
+        Window::default_attributes()
+            .with_inner_size(size)
+            .with_decorations(false)
+            .with_parent_window(Some(parent))
+    };
+    pump(Some(attributes))?
+        .ok_or_else(|| anyhow::anyhow!("The event loop did not create the child window"))
+}
+
+/// egui-ash in a child window of a window of the host, driven by the host.
+pub struct EmbeddedWindow<A: Allocator + 'static> {
+    window: Window,
+    integration: RawIntegration<A>,
+    state: egui_winit::State,
+    repaint_at: Option<Instant>,
+}
+impl<A: Allocator + 'static> EmbeddedWindow<A> {
+    /// create a child window of `parent` of `size` physical pixels and the integration rendering
+    /// into it. Create the instance of `render_state` with [`required_instance_extensions`].
+    ///
+    /// # Safety
+    ///
+    /// `parent` must stay valid until [`EmbeddedWindow::destroy`] or [`EmbeddedWindow::reparent`].
+    pub unsafe fn open(
+        context: egui::Context,
+        parent: RawWindowHandle,
+        size: winit::dpi::PhysicalSize<u32>,
+        render_state: AshRenderState<A>,
+        present_mode: vk::PresentModeKHR,
+    ) -> Result<Self> {
+        let window = create_child(parent, size)?;
+        let native_pixels_per_point = window.scale_factor() as f32;
+        // SAFETY: the window is dropped after the integration is destroyed.
+        let mut integration = unsafe {
+            RawIntegration::new(
+                context.clone(),
+                window.display_handle()?.as_raw(),
+                window.window_handle()?.as_raw(),
+                size,
+                native_pixels_per_point,
+                render_state,
+                present_mode,
+            )
+        };
+        let max_texture_side = integration.egui_input_mut().max_texture_side;
+        let state = egui_winit::State::new(
+            context,
+            egui::ViewportId::ROOT,
+            &window,
+            Some(native_pixels_per_point),
+            window.theme(),
+            max_texture_side,
+        );
+        Ok(Self {
+            window,
+            integration,
+            state,
+            repaint_at: Some(Instant::now()),
+        })
+    }
+
+    /// the integration, e.g. for the image registry or to push input events.
+    pub fn integration(&mut self) -> &mut RawIntegration<A> {
+        &mut self.integration
+    }
+
+    /// the child window
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// handle the pending window events and paint a frame if egui repaints. Call it from the timer or
+    /// idle callback of the host, e.g. 60 times per second.
+    ///
+    /// Returns the output of the frame, if one was painted. Its platform output was already applied
+    /// to the window (cursor, clipboard, IME and links).
+    pub fn idle(&mut self, app: &mut impl App) -> Result<Option<RawOutput>> {
+        profile_function!();
+        pump(None)?;
+        for event in take_events(self.window.id()) {
+            match &event {
+                WindowEvent::Resized(size) => {
+                    self.integration
+                        .resize(*size, self.window.scale_factor() as f32);
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    let size = self.window.inner_size();
+                    self.integration.resize(size, *scale_factor as f32);
+                }
+                _ => {}
+            }
+            let response = self.state.on_window_event(&self.window, &event);
+            if response.repaint {
+                self.repaint_at = Some(Instant::now());
+            }
+        }
+        if self.integration.context().has_requested_repaint() {
+            self.repaint_at = Some(Instant::now());
+        }
+        match self.repaint_at {
+            Some(repaint_at) if repaint_at <= Instant::now() => {}
+            _ => return Ok(None),
+        }
+
+        let mut raw_input = self.state.take_egui_input(&self.window);
+        let input = self.integration.egui_input_mut();
+        // events pushed by the host come first.
+        raw_input.events.splice(0..0, input.events.drain(..));
+        *input = raw_input;
+        let mut output = self.integration.run(app);
+        self.repaint_at = Instant::now().checked_add(output.repaint_delay);
+        self.state
+            .handle_platform_output(&self.window, std::mem::take(&mut output.platform_output));
+        Ok(Some(output))
+    }
+
+    /// resize the child window to `size` physical pixels, when the host resizes the editor.
+    pub fn set_size(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        let _ = self.window.request_inner_size(size);
+        self.integration
+            .resize(size, self.window.scale_factor() as f32);
+        self.repaint_at = Some(Instant::now());
+    }
+
+    /// move the editor into another parent window of the host.
+    ///
+    /// The child window is created anew under `parent`, egui keeps its state.
+    ///
+    /// # Safety
+    ///
+    /// `parent` must stay valid until [`EmbeddedWindow::destroy`] or the next `reparent`.
+    pub unsafe fn reparent(&mut self, parent: RawWindowHandle) -> Result<()> {
+        let window = create_child(parent, self.integration.size())?;
+        // SAFETY: the old window is dropped after the integration stops rendering into it.
+        unsafe {
+            self.integration.set_window(
+                window.display_handle()?.as_raw(),
+                window.window_handle()?.as_raw(),
+                window.inner_size(),
+            );
+        }
+        take_events(self.window.id());
+        self.window = window;
+        self.repaint_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// destroy the vulkan objects and the child window.
+    ///
+    /// Call this before destroying the device, e.g. when the host closes the editor.
+    pub fn destroy(self) {
+        self.integration.destroy();
+        take_events(self.window.id());
+    }
+}
@@ -0,0 +1,256 @@
+//! color emoji for egui, which draws the glyphs of its fonts in one color.
+//!
+//! The emoji are rasterized from a color emoji font (COLR, CBDT or sbix) into textures and drawn as images
+//! next to the text with [`emoji_label`]. Load the emoji font of the OS with [`install_system_font`] or
+//! another one with [`install_font`]. The textures are sRGB like all egui textures and are blended in
+//! gamma space.
+
+use anyhow::Result;
+use std::{collections::HashMap, sync::Arc};
+use swash::{
+    scale::{image::Content, Render, ScaleContext, Source, StrikeWith},
+    shape::ShapeContext,
+    zeno::Format,
+    FontRef,
+};
+
+const SYSTEM_FAMILIES: &[&str] = &[
+    "Segoe UI Emoji",
+    "Apple Color Emoji",
+    "Noto Color Emoji",
+    "Twemoji",
+];
+
+const VARIATION_SELECTOR: char = '\u{fe0f}';
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const KEYCAP: char = '\u{20e3}';
+
+#[derive(Clone)]
+struct EmojiFont {
+    data: Arc<[u8]>,
+    index: u32,
+}
+impl EmojiFont {
+    fn font_ref(&self) -> Option<FontRef<'_>> {
+        FontRef::from_index(&self.data, self.index as usize)
+    }
+}
+
+// emoji textures by emoji and size in pixels, `None` if the font has no glyph for it.
+type EmojiTextures = HashMap<(String, u32), Option<egui::TextureHandle>>;
+
+fn font_id() -> egui::Id {
+    egui::Id::new("egui_ash_emoji_font")
+}
+
+fn textures_id() -> egui::Id {
+    egui::Id::new("egui_ash_emoji_textures")
+}
+
+/// use the color emoji font of the OS, whether one was found.
+pub fn install_system_font(ctx: &egui::Context) -> bool {
+    profile_function!();
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    let font = SYSTEM_FAMILIES.iter().find_map(|&family| {
+        let id = db.query(&fontdb::Query {
+            families: &[fontdb::Family::Name(family)],
+            ..Default::default()
+        })?;
+        db.with_face_data(id, |data, index| EmojiFont {
+            data: data.into(),
+            index,
+        })
+    });
+    let Some(font) = font else {
+        warn!("No color emoji font of {SYSTEM_FAMILIES:?} found");
+        return false;
+    };
+    set_font(ctx, font);
+    true
+}
+
+/// use the color emoji font of a TTF or OTF file, `index` of the face in a font collection.
+pub fn install_font(ctx: &egui::Context, bytes: Vec<u8>, index: u32) -> Result<()> {
+    let font = EmojiFont {
+        data: bytes.into(),
+        index,
+    };
+    if font.font_ref().is_none() {
+        anyhow::bail!("Failed to parse the emoji font");
+    }
+    set_font(ctx, font);
+    Ok(())
+}
+
+fn set_font(ctx: &egui::Context, font: EmojiFont) {
+    ctx.data_mut(|data| {
+        data.insert_temp(font_id(), font);
+        data.remove_temp::<EmojiTextures>(textures_id());
+    });
+    ctx.request_repaint();
+}
+
+/// image of `emoji`, e.g. "👍" or "👩‍🚀", `size` points high. `None` if no emoji font is installed or it has
+/// no glyph for `emoji`.
+pub fn image(ctx: &egui::Context, emoji: &str, size: f32) -> Option<egui::Image<'static>> {
+    let pixels = (size * ctx.pixels_per_point()).round().max(1.0) as u32;
+    let key = (emoji.to_owned(), pixels);
+    let cached = ctx.data(|data| {
+        data.get_temp::<EmojiTextures>(textures_id())
+            .and_then(|textures| textures.get(&key).cloned())
+    });
+    let texture = match cached {
+        Some(texture) => texture,
+        None => {
+            let font = ctx.data(|data| data.get_temp::<EmojiFont>(font_id()))?;
+            let texture = rasterize(&font, emoji, pixels).map(|image| {
+                ctx.load_texture(
+                    format!("egui_ash_emoji_{emoji}_{pixels}"),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                )
+            });
+            ctx.data_mut(|data| {
+                data.get_temp_mut_or_default::<EmojiTextures>(textures_id())
+                    .insert(key, texture.clone());
+            });
+            texture
+        }
+    }?;
+    let aspect = texture.aspect_ratio();
+    Some(
+        egui::Image::new(egui::load::SizedTexture::from_handle(&texture))
+            .fit_to_exact_size(egui::vec2(size * aspect, size)),
+    )
+}
+
+/// a label drawing the emoji of `text` in color, in the body text style.
+pub fn emoji_label(ui: &mut egui::Ui, text: &str) -> egui::Response {
+    let size = ui.text_style_height(&egui::TextStyle::Body);
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for (is_emoji, segment) in segments(text) {
+            match is_emoji.then(|| image(ui.ctx(), segment, size)).flatten() {
+                Some(image) => ui.add(image),
+                None => ui.label(segment),
+            };
+        }
+    })
+    .response
+}
+
+// the emoji outside of the emoji blocks are listed, so that arrows and symbols in text stay text.
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1f000..=0x1faff | 0x2600..=0x27bf | 0x2194..=0x2199 | 0x21a9 | 0x21aa | 0x231a | 0x231b
+        | 0x2328 | 0x23cf | 0x23e9..=0x23f3 | 0x23f8..=0x23fa | 0x2b05..=0x2b07 | 0x2b1b | 0x2b1c
+        | 0x2b50 | 0x2b55 | 0x3030 | 0x303d | 0x3297 | 0x3299 | 0x203c | 0x2049)
+}
+
+// continues the emoji sequence before it: skin tones, tags, keycaps and variation selectors.
+fn is_modifier(c: char) -> bool {
+    matches!(c as u32, 0x1f3fb..=0x1f3ff | 0xe0020..=0xe007f)
+        || c == VARIATION_SELECTOR
+        || c == KEYCAP
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1f1e6..=0x1f1ff)
+}
+
+// `text` split into text and emoji sequences, `true` for the emoji.
+fn segments(text: &str) -> Vec<(bool, &str)> {
+    let mut segments = vec![];
+    let mut chars = text.char_indices().peekable();
+    let mut text_start = 0;
+    while let Some((start, c)) = chars.next() {
+        if !is_emoji(c) {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        // a flag is a pair of regional indicators
+        if is_regional_indicator(c) {
+            if let Some(&(i, next)) = chars
+                .peek()
+                .filter(|(_, next)| is_regional_indicator(*next))
+            {
+                end = i + next.len_utf8();
+                chars.next();
+            }
+        }
+        loop {
+            match chars.peek().copied() {
+                Some((i, next)) if is_modifier(next) => {
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                Some((_, ZERO_WIDTH_JOINER)) => {
+                    chars.next();
+                    match chars.next() {
+                        Some((i, next)) => end = i + next.len_utf8(),
+                        None => end = text.len(),
+                    }
+                }
+                _ => break,
+            }
+        }
+        if text_start < start {
+            segments.push((false, &text[text_start..start]));
+        }
+        segments.push((true, &text[start..end]));
+        text_start = end;
+    }
+    if text_start < text.len() {
+        segments.push((false, &text[text_start..]));
+    }
+    segments
+}
+
+// the emoji shaped into one glyph, e.g. the ligature of a ZWJ sequence, and rasterized in color.
+fn rasterize(font: &EmojiFont, emoji: &str, pixels: u32) -> Option<egui::ColorImage> {
+    let font_ref = font.font_ref()?;
+    let mut glyph_ids = vec![];
+    let mut shape_context = ShapeContext::new();
+    let mut shaper = shape_context.builder(font_ref).size(pixels as f32).build();
+    shaper.add_str(emoji);
+    shaper.shape_with(|cluster| glyph_ids.extend(cluster.glyphs.iter().map(|glyph| glyph.id)));
+    // sequences the font has no ligature for fall back to their first emoji.
+    let glyph_id = match glyph_ids.as_slice() {
+        [glyph_id] => *glyph_id,
+        _ => font_ref.charmap().map(emoji.chars().next()?),
+    };
+    if glyph_id == 0 {
+        return None;
+    }
+
+    let mut scale_context = ScaleContext::new();
+    let mut scaler = scale_context.builder(font_ref).size(pixels as f32).build();
+    let image = Render::new(&[
+        Source::ColorOutline(0),
+        Source::ColorBitmap(StrikeWith::BestFit),
+        Source::Outline,
+    ])
+    .format(Format::Alpha)
+    .render(&mut scaler, glyph_id)?;
+    let size = [
+        image.placement.width as usize,
+        image.placement.height as usize,
+    ];
+    if size[0] == 0 || size[1] == 0 {
+        return None;
+    }
+    match image.content {
+        Content::Color => Some(egui::ColorImage::from_rgba_unmultiplied(size, &image.data)),
+        // emoji without a color glyph are drawn white.
+        Content::Mask => Some(egui::ColorImage::new(
+            size,
+            image
+                .data
+                .iter()
+                .map(|&alpha| egui::Color32::from_white_alpha(alpha))
+                .collect(),
+        )),
+        Content::SubpixelMask => None,
+    }
+}
@@ -9,6 +9,24 @@ pub enum AppEvent {
     AboutToWait,
     LoopExiting,
     MemoryWarning,
+    /// the OS asked the app to open these files, e.g. when an associated document is double-clicked.
+    /// (macOS, `open-files` feature)
+    OpenFiles(Vec<std::path::PathBuf>),
+    /// the system switched between light and dark mode. Sent once, not per window.
+    ThemeChanged(egui::Theme),
+    /// a monitor was connected, disconnected or changed its mode. Windows left off-screen were
+    /// moved back onto a monitor. (winit backend only)
+    MonitorsChanged,
+    /// the app was launched again with [`crate::RunOption::single_instance`], e.g. to open a file.
+    /// `args` are the arguments of the launch without the program, relative paths are relative to
+    /// `cwd`. The second launch exited.
+    SecondInstance {
+        args: Vec<String>,
+        cwd: std::path::PathBuf,
+    },
+    /// the power source or the power saver mode of the system changed. Also sent once at startup.
+    #[cfg(feature = "power")]
+    PowerChanged(crate::power::PowerStatus),
 }
 
 pub enum Event<'a> {
@@ -23,10 +41,164 @@ pub enum Event<'a> {
     AppEvent {
         event: AppEvent,
     },
+    /// touch and pen input, sent even when egui consumed the event so that stroke pressure is not lost.
+    ///
+    /// On Windows a pen hovering above the surface sends `TouchPhase::Moved` with [`PenInfo::hovering`]
+    /// before it touches it. On other platforms pen hover arrives as `WindowEvent::CursorMoved` and
+    /// barrel buttons as `WindowEvent::MouseInput`.
+    TouchInput {
+        viewport_id: egui::ViewportId,
+        device_id: winit::event::DeviceId,
+        id: u64,
+        phase: winit::event::TouchPhase,
+        location: winit::dpi::PhysicalPosition<f64>,
+        /// normalized pressure in `0.0..=1.0`, if reported by the platform.
+        pressure: Option<f32>,
+        /// pen altitude angle in radians, if reported by the platform (iOS, Windows). `PI / 2` is perpendicular to the surface.
+        altitude_angle: Option<f64>,
+        /// tool, tilt, buttons and hover state of a pen, `None` for fingers. (Windows only)
+        pen: Option<PenInfo>,
+        /// time the event was received.
+        time: std::time::Instant,
+    },
+    /// every cursor move with the time it was received, sent even when egui consumed the event
+    /// or [`crate::PointerMovePolicy::CoalescePerFrame`] is set.
+    PointerMoved {
+        viewport_id: egui::ViewportId,
+        device_id: winit::event::DeviceId,
+        position: winit::dpi::PhysicalPosition<f64>,
+        time: std::time::Instant,
+    },
+    /// keyboard input with the physical key position, sent even when egui consumed the event.
+    ///
+    /// Use `physical_key` for layout-independent bindings such as WASD.
+    KeyInput {
+        viewport_id: egui::ViewportId,
+        device_id: winit::event::DeviceId,
+        physical_key: winit::keyboard::PhysicalKey,
+        logical_key: winit::keyboard::Key,
+        state: winit::event::ElementState,
+        repeat: bool,
+    },
     DeviceEvent {
         device_id: winit::event::DeviceId,
         event: winit::event::DeviceEvent,
     },
     #[cfg(feature = "accesskit")]
     AccessKitActionRequest(accesskit_winit::Event),
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    DragFinished {
+        result: crate::drag::DragResult,
+    },
+    /// a dialog of [`crate::file_dialog::FileDialogs`] was closed. `paths` is empty if it was
+    /// canceled.
+    #[cfg(feature = "file-dialog")]
+    FileDialogClosed {
+        id: crate::file_dialog::FileDialogId,
+        paths: Vec<std::path::PathBuf>,
+    },
+    /// raw gamepad input. D-pad, left stick and face buttons are also sent to egui as key events.
+    #[cfg(feature = "gamepad")]
+    GamepadEvent(gilrs::Event),
+    /// a registered global hotkey was pressed or released.
+    #[cfg(feature = "global-hotkey")]
+    GlobalHotkey {
+        id: u32,
+        state: crate::hotkey::HotKeyState,
+    },
+    /// a frame of the viewport did not finish on the GPU within [`crate::RunOption::gpu_hang_timeout`].
+    ///
+    /// egui-ash skips the frames of the viewport until the GPU catches up.
+    /// Recreate the device or exit if it does not recover.
+    GpuHang {
+        viewport_id: egui::ViewportId,
+    },
+    /// the shortcut of an accelerator registered in [`crate::Accelerators`] was pressed.
+    AcceleratorTriggered {
+        id: String,
+    },
+    /// the viewport was focused, minimized, occluded or the opposite, e.g. to pause a simulation
+    /// or audio while it can not be seen. (winit backend only)
+    VisibilityChanged {
+        viewport_id: egui::ViewportId,
+        visibility: ViewportVisibility,
+    },
+    /// a layout was restored by [`crate::layout::LayoutProfiles::restore`].
+    ///
+    /// Show the child viewports of `viewport_ids` that are not shown yet.
+    #[cfg(feature = "persistence")]
+    LayoutRestored {
+        name: String,
+        viewport_ids: Vec<egui::ViewportId>,
+    },
+    /// a native menu item was clicked or its shortcut was pressed.
+    #[cfg(feature = "menu")]
+    MenuActivated {
+        id: String,
+    },
+    /// a dialog of [`crate::message_dialog::MessageDialogs`] was closed with the button `result`.
+    #[cfg(feature = "message-dialog")]
+    MessageDialogClosed {
+        id: crate::message_dialog::MessageDialogId,
+        result: crate::message_dialog::MessageDialogResult,
+    },
+    #[cfg(feature = "sdl2")]
+    Sdl2Event(sdl2::event::Event),
+    /// the tray icon was clicked or hovered.
+    #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+    TrayIconEvent(crate::tray::TrayIconEvent),
+}
+
+/// focus and visibility of a viewport window, see [`Event::VisibilityChanged`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ViewportVisibility {
+    /// the window has the keyboard focus.
+    pub focused: bool,
+    /// the window is minimized.
+    pub minimized: bool,
+    /// the window is fully covered by other windows or on another workspace. Not every platform
+    /// reports it.
+    pub occluded: bool,
+}
+impl ViewportVisibility {
+    /// whether the window can be seen, i.e. it is neither minimized nor occluded.
+    pub fn is_visible(&self) -> bool {
+        !self.minimized && !self.occluded
+    }
+}
+
+/// the end of the pen touching the surface, see [`PenInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PenTool {
+    Pen,
+    /// the eraser end, or the tip with the eraser button held.
+    Eraser,
+}
+
+/// tool, tilt, buttons and hover state of a pen in [`Event::TouchInput`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenInfo {
+    pub tool: PenTool,
+    /// tilt in radians from the perpendicular along the x axis (positive to the right) and the y
+    /// axis (positive towards the user), if reported by the pen.
+    pub tilt: Option<[f32; 2]>,
+    /// clockwise rotation of the pen around its axis in radians, if reported by the pen.
+    pub twist: Option<f32>,
+    /// the barrel button is held.
+    pub barrel_button: bool,
+    /// the pen is in range above the surface without touching it, e.g. to draw a brush cursor.
+    pub hovering: bool,
+}
+impl PenInfo {
+    /// angle between the pen and the surface in radians, `PI / 2` is perpendicular.
+    pub fn altitude_angle(&self) -> Option<f32> {
+        let [x, y] = self.tilt?.map(f32::tan);
+        Some(x.hypot(y).recip().atan())
+    }
+
+    /// direction the pen leans towards in radians, clockwise from the x axis.
+    pub fn azimuth_angle(&self) -> Option<f32> {
+        let [x, y] = self.tilt?.map(f32::tan);
+        Some(y.atan2(x).rem_euclid(std::f32::consts::TAU))
+    }
 }
@@ -0,0 +1,113 @@
+//! native file dialogs that do not block the event loop.
+//!
+//! A blocking `rfd::FileDialog` opened in [`crate::App::ui`] stops the painting of all viewports
+//! until it closes. [`FileDialogs`] runs an `rfd::AsyncFileDialog` on a thread of its own instead,
+//! and delivers the chosen paths as [`crate::event::Event::FileDialogClosed`].
+
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{integration::IntegrationEvent, utils};
+
+pub use rfd::AsyncFileDialog;
+
+/// id of a dialog opened with [`FileDialogs`], to match [`crate::event::Event::FileDialogClosed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileDialogId(u64);
+
+type PathsFuture = Pin<Box<dyn Future<Output = Vec<PathBuf>> + Send>>;
+
+/// opens file dialogs without blocking the event loop.
+///
+/// Configure the dialog with [`AsyncFileDialog`], e.g. its title, filters and parent window. Each
+/// dialog is closed with [`crate::event::Event::FileDialogClosed`], with no paths when it was
+/// canceled.
+#[derive(Clone)]
+pub struct FileDialogs {
+    event_loop_proxy: EventLoopProxy<IntegrationEvent>,
+    next_id: Arc<AtomicU64>,
+}
+impl FileDialogs {
+    pub(crate) fn new(event_loop_proxy: EventLoopProxy<IntegrationEvent>) -> Self {
+        Self {
+            event_loop_proxy,
+            next_id: Arc::default(),
+        }
+    }
+
+    /// pick a file to open.
+    pub fn pick_file(&self, dialog: AsyncFileDialog) -> FileDialogId {
+        let picked = dialog.pick_file();
+        self.spawn(Box::pin(async move {
+            picked
+                .await
+                .into_iter()
+                .map(|file| file.path().to_owned())
+                .collect()
+        }))
+    }
+
+    /// pick several files to open.
+    pub fn pick_files(&self, dialog: AsyncFileDialog) -> FileDialogId {
+        let picked = dialog.pick_files();
+        self.spawn(Box::pin(async move {
+            picked
+                .await
+                .unwrap_or_default()
+                .iter()
+                .map(|file| file.path().to_owned())
+                .collect()
+        }))
+    }
+
+    /// pick a folder.
+    pub fn pick_folder(&self, dialog: AsyncFileDialog) -> FileDialogId {
+        let picked = dialog.pick_folder();
+        self.spawn(Box::pin(async move {
+            picked
+                .await
+                .into_iter()
+                .map(|file| file.path().to_owned())
+                .collect()
+        }))
+    }
+
+    /// pick the path to save a file to.
+    pub fn save_file(&self, dialog: AsyncFileDialog) -> FileDialogId {
+        let picked = dialog.save_file();
+        self.spawn(Box::pin(async move {
+            picked
+                .await
+                .into_iter()
+                .map(|file| file.path().to_owned())
+                .collect()
+        }))
+    }
+
+    // the dialogs are created on the calling thread, which macOS requires, and awaited on another.
+    fn spawn(&self, future: PathsFuture) -> FileDialogId {
+        let id = FileDialogId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        let spawned = std::thread::Builder::new()
+            .name("egui-ash file dialog".to_owned())
+            .spawn(move || {
+                let paths = utils::block_on(future);
+                let _ = event_loop_proxy.send_event(IntegrationEvent::FileDialogClosed(id, paths));
+            });
+        if let Err(err) = spawned {
+            error!("Failed to start the file dialog thread: {err}");
+            let _ = self
+                .event_loop_proxy
+                .send_event(IntegrationEvent::FileDialogClosed(id, Vec::new()));
+        }
+        id
+    }
+}
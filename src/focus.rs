@@ -0,0 +1,88 @@
+/// direction of Tab navigation across the edges of the egui surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabDirection {
+    /// Tab, to the next control.
+    Forward,
+    /// Shift+Tab, to the previous control.
+    Backward,
+}
+
+fn start_id() -> egui::Id {
+    egui::Id::new("egui_ash_focus_start")
+}
+
+fn end_id() -> egui::Id {
+    egui::Id::new("egui_ash_focus_end")
+}
+
+/// Tab navigation into and out of an egui surface embedded between native controls.
+///
+/// Two focusable widgets without a rect surround the ui of the app. egui moves the focus to them with
+/// Tab on the last widget of the app and Shift+Tab on the first one, where the focus leaves egui
+/// instead of wrapping around.
+#[derive(Debug, Default)]
+pub(crate) struct FocusBoundary {
+    pub(crate) enabled: bool,
+    entering: Option<TabDirection>,
+    entered: Option<TabDirection>,
+}
+impl FocusBoundary {
+    /// focus the first widget (`Forward`) or the last widget (`Backward`) in the next pass.
+    pub(crate) fn enter(&mut self, direction: TabDirection) {
+        self.entering = Some(direction);
+    }
+
+    /// before the ui of the app.
+    pub(crate) fn begin_pass(&mut self, ctx: &egui::Context) {
+        self.entered = self.entering.take();
+        if !self.enabled {
+            return;
+        }
+        ctx.memory_mut(|memory| {
+            // the sentinel passes the focus on to the first or last widget.
+            match self.entered {
+                Some(TabDirection::Forward) => {
+                    memory.request_focus(start_id());
+                    memory.move_focus(egui::FocusDirection::Next);
+                }
+                Some(TabDirection::Backward) => {
+                    memory.request_focus(end_id());
+                    memory.move_focus(egui::FocusDirection::Previous);
+                }
+                None => {}
+            }
+            memory.interested_in_focus(start_id(), egui::LayerId::background());
+        });
+    }
+
+    /// after the ui of the app, the direction in which the focus left egui.
+    pub(crate) fn end_pass(&mut self, ctx: &egui::Context) -> Option<TabDirection> {
+        if !self.enabled {
+            return None;
+        }
+        // Shift+Tab reaches the start sentinel in the next pass.
+        if self.entered.is_some() || ctx.input(|input| input.key_pressed(egui::Key::Tab)) {
+            ctx.request_repaint();
+        }
+        ctx.memory_mut(|memory| {
+            memory.interested_in_focus(end_id(), egui::LayerId::background());
+            let focused = memory.focused()?;
+            let exit = if focused == end_id() && self.entered != Some(TabDirection::Backward) {
+                TabDirection::Forward
+            } else if focused == start_id() && self.entered != Some(TabDirection::Forward) {
+                TabDirection::Backward
+            } else {
+                return None;
+            };
+            memory.surrender_focus(focused);
+            Some(exit)
+        })
+    }
+}
+
+/// rect of the focused widget in physical pixels, for OS magnifiers and on-screen keyboards.
+pub(crate) fn focused_rect(ctx: &egui::Context) -> Option<egui::Rect> {
+    let focused = ctx.memory(|memory| memory.focused())?;
+    let rect = ctx.read_response(focused)?.rect;
+    Some(rect * ctx.pixels_per_point())
+}
@@ -0,0 +1,216 @@
+use anyhow::Result;
+use egui::epaint::text::FontPriority;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, SystemTime},
+};
+
+// interval of the modification time checks of the hot reload.
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+enum FontSource {
+    Bytes,
+    File {
+        path: PathBuf,
+        modified: Option<SystemTime>,
+    },
+}
+
+struct RegisteredFont {
+    name: String,
+    source: FontSource,
+    data: Arc<egui::FontData>,
+    families: Vec<egui::FontFamily>,
+    priority: FontPriority,
+}
+
+struct FontsInner {
+    base: egui::FontDefinitions,
+    fonts: Vec<RegisteredFont>,
+    hot_reload: bool,
+    watching: bool,
+}
+
+/// fonts registered on top of the default fonts, passed as [`crate::CreationContext::fonts`].
+///
+/// Each change rebuilds the font atlas, which is uploaded with the next frame. Fonts set with
+/// `egui::Context::set_fonts` are replaced, use [`Fonts::set_base`] instead.
+#[derive(Clone)]
+pub struct Fonts {
+    context: egui::Context,
+    inner: Arc<Mutex<FontsInner>>,
+}
+impl Fonts {
+    pub(crate) fn new(context: egui::Context) -> Self {
+        Self {
+            context,
+            inner: Arc::new(Mutex::new(FontsInner {
+                base: egui::FontDefinitions::default(),
+                fonts: vec![],
+                hot_reload: false,
+                watching: false,
+            })),
+        }
+    }
+
+    /// set the fonts the registered fonts are added to, `egui::FontDefinitions::default()` by default.
+    pub fn set_base(&self, base: egui::FontDefinitions) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.base = base;
+        inner.apply(&self.context);
+    }
+
+    /// register a font from the bytes of a TTF or OTF file, used first or last in `families`.
+    /// A font registered under the same name is replaced.
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        bytes: Vec<u8>,
+        families: &[egui::FontFamily],
+        priority: FontPriority,
+    ) {
+        self.insert(RegisteredFont {
+            name: name.into(),
+            source: FontSource::Bytes,
+            data: Arc::new(egui::FontData::from_owned(bytes)),
+            families: families.to_vec(),
+            priority,
+        });
+    }
+
+    /// register the font of a TTF or OTF file like [`Fonts::register`].
+    /// The font is loaded again when the file changes while the hot reload is enabled.
+    pub fn register_file(
+        &self,
+        name: impl Into<String>,
+        path: impl AsRef<Path>,
+        families: &[egui::FontFamily],
+        priority: FontPriority,
+    ) -> Result<()> {
+        let path = path.as_ref().to_owned();
+        let modified = modified(&path);
+        let bytes = std::fs::read(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to read font {path:?}: {err}"))?;
+        self.insert(RegisteredFont {
+            name: name.into(),
+            source: FontSource::File { path, modified },
+            data: Arc::new(egui::FontData::from_owned(bytes)),
+            families: families.to_vec(),
+            priority,
+        });
+        Ok(())
+    }
+
+    /// unregister the font registered under `name`.
+    pub fn unregister(&self, name: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let len = inner.fonts.len();
+        inner.fonts.retain(|font| font.name != name);
+        if inner.fonts.len() != len {
+            inner.apply(&self.context);
+        }
+    }
+
+    /// reload the fonts registered with [`Fonts::register_file`] when their files change,
+    /// e.g. while designing a font. The files are checked twice a second on a background thread.
+    pub fn set_hot_reload(&self, enabled: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.hot_reload = enabled;
+        if !enabled || inner.watching {
+            return;
+        }
+        inner.watching = true;
+        let weak = Arc::downgrade(&self.inner);
+        let context = self.context.clone();
+        let spawned = std::thread::Builder::new()
+            .name("egui-ash font hot reload".to_owned())
+            .spawn(move || watch(weak, context));
+        if let Err(err) = spawned {
+            error!("Failed to start the font hot reload: {err}");
+            inner.watching = false;
+        }
+    }
+
+    fn insert(&self, font: RegisteredFont) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .fonts
+            .retain(|registered| registered.name != font.name);
+        inner.fonts.push(font);
+        inner.apply(&self.context);
+    }
+}
+
+impl FontsInner {
+    fn apply(&self, context: &egui::Context) {
+        let mut definitions = self.base.clone();
+        for font in &self.fonts {
+            definitions
+                .font_data
+                .insert(font.name.clone(), font.data.clone());
+            for family in &font.families {
+                let names = definitions.families.entry(family.clone()).or_default();
+                match font.priority {
+                    FontPriority::Highest => names.insert(0, font.name.clone()),
+                    FontPriority::Lowest => names.push(font.name.clone()),
+                }
+            }
+        }
+        context.set_fonts(definitions);
+        context.request_repaint();
+    }
+
+    /// load the changed font files again, whether any changed.
+    fn reload_changed(&mut self) -> bool {
+        let mut changed = false;
+        for font in &mut self.fonts {
+            let FontSource::File {
+                path,
+                modified: last,
+            } = &mut font.source
+            else {
+                continue;
+            };
+            let current = modified(path);
+            if current == *last {
+                continue;
+            }
+            *last = current;
+            match std::fs::read(&*path) {
+                Ok(bytes) => {
+                    debug!("Reloading font {path:?}");
+                    font.data = Arc::new(egui::FontData::from_owned(bytes));
+                    changed = true;
+                }
+                // e.g. while the file is written, it is read again with the next change.
+                Err(err) => warn!("Failed to reload font {path:?}: {err}"),
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// runs until the hot reload is disabled or the fonts are dropped.
+fn watch(inner: Weak<Mutex<FontsInner>>, context: egui::Context) {
+    loop {
+        std::thread::sleep(HOT_RELOAD_INTERVAL);
+        let Some(inner) = inner.upgrade() else {
+            return;
+        };
+        let mut inner = inner.lock().unwrap();
+        if !inner.hot_reload {
+            inner.watching = false;
+            return;
+        }
+        if inner.reload_changed() {
+            inner.apply(&context);
+        }
+    }
+}
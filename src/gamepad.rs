@@ -0,0 +1,156 @@
+use egui_winit::winit::event_loop::EventLoopProxy;
+use gilrs::{ev::filter::Repeat, Axis, Button, EventType, Filter, GamepadId};
+use std::{collections::HashMap, time::Duration};
+
+use crate::integration::IntegrationEvent;
+
+// stick deflection to start and stop a navigation key press.
+const STICK_PRESS_THRESHOLD: f32 = 0.5;
+const STICK_RELEASE_THRESHOLD: f32 = 0.3;
+
+/// poll gamepads on a background thread and send the events to the event loop.
+pub(crate) fn spawn(event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+    let result = std::thread::Builder::new()
+        .name("egui-ash gamepad".to_owned())
+        .spawn(move || {
+            let mut gilrs = match gilrs::Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(err) => {
+                    error!("Failed to initialize gamepad input: {}", err);
+                    return;
+                }
+            };
+            let repeat = Repeat::new();
+            loop {
+                // a timeout is required for the repeat filter to generate repeated presses.
+                let Some(event) = gilrs
+                    .next_event_blocking(Some(Duration::from_millis(50)))
+                    .filter_ev(&repeat, &mut gilrs)
+                else {
+                    continue;
+                };
+                gilrs.update(&event);
+                if event.event == EventType::Dropped {
+                    continue;
+                }
+                if event_loop_proxy
+                    .send_event(IntegrationEvent::Gamepad(event))
+                    .is_err()
+                {
+                    // event loop has exited.
+                    return;
+                }
+            }
+        });
+    if let Err(err) = result {
+        error!("Failed to spawn gamepad thread: {}", err);
+    }
+}
+
+/// translate gamepad input into egui key events for focus navigation.
+///
+/// D-pad and left stick move the focus with arrow keys, bumpers with Tab and Shift+Tab,
+/// the south button (A / Cross) presses Enter and the east button (B / Circle) presses Escape.
+#[derive(Default)]
+pub(crate) struct GamepadNavigation {
+    stick_keys: HashMap<(GamepadId, Axis), egui::Key>,
+}
+impl GamepadNavigation {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn translate(&mut self, event: &gilrs::Event) -> Vec<egui::Event> {
+        match event.event {
+            EventType::ButtonPressed(button, _) => translate_button(button)
+                .map(|(key, modifiers)| vec![key_event(key, modifiers, true, false)])
+                .unwrap_or_default(),
+            EventType::ButtonRepeated(button, _) => translate_button(button)
+                .map(|(key, modifiers)| vec![key_event(key, modifiers, true, true)])
+                .unwrap_or_default(),
+            EventType::ButtonReleased(button, _) => translate_button(button)
+                .map(|(key, modifiers)| vec![key_event(key, modifiers, false, false)])
+                .unwrap_or_default(),
+            EventType::AxisChanged(axis, value, _) => self.translate_axis(event.id, axis, value),
+            EventType::Disconnected => {
+                // release keys held by the sticks of the disconnected gamepad.
+                let mut events = vec![];
+                self.stick_keys.retain(|&(id, _), key| {
+                    if id == event.id {
+                        events.push(key_event(*key, egui::Modifiers::NONE, false, false));
+                    }
+                    id != event.id
+                });
+                events
+            }
+            _ => vec![],
+        }
+    }
+
+    fn translate_axis(&mut self, id: GamepadId, axis: Axis, value: f32) -> Vec<egui::Event> {
+        let (negative, positive) = match axis {
+            Axis::LeftStickX => (egui::Key::ArrowLeft, egui::Key::ArrowRight),
+            // gilrs reports up as positive.
+            Axis::LeftStickY => (egui::Key::ArrowDown, egui::Key::ArrowUp),
+            _ => return vec![],
+        };
+
+        let current = self.stick_keys.get(&(id, axis)).copied();
+        let next = if value >= STICK_PRESS_THRESHOLD {
+            Some(positive)
+        } else if value <= -STICK_PRESS_THRESHOLD {
+            Some(negative)
+        } else if value.abs() > STICK_RELEASE_THRESHOLD {
+            current
+        } else {
+            None
+        };
+        if current == next {
+            return vec![];
+        }
+
+        let mut events = vec![];
+        if let Some(key) = current {
+            events.push(key_event(key, egui::Modifiers::NONE, false, false));
+        }
+        match next {
+            Some(key) => {
+                events.push(key_event(key, egui::Modifiers::NONE, true, false));
+                self.stick_keys.insert((id, axis), key);
+            }
+            None => {
+                self.stick_keys.remove(&(id, axis));
+            }
+        }
+        events
+    }
+}
+
+fn translate_button(button: Button) -> Option<(egui::Key, egui::Modifiers)> {
+    match button {
+        Button::DPadUp => Some((egui::Key::ArrowUp, egui::Modifiers::NONE)),
+        Button::DPadDown => Some((egui::Key::ArrowDown, egui::Modifiers::NONE)),
+        Button::DPadLeft => Some((egui::Key::ArrowLeft, egui::Modifiers::NONE)),
+        Button::DPadRight => Some((egui::Key::ArrowRight, egui::Modifiers::NONE)),
+        Button::South => Some((egui::Key::Enter, egui::Modifiers::NONE)),
+        Button::East => Some((egui::Key::Escape, egui::Modifiers::NONE)),
+        Button::RightTrigger => Some((egui::Key::Tab, egui::Modifiers::NONE)),
+        Button::LeftTrigger => Some((egui::Key::Tab, egui::Modifiers::SHIFT)),
+        _ => None,
+    }
+}
+
+fn key_event(
+    key: egui::Key,
+    modifiers: egui::Modifiers,
+    pressed: bool,
+    repeat: bool,
+) -> egui::Event {
+    egui::Event::Key {
+        key,
+        physical_key: None,
+        pressed,
+        repeat,
+        modifiers,
+    }
+}
@@ -56,6 +56,17 @@ impl allocator::AllocationCreateInfo for AllocationCreateDesc<'static> {
     }
 }
 
+impl From<allocator::AllocatorDebugOptions> for gpu_allocator::AllocatorDebugSettings {
+    fn from(options: allocator::AllocatorDebugOptions) -> Self {
+        Self {
+            log_leaks_on_shutdown: options.log_leaks_on_shutdown,
+            store_stack_traces: options.store_stack_traces,
+            log_stack_traces: options.store_stack_traces,
+            ..Default::default()
+        }
+    }
+}
+
 impl allocator::Allocator for Arc<Mutex<Allocator>> {
     type Allocation = Allocation;
     type AllocationCreateInfo = AllocationCreateDesc<'static>;
@@ -67,4 +78,23 @@ impl allocator::Allocator for Arc<Mutex<Allocator>> {
     fn free(&self, allocation: Self::Allocation) -> Result<()> {
         Ok(Allocator::free(&mut self.lock().unwrap(), allocation)?)
     }
+
+    fn report(&self) -> Option<allocator::AllocatorReport> {
+        let report = self.lock().unwrap().generate_report();
+        // gpu-allocator does not report the heaps of its memory blocks.
+        Some(allocator::AllocatorReport {
+            allocation_count: report.allocations.len(),
+            total_allocated: report.total_allocated_bytes,
+            total_reserved: report.total_reserved_bytes,
+            heaps: vec![],
+            allocations: report
+                .allocations
+                .into_iter()
+                .map(|allocation| allocator::NamedAllocation {
+                    name: allocation.name,
+                    size: allocation.size,
+                })
+                .collect(),
+        })
+    }
 }
@@ -0,0 +1,127 @@
+use egui_winit::winit::{
+    application::ApplicationHandler,
+    event::{DeviceEvent, DeviceId, StartCause, WindowEvent},
+    event_loop::ActiveEventLoop,
+    window::WindowId,
+};
+
+use crate::integration::IntegrationEvent;
+
+/// when the handler of [`crate::run_with_handler`] receives the events, relative to egui-ash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerOrder {
+    /// before egui-ash, e.g. to see the window events egui consumes.
+    Before,
+    /// after egui-ash, e.g. to override the control flow set in `about_to_wait`.
+    After,
+}
+
+/// the handler of egui-ash composed with the one of the app.
+pub(crate) struct ComposedHandler<'a, S> {
+    pub(crate) state: &'a mut S,
+    pub(crate) handler: Option<(Box<dyn ApplicationHandler>, HandlerOrder)>,
+}
+impl<S: ApplicationHandler<IntegrationEvent>> ComposedHandler<'_, S> {
+    fn dispatch(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        state: impl FnOnce(&mut S, &ActiveEventLoop),
+        handler: impl FnOnce(&mut dyn ApplicationHandler, &ActiveEventLoop),
+    ) {
+        match &mut self.handler {
+            Some((other, HandlerOrder::Before)) => {
+                handler(other.as_mut(), event_loop);
+                state(&mut *self.state, event_loop);
+            }
+            Some((other, HandlerOrder::After)) => {
+                state(&mut *self.state, event_loop);
+                handler(other.as_mut(), event_loop);
+            }
+            None => state(&mut *self.state, event_loop),
+        }
+    }
+}
+impl<S: ApplicationHandler<IntegrationEvent>> ApplicationHandler<IntegrationEvent>
+    for ComposedHandler<'_, S>
+{
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.new_events(event_loop, cause),
+            |handler, event_loop| handler.new_events(event_loop, cause),
+        );
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.resumed(event_loop),
+            |handler, event_loop| handler.resumed(event_loop),
+        );
+    }
+
+    // egui-ash events stay with egui-ash, the handler has no user events.
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: IntegrationEvent) {
+        self.state.user_event(event_loop, event);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let other = event.clone();
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.window_event(event_loop, window_id, event),
+            |handler, event_loop| handler.window_event(event_loop, window_id, other),
+        );
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        let other = event.clone();
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.device_event(event_loop, device_id, event),
+            |handler, event_loop| handler.device_event(event_loop, device_id, other),
+        );
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.about_to_wait(event_loop),
+            |handler, event_loop| handler.about_to_wait(event_loop),
+        );
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.suspended(event_loop),
+            |handler, event_loop| handler.suspended(event_loop),
+        );
+    }
+
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.exiting(event_loop),
+            |handler, event_loop| handler.exiting(event_loop),
+        );
+    }
+
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        self.dispatch(
+            event_loop,
+            |state, event_loop| state.memory_warning(event_loop),
+            |handler, event_loop| handler.memory_warning(event_loop),
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::rc::Rc;
+
+pub use global_hotkey::{
+    hotkey::{Code, HotKey, Modifiers},
+    HotKeyState,
+};
+
+use crate::integration::IntegrationEvent;
+
+/// OS-level hotkeys that are delivered even when no window of the app has the focus.
+///
+/// Presses and releases are delivered to [`crate::App::handle_event`] as [`crate::event::Event::GlobalHotkey`]
+/// and wake the event loop.
+/// Not available on Wayland.
+#[derive(Clone)]
+pub struct GlobalHotkeys {
+    manager: Option<Rc<global_hotkey::GlobalHotKeyManager>>,
+}
+impl GlobalHotkeys {
+    /// must be called on the main thread.
+    pub(crate) fn new() -> Self {
+        let manager = match global_hotkey::GlobalHotKeyManager::new() {
+            Ok(manager) => Some(Rc::new(manager)),
+            Err(err) => {
+                error!("Failed to create global hotkey manager: {}", err);
+                None
+            }
+        };
+        Self { manager }
+    }
+
+    fn manager(&self) -> Result<&global_hotkey::GlobalHotKeyManager> {
+        self.manager
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Global hotkeys are not available"))
+    }
+
+    /// register a hotkey. Use [`HotKey::id`] to identify it in the events.
+    pub fn register(&self, hotkey: HotKey) -> Result<()> {
+        self.manager()?.register(hotkey)?;
+        Ok(())
+    }
+
+    /// unregister a hotkey.
+    pub fn unregister(&self, hotkey: HotKey) -> Result<()> {
+        self.manager()?.unregister(hotkey)?;
+        Ok(())
+    }
+}
+impl std::fmt::Debug for GlobalHotkeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlobalHotkeys").finish()
+    }
+}
+
+/// send hotkey events to the event loop. The handler can be set only once per process.
+pub(crate) fn set_event_handler(event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+    global_hotkey::GlobalHotKeyEvent::set_event_handler(Some(
+        move |event: global_hotkey::GlobalHotKeyEvent| {
+            let _ = event_loop_proxy.send_event(IntegrationEvent::GlobalHotkey(event));
+        },
+    ));
+}
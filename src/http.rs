@@ -0,0 +1,355 @@
+//! `http://` and `https://` images for `egui::Image::from_uri`, like the `http` feature of egui_extras.
+//!
+//! [`install`] adds a bytes loader, for other loaders of the context like an SVG loader, and a texture
+//! loader that decodes the images on the fetch threads and packs them into the atlas pages of the
+//! renderer with [`ImageRegistry::register_atlas_image`]. Responses are kept in a disk cache when
+//! [`HttpOptions::cache_dir`] is set.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+use egui::load::{
+    BytesLoadResult, BytesLoader, BytesPoll, LoadError, SizedTexture, TextureLoadResult,
+    TextureLoader, TexturePoll,
+};
+
+use crate::{assets, ImageRegistry};
+
+/// options of the loaders of [`install`].
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// number of threads fetching and decoding the images.
+    pub threads: usize,
+    /// directory of the disk cache, `None` disables it.
+    pub cache_dir: Option<PathBuf>,
+    /// age after which cached responses are fetched again.
+    pub cache_max_age: Duration,
+    /// largest response body in bytes.
+    pub max_size: u64,
+}
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            threads: 4,
+            cache_dir: None,
+            cache_max_age: Duration::from_secs(24 * 60 * 60),
+            max_size: 32 * 1024 * 1024,
+        }
+    }
+}
+impl HttpOptions {
+    /// default options with the disk cache in the cache directory of `app_id`.
+    pub fn new(app_id: &str) -> Self {
+        Self {
+            cache_dir: directories_next::ProjectDirs::from("", "", app_id)
+                .map(|project_dirs| project_dirs.cache_dir().join("http")),
+            ..Default::default()
+        }
+    }
+}
+
+/// add the `http(s)://` loaders to `ctx`, uploading the images with `image_registry`.
+pub fn install(ctx: &egui::Context, image_registry: &ImageRegistry, options: HttpOptions) {
+    let loader = Arc::new(HttpLoader::new(image_registry.clone(), options));
+    ctx.add_bytes_loader(loader.clone());
+    ctx.add_texture_loader(loader);
+}
+
+const LOADER_ID: &str = egui::generate_loader_id!(HttpLoader);
+
+enum JobKind {
+    Bytes,
+    Texture,
+}
+
+struct Job {
+    uri: String,
+    kind: JobKind,
+    // the context of the loading pass is repainted when the job finishes.
+    ctx: egui::Context,
+}
+
+struct Fetched {
+    bytes: Vec<u8>,
+    mime: Option<String>,
+}
+
+// `None` while the job is queued or running.
+type Entries<T> = Mutex<HashMap<String, Option<Result<T, String>>>>;
+
+#[derive(Default)]
+struct Shared {
+    bytes: Entries<(Arc<[u8]>, Option<String>)>,
+    textures: Entries<SizedTexture>,
+}
+
+struct HttpLoader {
+    shared: Arc<Shared>,
+    image_registry: ImageRegistry,
+    // the workers stop when the loader is dropped with the context.
+    jobs: Mutex<mpsc::Sender<Job>>,
+}
+impl HttpLoader {
+    fn new(image_registry: ImageRegistry, options: HttpOptions) -> Self {
+        let shared = Arc::new(Shared::default());
+        let (jobs, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let options = Arc::new(options);
+        for index in 0..options.threads.max(1) {
+            let shared = shared.clone();
+            let receiver = receiver.clone();
+            let options = options.clone();
+            let image_registry = image_registry.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("egui-ash http {index}"))
+                .spawn(move || worker(&receiver, &shared, &options, &image_registry));
+            if let Err(err) = spawned {
+                error!("Failed to start an http loader thread: {err}");
+            }
+        }
+        Self {
+            shared,
+            image_registry,
+            jobs: Mutex::new(jobs),
+        }
+    }
+
+    fn queue(&self, ctx: &egui::Context, uri: &str, kind: JobKind) {
+        let job = Job {
+            uri: uri.to_owned(),
+            kind,
+            ctx: ctx.clone(),
+        };
+        if self.jobs.lock().unwrap().send(job).is_err() {
+            error!("No http loader thread is running");
+        }
+    }
+}
+
+fn is_http(uri: &str) -> bool {
+    uri.starts_with("http://") || uri.starts_with("https://")
+}
+
+impl BytesLoader for HttpLoader {
+    fn id(&self) -> &str {
+        LOADER_ID
+    }
+
+    fn load(&self, ctx: &egui::Context, uri: &str) -> BytesLoadResult {
+        if !is_http(uri) {
+            return Err(LoadError::NotSupported);
+        }
+        let mut entries = self.shared.bytes.lock().unwrap();
+        match entries.get(uri) {
+            Some(Some(Ok((bytes, mime)))) => Ok(BytesPoll::Ready {
+                size: None,
+                bytes: egui::load::Bytes::Shared(bytes.clone()),
+                mime: mime.clone(),
+            }),
+            Some(Some(Err(err))) => Err(LoadError::Loading(err.clone())),
+            Some(None) => Ok(BytesPoll::Pending { size: None }),
+            None => {
+                entries.insert(uri.to_owned(), None);
+                drop(entries);
+                self.queue(ctx, uri, JobKind::Bytes);
+                Ok(BytesPoll::Pending { size: None })
+            }
+        }
+    }
+
+    fn forget(&self, uri: &str) {
+        self.shared.bytes.lock().unwrap().remove(uri);
+    }
+
+    fn forget_all(&self) {
+        self.shared.bytes.lock().unwrap().clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        self.shared
+            .bytes
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| match entry {
+                Some(Ok((bytes, _))) => bytes.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl TextureLoader for HttpLoader {
+    fn id(&self) -> &str {
+        LOADER_ID
+    }
+
+    // the atlas pages are sampled linearly, whatever the texture options.
+    fn load(
+        &self,
+        ctx: &egui::Context,
+        uri: &str,
+        _texture_options: egui::TextureOptions,
+        _size_hint: egui::SizeHint,
+    ) -> TextureLoadResult {
+        if !is_http(uri) {
+            return Err(LoadError::NotSupported);
+        }
+        let mut entries = self.shared.textures.lock().unwrap();
+        match entries.get(uri) {
+            Some(Some(Ok(texture))) => Ok(TexturePoll::Ready { texture: *texture }),
+            Some(Some(Err(err))) => Err(LoadError::Loading(err.clone())),
+            Some(None) => Ok(TexturePoll::Pending { size: None }),
+            None => {
+                entries.insert(uri.to_owned(), None);
+                drop(entries);
+                self.queue(ctx, uri, JobKind::Texture);
+                Ok(TexturePoll::Pending { size: None })
+            }
+        }
+    }
+
+    fn forget(&self, uri: &str) {
+        if let Some(Some(Ok(texture))) = self.shared.textures.lock().unwrap().remove(uri) {
+            self.image_registry.unregister_user_texture(texture.id);
+        }
+    }
+
+    fn forget_all(&self) {
+        for (_, entry) in self.shared.textures.lock().unwrap().drain() {
+            if let Some(Ok(texture)) = entry {
+                self.image_registry.unregister_user_texture(texture.id);
+            }
+        }
+    }
+
+    fn byte_size(&self) -> usize {
+        self.shared
+            .textures
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| match entry {
+                Some(Ok(texture)) => texture.size.x as usize * texture.size.y as usize * 4,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+fn worker(
+    receiver: &Mutex<mpsc::Receiver<Job>>,
+    shared: &Shared,
+    options: &HttpOptions,
+    image_registry: &ImageRegistry,
+) {
+    loop {
+        let Ok(job) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+        profile_scope!("http_load", job.uri.as_str());
+        let fetched = fetch(&job.uri, options);
+        match job.kind {
+            JobKind::Bytes => {
+                let result = fetched.map(|fetched| (fetched.bytes.into(), fetched.mime));
+                // forgotten while loading
+                if let Some(entry) = shared.bytes.lock().unwrap().get_mut(&job.uri) {
+                    *entry = Some(result);
+                }
+            }
+            JobKind::Texture => {
+                let result = fetched.and_then(|fetched| {
+                    assets::decode(&fetched.bytes).map_err(|err| err.to_string())
+                });
+                let result = result.map(|image| {
+                    let size = egui::vec2(image.size[0] as f32, image.size[1] as f32);
+                    SizedTexture::new(image_registry.register_atlas_image(image), size)
+                });
+                let mut textures = shared.textures.lock().unwrap();
+                match textures.get_mut(&job.uri) {
+                    Some(entry) => *entry = Some(result),
+                    None => {
+                        if let Ok(texture) = result {
+                            image_registry.unregister_user_texture(texture.id);
+                        }
+                    }
+                }
+            }
+        }
+        job.ctx.request_repaint();
+    }
+}
+
+fn fetch(uri: &str, options: &HttpOptions) -> Result<Fetched, String> {
+    let cache_path = options.cache_dir.as_deref().map(|dir| cache_path(dir, uri));
+    if let Some(path) = &cache_path {
+        if let Some(bytes) = read_cache(path, options.cache_max_age) {
+            debug!("Loaded {uri} from the http cache");
+            let mime = image::guess_format(&bytes)
+                .ok()
+                .map(|format| format.to_mime_type().to_owned());
+            return Ok(Fetched { bytes, mime });
+        }
+    }
+
+    let mut response = ureq::get(uri)
+        .call()
+        .map_err(|err| format!("Failed to fetch {uri}: {err}"))?;
+    let mime = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let bytes = response
+        .body_mut()
+        .with_config()
+        .limit(options.max_size)
+        .read_to_vec()
+        .map_err(|err| format!("Failed to read {uri}: {err}"))?;
+
+    if let Some(path) = &cache_path {
+        if let Err(err) = write_cache(path, &bytes) {
+            warn!("Failed to cache {uri}: {err}");
+        }
+    }
+    Ok(Fetched { bytes, mime })
+}
+
+// file name of the uri, FNV-1a so that it stays the same across builds.
+fn cache_path(dir: &Path, uri: &str) -> PathBuf {
+    let hash = uri.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    dir.join(format!("{hash:016x}"))
+}
+
+fn read_cache(path: &Path, max_age: Duration) -> Option<Vec<u8>> {
+    let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+    let age = SystemTime::now().duration_since(modified.ok()?).ok()?;
+    if age > max_age {
+        return None;
+    }
+    std::fs::read(path).ok()
+}
+
+// written to a temporary file first, so that other threads and runs never read a partial file.
+fn write_cache(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let temp_path = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&temp_path, bytes)?;
+    std::fs::rename(&temp_path, path)
+}
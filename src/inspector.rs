@@ -0,0 +1,392 @@
+use ash::vk;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    allocator::{AllocatorReport, MemoryBudget},
+    renderer::UserTextureInfo,
+    repaint::RepaintCause,
+};
+
+/// the integration state is collected while the inspector was shown this recently.
+const SHOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// swapchain of a viewport, as shown by [`inspector_ui`].
+pub(crate) struct SwapchainInfo {
+    pub(crate) format: vk::Format,
+    pub(crate) extent: vk::Extent2D,
+    pub(crate) image_count: usize,
+    pub(crate) present_mode: vk::PresentModeKHR,
+    // submitted frames the GPU has not finished yet.
+    pub(crate) frames_in_flight: usize,
+}
+
+/// textures and memory of the renderer, as shown by [`inspector_ui`].
+#[derive(Default)]
+pub(crate) struct TextureInfo {
+    pub(crate) managed_textures: usize,
+    pub(crate) user_textures: usize,
+    pub(crate) atlas_images: usize,
+    pub(crate) atlas_pages: usize,
+    pub(crate) texture_memory: u64,
+    pub(crate) buffer_memory: u64,
+    pub(crate) allocator: Option<AllocatorReport>,
+    pub(crate) budget: Option<Vec<MemoryBudget>>,
+}
+
+/// texture registered through [`crate::ImageRegistry`], as shown by [`texture_debugger_ui`].
+pub(crate) struct RegisteredTexture {
+    pub(crate) id: egui::TextureId,
+    pub(crate) atlas: bool,
+    pub(crate) info: Option<UserTextureInfo>,
+    // egui pass of the viewport the texture was last drawn in.
+    pub(crate) last_drawn: Option<u64>,
+    pub(crate) last_used: Option<Instant>,
+}
+
+#[derive(Default)]
+struct InspectorState {
+    shown: Option<Instant>,
+    swapchains: HashMap<egui::ViewportId, SwapchainInfo>,
+    repaint_causes: HashMap<egui::ViewportId, Vec<String>>,
+    textures: TextureInfo,
+    texture_debugger_shown: Option<Instant>,
+    registered_textures: Vec<RegisteredTexture>,
+    // textures to unregister, requested by the evict buttons.
+    evicted: Vec<egui::TextureId>,
+}
+
+/// integration state collected for [`inspector_ui`], stored in the egui context.
+#[derive(Clone, Default)]
+pub(crate) struct Inspector {
+    state: Arc<Mutex<InspectorState>>,
+}
+impl Inspector {
+    fn id() -> egui::Id {
+        egui::Id::new("egui_ash_inspector")
+    }
+
+    /// create the inspector of the integration of `context`.
+    pub(crate) fn install(context: &egui::Context) -> Self {
+        let inspector = Self::default();
+        context.data_mut(|data| data.insert_temp(Self::id(), inspector.clone()));
+        inspector
+    }
+
+    /// whether [`inspector_ui`] or [`texture_debugger_ui`] is shown, so that the state is worth collecting.
+    pub(crate) fn is_shown(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        [state.shown, state.texture_debugger_shown]
+            .into_iter()
+            .flatten()
+            .any(|shown| shown.elapsed() < SHOWN_TIMEOUT)
+    }
+
+    pub(crate) fn is_texture_debugger_shown(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .texture_debugger_shown
+            .is_some_and(|shown| shown.elapsed() < SHOWN_TIMEOUT)
+    }
+
+    pub(crate) fn set_swapchains(&self, swapchains: HashMap<egui::ViewportId, SwapchainInfo>) {
+        self.state.lock().unwrap().swapchains = swapchains;
+    }
+
+    pub(crate) fn set_repaint_causes(
+        &self,
+        viewport_id: egui::ViewportId,
+        causes: &[RepaintCause],
+    ) {
+        let causes = causes.iter().map(ToString::to_string).collect();
+        self.state
+            .lock()
+            .unwrap()
+            .repaint_causes
+            .insert(viewport_id, causes);
+    }
+
+    /// forget the closed viewports.
+    pub(crate) fn retain_viewports(&self, viewport_ids: &egui::ViewportIdSet) {
+        let mut state = self.state.lock().unwrap();
+        state.swapchains.retain(|id, _| viewport_ids.contains(id));
+        state
+            .repaint_causes
+            .retain(|id, _| viewport_ids.contains(id));
+    }
+
+    pub(crate) fn set_textures(&self, textures: TextureInfo) {
+        self.state.lock().unwrap().textures = textures;
+    }
+
+    pub(crate) fn set_registered_textures(&self, textures: Vec<RegisteredTexture>) {
+        self.state.lock().unwrap().registered_textures = textures;
+    }
+
+    /// textures the user evicted since the last call.
+    pub(crate) fn take_evicted(&self) -> Vec<egui::TextureId> {
+        std::mem::take(&mut self.state.lock().unwrap().evicted)
+    }
+}
+
+/// show a debug window with the swapchains, frames in flight, repaint causes,
+/// textures and memory of egui-ash.
+///
+/// Call it in [`crate::App::ui`]. The state is only collected while the window is shown.
+pub fn inspector_ui(ctx: &egui::Context) {
+    let inspector = ctx.data(|data| data.get_temp::<Inspector>(Inspector::id()));
+    egui::Window::new("egui-ash inspector").show(ctx, |ui| {
+        let Some(inspector) = inspector else {
+            ui.label("This context is not painted by egui-ash.");
+            return;
+        };
+        let mut state = inspector.state.lock().unwrap();
+        state.shown = Some(Instant::now());
+
+        egui::CollapsingHeader::new("Textures")
+            .default_open(true)
+            .show(ui, |ui| {
+                let textures = &state.textures;
+                egui::Grid::new("egui_ash_inspector_textures")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let rows = [
+                            ("managed textures", textures.managed_textures.to_string()),
+                            ("user textures", textures.user_textures.to_string()),
+                            ("atlas images", textures.atlas_images.to_string()),
+                            ("atlas pages", textures.atlas_pages.to_string()),
+                            ("texture memory", format_bytes(textures.texture_memory)),
+                            (
+                                "vertex and index buffers",
+                                format_bytes(textures.buffer_memory),
+                            ),
+                        ];
+                        for (label, value) in rows {
+                            ui.label(label);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        egui::CollapsingHeader::new("Allocator")
+            .default_open(true)
+            .show(ui, |ui| {
+                let Some(report) = &state.textures.allocator else {
+                    ui.weak("the allocator does not report its memory usage");
+                    return;
+                };
+                egui::Grid::new("egui_ash_inspector_allocator")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("allocations");
+                        ui.label(report.allocation_count.to_string());
+                        ui.end_row();
+                        ui.label("allocated");
+                        ui.label(format_bytes(report.total_allocated));
+                        ui.end_row();
+                        ui.label("reserved");
+                        ui.label(format_bytes(report.total_reserved));
+                        ui.end_row();
+                        for heap in &report.heaps {
+                            ui.label(format!("heap {}", heap.heap_index));
+                            ui.label(format!(
+                                "{} / {}",
+                                format_bytes(heap.allocated),
+                                format_bytes(heap.reserved)
+                            ));
+                            ui.end_row();
+                        }
+                        if let Some(budget) = &state.textures.budget {
+                            for heap in budget {
+                                ui.label(format!("heap {} budget", heap.heap_index));
+                                ui.label(format!(
+                                    "{} / {} ({:.0}%)",
+                                    format_bytes(heap.usage),
+                                    format_bytes(heap.budget),
+                                    heap.pressure() * 100.0
+                                ));
+                                ui.end_row();
+                            }
+                        }
+                    });
+                if !report.allocations.is_empty() {
+                    // count and size of the allocations sharing a name.
+                    let mut named = BTreeMap::<&str, (usize, u64)>::new();
+                    for allocation in &report.allocations {
+                        let entry = named.entry(allocation.name.as_str()).or_default();
+                        entry.0 += 1;
+                        entry.1 += allocation.size;
+                    }
+                    egui::Grid::new("egui_ash_inspector_allocations")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (name, (count, size)) in named {
+                                ui.label(name);
+                                ui.label(count.to_string());
+                                ui.label(format_bytes(size));
+                                ui.end_row();
+                            }
+                        });
+                }
+            });
+
+        let mut viewport_ids = state
+            .swapchains
+            .keys()
+            .chain(state.repaint_causes.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        viewport_ids.sort_by_key(|id| (*id != egui::ViewportId::ROOT, format!("{id:?}")));
+        viewport_ids.dedup();
+        for viewport_id in viewport_ids {
+            egui::CollapsingHeader::new(format!("{viewport_id:?}"))
+                .id_salt(viewport_id)
+                .default_open(viewport_id == egui::ViewportId::ROOT)
+                .show(ui, |ui| {
+                    match state.swapchains.get(&viewport_id) {
+                        Some(swapchain) => {
+                            egui::Grid::new(("egui_ash_inspector_swapchain", viewport_id))
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    let rows = [
+                                        ("format", format!("{:?}", swapchain.format)),
+                                        (
+                                            "extent",
+                                            format!(
+                                                "{}x{}",
+                                                swapchain.extent.width, swapchain.extent.height
+                                            ),
+                                        ),
+                                        ("images", swapchain.image_count.to_string()),
+                                        ("present mode", format!("{:?}", swapchain.present_mode)),
+                                        (
+                                            "frames in flight",
+                                            swapchain.frames_in_flight.to_string(),
+                                        ),
+                                    ];
+                                    for (label, value) in rows {
+                                        ui.label(label);
+                                        ui.label(value);
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+                        None => {
+                            ui.label("no swapchain");
+                        }
+                    }
+                    ui.label("repaint causes:");
+                    let causes = state
+                        .repaint_causes
+                        .get(&viewport_id)
+                        .map_or(&[][..], Vec::as_slice);
+                    if causes.is_empty() {
+                        ui.weak("none");
+                    }
+                    for cause in causes {
+                        ui.monospace(cause);
+                    }
+                });
+        }
+    });
+}
+
+/// show a debug window listing the textures registered through [`crate::ImageRegistry`],
+/// e.g. to find leaked textures.
+///
+/// Each texture shows its size, format and memory if it was described with
+/// [`crate::ImageRegistry::describe_user_texture`], the egui pass it was last drawn in and a thumbnail.
+/// Drawing the thumbnails counts as drawing the textures.
+///
+/// The evict button unregisters the texture like [`crate::ImageRegistry::unregister_user_texture`].
+/// The image and image view of a user texture stay owned by the app.
+pub fn texture_debugger_ui(ctx: &egui::Context) {
+    const THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(64.0, 64.0);
+
+    let inspector = ctx.data(|data| data.get_temp::<Inspector>(Inspector::id()));
+    egui::Window::new("egui-ash textures").show(ctx, |ui| {
+        let Some(inspector) = inspector else {
+            ui.label("This context is not painted by egui-ash.");
+            return;
+        };
+        let mut state = inspector.state.lock().unwrap();
+        state.texture_debugger_shown = Some(Instant::now());
+
+        let memory = state
+            .registered_textures
+            .iter()
+            .filter_map(|texture| texture.info.as_ref())
+            .map(|info| info.memory)
+            .sum();
+        ui.label(format!(
+            "{} registered textures, {} described",
+            state.registered_textures.len(),
+            format_bytes(memory)
+        ));
+        ui.separator();
+
+        let mut evicted = vec![];
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for texture in &state.registered_textures {
+                ui.horizontal(|ui| {
+                    let size = texture.info.as_ref().map_or(THUMBNAIL_SIZE, |info| {
+                        egui::vec2(info.size[0] as f32, info.size[1] as f32)
+                    });
+                    ui.add(
+                        egui::Image::new(egui::load::SizedTexture::new(texture.id, size))
+                            .max_size(THUMBNAIL_SIZE),
+                    );
+                    ui.vertical(|ui| {
+                        let label = texture
+                            .info
+                            .as_ref()
+                            .and_then(|info| info.label.as_deref())
+                            .unwrap_or_default();
+                        let kind = if texture.atlas {
+                            "atlas image"
+                        } else {
+                            "user texture"
+                        };
+                        ui.strong(format!("{:?} {label}", texture.id));
+                        match &texture.info {
+                            Some(info) => ui.label(format!(
+                                "{kind}, {}x{} {:?}, {}",
+                                info.size[0],
+                                info.size[1],
+                                info.format,
+                                format_bytes(info.memory)
+                            )),
+                            None => ui.label(format!("{kind}, not described")),
+                        };
+                        match (texture.last_drawn, texture.last_used) {
+                            (Some(pass_nr), Some(last_used)) => ui.label(format!(
+                                "last drawn in pass {pass_nr}, {:.1} s ago",
+                                last_used.elapsed().as_secs_f32()
+                            )),
+                            _ => ui.weak("never drawn"),
+                        };
+                        if ui.button("evict").clicked() {
+                            evicted.push(texture.id);
+                        }
+                    });
+                });
+                ui.separator();
+            }
+        });
+        state.evicted.extend(evicted);
+    });
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    format!("{:.2} MiB", bytes as f64 / MIB)
+}
@@ -2,25 +2,96 @@ use egui::{ahash::HashMapExt, DeferredViewportUiCallback, ViewportIdMap};
 #[cfg(feature = "accesskit")]
 use egui_winit::accesskit_winit::Event as AccessKitEvent;
 use egui_winit::winit::{self, event_loop::ActiveEventLoop};
-use std::time::Instant;
+use raw_window_handle::HasWindowHandle as _;
+#[cfg(feature = "accesskit")]
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
 };
 
+use crate::accelerator::Accelerators;
+#[cfg(feature = "accesskit")]
+use crate::access_tree;
+use crate::activation;
 use crate::allocator::Allocator;
-use crate::presenters::Presenters;
-use crate::renderer::{EguiCommand, ImageRegistryReceiver, Renderer};
+#[cfg(feature = "accesskit")]
+use crate::announce;
+use crate::background;
+use crate::child_viewport;
+use crate::clear_color;
+use crate::cursor::CustomCursors;
+#[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+use crate::drag;
+use crate::event::ViewportVisibility;
+use crate::inspector::Inspector;
+use crate::layer;
+#[cfg(feature = "persistence")]
+use crate::layout::{LayoutProfile, LayoutProfiles, LayoutRequest};
+#[cfg(feature = "menu")]
+use crate::menu::NativeMenu;
+use crate::mirror::{self, MirrorWindow};
+use crate::monitors;
+use crate::passthrough::{self, MousePassthrough, PassthroughState};
+use crate::presenters::{GpuHangs, PresenterOptions, Presenters, StaleSwapchains};
+use crate::render_thread::RenderThread;
+use crate::renderer::{
+    self, EguiCommand, EguiFrame, ImageRegistryReceiver, Renderer, RendererOptions,
+};
+use crate::repaint::{self, RepaintCause};
+#[cfg(feature = "replay")]
+use crate::replay::{InputRecorder, InputReplay};
+use crate::run::SuspendPolicy;
+use crate::scroll::ScrollOptions;
+use crate::show::{self, ShowPolicy};
+use crate::stats::FrameStatistics;
 #[cfg(feature = "persistence")]
 use crate::storage::Storage;
+use crate::submit::SubmitHooks;
+use crate::theme;
+use crate::timestep::{FrameClock, FrameStepper};
 #[cfg(feature = "persistence")]
 use crate::utils;
+use crate::viewport_drag::{self, ViewportDrag};
+use crate::window_level;
+use crate::window_role;
+use crate::zoom;
 use crate::AshRenderState;
 
 #[derive(Debug)]
-pub(crate) struct IntegrationEvent {
+pub(crate) enum IntegrationEvent {
+    /// sent by [`crate::ExitSignal::send`].
+    Exit,
+    /// egui requested a repaint, wakes the event loop up.
+    RequestRepaint,
     #[cfg(feature = "accesskit")]
-    pub(crate) accesskit: AccessKitEvent,
+    AccessKit(AccessKitEvent),
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    DragFinished(drag::DragResult),
+    #[cfg(feature = "file-dialog")]
+    FileDialogClosed(crate::file_dialog::FileDialogId, Vec<std::path::PathBuf>),
+    #[cfg(feature = "gamepad")]
+    Gamepad(gilrs::Event),
+    #[cfg(feature = "global-hotkey")]
+    GlobalHotkey(global_hotkey::GlobalHotKeyEvent),
+    #[cfg(all(feature = "menu", any(target_os = "windows", target_os = "macos")))]
+    MenuActivated(String),
+    #[cfg(feature = "message-dialog")]
+    MessageDialogClosed(
+        crate::message_dialog::MessageDialogId,
+        crate::message_dialog::MessageDialogResult,
+    ),
+    #[cfg(target_os = "macos")]
+    OpenFiles(Vec<std::path::PathBuf>),
+    #[cfg(feature = "power")]
+    PowerChanged(crate::power::PowerStatus),
+    /// sent every few seconds to compare the monitors, see [`crate::monitors::MonitorWatch`].
+    CheckMonitors,
+    /// sent by the listener of [`crate::RunOption::single_instance`].
+    SecondInstance(crate::single_instance::Forwarded),
+    #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+    TrayIcon(tray_icon::TrayIconEvent),
 }
 
 pub(crate) type ViewportUiCallback = Arc<dyn Fn(&egui::Context) + Send + Sync>;
@@ -28,10 +99,7 @@ pub(crate) type ViewportUiCallback = Arc<dyn Fn(&egui::Context) + Send + Sync>;
 #[cfg(feature = "accesskit")]
 impl From<AccessKitEvent> for IntegrationEvent {
     fn from(event: AccessKitEvent) -> Self {
-        Self {
-            #[cfg(feature = "accesskit")]
-            accesskit: event,
-        }
+        Self::AccessKit(event)
     }
 }
 
@@ -41,14 +109,212 @@ struct Viewport {
     builder: egui::ViewportBuilder,
     info: egui::ViewportInfo,
     is_first_frame: bool,
+    visibility: ViewportVisibility,
     window: winit::window::Window,
     state: egui_winit::State,
     ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    custom_cursor_applied: bool,
+    cursor_grab: egui::viewport::CursorGrab,
+    cursor_visible: bool,
+    ime_rects_px: Option<(egui::Rect, egui::Rect)>,
+    mouse_passthrough: PassthroughState,
+    // rect of the child window in the parent window, see `set_child_viewport`.
+    child_rect: Option<egui::Rect>,
 }
 impl Viewport {
+    /// minimized or fully covered by other windows, so there is nothing to present.
+    fn is_hidden(&self) -> bool {
+        // a new window becomes visible after its first frames, paint it until then.
+        if self.is_first_frame || self.window.is_visible() == Some(false) {
+            return false;
+        }
+        let size = self.window.inner_size();
+        self.visibility.occluded
+            || self.window.is_minimized() == Some(true)
+            || size.width == 0
+            || size.height == 0
+    }
+
+    /// the visibility after `window_event`, a focus, occlusion or resize.
+    fn current_visibility(&self, window_event: &winit::event::WindowEvent) -> ViewportVisibility {
+        let mut visibility = self.visibility;
+        match window_event {
+            winit::event::WindowEvent::Focused(focused) => visibility.focused = *focused,
+            winit::event::WindowEvent::Occluded(occluded) => visibility.occluded = *occluded,
+            _ => {}
+        }
+        // winit has no minimize event, minimizing resizes or occludes the window on most platforms.
+        let size = self.window.inner_size();
+        visibility.minimized = self
+            .window
+            .is_minimized()
+            .unwrap_or(size.width == 0 || size.height == 0);
+        visibility
+    }
+
+    fn update_mouse_passthrough(&mut self, ctx: &egui::Context) {
+        let pixels_per_point = egui_winit::pixels_per_point(ctx, &self.window);
+        self.mouse_passthrough
+            .update(&self.window, pixels_per_point);
+    }
+
+    /// show the window once its first frame is on screen, as allowed by the show policy.
+    fn show_after_frame(&mut self, ctx: &egui::Context, presented: bool) {
+        if self.is_first_frame {
+            self.is_first_frame = false;
+        } else if show::can_show(ctx, presented) {
+            self.window.set_visible(true);
+        } else {
+            // keep painting until the window can be shown.
+            ctx.request_repaint_of(self.ids.this);
+        }
+    }
+
     fn update_viewport_info(&mut self, ctx: &egui::Context) {
         egui_winit::update_viewport_info(&mut self.info, ctx, &self.window, false);
     }
+
+    fn handle_platform_output(
+        &mut self,
+        ctx: &egui::Context,
+        event_loop: &ActiveEventLoop,
+        custom_cursors: &CustomCursors,
+        platform_output: egui::PlatformOutput,
+    ) {
+        let cursor_icon = platform_output.cursor_icon;
+        let ime = platform_output.ime;
+        #[cfg(feature = "accesskit")]
+        if let Some(update) = &platform_output.accesskit_update {
+            access_tree::store(ctx, self.ids.this, update);
+        }
+        self.state
+            .handle_platform_output(&self.window, platform_output);
+        custom_cursors.apply(
+            event_loop,
+            &self.window,
+            &mut self.custom_cursor_applied,
+            cursor_icon,
+        );
+        if !self.cursor_visible {
+            // egui-winit shows the cursor when the cursor icon changes.
+            self.window.set_cursor_visible(false);
+        }
+        self.update_ime_cursor_area(ctx, ime);
+    }
+
+    /// place the IME candidate window at the text cursor.
+    ///
+    /// egui-winit uses the whole text edit rect, so the candidate window of a multiline text edit
+    /// appears below the text edit instead of below the current line.
+    fn update_ime_cursor_area(
+        &mut self,
+        ctx: &egui::Context,
+        ime: Option<egui::output::IMEOutput>,
+    ) {
+        let Some(ime) = ime else {
+            self.ime_rects_px = None;
+            return;
+        };
+        let pixels_per_point = egui_winit::pixels_per_point(ctx, &self.window);
+        let rects_px = (
+            pixels_per_point * ime.rect,
+            pixels_per_point * ime.cursor_rect,
+        );
+        // egui-winit sets its own area under the same conditions, so it has to be overridden each time.
+        if self.ime_rects_px != Some(rects_px) || ctx.input(|i| !i.events.is_empty()) {
+            self.ime_rects_px = Some(rects_px);
+            let cursor_rect_px = rects_px.1;
+            self.window.set_ime_cursor_area(
+                winit::dpi::PhysicalPosition {
+                    x: cursor_rect_px.min.x,
+                    y: cursor_rect_px.min.y,
+                },
+                winit::dpi::PhysicalSize {
+                    width: cursor_rect_px.width(),
+                    height: cursor_rect_px.height(),
+                },
+            );
+        }
+    }
+
+    fn process_viewport_commands(
+        &mut self,
+        ctx: &egui::Context,
+        commands: impl IntoIterator<Item = egui::ViewportCommand>,
+    ) {
+        // cursor grab and visibility are tracked here to keep them across frames and focus changes.
+        let commands = commands
+            .into_iter()
+            .filter(|command| match command {
+                egui::ViewportCommand::CursorGrab(grab) => {
+                    self.set_cursor_grab(*grab);
+                    false
+                }
+                egui::ViewportCommand::CursorVisible(visible) => {
+                    self.cursor_visible = *visible;
+                    self.window.set_cursor_visible(*visible);
+                    false
+                }
+                egui::ViewportCommand::WindowLevel(level) => {
+                    window_level::store_window_level(ctx, self.ids.this, *level);
+                    true
+                }
+                egui::ViewportCommand::MousePassthrough(enabled) => {
+                    self.mouse_passthrough.set_passthrough(if *enabled {
+                        MousePassthrough::Enabled
+                    } else {
+                        MousePassthrough::Disabled
+                    });
+                    self.update_mouse_passthrough(ctx);
+                    false
+                }
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+
+        let mut _actions = Vec::new();
+        egui_winit::process_viewport_commands(
+            ctx,
+            &mut self.info,
+            commands,
+            &self.window,
+            &mut _actions,
+        );
+    }
+
+    fn set_cursor_grab(&mut self, grab: egui::viewport::CursorGrab) {
+        use egui::viewport::CursorGrab;
+        use winit::window::CursorGrabMode;
+
+        let (mode, fallback) = match grab {
+            CursorGrab::None => (CursorGrabMode::None, None),
+            CursorGrab::Confined => (CursorGrabMode::Confined, Some(CursorGrabMode::Locked)),
+            CursorGrab::Locked => (CursorGrabMode::Locked, Some(CursorGrabMode::Confined)),
+        };
+        // not every platform supports both modes, e.g. Windows and X11 have no locked mode.
+        let result = self
+            .window
+            .set_cursor_grab(mode)
+            .or_else(|err| match fallback {
+                Some(fallback) => self.window.set_cursor_grab(fallback),
+                None => Err(err),
+            });
+        match result {
+            Ok(()) => self.cursor_grab = grab,
+            Err(err) => warn!("Failed to set cursor grab {:?}: {}", grab, err),
+        }
+    }
+
+    /// release the cursor grab and show the cursor.
+    fn release_cursor(&mut self) {
+        if self.cursor_grab != egui::viewport::CursorGrab::None {
+            self.set_cursor_grab(egui::viewport::CursorGrab::None);
+        }
+        if !self.cursor_visible {
+            self.cursor_visible = true;
+            self.window.set_cursor_visible(true);
+        }
+    }
 }
 
 pub enum PaintResult {
@@ -56,6 +322,44 @@ pub enum PaintResult {
     Wait,
 }
 
+/// frames of the viewports painted in one event loop iteration, recorded and presented together.
+#[derive(Clone)]
+struct FrameQueue {
+    frames: Arc<Mutex<Vec<(egui::ViewportId, EguiCommand)>>>,
+    presenters: Arc<Mutex<Presenters>>,
+    render_thread: Option<RenderThread>,
+}
+impl FrameQueue {
+    fn push(&self, viewport_id: egui::ViewportId, egui_cmd: EguiCommand) {
+        self.frames.lock().unwrap().push((viewport_id, egui_cmd));
+    }
+
+    /// present the queued frames. Their textures are updated in the order they were queued,
+    /// so a frame recorded outside of the queue flushes it first.
+    fn flush(&self) {
+        let frames = std::mem::take(&mut *self.frames.lock().unwrap());
+        if frames.is_empty() {
+            return;
+        }
+        match &self.render_thread {
+            Some(render_thread) => render_thread.present(frames),
+            None => self.presenters.lock().unwrap().present_egui(frames),
+        }
+    }
+
+    /// present the queued frames and wait for the render thread,
+    /// before destroying resources the frames use.
+    fn finish(&self) {
+        self.flush();
+        if let Some(render_thread) = &self.render_thread {
+            render_thread.wait_idle();
+        }
+    }
+}
+
+/// user textures not drawn for this long are passed to [`crate::App::trim_memory`].
+const UNUSED_USER_TEXTURE_AGE: Duration = Duration::from_secs(1);
+
 pub(crate) struct Integration<A: Allocator + 'static> {
     _app_id: String,
     beginning: Instant,
@@ -67,9 +371,48 @@ pub(crate) struct Integration<A: Allocator + 'static> {
     window_id_to_viewport_id: Arc<Mutex<HashMap<winit::window::WindowId, egui::ViewportId>>>,
     viewports: Arc<Mutex<ViewportIdMap<Viewport>>>,
     focused_viewport: Arc<Mutex<Option<egui::ViewportId>>>,
+    repaint_deadlines: Arc<Mutex<ViewportIdMap<Instant>>>,
     max_texture_side: usize,
 
     theme: Option<winit::window::Theme>,
+    // theme of the windows set with `theme::set_theme_preference`, `None` follows the system.
+    window_theme: Option<winit::window::Theme>,
+    custom_cursors: CustomCursors,
+    scroll_options: ScrollOptions,
+    accelerators: Accelerators,
+    suspend_policy: SuspendPolicy,
+    suspended: bool,
+    render_thread: Option<RenderThread>,
+    frame_queue: FrameQueue,
+    frame_clock: FrameClock,
+    frame_stepper: FrameStepper,
+    // frame time of the manual step being painted.
+    step_dt: Option<Duration>,
+    frame_stats: FrameStatistics,
+    inspector: Inspector,
+    // time spent handling the window events of each viewport since its last frame.
+    event_handling_times: HashMap<egui::ViewportId, Duration>,
+    // causes of the next frame of each viewport, besides input and repaint requests.
+    repaint_causes: HashMap<egui::ViewportId, Vec<RepaintCause>>,
+    viewport_drag: ViewportDrag,
+    // by source viewport.
+    mirror_windows: HashMap<egui::ViewportId, MirrorWindow>,
+    gpu_hangs: GpuHangs,
+    stale_swapchains: StaleSwapchains,
+    #[cfg(feature = "menu")]
+    native_menu: NativeMenu,
+    #[cfg(feature = "tray")]
+    viewports_hidden: bool,
+    // for the AccessKit adapters of new viewport windows.
+    #[cfg(feature = "accesskit")]
+    event_loop_proxy: winit::event_loop::EventLoopProxy<IntegrationEvent>,
+    // viewports whose AccessKit adapter is active, egui builds the trees while any is.
+    #[cfg(feature = "accesskit")]
+    accesskit_viewports: HashSet<egui::ViewportId>,
+    #[cfg(feature = "replay")]
+    input_recorder: Option<InputRecorder>,
+    #[cfg(feature = "replay")]
+    input_replay: Option<InputReplay>,
 
     #[cfg(feature = "persistence")]
     pub(crate) storage: Storage,
@@ -78,43 +421,111 @@ pub(crate) struct Integration<A: Allocator + 'static> {
     #[cfg(feature = "persistence")]
     persistent_egui_memory: bool,
     #[cfg(feature = "persistence")]
+    save_on_focus_loss: bool,
+    #[cfg(feature = "persistence")]
+    save_requested: bool,
+    #[cfg(feature = "persistence")]
+    layout_profiles: LayoutProfiles,
+    #[cfg(feature = "persistence")]
     last_auto_save: Instant,
 }
 
+/// options of the [`Integration`], built from [`crate::RunOption`] by `run`.
+pub(crate) struct IntegrationOptions {
+    pub(crate) app_id: String,
+    pub(crate) root_builder: egui::ViewportBuilder,
+    pub(crate) theme: Option<winit::window::Theme>,
+    pub(crate) custom_cursors: CustomCursors,
+    pub(crate) scroll_options: ScrollOptions,
+    pub(crate) accelerators: Accelerators,
+    pub(crate) suspend_policy: SuspendPolicy,
+    pub(crate) render_thread: bool,
+    pub(crate) presenter: PresenterOptions,
+    pub(crate) renderer: RendererOptions,
+    pub(crate) frame_stats: FrameStatistics,
+    pub(crate) submit_hooks: SubmitHooks,
+    pub(crate) frame_stepper: FrameStepper,
+    #[cfg(feature = "menu")]
+    pub(crate) native_menu: NativeMenu,
+    #[cfg(feature = "persistence")]
+    pub(crate) storage: Storage,
+    #[cfg(feature = "persistence")]
+    pub(crate) persistent_windows: bool,
+    #[cfg(feature = "persistence")]
+    pub(crate) persistent_egui_memory: bool,
+    #[cfg(feature = "persistence")]
+    pub(crate) save_on_focus_loss: bool,
+    #[cfg(feature = "persistence")]
+    pub(crate) layout_profiles: LayoutProfiles,
+}
+
 impl<A: Allocator + 'static> Integration<A> {
-    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
-        app_id: &str,
         event_loop: &ActiveEventLoop,
         context: egui::Context,
         main_window: winit::window::Window,
         render_state: AshRenderState<A>,
-        present_mode: ash::vk::PresentModeKHR,
         receiver: ImageRegistryReceiver,
-        theme: Option<winit::window::Theme>,
-        #[cfg(feature = "accesskit")] event_loop_proxy: &winit::event_loop::EventLoopProxy<IntegrationEvent>,
-        #[cfg(feature = "persistence")] storage: Storage,
-        #[cfg(feature = "persistence")] persistent_windows: bool,
-        #[cfg(feature = "persistence")] persistent_egui_memory: bool,
+        event_loop_proxy: &winit::event_loop::EventLoopProxy<IntegrationEvent>,
+        options: IntegrationOptions,
     ) -> Self {
+        let IntegrationOptions {
+            app_id,
+            root_builder,
+            theme,
+            custom_cursors,
+            scroll_options,
+            accelerators,
+            suspend_policy,
+            render_thread,
+            presenter,
+            renderer,
+            frame_stats,
+            submit_hooks,
+            frame_stepper,
+            #[cfg(feature = "menu")]
+            native_menu,
+            #[cfg(feature = "persistence")]
+            storage,
+            #[cfg(feature = "persistence")]
+            persistent_windows,
+            #[cfg(feature = "persistence")]
+            persistent_egui_memory,
+            #[cfg(feature = "persistence")]
+            save_on_focus_loss,
+            #[cfg(feature = "persistence")]
+            layout_profiles,
+        } = options;
+        let inspector = Inspector::install(&context);
         let presenters = Arc::new(Mutex::new(Presenters::new(
-            render_state.entry.clone(),
-            render_state.instance.clone(),
-            render_state.physical_device,
-            render_state.device.clone(),
-            render_state.surface_loader.clone(),
-            render_state.swapchain_loader.clone(),
-            render_state.queue,
-            render_state.command_pool,
-            present_mode,
+            &render_state,
+            presenter,
+            frame_stats.clone(),
+            inspector.clone(),
+            submit_hooks,
         )));
+        let gpu_hangs = presenters.lock().unwrap().gpu_hangs();
+        let stale_swapchains = presenters.lock().unwrap().stale_swapchains();
+        let limits = unsafe {
+            let properties = render_state
+                .instance
+                .get_physical_device_properties(render_state.physical_device);
+            properties.limits
+        };
         let renderer = Renderer::new(
-            render_state.device.clone(),
-            render_state.queue,
-            render_state.queue_family_index,
-            render_state.allocator,
+            &render_state,
+            &limits,
             receiver,
+            renderer,
+            frame_stats.clone(),
+            inspector.clone(),
         );
+        let render_thread = render_thread.then(|| RenderThread::spawn(presenters.clone()));
+        let frame_queue = FrameQueue {
+            frames: Arc::new(Mutex::new(vec![])),
+            presenters: presenters.clone(),
+            render_thread: render_thread.clone(),
+        };
 
         let main_window_id = main_window.id();
 
@@ -130,21 +541,17 @@ impl<A: Allocator + 'static> Integration<A> {
         // use native window viewports
         context.set_embed_viewports(false);
 
-        let limits = unsafe {
-            let properties = render_state
-                .instance
-                .get_physical_device_properties(render_state.physical_device);
-            properties.limits
-        };
-        let max_texture_side = limits.max_image_dimension2_d as usize;
+        let max_texture_side = renderer::max_texture_side(&limits);
 
-        let root_state = egui_winit::State::new(
-            context.clone(),
+        let root_state = create_viewport_state(
+            &context,
+            event_loop,
             egui::ViewportId::ROOT,
-            &event_loop,
-            Some(main_window.scale_factor() as f32),
+            &main_window,
             theme,
-            Some(max_texture_side),
+            max_texture_side,
+            #[cfg(feature = "accesskit")]
+            event_loop_proxy,
         );
 
         let mut window_id_to_viewport_id = HashMap::new();
@@ -152,32 +559,27 @@ impl<A: Allocator + 'static> Integration<A> {
         let window_id_to_viewport_id = Arc::new(Mutex::new(window_id_to_viewport_id));
 
         let viewports = Arc::new(Mutex::new(ViewportIdMap::new()));
-        #[allow(unused_mut)] // for accesskit
-        let mut root_viewport = Viewport {
+        if let Some(level) = root_builder.window_level {
+            window_level::store_window_level(&context, egui::ViewportId::ROOT, level);
+        }
+        let root_viewport = Viewport {
             ids: egui::ViewportIdPair::ROOT,
             class: egui::ViewportClass::Root,
-            builder: egui::ViewportBuilder::default(),
+            mouse_passthrough: PassthroughState::new(&root_builder),
+            builder: root_builder,
             info: egui::ViewportInfo::default(),
             is_first_frame: true,
+            visibility: ViewportVisibility::default(),
             window: main_window,
             state: root_state,
             ui_cb: None,
+            custom_cursor_applied: false,
+            cursor_grab: egui::viewport::CursorGrab::None,
+            cursor_visible: true,
+            ime_rects_px: None,
+            child_rect: None,
         };
 
-        #[cfg(feature = "accesskit")]
-        {
-            //let ctx = context.clone();
-            root_viewport.state.init_accesskit(
-                event_loop,
-                &root_viewport.window,
-                event_loop_proxy.clone(),
-                /*move || {
-                    ctx.enable_accesskit();
-                    ctx.request_repaint();
-                    ctx.accesskit_placeholder_tree_update()
-                },*/
-            );
-        }
         {
             let mut viewports = viewports.lock().unwrap();
             viewports.insert(egui::ViewportId::ROOT, root_viewport);
@@ -185,23 +587,46 @@ impl<A: Allocator + 'static> Integration<A> {
 
         let focused_viewport = Arc::new(Mutex::new(None));
 
+        // only the viewports egui requested a repaint for are painted.
+        let mut repaint_deadlines = ViewportIdMap::new();
+        repaint_deadlines.insert(egui::ViewportId::ROOT, Instant::now());
+        let repaint_deadlines = Arc::new(Mutex::new(repaint_deadlines));
+        context.set_request_repaint_callback({
+            let repaint_deadlines = repaint_deadlines.clone();
+            let event_loop_proxy = event_loop_proxy.clone();
+            move |info| {
+                let deadline = Instant::now() + info.delay;
+                let mut repaint_deadlines = repaint_deadlines.lock().unwrap();
+                let entry = repaint_deadlines
+                    .entry(info.viewport_id)
+                    .or_insert(deadline);
+                *entry = (*entry).min(deadline);
+                drop(repaint_deadlines);
+                let _ = event_loop_proxy.send_event(IntegrationEvent::RequestRepaint);
+            }
+        });
+
         egui::Context::set_immediate_viewport_renderer(immediate_viewport_renderer(
             &presenters,
             &renderer,
             &viewports,
             &window_id_to_viewport_id,
             &focused_viewport,
+            &frame_queue,
             max_texture_side,
             theme,
+            &custom_cursors,
             #[cfg(feature = "persistence")]
             &storage,
             #[cfg(feature = "persistence")]
             persistent_windows,
+            #[cfg(feature = "accesskit")]
+            event_loop_proxy,
             event_loop,
         ));
 
         Self {
-            _app_id: app_id.to_owned(),
+            _app_id: app_id,
             beginning: Instant::now(),
 
             presenters,
@@ -211,9 +636,41 @@ impl<A: Allocator + 'static> Integration<A> {
             window_id_to_viewport_id,
             viewports,
             focused_viewport,
+            repaint_deadlines,
             max_texture_side,
 
             theme,
+            window_theme: None,
+            custom_cursors,
+            scroll_options,
+            accelerators,
+            suspend_policy,
+            suspended: false,
+            render_thread,
+            frame_queue,
+            frame_clock: FrameClock::default(),
+            frame_stepper,
+            step_dt: None,
+            frame_stats,
+            inspector,
+            event_handling_times: HashMap::new(),
+            repaint_causes: HashMap::new(),
+            viewport_drag: ViewportDrag::default(),
+            mirror_windows: HashMap::new(),
+            gpu_hangs,
+            stale_swapchains,
+            #[cfg(feature = "menu")]
+            native_menu,
+            #[cfg(feature = "tray")]
+            viewports_hidden: false,
+            #[cfg(feature = "accesskit")]
+            event_loop_proxy: event_loop_proxy.clone(),
+            #[cfg(feature = "accesskit")]
+            accesskit_viewports: HashSet::new(),
+            #[cfg(feature = "replay")]
+            input_recorder: None,
+            #[cfg(feature = "replay")]
+            input_replay: None,
 
             #[cfg(feature = "persistence")]
             storage,
@@ -222,6 +679,12 @@ impl<A: Allocator + 'static> Integration<A> {
             #[cfg(feature = "persistence")]
             persistent_egui_memory,
             #[cfg(feature = "persistence")]
+            save_on_focus_loss,
+            #[cfg(feature = "persistence")]
+            save_requested: false,
+            #[cfg(feature = "persistence")]
+            layout_profiles,
+            #[cfg(feature = "persistence")]
             last_auto_save: Instant::now(),
         }
     }
@@ -234,6 +697,114 @@ impl<A: Allocator + 'static> Integration<A> {
         window_id_to_viewport_id.get(&window_id).copied()
     }
 
+    /// add the time spent handling an event of the window to the stats of its next frame.
+    pub(crate) fn add_event_handling_time(
+        &mut self,
+        window_id: winit::window::WindowId,
+        duration: Duration,
+    ) {
+        if let Some(viewport_id) = self.viewport_id_from_window_id(window_id) {
+            *self.event_handling_times.entry(viewport_id).or_default() += duration;
+        }
+    }
+
+    /// viewports whose frames hung on the GPU since the last call.
+    pub(crate) fn take_gpu_hangs(&self) -> Vec<egui::ViewportId> {
+        std::mem::take(&mut *self.gpu_hangs.lock().unwrap())
+    }
+
+    /// repaint the viewports whose swapchain went suboptimal, so that it is recreated right away
+    /// instead of on the next input.
+    pub(crate) fn repaint_stale_swapchains(&mut self) {
+        let stale_swapchains = std::mem::take(&mut *self.stale_swapchains.lock().unwrap());
+        for viewport_id in stale_swapchains {
+            repaint::push_cause(
+                self.repaint_causes.entry(viewport_id).or_default(),
+                RepaintCause::Window,
+            );
+            self.context.request_repaint_of(viewport_id);
+        }
+    }
+
+    /// open and close the mirror windows requested with [`crate::Mirrors`], and create or recreate
+    /// their swapchains.
+    pub(crate) fn update_mirrors(&mut self, event_loop: &ActiveEventLoop) {
+        let requests = mirror::take_requests(&self.context);
+        let viewports = self.viewports.lock().unwrap();
+        let mut presenters = self.presenters.lock().unwrap();
+        for (source, options) in requests {
+            if self.mirror_windows.remove(&source).is_some() {
+                presenters.stop_mirror(source);
+            }
+            let (Some(options), Some(viewport)) = (options, viewports.get(&source)) else {
+                continue;
+            };
+            if let Some(mirror_window) = MirrorWindow::create(event_loop, &viewport.window, options)
+            {
+                self.mirror_windows.insert(source, mirror_window);
+            }
+        }
+        // the mirrors of closed viewports close with them.
+        self.mirror_windows.retain(|source, _| {
+            let open = viewports.contains_key(source);
+            if !open {
+                presenters.stop_mirror(*source);
+            }
+            open
+        });
+        if self.suspended {
+            return;
+        }
+        for (&source, mirror_window) in &self.mirror_windows {
+            let Some(viewport) = viewports.get(&source) else {
+                continue;
+            };
+            let created = presenters.update_mirror(
+                source,
+                &mirror_window.window,
+                mirror_window.window.inner_size(),
+                viewport.window.scale_factor() as f32,
+                mirror_window.options.crop,
+                mirror_window.options.fit,
+            );
+            if created {
+                self.context.request_repaint_of(source);
+            }
+        }
+    }
+
+    /// handle the events of the mirror windows, returns whether `window_id` is one.
+    pub(crate) fn handle_mirror_window_event(
+        &mut self,
+        window_id: winit::window::WindowId,
+        window_event: &winit::event::WindowEvent,
+    ) -> bool {
+        let Some(source) = self
+            .mirror_windows
+            .iter()
+            .find(|(_, mirror_window)| mirror_window.window.id() == window_id)
+            .map(|(&source, _)| source)
+        else {
+            return false;
+        };
+        match window_event {
+            winit::event::WindowEvent::CloseRequested => {
+                self.mirror_windows.remove(&source);
+                self.presenters.lock().unwrap().stop_mirror(source);
+            }
+            winit::event::WindowEvent::Resized(_)
+            | winit::event::WindowEvent::ScaleFactorChanged { .. }
+            | winit::event::WindowEvent::Occluded(false) => {
+                self.presenters.lock().unwrap().dirty_mirror(source);
+            }
+            winit::event::WindowEvent::RedrawRequested => {
+                self.context.request_repaint_of(source);
+            }
+            _ => {}
+        }
+        true
+    }
+
     pub(crate) fn get_viewport_size(
         &self,
         viewport_id: egui::ViewportId,
@@ -248,9 +819,16 @@ impl<A: Allocator + 'static> Integration<A> {
         window_id: winit::window::WindowId,
         window_event: &winit::event::WindowEvent,
         event_loop: &ActiveEventLoop,
-        follow_system_theme: bool,
         app: &mut impl crate::App,
     ) -> bool {
+        if let winit::event::WindowEvent::ThemeChanged(theme) = window_event {
+            self.system_theme_changed(*theme, app);
+        }
+
+        let mut accelerators_triggered = vec![];
+        #[cfg(feature = "menu")]
+        let mut menu_activated = vec![];
+        let mut visibility_changed = None;
         let event_response = {
             let window_id_to_viewport_id = self.window_id_to_viewport_id.lock().unwrap();
             let Some(&viewport_id) = window_id_to_viewport_id.get(&window_id) else {
@@ -263,25 +841,59 @@ impl<A: Allocator + 'static> Integration<A> {
             };
 
             match window_event {
-                winit::event::WindowEvent::ThemeChanged(theme) => {
-                    if follow_system_theme {
-                        viewport.window.set_theme(Some(*theme));
-                    }
-                }
                 winit::event::WindowEvent::Focused(focused) => {
                     if *focused {
                         *self.focused_viewport.lock().unwrap() = Some(viewport_id);
+                        activation::request_token(&viewport.window);
                     } else {
                         *self.focused_viewport.lock().unwrap() = None;
+                        viewport.release_cursor();
+                        #[cfg(feature = "persistence")]
+                        if self.save_on_focus_loss {
+                            self.save_requested = true;
+                        }
                     }
                 }
+                winit::event::WindowEvent::ActivationTokenDone { token, .. } => {
+                    activation::store_token(&self.context, token.clone());
+                }
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    viewport
+                        .mouse_passthrough
+                        .cursor_moved(&viewport.window, *position);
+                    viewport.update_mouse_passthrough(&self.context);
+                }
+                winit::event::WindowEvent::MouseInput { state, .. } => {
+                    viewport.mouse_passthrough.mouse_input(*state);
+                    viewport.update_mouse_passthrough(&self.context);
+                }
                 winit::event::WindowEvent::Resized(_) => {
                     let mut presenters = self.presenters.lock().unwrap();
                     presenters.dirty_swapchain(viewport_id);
+                    repaint::push_cause(
+                        self.repaint_causes.entry(viewport_id).or_default(),
+                        RepaintCause::Window,
+                    );
                 }
                 winit::event::WindowEvent::ScaleFactorChanged { .. } => {
                     let mut presenters = self.presenters.lock().unwrap();
                     presenters.dirty_swapchain(viewport_id);
+                    repaint::push_cause(
+                        self.repaint_causes.entry(viewport_id).or_default(),
+                        RepaintCause::Window,
+                    );
+                }
+                winit::event::WindowEvent::Occluded(occluded) => {
+                    if !occluded {
+                        // the swapchain may be out of date after the window was hidden.
+                        let mut presenters = self.presenters.lock().unwrap();
+                        presenters.dirty_swapchain(viewport_id);
+                        repaint::push_cause(
+                            self.repaint_causes.entry(viewport_id).or_default(),
+                            RepaintCause::Window,
+                        );
+                        self.context.request_repaint_of(viewport_id);
+                    }
                 }
                 winit::event::WindowEvent::CloseRequested => {
                     if viewport_id == egui::ViewportId::ROOT {
@@ -294,17 +906,67 @@ impl<A: Allocator + 'static> Integration<A> {
                 _ => {}
             }
 
+            let event_count = viewport.state.egui_input_mut().events.len();
             let event_response = viewport
                 .state
                 .on_window_event(&viewport.window, window_event);
+            if let winit::event::WindowEvent::MouseWheel { .. } = window_event {
+                self.scroll_options
+                    .apply(&mut viewport.state.egui_input_mut().events, event_count);
+            }
+            if let winit::event::WindowEvent::KeyboardInput { .. } = window_event {
+                accelerators_triggered = self
+                    .accelerators
+                    .take_triggered(&mut viewport.state.egui_input_mut().events, event_count);
+            }
+            #[cfg(feature = "menu")]
+            if let winit::event::WindowEvent::KeyboardInput { .. } = window_event {
+                menu_activated = self
+                    .native_menu
+                    .take_shortcuts(&mut viewport.state.egui_input_mut().events, event_count);
+            }
 
             if event_response.repaint {
                 viewport.window.request_redraw();
             }
 
+            if let winit::event::WindowEvent::Focused(_)
+            | winit::event::WindowEvent::Occluded(_)
+            | winit::event::WindowEvent::Resized(_) = window_event
+            {
+                let visibility = viewport.current_visibility(window_event);
+                if visibility != viewport.visibility {
+                    viewport.visibility = visibility;
+                    visibility_changed = Some((viewport_id, visibility));
+                }
+            }
+
             event_response
         };
 
+        self.forward_drag(window_id, window_event);
+
+        if let Some((viewport_id, visibility)) = visibility_changed {
+            app.handle_event(crate::event::Event::VisibilityChanged {
+                viewport_id,
+                visibility,
+            });
+        }
+
+        if !accelerators_triggered.is_empty() {
+            for id in accelerators_triggered {
+                app.handle_event(crate::event::Event::AcceleratorTriggered { id });
+            }
+            return true;
+        }
+        #[cfg(feature = "menu")]
+        if !menu_activated.is_empty() {
+            for id in menu_activated {
+                app.handle_event(crate::event::Event::MenuActivated { id });
+            }
+            return true;
+        }
+
         if window_event == &winit::event::WindowEvent::RedrawRequested {
             self.paint(event_loop, window_id, app);
         }
@@ -312,30 +974,336 @@ impl<A: Allocator + 'static> Integration<A> {
         event_response.consumed
     }
 
-    #[cfg(feature = "accesskit")]
-    pub(crate) fn handle_accesskit_event(
+    /// forward the pointer to the viewport under it while an egui payload is dragged out of the
+    /// window holding the pointer, see [`crate::viewport_drag`].
+    fn forward_drag(
+        &mut self,
+        window_id: winit::window::WindowId,
+        window_event: &winit::event::WindowEvent,
+    ) {
+        let released = match window_event {
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                self.viewport_drag.cursor_moved(*position);
+                false
+            }
+            winit::event::WindowEvent::MouseInput {
+                state: winit::event::ElementState::Released,
+                ..
+            } => true,
+            _ => return,
+        };
+        let Some(source_id) = self
+            .window_id_to_viewport_id
+            .lock()
+            .unwrap()
+            .get(&window_id)
+            .copied()
+        else {
+            return;
+        };
+
+        let mut viewports = self.viewports.lock().unwrap();
+        let target = if egui::DragAndDrop::has_any_payload(&self.context) {
+            drag_target(
+                &self.context,
+                &viewports,
+                source_id,
+                self.viewport_drag.cursor(),
+            )
+        } else {
+            None
+        };
+        if let Some(previous) = self
+            .viewport_drag
+            .set_target(target.map(|(target_id, _)| target_id))
+        {
+            if let Some(viewport) = viewports.get_mut(&previous) {
+                viewport
+                    .state
+                    .egui_input_mut()
+                    .events
+                    .push(egui::Event::PointerGone);
+                self.context.request_repaint_of(previous);
+            }
+        }
+        let Some((target_id, pos)) = target else {
+            return;
+        };
+
+        // the release is taken from the source, it sees it after the target dropped the payload.
+        let release = if released {
+            viewports.get_mut(&source_id).and_then(|source| {
+                let events = &mut source.state.egui_input_mut().events;
+                let index = events.iter().rposition(|event| {
+                    matches!(event, egui::Event::PointerButton { pressed: false, .. })
+                })?;
+                Some(events.remove(index))
+            })
+        } else {
+            None
+        };
+        let Some(target) = viewports.get_mut(&target_id) else {
+            return;
+        };
+        let events = &mut target.state.egui_input_mut().events;
+        events.push(egui::Event::PointerMoved(pos));
+        if let Some(release) = release {
+            if let egui::Event::PointerButton {
+                button, modifiers, ..
+            } = release
+            {
+                events.push(egui::Event::PointerButton {
+                    pos,
+                    button,
+                    pressed: false,
+                    modifiers,
+                });
+            }
+            self.viewport_drag
+                .defer_release(source_id, target_id, release);
+            self.viewport_drag.set_target(None);
+        }
+        self.context.request_repaint_of(target_id);
+    }
+
+    /// deliver the release held back by [`Self::forward_drag`] once the target viewport painted.
+    fn deliver_drag_release(&mut self, painted: egui::ViewportId) {
+        let Some((source_id, release)) = self.viewport_drag.take_release(painted) else {
+            return;
+        };
+        let mut viewports = self.viewports.lock().unwrap();
+        if let Some(source) = viewports.get_mut(&source_id) {
+            source.state.egui_input_mut().events.push(release);
+            self.context.request_repaint_of(source_id);
+        }
+    }
+
+    pub(crate) fn handle_device_event(&mut self, device_event: &winit::event::DeviceEvent) {
+        let winit::event::DeviceEvent::MouseMotion { delta } = device_event else {
+            return;
+        };
+        let mut viewports = self.viewports.lock().unwrap();
+        for viewport in viewports.values_mut() {
+            if viewport.mouse_passthrough.passthrough() == MousePassthrough::OutsideEgui {
+                viewport.mouse_passthrough.mouse_motion(*delta);
+                viewport.update_mouse_passthrough(&self.context);
+            }
+        }
+    }
+
+    // every window reports the change of the system theme, restyle all viewports on the first.
+    fn system_theme_changed(&mut self, theme: winit::window::Theme, app: &mut impl crate::App) {
+        if self.theme == Some(theme) {
+            return;
+        }
+        self.theme = Some(theme);
+
+        let egui_theme = theme::egui_theme(theme);
+        {
+            let mut viewports = self.viewports.lock().unwrap();
+            for (&viewport_id, viewport) in viewports.iter_mut() {
+                viewport.state.egui_input_mut().system_theme = Some(egui_theme);
+                self.context.request_repaint_of(viewport_id);
+            }
+        }
+        app.handle_event(crate::event::Event::AppEvent {
+            event: crate::event::AppEvent::ThemeChanged(egui_theme),
+        });
+    }
+
+    /// move the windows left off-screen back onto a monitor and recreate the swapchains,
+    /// after a monitor was connected, disconnected or changed its mode.
+    pub(crate) fn monitors_changed(
         &mut self,
-        event: &AccessKitEvent,
         event_loop: &ActiveEventLoop,
         app: &mut impl crate::App,
     ) {
-        let AccessKitEvent {
-            window_id,
-            window_event,
-        } = event;
         {
-            use egui_winit::accesskit_winit::WindowEvent;
+            let viewports = self.viewports.lock().unwrap();
+            let mut presenters = self.presenters.lock().unwrap();
+            for (&viewport_id, viewport) in viewports.iter() {
+                // child windows are placed in their parent window.
+                if viewport.child_rect.is_none() {
+                    monitors::clamp_to_monitors(&viewport.window, event_loop);
+                }
+                presenters.dirty_swapchain(viewport_id);
+                repaint::push_cause(
+                    self.repaint_causes.entry(viewport_id).or_default(),
+                    RepaintCause::Window,
+                );
+                self.context.request_repaint_of(viewport_id);
+            }
+        }
+        app.handle_event(crate::event::Event::AppEvent {
+            event: crate::event::AppEvent::MonitorsChanged,
+        });
+    }
 
-            let Some(viewport_id) = self.viewport_id_from_window_id(*window_id) else {
-                return;
-            };
-            let mut viewports = self.viewports.lock().unwrap();
-            let viewport = viewports.get_mut(&viewport_id).unwrap();
-            if let WindowEvent::ActionRequested(request) = window_event {
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    pub(crate) fn start_drag(
+        &self,
+        request: drag::DragRequest,
+        event_loop_proxy: &winit::event_loop::EventLoopProxy<IntegrationEvent>,
+    ) {
+        let window_handle = {
+            let viewports = self.viewports.lock().unwrap();
+            let viewport_id = self
+                .focused_viewport
+                .lock()
+                .unwrap()
+                .unwrap_or(egui::ViewportId::ROOT);
+            viewports
+                .get(&viewport_id)
+                .or_else(|| viewports.get(&egui::ViewportId::ROOT))
+                .and_then(|viewport| drag::window_handle(&viewport.window))
+        };
+        let Some(window_handle) = window_handle else {
+            error!("Failed to start drag: no window to drag from");
+            return;
+        };
+
+        let event_loop_proxy = event_loop_proxy.clone();
+        if let Err(err) = drag::start_drag(window_handle, request, move |result| {
+            let _ = event_loop_proxy.send_event(IntegrationEvent::DragFinished(result));
+        }) {
+            error!("Failed to start drag: {}", err);
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn handle_gamepad_events(&mut self, events: Vec<egui::Event>) {
+        if events.is_empty() {
+            return;
+        }
+        // gamepad input goes to the focused viewport, like keyboard input.
+        let Some(viewport_id) = *self.focused_viewport.lock().unwrap() else {
+            return;
+        };
+        let mut viewports = self.viewports.lock().unwrap();
+        let Some(viewport) = viewports.get_mut(&viewport_id) else {
+            return;
+        };
+        viewport.state.egui_input_mut().events.extend(events);
+        viewport.window.request_redraw();
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        if self.suspend_policy != SuspendPolicy::ReleaseResources {
+            return;
+        }
+        self.suspended = true;
+        self.frame_queue.finish();
+        let mut renderer = self.renderer.lock().unwrap();
+        renderer.release_resources();
+        // the swapchains are created again when the viewports are painted.
+        self.presenters.lock().unwrap().destroy_root();
+    }
+
+    pub(crate) fn resume(&mut self) {
+        if !self.suspended {
+            return;
+        }
+        self.suspended = false;
+        self.context.request_repaint();
+    }
+
+    /// whether the root window is on screen, after its first frames.
+    #[cfg(feature = "splash")]
+    pub(crate) fn is_root_shown(&self) -> bool {
+        let viewports = self.viewports.lock().unwrap();
+        viewports
+            .get(&egui::ViewportId::ROOT)
+            .is_some_and(|root| !root.is_first_frame && root.window.is_visible() != Some(false))
+    }
+
+    #[cfg(feature = "tray")]
+    pub(crate) fn set_viewports_visible(&mut self, visible: bool) {
+        self.viewports_hidden = !visible;
+        let viewports = self.viewports.lock().unwrap();
+        for viewport in viewports.values() {
+            viewport.window.set_visible(visible);
+        }
+        if !visible {
+            return;
+        }
+        if let Some(root) = viewports.get(&egui::ViewportId::ROOT) {
+            root.window.focus_window();
+        }
+        drop(viewports);
+        self.context.request_repaint();
+    }
+
+    /// record the input of the frames to `record`, and replay the recording of `replay`.
+    #[cfg(feature = "replay")]
+    pub(crate) fn set_input_replay(
+        &mut self,
+        record: Option<&std::path::Path>,
+        replay: Option<&std::path::Path>,
+    ) {
+        self.input_recorder = record.and_then(|path| {
+            InputRecorder::create(path)
+                .inspect_err(|err| error!("Failed to record the input to {path:?}: {err}"))
+                .ok()
+        });
+        self.input_replay = replay.and_then(|path| {
+            InputReplay::open(path)
+                .inspect_err(|err| error!("Failed to replay the input of {path:?}: {err}"))
+                .ok()
+        });
+    }
+
+    /// route an AccessKit event to the viewport of its window.
+    ///
+    /// The tree of each viewport is built by egui in its pass and passed to the adapter of its window
+    /// with the platform output.
+    #[cfg(feature = "accesskit")]
+    pub(crate) fn handle_accesskit_event(&mut self, event: &AccessKitEvent) {
+        use egui_winit::accesskit_winit::WindowEvent;
+
+        let Some(viewport_id) = self.viewport_id_from_window_id(event.window_id) else {
+            return;
+        };
+        match &event.window_event {
+            WindowEvent::InitialTreeRequested => {
+                self.accesskit_viewports.insert(viewport_id);
+                self.context.enable_accesskit();
+            }
+            WindowEvent::ActionRequested(request) => {
+                let mut viewports = self.viewports.lock().unwrap();
+                let Some(viewport) = viewports.get_mut(&viewport_id) else {
+                    return;
+                };
                 viewport.state.on_accesskit_action_request(request.clone());
             }
+            WindowEvent::AccessibilityDeactivated => {
+                self.accesskit_viewports.remove(&viewport_id);
+                if self.accesskit_viewports.is_empty() {
+                    self.context.disable_accesskit();
+                }
+                return;
+            }
+        }
+        self.context.request_repaint_of(viewport_id);
+    }
+
+    fn next_root_frame(&mut self) -> crate::FrameInfo {
+        let viewports = self.viewports.lock().unwrap();
+        let window = &viewports[&egui::ViewportId::ROOT].window;
+        let refresh_period = window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map(|millihertz| Duration::from_secs_f64(1000.0 / f64::from(millihertz)));
+        let mut frame_info = self.frame_clock.next_frame(
+            &self.context,
+            egui::ViewportId::ROOT,
+            window.inner_size(),
+            refresh_period,
+        );
+        if let Some(dt) = self.step_dt {
+            frame_info.delta_time = dt;
+            frame_info.predicted_present_time = None;
         }
-        self.paint(event_loop, *window_id, app);
+        frame_info
     }
 
     pub(crate) fn run_ui_and_record_paint_cmd(
@@ -345,17 +1313,17 @@ impl<A: Allocator + 'static> Integration<A> {
         window_id: winit::window::WindowId,
         create_swapchain_internal: bool,
     ) -> (Option<EguiCommand>, PaintResult) {
-        let (viewport_id, viewport_ui_cb, raw_input) = {
+        let (viewport_id, viewport_ui_cb, mut raw_input) = {
             let window_id_to_viewport_id = self.window_id_to_viewport_id.lock().unwrap();
             let Some(viewport_id) = window_id_to_viewport_id.get(&window_id).copied() else {
-                log::error!("window_id not found");
+                error!("window_id not found");
                 return (None, PaintResult::Wait);
             };
 
             if viewport_id != egui::ViewportId::ROOT {
                 let viewports = self.viewports.lock().unwrap();
                 let Some(viewport) = viewports.get(&viewport_id) else {
-                    log::error!("viewport not found");
+                    error!("viewport not found");
                     return (None, PaintResult::Wait);
                 };
 
@@ -372,31 +1340,75 @@ impl<A: Allocator + 'static> Integration<A> {
 
             let mut viewports = self.viewports.lock().unwrap();
             let Some(viewport) = viewports.get_mut(&viewport_id) else {
-                log::error!("viewport not found");
+                error!("viewport not found");
                 return (None, PaintResult::Wait);
             };
             viewport.update_viewport_info(&self.context);
 
             let viewport_ui_cb = viewport.ui_cb.clone();
 
+            clear_color::set_transparent(
+                &self.context,
+                viewport_id,
+                viewport.builder.transparent.unwrap_or(false),
+            );
             let mut presenters = self.presenters.lock().unwrap();
             if create_swapchain_internal {
-                presenters.recreate_swapchain_if_needed(viewport_id, &viewport.window);
+                presenters.recreate_swapchain_if_needed(
+                    viewport_id,
+                    &viewport.window,
+                    viewport.window.inner_size(),
+                    viewport.builder.transparent.unwrap_or(false),
+                );
             } else {
                 presenters.destroy_swapchain_if_needed(viewport_id);
             }
 
             let mut raw_input = viewport.state.take_egui_input(&viewport.window);
 
-            raw_input.time = Some(self.beginning.elapsed().as_secs_f64());
+            raw_input.time = Some(
+                self.frame_stepper
+                    .time()
+                    .unwrap_or_else(|| self.beginning.elapsed())
+                    .as_secs_f64(),
+            );
+            if let Some(dt) = self.step_dt {
+                raw_input.predicted_dt = dt.as_secs_f32();
+            }
             raw_input.viewports = viewports
                 .iter()
                 .map(|(id, viewport)| (*id, viewport.info.clone()))
                 .collect();
 
+            #[cfg(feature = "replay")]
+            if let Some(replay) = &mut self.input_replay {
+                // the live input is dropped while replaying.
+                let replayed = replay.next_input(&self.context, viewport_id);
+                let finished = replay.is_finished();
+                if finished {
+                    debug!("Finished replaying the input");
+                    self.input_replay = None;
+                }
+                match replayed {
+                    Some(input) => raw_input = input,
+                    None if finished => {}
+                    None => return (None, PaintResult::Wait),
+                }
+            }
+            #[cfg(feature = "replay")]
+            if let Some(recorder) = &mut self.input_recorder {
+                if let Err(err) = recorder.record(viewport_id, &raw_input) {
+                    error!("Failed to record the input, stopped recording: {err}");
+                    self.input_recorder = None;
+                }
+            }
+
             (viewport_id, viewport_ui_cb, raw_input)
         };
+        app.on_raw_input(viewport_id, &mut raw_input);
+        let frame_info = viewport_ui_cb.is_none().then(|| self.next_root_frame());
 
+        let mut interactive_rects = vec![];
         let egui::FullOutput {
             platform_output,
             textures_delta,
@@ -406,15 +1418,56 @@ impl<A: Allocator + 'static> Integration<A> {
         } = {
             let close_requested = raw_input.viewport().close_requested();
 
+            let mut causes = self.repaint_causes.remove(&viewport_id).unwrap_or_default();
+            if !raw_input.events.is_empty() {
+                causes.insert(
+                    0,
+                    RepaintCause::Input {
+                        events: raw_input.events.len(),
+                    },
+                );
+            }
+
+            profile_scope!("run_ui");
+            let start = Instant::now();
+            #[cfg(feature = "persistence")]
+            crate::memory::apply_pending(&self.context);
             let full_output = self.context.run(raw_input, |ctx| {
+                let mut causes = causes.clone();
+                for cause in RepaintCause::requested(ctx) {
+                    repaint::push_cause(&mut causes, cause);
+                }
+                if self.inspector.is_shown() {
+                    self.inspector.set_repaint_causes(viewport_id, &causes);
+                }
+                repaint::set_repaint_causes(ctx, causes);
                 if let Some(viewport_ui_cb) = viewport_ui_cb.clone() {
                     // child viewport
                     viewport_ui_cb(ctx);
-                } else {
+                } else if let Some(frame_info) = &frame_info {
                     // ROOT viewport
-                    app.ui(ctx);
+                    app.ui(ctx, frame_info);
                 }
+                zoom::handle_zoom(ctx);
+                #[cfg(feature = "message-dialog")]
+                crate::message_dialog::paint_backdrop(ctx);
+                crate::taskbar::apply_badge(ctx);
+                #[cfg(feature = "accesskit")]
+                announce::update_live_regions(ctx);
+                layer::paint_layer_split(ctx);
+                interactive_rects = passthrough::interactive_rects(ctx);
             });
+            let egui_run_time = start.elapsed();
+            let event_handling_time = self
+                .event_handling_times
+                .remove(&viewport_id)
+                .unwrap_or_default();
+            self.frame_stats.update(viewport_id, |stats| {
+                stats.event_handling_time = event_handling_time;
+                stats.egui_run_time = egui_run_time;
+            });
+            app.on_full_output(viewport_id, &full_output);
+            self.deliver_drag_release(viewport_id);
 
             let is_root_viewport = viewport_ui_cb.is_none();
             if is_root_viewport && close_requested {
@@ -434,17 +1487,26 @@ impl<A: Allocator + 'static> Integration<A> {
             let egui_cmd = if let Some(viewport) = viewports.get_mut(&viewport_id) {
                 viewport.info.events.clear();
 
+                viewport.handle_platform_output(
+                    &self.context,
+                    event_loop,
+                    &self.custom_cursors,
+                    platform_output,
+                );
                 viewport
-                    .state
-                    .handle_platform_output(&viewport.window, platform_output);
+                    .mouse_passthrough
+                    .set_interactive_rects(interactive_rects);
 
                 let mut renderer = self.renderer.lock().unwrap();
 
-                let clipped_primitives = self.context.tessellate(shapes, pixels_per_point);
                 renderer.create_egui_cmd(
                     viewport.ids.this,
-                    clipped_primitives,
-                    textures_delta,
+                    &self.context,
+                    EguiFrame {
+                        shapes,
+                        pixels_per_point,
+                        textures_delta,
+                    },
                     viewport.window.scale_factor() as f32 * self.context.zoom_factor(),
                     viewport.window.inner_size(),
                 )
@@ -476,23 +1538,55 @@ impl<A: Allocator + 'static> Integration<A> {
                     &self.storage,
                     #[cfg(feature = "persistence")]
                     self.persistent_windows,
+                    #[cfg(feature = "accesskit")]
+                    &self.event_loop_proxy,
                 );
                 if window_initialized {
+                    if self.window_theme.is_some() {
+                        viewport.window.set_theme(self.window_theme);
+                    }
                     app.handle_event(crate::event::Event::DeferredViewportCreated {
                         viewport_id: ids.this,
                         window: &viewport.window,
                     });
+                    self.context.request_repaint_of(ids.this);
+                    repaint::push_cause(
+                        self.repaint_causes.entry(ids.this).or_default(),
+                        RepaintCause::Window,
+                    );
                 }
 
                 viewport.info.focused = Some(*focused_viewport == Some(viewport_id));
-                let mut _actions = Vec::new();
-                egui_winit::process_viewport_commands(
-                    &self.context,
-                    &mut viewport.info,
-                    output.commands.clone(),
-                    &viewport.window,
-                    &mut _actions,
-                );
+                if !output.commands.is_empty() {
+                    repaint::push_cause(
+                        self.repaint_causes.entry(viewport_id).or_default(),
+                        RepaintCause::ViewportCommand,
+                    );
+                }
+                viewport.process_viewport_commands(&self.context, output.commands.clone());
+                if let Some(effect) = background::take_background_effect(&self.context, viewport_id)
+                {
+                    background::apply_background_effect(&viewport.window, effect);
+                }
+                if let Some(mouse_passthrough) =
+                    passthrough::take_mouse_passthrough(&self.context, viewport_id)
+                {
+                    viewport
+                        .mouse_passthrough
+                        .set_passthrough(mouse_passthrough);
+                    if mouse_passthrough == MousePassthrough::OutsideEgui {
+                        // follow the cursor while it passes through the window.
+                        event_loop.listen_device_events(winit::event_loop::DeviceEvents::Always);
+                    }
+                }
+                viewport.update_mouse_passthrough(&self.context);
+            }
+
+            if let Some(preference) = theme::take_theme_preference(&self.context) {
+                self.window_theme = theme::window_theme(preference);
+                for viewport in viewports.values() {
+                    viewport.window.set_theme(self.window_theme);
+                }
             }
 
             if let Some(viewport) = viewports.get_mut(&viewport_id) {
@@ -506,6 +1600,22 @@ impl<A: Allocator + 'static> Integration<A> {
             // Prune dead viewports
             let active_viewports_ids: egui::ViewportIdSet =
                 viewport_output.keys().copied().collect();
+            #[cfg(feature = "persistence")]
+            if self.persistent_windows {
+                save_closed_windows(
+                    &self.context,
+                    &viewports,
+                    &active_viewports_ids,
+                    &self.storage,
+                );
+            }
+            // the queued frames of the closed viewports use their resources.
+            if viewports
+                .keys()
+                .any(|id| !active_viewports_ids.contains(id))
+            {
+                self.frame_queue.finish();
+            }
             viewports.retain(|id, _| active_viewports_ids.contains(id));
             {
                 let mut renderer = self.renderer.lock().unwrap();
@@ -514,23 +1624,37 @@ impl<A: Allocator + 'static> Integration<A> {
                 presenters.destroy_viewports(&active_viewports_ids);
                 renderer.destroy_viewports(&active_viewports_ids);
                 window_id_to_viewport_id.retain(|_, id| active_viewports_ids.contains(id));
+                let mut repaint_deadlines = self.repaint_deadlines.lock().unwrap();
+                repaint_deadlines.retain(|id, _| active_viewports_ids.contains(id));
+                self.event_handling_times
+                    .retain(|id, _| active_viewports_ids.contains(id));
+                self.repaint_causes
+                    .retain(|id, _| active_viewports_ids.contains(id));
+                self.inspector.retain_viewports(&active_viewports_ids);
             }
 
             egui_cmd
         };
 
-        // autosave
-        self.maybe_autosave(app);
-
         (Some(egui_cmd), PaintResult::Wait)
     }
 
-    pub(crate) fn present_egui(&mut self, viewport_id: egui::ViewportId, egui_cmd: EguiCommand) {
-        let mut presenters = self.presenters.lock().unwrap();
-        presenters.present_egui(viewport_id, egui_cmd);
+    pub(crate) fn paint(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: winit::window::WindowId,
+        app: &mut impl crate::App,
+    ) {
+        if self.frame_stepper.is_manual() {
+            return;
+        }
+        self.paint_viewport(event_loop, window_id, app);
+        self.frame_queue.flush();
+        crate::profiling::finish_frame();
     }
 
-    pub(crate) fn paint(
+    /// run the ui of the viewport and queue its frame.
+    fn paint_viewport(
         &mut self,
         event_loop: &ActiveEventLoop,
         window_id: winit::window::WindowId,
@@ -539,14 +1663,39 @@ impl<A: Allocator + 'static> Integration<A> {
         let Some(viewport_id) = self.viewport_id_from_window_id(window_id) else {
             return;
         };
+        profile_scope!("paint_viewport", format!("{viewport_id:?}"));
+        span!("frame", viewport = ?viewport_id);
+        // requests during the frame schedule the next one. Skipped frames are requested again
+        // when the window is shown.
+        self.repaint_deadlines.lock().unwrap().remove(&viewport_id);
+        // painting makes the windows visible again, so skip it while they are hidden.
+        #[cfg(feature = "tray")]
+        if self.viewports_hidden {
+            return;
+        }
+        // the resources are rebuilt on the first frame after resuming.
+        if self.suspended {
+            return;
+        }
+        // skip acquire and present for windows that are not seen.
+        let hidden = self
+            .viewports
+            .lock()
+            .unwrap()
+            .get(&viewport_id)
+            .is_some_and(Viewport::is_hidden);
+        if hidden {
+            return;
+        }
 
         let handle_redraw = app.request_redraw(viewport_id);
+        let handle_redraw_auto = matches!(handle_redraw, crate::HandleRedraw::Auto);
         let paint_result = match handle_redraw {
             crate::HandleRedraw::Auto => {
                 let (egui_cmd, paint_result) =
                     self.run_ui_and_record_paint_cmd(event_loop, app, window_id, true);
                 if let Some(egui_cmd) = egui_cmd {
-                    self.present_egui(viewport_id, egui_cmd);
+                    self.frame_queue.push(viewport_id, egui_cmd);
                 }
                 paint_result
             }
@@ -555,20 +1704,23 @@ impl<A: Allocator + 'static> Integration<A> {
                     self.run_ui_and_record_paint_cmd(event_loop, app, window_id, false);
                 if let Some(size) = self.get_viewport_size(viewport_id) {
                     if let Some(egui_cmd) = egui_cmd {
-                        handler(size, egui_cmd);
+                        self.frame_queue.flush();
+                        match &self.render_thread {
+                            Some(render_thread) => render_thread.handle(handler, size, egui_cmd),
+                            None => handler(size, egui_cmd),
+                        }
                     }
                 }
                 paint_result
             }
         };
 
+        // the frames handled by the app are presented by it.
+        let presented =
+            !handle_redraw_auto || self.presenters.lock().unwrap().has_presented(viewport_id);
         let mut viewports = self.viewports.lock().unwrap();
         if let Some(viewport) = viewports.get_mut(&viewport_id) {
-            if viewport.is_first_frame {
-                viewport.is_first_frame = false;
-            } else {
-                viewport.window.set_visible(true);
-            }
+            viewport.show_after_frame(&self.context, presented);
         }
 
         match paint_result {
@@ -577,58 +1729,252 @@ impl<A: Allocator + 'static> Integration<A> {
         }
     }
 
+    /// paint the viewports whose repaint is due.
     pub(crate) fn paint_all(&mut self, event_loop: &ActiveEventLoop, app: &mut impl crate::App) {
+        if self.frame_stepper.is_manual() {
+            return;
+        }
         let window_ids = {
+            let now = Instant::now();
             let window_id_to_viewport_id = self.window_id_to_viewport_id.lock().unwrap();
-            window_id_to_viewport_id.keys().copied().collect::<Vec<_>>()
+            let repaint_deadlines = self.repaint_deadlines.lock().unwrap();
+            window_id_to_viewport_id
+                .iter()
+                .filter(|(_, viewport_id)| {
+                    repaint_deadlines
+                        .get(viewport_id)
+                        .is_some_and(|deadline| *deadline <= now)
+                })
+                .map(|(window_id, _)| *window_id)
+                .collect::<Vec<_>>()
         };
         for window_id in window_ids {
-            self.paint(event_loop, window_id, app);
+            self.paint_viewport(event_loop, window_id, app);
         }
+        self.frame_queue.flush();
+        crate::profiling::finish_frame();
     }
 
-    fn maybe_autosave(&mut self, _app: &mut impl crate::App) {
-        #[cfg(feature = "persistence")]
-        {
-            if self.last_auto_save.elapsed() < _app.auto_save_interval() {
-                return;
+    /// paint one frame of every viewport for each step of the [`FrameStepper`].
+    pub(crate) fn paint_steps(&mut self, event_loop: &ActiveEventLoop, app: &mut impl crate::App) {
+        while let Some(dt) = self.frame_stepper.take_step() {
+            self.step_dt = Some(dt);
+            let window_ids = self
+                .window_id_to_viewport_id
+                .lock()
+                .unwrap()
+                .keys()
+                .copied()
+                .collect::<Vec<_>>();
+            for window_id in window_ids {
+                self.paint_viewport(event_loop, window_id, app);
+            }
+            self.frame_queue.flush();
+            crate::profiling::finish_frame();
+        }
+        self.step_dt = None;
+    }
+
+    /// whether the repaint of a viewport is due.
+    pub(crate) fn repaint_due(&self) -> bool {
+        self.next_repaint()
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// time of the earliest requested repaint.
+    pub(crate) fn next_repaint(&self) -> Option<Instant> {
+        // the frames are only painted by the steps.
+        if self.frame_stepper.is_manual() {
+            return None;
+        }
+        let window_id_to_viewport_id = self.window_id_to_viewport_id.lock().unwrap();
+        let repaint_deadlines = self.repaint_deadlines.lock().unwrap();
+        // requests of closed viewports can not be painted.
+        window_id_to_viewport_id
+            .values()
+            .filter_map(|viewport_id| repaint_deadlines.get(viewport_id))
+            .min()
+            .copied()
+    }
+
+    /// save when the autosave interval elapsed or a window lost the focus.
+    #[cfg(feature = "persistence")]
+    pub(crate) fn maybe_autosave(&mut self, app: &mut impl crate::App) {
+        if !self.save_requested && self.last_auto_save.elapsed() < app.auto_save_interval() {
+            return;
+        }
+        self.save(app);
+        self.save_requested = false;
+        self.last_auto_save = Instant::now();
+    }
+
+    /// free the egui image caches and let the app free the user textures it does not draw.
+    pub(crate) fn trim_memory(&mut self, app: &mut impl crate::App) {
+        self.context.forget_all_images();
+        self.context.request_repaint();
+        let unused_user_textures = self
+            .renderer
+            .lock()
+            .unwrap()
+            .unused_user_textures(UNUSED_USER_TEXTURE_AGE);
+        app.trim_memory(&unused_user_textures);
+    }
+
+    /// save and restore the layouts requested by the app in the last frame.
+    #[cfg(feature = "persistence")]
+    pub(crate) fn handle_layout_requests(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        app: &mut impl crate::App,
+    ) {
+        for request in self.layout_profiles.take_requests() {
+            match request {
+                LayoutRequest::Save(name) => {
+                    let profile = LayoutProfile {
+                        windows: self.window_settings(),
+                        egui_memory: self.context.memory(|m| m.clone()),
+                    };
+                    self.layout_profiles.set_profile(name, profile);
+                    self.storage.flush();
+                }
+                LayoutRequest::Restore(name) => {
+                    let Some(profile) = self.layout_profiles.profile(&name) else {
+                        error!("Failed to find layout {name:?}");
+                        continue;
+                    };
+                    self.restore_layout(event_loop, &profile);
+                    let layout_event = crate::event::Event::LayoutRestored {
+                        name,
+                        viewport_ids: profile.windows.keys().copied().collect(),
+                    };
+                    app.handle_event(layout_event);
+                }
             }
-            self.save(_app);
-            self.last_auto_save = Instant::now();
         }
     }
 
+    #[cfg(feature = "persistence")]
+    fn restore_layout(&mut self, event_loop: &ActiveEventLoop, profile: &LayoutProfile) {
+        self.context
+            .memory_mut(|m| *m = profile.egui_memory.clone());
+        // child viewports opened later by the app read their geometry from the storage.
+        self.storage.set_windows(&profile.windows);
+
+        let egui_zoom_factor = self.context.zoom_factor();
+        let viewports = self.viewports.lock().unwrap();
+        for (id, viewport) in viewports.iter() {
+            let Some(mut settings) = profile.windows.get(id).cloned() else {
+                continue;
+            };
+            settings.clamp_size_to_sane_values(utils::largest_monitor_point_size(
+                egui_zoom_factor,
+                event_loop,
+            ));
+            settings.clamp_position_to_monitors(egui_zoom_factor, event_loop);
+            let builder = settings.initialize_viewport_builder(
+                egui_zoom_factor,
+                event_loop,
+                egui::ViewportBuilder::default(),
+            );
+            egui_winit::apply_viewport_builder_to_window(&self.context, &viewport.window, &builder);
+            settings.initialize_window(&viewport.window);
+        }
+        drop(viewports);
+        self.context.request_repaint();
+    }
+
+    #[cfg(feature = "persistence")]
+    fn window_settings(&self) -> HashMap<egui::ViewportId, egui_winit::WindowSettings> {
+        let viewports = self.viewports.lock().unwrap();
+        let mut windows = HashMap::new();
+        for (&id, viewport) in viewports.iter() {
+            let settings = egui_winit::WindowSettings::from_window(
+                self.context.zoom_factor(),
+                &viewport.window,
+            );
+            windows.insert(id, settings);
+        }
+        windows
+    }
+
     #[cfg(feature = "persistence")]
     pub(crate) fn save(&mut self, app: &mut impl crate::App) {
-        let storage = &mut self.storage;
         if self.persistent_windows {
-            let viewports = self.viewports.lock().unwrap();
-            let mut windows = HashMap::new();
-            for (&id, viewport) in viewports.iter() {
-                let settings = egui_winit::WindowSettings::from_window(
-                    self.context.zoom_factor(),
-                    &viewport.window,
-                );
-                windows.insert(id, settings);
-            }
-            storage.set_windows(&windows);
+            self.storage.set_windows(&self.window_settings());
         }
+        let storage = &mut self.storage;
         if self.persistent_egui_memory {
             storage.set_egui_memory(&self.context.memory(|m| m.clone()));
         }
+        zoom::save_zoom_factor(&self.context, storage);
         app.save(storage);
         storage.flush();
     }
 
+    /// make the state locked by a panicking frame usable again, for saving and destroying after the panic.
+    pub(crate) fn clear_poison(&self) {
+        self.presenters.clear_poison();
+        self.renderer.clear_poison();
+        self.window_id_to_viewport_id.clear_poison();
+        self.viewports.clear_poison();
+        self.focused_viewport.clear_poison();
+        #[cfg(feature = "persistence")]
+        self.storage.clear_poison();
+    }
+
     pub fn destroy(&mut self) {
+        self.frame_queue.flush();
+        if let Some(render_thread) = self.render_thread.take() {
+            render_thread.shutdown();
+        }
         let mut presenters = self.presenters.lock().unwrap();
         let mut renderer = self.renderer.lock().unwrap();
+        // finish the frames in flight before their swapchains are destroyed.
+        renderer.wait_device_idle();
         presenters.destroy_root();
         renderer.destroy_root();
         egui::Context::set_immediate_viewport_renderer(|_, _| {});
     }
 }
 
+/// the viewport under the cursor and the cursor position in it, if the cursor left the window
+/// of `source_id`.
+fn drag_target(
+    ctx: &egui::Context,
+    viewports: &ViewportIdMap<Viewport>,
+    source_id: egui::ViewportId,
+    cursor: winit::dpi::PhysicalPosition<f64>,
+) -> Option<(egui::ViewportId, egui::Pos2)> {
+    let source = viewports.get(&source_id)?;
+    let origin = winit::dpi::PhysicalPosition::new(0, 0);
+    if viewport_drag::position_in(cursor, origin, source.window.inner_size()).is_some() {
+        return None;
+    }
+    let source_position = source.window.inner_position().ok()?;
+    let screen_pos = winit::dpi::PhysicalPosition::new(
+        source_position.x as f64 + cursor.x,
+        source_position.y as f64 + cursor.y,
+    );
+    viewports
+        .iter()
+        .filter(|&(&id, viewport)| id != source_id && viewport.window.is_visible() != Some(false))
+        .find_map(|(&id, viewport)| {
+            let position = viewport_drag::position_in(
+                screen_pos,
+                viewport.window.inner_position().ok()?,
+                viewport.window.inner_size(),
+            )?;
+            let pixels_per_point = egui_winit::pixels_per_point(ctx, &viewport.window);
+            Some((
+                id,
+                egui::pos2(
+                    position.x as f32 / pixels_per_point,
+                    position.y as f32 / pixels_per_point,
+                ),
+            ))
+        })
+}
+
 #[allow(clippy::too_many_arguments)]
 fn initialize_or_update_viewport<'vp>(
     context: &egui::Context,
@@ -645,6 +1991,9 @@ fn initialize_or_update_viewport<'vp>(
     theme: Option<winit::window::Theme>,
     #[cfg(feature = "persistence")] storage: &Storage,
     #[cfg(feature = "persistence")] persistent_windows: bool,
+    #[cfg(feature = "accesskit")] event_loop_proxy: &winit::event_loop::EventLoopProxy<
+        IntegrationEvent,
+    >,
 ) -> &'vp mut Viewport {
     if builder.icon.is_none() {
         // Inherit icon from parent
@@ -654,6 +2003,15 @@ fn initialize_or_update_viewport<'vp>(
     }
     *window_initialized = false;
 
+    // the window of the parent viewport, for child windows owned by it or embedded in it.
+    let has_parent = window_role::window_role(context, ids.this).owned_by_parent
+        || child_viewport::child_rect(context, ids.this).is_some();
+    let parent = (ids.parent != ids.this && has_parent)
+        .then(|| viewports.get(&ids.parent))
+        .flatten()
+        .and_then(|parent| parent.window.window_handle().ok())
+        .map(|handle| handle.as_raw());
+
     match viewports.entry(ids.this) {
         std::collections::hash_map::Entry::Vacant(entry) => {
             *window_initialized = true;
@@ -663,19 +2021,26 @@ fn initialize_or_update_viewport<'vp>(
                 window_id_to_viewport_id,
                 ids.this,
                 builder.clone(),
+                parent,
                 #[cfg(feature = "persistence")]
                 storage,
                 #[cfg(feature = "persistence")]
                 persistent_windows,
             );
-            let state = egui_winit::State::new(
-                context.clone(),
-                ids.this,
+            let state = create_viewport_state(
+                context,
                 event_loop,
-                Some(window.scale_factor() as f32),
+                ids.this,
+                &window,
                 theme,
-                Some(max_texture_side),
+                max_texture_side,
+                #[cfg(feature = "accesskit")]
+                event_loop_proxy,
             );
+            let mouse_passthrough = PassthroughState::new(&builder);
+            if let Some(level) = builder.window_level {
+                window_level::store_window_level(context, ids.this, level);
+            }
             entry.insert(Viewport {
                 ids,
                 class,
@@ -686,9 +2051,16 @@ fn initialize_or_update_viewport<'vp>(
                     ..Default::default()
                 },
                 is_first_frame: true,
+                visibility: ViewportVisibility::default(),
                 window,
                 state,
                 ui_cb: viewport_ui_cb,
+                custom_cursor_applied: false,
+                cursor_grab: egui::viewport::CursorGrab::None,
+                cursor_visible: true,
+                ime_rects_px: None,
+                mouse_passthrough,
+                child_rect: child_viewport::child_rect(context, ids.this),
             })
         }
 
@@ -710,29 +2082,32 @@ fn initialize_or_update_viewport<'vp>(
                     window_id_to_viewport_id,
                     ids.this,
                     builder.clone(),
+                    parent,
                     #[cfg(feature = "persistence")]
                     storage,
                     #[cfg(feature = "persistence")]
                     persistent_windows,
                 );
-                viewport.state = egui_winit::State::new(
-                    context.clone(),
-                    ids.this,
+                // the AccessKit adapter of the old window is dropped with its state.
+                viewport.state = create_viewport_state(
+                    context,
                     event_loop,
-                    Some(viewport.window.scale_factor() as f32),
+                    ids.this,
+                    &viewport.window,
                     theme,
-                    Some(max_texture_side),
+                    max_texture_side,
+                    #[cfg(feature = "accesskit")]
+                    event_loop_proxy,
                 );
                 viewport.is_first_frame = true;
             } else {
                 viewport.info.focused = Some(focused_viewport == Some(ids.this));
-                let mut _actions = Vec::new();
-                egui_winit::process_viewport_commands(
+                viewport.process_viewport_commands(context, delta_commands);
+                child_viewport::update_child_window(
                     context,
-                    &mut viewport.info,
-                    delta_commands,
+                    ids.this,
                     &viewport.window,
-                    &mut _actions,
+                    &mut viewport.child_rect,
                 );
             }
 
@@ -741,6 +2116,39 @@ fn initialize_or_update_viewport<'vp>(
     }
 }
 
+/// egui-winit state of a viewport window, with an AccessKit adapter for the window.
+fn create_viewport_state(
+    context: &egui::Context,
+    event_loop: &ActiveEventLoop,
+    viewport_id: egui::ViewportId,
+    window: &winit::window::Window,
+    theme: Option<winit::window::Theme>,
+    max_texture_side: usize,
+    #[cfg(feature = "accesskit")] event_loop_proxy: &winit::event_loop::EventLoopProxy<
+        IntegrationEvent,
+    >,
+) -> egui_winit::State {
+    #[allow(unused_mut)] // for accesskit
+    let mut state = egui_winit::State::new(
+        context.clone(),
+        viewport_id,
+        event_loop,
+        Some(window.scale_factor() as f32),
+        theme,
+        Some(max_texture_side),
+    );
+    #[cfg(feature = "accesskit")]
+    {
+        // the adapter has to be created before the window is shown.
+        state.init_accesskit(event_loop, window, event_loop_proxy.clone());
+        if show::show_policy(context) == ShowPolicy::Immediately {
+            window.set_visible(true);
+        }
+    }
+    state
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_viewport_window(
     event_loop: &ActiveEventLoop,
     context: &egui::Context,
@@ -748,13 +2156,18 @@ fn create_viewport_window(
     viewport_id: egui::ViewportId,
     #[allow(unused_mut)] // for persistence
     mut builder: egui::ViewportBuilder,
+    parent: Option<raw_window_handle::RawWindowHandle>,
     #[cfg(feature = "persistence")] storage: &Storage,
     #[cfg(feature = "persistence")] persistent_windows: bool,
 ) -> winit::window::Window {
+    let child_rect = child_viewport::child_rect(context, viewport_id);
     #[cfg(feature = "persistence")]
-    if persistent_windows {
+    let mut window_settings = None;
+    // child windows are placed by the parent viewport.
+    #[cfg(feature = "persistence")]
+    if persistent_windows && child_rect.is_none() {
         let egui_zoom_factor = context.zoom_factor();
-        let window_settings = storage
+        window_settings = storage
             .get_windows()
             .and_then(|windows| windows.get(&viewport_id).map(|s| s.to_owned()))
             .map(|mut settings| {
@@ -766,22 +2179,61 @@ fn create_viewport_window(
                 settings.to_owned()
             });
 
-        if let Some(window_settings) = window_settings {
+        if let Some(window_settings) = &window_settings {
             builder =
                 window_settings.initialize_viewport_builder(egui_zoom_factor, event_loop, builder);
         }
     }
 
-    builder = builder.with_visible(false);
-    let window = egui_winit::create_window(context, event_loop, &builder).unwrap();
+    builder = builder.with_visible(
+        show::show_policy(context) == ShowPolicy::Immediately && !cfg!(feature = "accesskit"),
+    );
+    let role = window_role::window_role(context, viewport_id);
+    let mut window_attributes = window_role::apply_window_role(
+        egui_winit::create_winit_window_attributes(context, builder.clone()),
+        role,
+        parent,
+    );
+    if let (Some(rect), Some(parent)) = (child_rect, parent) {
+        window_attributes =
+            child_viewport::apply_child_window(window_attributes, context, rect, parent);
+    }
+    let window_attributes = activation::apply_token(context, window_attributes);
+    let window = event_loop.create_window(window_attributes).unwrap();
 
     egui_winit::apply_viewport_builder_to_window(context, &window, &builder);
+    #[cfg(feature = "persistence")]
+    if let Some(window_settings) = &window_settings {
+        window_settings.initialize_window(&window);
+    }
 
     window_id_to_viewport_id.insert(window.id(), viewport_id);
 
     window
 }
 
+/// store the window settings of the viewports that are pruned, the next save would miss them.
+#[cfg(feature = "persistence")]
+fn save_closed_windows(
+    context: &egui::Context,
+    viewports: &ViewportIdMap<Viewport>,
+    active_viewports_ids: &egui::ViewportIdSet,
+    storage: &Storage,
+) {
+    let closed_windows = viewports
+        .iter()
+        .filter(|(id, _)| !active_viewports_ids.contains(id))
+        .map(|(&id, viewport)| {
+            let settings =
+                egui_winit::WindowSettings::from_window(context.zoom_factor(), &viewport.window);
+            (id, settings)
+        })
+        .collect::<HashMap<_, _>>();
+    if !closed_windows.is_empty() {
+        storage.set_windows(&closed_windows);
+    }
+}
+
 #[cfg(feature = "persistence")]
 fn restore_main_window(
     event_loop: &ActiveEventLoop,
@@ -817,27 +2269,39 @@ fn immediate_viewport_renderer(
     viewports: &Arc<Mutex<ViewportIdMap<Viewport>>>,
     window_id_to_viewport_id: &Arc<Mutex<HashMap<winit::window::WindowId, egui::ViewportId>>>,
     focused_viewport: &Arc<Mutex<Option<egui::ViewportId>>>,
+    frame_queue: &FrameQueue,
     max_texture_side: usize,
     theme: Option<winit::window::Theme>,
+    custom_cursors: &CustomCursors,
     #[cfg(feature = "persistence")] storage: &Storage,
     #[cfg(feature = "persistence")] persistent_windows: bool,
+    #[cfg(feature = "accesskit")] event_loop_proxy: &winit::event_loop::EventLoopProxy<
+        IntegrationEvent,
+    >,
     event_loop: &ActiveEventLoop,
 ) -> impl for<'b, 'a> Fn(&'b egui::Context, egui::ImmediateViewport<'a>) {
     let presenters = presenters.clone();
+    let frame_queue = frame_queue.clone();
     let renderer = renderer.clone();
     let viewports = viewports.clone();
     let window_id_to_viewport_id = window_id_to_viewport_id.clone();
     let focused_viewport = focused_viewport.clone();
+    let custom_cursors = custom_cursors.clone();
     #[cfg(feature = "persistence")]
     let storage = storage.clone();
+    #[cfg(feature = "accesskit")]
+    let event_loop_proxy = event_loop_proxy.clone();
 
     // SAFETY: the event loop lives longer than this callback
     #[allow(unsafe_code)]
-    let event_loop = unsafe { std::ptr::from_ref::<ActiveEventLoop>(event_loop).as_ref().unwrap() };
+    let event_loop = unsafe {
+        std::ptr::from_ref::<ActiveEventLoop>(event_loop)
+            .as_ref()
+            .unwrap()
+    };
 
     move |ctx, mut immediate_viewport| {
         let mut renderer = renderer.lock().unwrap();
-        let mut presenters = presenters.lock().unwrap();
         let mut viewports = viewports.lock().unwrap();
         let mut window_id_to_viewport_id = window_id_to_viewport_id.lock().unwrap();
         let focused_viewport = focused_viewport.lock().unwrap();
@@ -861,9 +2325,16 @@ fn immediate_viewport_renderer(
                 &storage,
                 #[cfg(feature = "persistence")]
                 persistent_windows,
+                #[cfg(feature = "accesskit")]
+                &event_loop_proxy,
             );
             if window_initialized {
-                presenters.recreate_swapchain_if_needed(viewport.ids.this, &viewport.window);
+                presenters.lock().unwrap().recreate_swapchain_if_needed(
+                    viewport.ids.this,
+                    &viewport.window,
+                    viewport.window.inner_size(),
+                    viewport.builder.transparent.unwrap_or(false),
+                );
             }
             egui_winit::apply_viewport_builder_to_window(ctx, &viewport.window, &viewport.builder);
 
@@ -891,25 +2362,25 @@ fn immediate_viewport_renderer(
         let viewport = viewports.get_mut(&immediate_viewport.ids.this).unwrap();
         viewport.info.events.clear();
 
-        viewport
-            .state
-            .handle_platform_output(&viewport.window, platform_output);
+        viewport.handle_platform_output(ctx, event_loop, &custom_cursors, platform_output);
 
-        let clipped_primitives = ctx.tessellate(shapes, pixels_per_point);
         let egui_cmd = renderer.create_egui_cmd(
             viewport.ids.this,
-            clipped_primitives,
-            textures_delta,
+            ctx,
+            EguiFrame {
+                shapes,
+                pixels_per_point,
+                textures_delta,
+            },
             ctx.zoom_factor(),
             viewport.window.inner_size(),
         );
 
-        presenters.present_egui(viewport.ids.this, egui_cmd);
-        if viewport.is_first_frame {
-            viewport.is_first_frame = false;
-        } else {
-            viewport.window.set_visible(true);
-        }
+        // presented with the frames queued before, its texture updates follow theirs.
+        frame_queue.push(viewport.ids.this, egui_cmd);
+        frame_queue.flush();
+        let presented = presenters.lock().unwrap().has_presented(viewport.ids.this);
+        viewport.show_after_frame(ctx, presented);
 
         // handle viewport output
         for (&viewport_id, output) in &viewport_output {
@@ -933,26 +2404,39 @@ fn immediate_viewport_renderer(
                 &storage,
                 #[cfg(feature = "persistence")]
                 persistent_windows,
+                #[cfg(feature = "accesskit")]
+                &event_loop_proxy,
             );
             if window_initialized {
-                presenters.recreate_swapchain_if_needed(viewport.ids.this, &viewport.window);
+                presenters.lock().unwrap().recreate_swapchain_if_needed(
+                    viewport.ids.this,
+                    &viewport.window,
+                    viewport.window.inner_size(),
+                    viewport.builder.transparent.unwrap_or(false),
+                );
             }
 
             viewport.info.focused = Some(*focused_viewport == Some(viewport_id));
-            let mut _actions = Vec::new();
-            egui_winit::process_viewport_commands(
-                ctx,
-                &mut viewport.info,
-                output.commands.clone(),
-                &viewport.window,
-                &mut _actions,
-            );
+            viewport.process_viewport_commands(ctx, output.commands.clone());
         }
 
         // Prune dead viewports
         let active_viewports_ids: egui::ViewportIdSet = viewport_output.keys().copied().collect();
+        #[cfg(feature = "persistence")]
+        if persistent_windows {
+            save_closed_windows(ctx, &viewports, &active_viewports_ids, &storage);
+        }
+        if viewports
+            .keys()
+            .any(|id| !active_viewports_ids.contains(id))
+        {
+            frame_queue.finish();
+        }
         viewports.retain(|id, _| active_viewports_ids.contains(id));
-        presenters.destroy_viewports(&active_viewports_ids);
+        presenters
+            .lock()
+            .unwrap()
+            .destroy_viewports(&active_viewports_ids);
         renderer.destroy_viewports(&active_viewports_ids);
         window_id_to_viewport_id.retain(|_, id| active_viewports_ids.contains(id));
     }
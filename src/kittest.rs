@@ -0,0 +1,122 @@
+//! [`egui_kittest`] with the Vulkan renderer of egui-ash, for snapshot tests without wgpu.
+//!
+//! [`AshTestRenderer`] renders the frames of a [`egui_kittest::Harness`] into an offscreen image, so
+//! `Harness::render` and `Harness::snapshot` capture what egui-ash paints. [`harness`] drives an
+//! [`App`] with it. The AccessKit queries of the harness (`get_by_label`, `get_by_role`, etc.) see the
+//! tree of the app, with the live regions of `announce` when the `accesskit` feature is enabled.
+
+use anyhow::Result;
+use egui_kittest::{Harness, HarnessBuilder, TestRenderer};
+use egui_winit::winit;
+use std::time::Duration;
+
+use crate::{
+    inspector::Inspector, layer, testing::OffscreenTarget, zoom, Allocator, App, AshRenderState,
+    FrameInfo, ImageRegistry,
+};
+
+/// [`TestRenderer`] rendering the harness with egui-ash into an offscreen image.
+///
+/// Drop the harness before destroying the device, the vulkan objects are destroyed on drop.
+pub struct AshTestRenderer<A: Allocator + 'static> {
+    target: OffscreenTarget<A>,
+    // the deltas of the frames since the last render, kittest only renders on demand.
+    textures_delta: egui::TexturesDelta,
+}
+impl<A: Allocator + 'static> AshTestRenderer<A> {
+    /// create a renderer on the device of `render_state`.
+    pub fn new(render_state: AshRenderState<A>) -> Result<Self> {
+        // resized to the screen of the harness on the first render.
+        let target = OffscreenTarget::new(
+            render_state,
+            winit::dpi::PhysicalSize::new(1, 1),
+            Inspector::default(),
+        )?;
+        Ok(Self {
+            target,
+            textures_delta: Default::default(),
+        })
+    }
+
+    /// image registry to register user textures.
+    pub fn image_registry(&self) -> ImageRegistry {
+        self.target.image_registry.clone()
+    }
+}
+impl<A: Allocator + 'static> TestRenderer for AshTestRenderer<A> {
+    fn handle_delta(&mut self, delta: &egui::TexturesDelta) {
+        self.textures_delta.append(delta.clone());
+    }
+
+    fn render(
+        &mut self,
+        ctx: &egui::Context,
+        output: &egui::FullOutput,
+    ) -> Result<image::RgbaImage, String> {
+        profile_function!();
+        let size = (ctx.viewport_rect().size() * output.pixels_per_point).round();
+        self.target
+            .resize(winit::dpi::PhysicalSize::new(
+                (size.x as u32).max(1),
+                (size.y as u32).max(1),
+            ))
+            .map_err(|err| err.to_string())?;
+        self.target
+            .render(
+                ctx,
+                output.shapes.clone(),
+                output.pixels_per_point,
+                std::mem::take(&mut self.textures_delta),
+            )
+            .map_err(|err| err.to_string())?;
+        let pixels = self.target.read_pixels().map_err(|err| err.to_string())?;
+        let bytes = pixels
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_array())
+            .collect();
+        image::RgbaImage::from_raw(pixels.size[0] as u32, pixels.size[1] as u32, bytes)
+            .ok_or_else(|| "The image size does not match its pixels".to_owned())
+    }
+}
+impl<A: Allocator + 'static> Drop for AshTestRenderer<A> {
+    fn drop(&mut self) {
+        self.target.destroy();
+    }
+}
+
+/// build a harness running `app` like egui-ash does, rendered by `renderer`.
+///
+/// Configure the harness with `builder`, e.g. `Harness::builder().with_size(..)`. The app is the state
+/// of the harness, `harness.state_mut()` gives access to it between the frames.
+pub fn harness<'a, T: App + 'a, A: Allocator + 'static>(
+    builder: HarnessBuilder<T>,
+    renderer: AshTestRenderer<A>,
+    app: T,
+) -> Harness<'a, T> {
+    let mut frame_index = 0;
+    builder.renderer(renderer).build_state(
+        move |ctx, app: &mut T| {
+            let size = ctx.viewport_rect().size() * ctx.pixels_per_point();
+            let frame_info = FrameInfo {
+                delta_time: if frame_index == 0 {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs_f32(ctx.input(|input| input.unstable_dt))
+                },
+                frame_index,
+                viewport_id: ctx.viewport_id(),
+                predicted_present_time: None,
+                window_size: winit::dpi::PhysicalSize::new(size.x as u32, size.y as u32),
+                fixed_update_alpha: crate::fixed_update_alpha(ctx),
+            };
+            app.ui(ctx, &frame_info);
+            zoom::handle_zoom(ctx);
+            #[cfg(feature = "accesskit")]
+            crate::announce::update_live_regions(ctx);
+            layer::paint_layer_split(ctx);
+            frame_index += 1;
+        },
+        app,
+    )
+}
@@ -0,0 +1,54 @@
+use std::{
+    any::Any,
+    sync::{Arc, OnceLock},
+};
+
+/// layer of the egui output recorded by [`crate::EguiCommand::record_layer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EguiLayer {
+    /// the panels, painted in `egui::Order::Background`, e.g. below the 3D scene of a game.
+    Background,
+    /// the windows, popups and tooltips painted above the panels, e.g. over the 3D scene.
+    Foreground,
+}
+
+// paint callback marking the end of the background layer in the shapes of a frame.
+struct LayerSplit;
+
+// the same callback every frame, so that unchanged frames keep comparing equal.
+fn layer_split() -> Arc<dyn Any + Send + Sync> {
+    static LAYER_SPLIT: OnceLock<Arc<dyn Any + Send + Sync>> = OnceLock::new();
+    LAYER_SPLIT.get_or_init(|| Arc::new(LayerSplit)).clone()
+}
+
+/// paint the split between the background and foreground layers, after the ui of the frame.
+///
+/// The marker is painted in its own `egui::Order::Background` layer, which egui draws after the
+/// panels of the background layer and before the windows.
+pub(crate) fn paint_layer_split(ctx: &egui::Context) {
+    let rect = ctx.viewport_rect();
+    let layer_id = egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("egui_ash_layer_split"),
+    );
+    ctx.layer_painter(layer_id)
+        .add(egui::Shape::Callback(egui::PaintCallback {
+            rect,
+            callback: layer_split(),
+        }));
+}
+
+/// whether `callback` is the marker of [`paint_layer_split`].
+pub(crate) fn is_layer_split(callback: &egui::PaintCallback) -> bool {
+    callback.callback.is::<LayerSplit>()
+}
+
+/// index of the marker of [`paint_layer_split`] in the primitives of a frame.
+pub(crate) fn split_index(clipped_primitives: &[egui::ClippedPrimitive]) -> Option<usize> {
+    clipped_primitives.iter().position(|clipped_primitive| {
+        matches!(
+            &clipped_primitive.primitive,
+            egui::epaint::Primitive::Callback(callback) if is_layer_split(callback)
+        )
+    })
+}
@@ -0,0 +1,98 @@
+use egui_winit::WindowSettings;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::storage::Storage;
+
+const STORAGE_LAYOUT_PROFILES_KEY: &str = "egui_layout_profiles";
+
+/// snapshot of the viewports and the egui memory, saved by [`LayoutProfiles::save`].
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct LayoutProfile {
+    pub(crate) windows: HashMap<egui::ViewportId, WindowSettings>,
+    pub(crate) egui_memory: egui::Memory,
+}
+
+pub(crate) enum LayoutRequest {
+    Save(String),
+    Restore(String),
+}
+
+/// named window layouts, e.g. "Editing" and "Debugging". (winit backend only)
+///
+/// A layout holds the open viewports, their geometry and the egui memory of all viewports.
+/// Saving and restoring happen after the current frame.
+/// After restoring, [`crate::event::Event::LayoutRestored`] lists the viewports of the layout,
+/// the app shows the child viewports it does not show yet and they open with the saved geometry.
+#[derive(Clone)]
+pub struct LayoutProfiles {
+    storage: Storage,
+    requests: Rc<RefCell<Vec<LayoutRequest>>>,
+}
+impl LayoutProfiles {
+    pub(crate) fn new(storage: Storage) -> Self {
+        Self {
+            storage,
+            requests: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// save the current layout as `name`, replacing the layout of the same name.
+    pub fn save(&self, name: impl Into<String>) {
+        self.requests
+            .borrow_mut()
+            .push(LayoutRequest::Save(name.into()));
+    }
+
+    /// restore the layout saved as `name`.
+    pub fn restore(&self, name: impl Into<String>) {
+        self.requests
+            .borrow_mut()
+            .push(LayoutRequest::Restore(name.into()));
+    }
+
+    /// delete the layout saved as `name`.
+    pub fn delete(&self, name: &str) {
+        let mut profiles = self.profiles();
+        if profiles.remove(name).is_some() {
+            self.set_profiles(&profiles);
+        }
+    }
+
+    /// names of the saved layouts in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.profiles().into_keys().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    pub(crate) fn take_requests(&self) -> Vec<LayoutRequest> {
+        std::mem::take(&mut self.requests.borrow_mut())
+    }
+
+    pub(crate) fn profile(&self, name: &str) -> Option<LayoutProfile> {
+        self.profiles().remove(name)
+    }
+
+    pub(crate) fn set_profile(&self, name: String, profile: LayoutProfile) {
+        let mut profiles = self.profiles();
+        profiles.insert(name, profile);
+        self.set_profiles(&profiles);
+    }
+
+    fn profiles(&self) -> HashMap<String, LayoutProfile> {
+        self.storage
+            .get_value(STORAGE_LAYOUT_PROFILES_KEY)
+            .unwrap_or_default()
+    }
+
+    fn set_profiles(&self, profiles: &HashMap<String, LayoutProfile>) {
+        self.storage
+            .clone()
+            .set_value(STORAGE_LAYOUT_PROFILES_KEY, profiles);
+    }
+}
+impl std::fmt::Debug for LayoutProfiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayoutProfiles").finish()
+    }
+}
@@ -1,21 +1,151 @@
+// declared first so that the profiling and tracing macros are visible in the other modules.
+#[macro_use]
+mod profiling;
+#[macro_use]
+mod trace;
+mod accelerator;
+#[cfg(feature = "accesskit")]
+mod access_tree;
+mod activation;
 mod allocator;
+#[cfg(feature = "accesskit")]
+mod announce;
 mod app;
+#[cfg(feature = "assets")]
+pub mod assets;
+mod background;
+mod child_viewport;
+mod clear_color;
+mod crash_diagnostics;
+mod cursor;
+pub mod device;
+pub mod documents;
+#[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+pub mod drag;
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+pub mod embed;
+#[cfg(feature = "color-emoji")]
+pub mod emoji;
 pub mod event;
+#[cfg(feature = "file-dialog")]
+pub mod file_dialog;
+mod focus;
+mod fonts;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod handler;
+#[cfg(feature = "global-hotkey")]
+pub mod hotkey;
+#[cfg(feature = "http")]
+pub mod http;
+mod inspector;
 mod integration;
+#[cfg(feature = "kittest")]
+pub mod kittest;
+mod layer;
+#[cfg(feature = "persistence")]
+pub mod layout;
+#[cfg(feature = "persistence")]
+pub mod memory;
+#[cfg(feature = "menu")]
+pub mod menu;
+#[cfg(feature = "message-dialog")]
+pub mod message_dialog;
+mod mirror;
+mod monitors;
+#[cfg(all(feature = "open-files", target_os = "macos"))]
+mod open_files;
+mod passthrough;
+#[cfg(target_os = "windows")]
+mod pen;
+mod pixel_snapping;
+mod post_process;
+#[cfg(feature = "power")]
+pub mod power;
 mod presenters;
+pub mod raw;
+mod render_state_check;
+mod render_thread;
 mod renderer;
+mod repaint;
+#[cfg(feature = "replay")]
+pub mod replay;
+mod resource_usage;
+#[cfg(feature = "rich-clipboard")]
+pub mod rich_clipboard;
 mod run;
+mod scroll;
+#[cfg(feature = "sdl2")]
+pub mod sdl2;
+mod show;
+mod single_instance;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(feature = "splash")]
+pub mod splash;
+mod stats;
 #[cfg(feature = "persistence")]
 pub mod storage;
+mod submit;
+#[cfg(feature = "system-fonts")]
+pub mod system_fonts;
+#[cfg(feature = "async")]
+pub mod task;
+mod taskbar;
+pub mod testing;
+mod theme;
+mod timestep;
+#[cfg(feature = "tray")]
+pub mod tray;
 mod utils;
+#[cfg(feature = "validation")]
+pub mod validation;
+mod viewport_drag;
+mod window_level;
+mod window_role;
+#[cfg(feature = "openxr")]
+pub mod xr;
+mod zoom;
 
 pub use egui_winit::winit;
 pub use raw_window_handle;
 
+pub use accelerator::Accelerators;
+#[cfg(feature = "accesskit")]
+pub use access_tree::{access_tree, AccessNode, AccessTree};
 pub use allocator::*;
+#[cfg(feature = "accesskit")]
+pub use announce::{announce, AnnouncePriority};
 pub use app::*;
+pub use background::{set_background_effect, BackgroundEffect};
+pub use child_viewport::set_child_viewport;
+pub use clear_color::{clear_color, set_clear_color};
+pub use crash_diagnostics::GpuCrashDiagnostics;
+pub use cursor::{CustomCursorId, CustomCursors};
+pub use fonts::Fonts;
+pub use handler::HandlerOrder;
+pub use inspector::{inspector_ui, texture_debugger_ui};
+pub use layer::EguiLayer;
+pub use mirror::{MirrorFit, MirrorOptions, Mirrors};
+pub use passthrough::{set_mouse_passthrough, MousePassthrough};
+pub use pixel_snapping::{pixel_snapping, set_pixel_snapping};
+pub use post_process::{post_process, set_post_process, ColorBlindness, ColorFilter, PostProcess};
 pub use renderer::*;
+pub use repaint::{repaint_cause_overlay, repaint_causes, RepaintCause, RepaintSignal};
+pub use resource_usage::{EguiResource, ResourceUsage};
 pub use run::*;
+pub use scroll::ScrollOptions;
+pub use show::{signal_ready, ShowPolicy};
+pub use stats::{FrameStatistics, FrameStats};
+pub use submit::{FinalPassInfo, SubmitHooks, SubmitInfo, SubmittedFrame};
+pub use taskbar::Taskbar;
+pub use theme::{set_theme_preference, theme_preference};
+pub use timestep::{fixed_update_alpha, FrameLimiter, FrameStepper};
+pub use window_level::{set_window_level, window_level};
+pub use window_role::{set_window_role, WindowRole};
+pub use zoom::ZoomOptions;
 
 #[cfg(feature = "gpu-allocator")]
 mod gpu_allocator;
+#[cfg(feature = "vk-mem")]
+pub mod vk_mem_allocator;
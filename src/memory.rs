@@ -0,0 +1,52 @@
+//! export and import of the egui memory while the app runs, e.g. to reset the UI state or to share
+//! a layout.
+//!
+//! The memory holds the UI state of all viewports of a context: open collapsing headers, window
+//! positions, scroll offsets, panel sizes. The [`egui::Options`], e.g. the theme and the zoom
+//! factor, are not part of it and stay as they are. An import is applied before the next frame.
+
+use anyhow::Result;
+
+use crate::storage::StorageFormat;
+
+fn pending_id() -> egui::Id {
+    egui::Id::new("egui_ash_pending_memory")
+}
+
+/// the egui memory of `ctx` encoded in `format`. RON is readable and can be pasted in a chat.
+pub fn export_memory(ctx: &egui::Context, format: StorageFormat) -> Result<Vec<u8>> {
+    ctx.memory(|memory| format.encode(memory))
+}
+
+/// replace the egui memory of `ctx` with the one of [`export_memory`], encoded in `format`.
+pub fn import_memory(ctx: &egui::Context, bytes: &[u8], format: StorageFormat) -> Result<()> {
+    let memory = format.decode::<egui::Memory>(bytes)?;
+    set_pending(ctx, memory);
+    Ok(())
+}
+
+/// reset the UI state of `ctx`, as if the app was started without persisted memory.
+pub fn reset_memory(ctx: &egui::Context) {
+    set_pending(ctx, egui::Memory::default());
+}
+
+fn set_pending(ctx: &egui::Context, memory: egui::Memory) {
+    ctx.data_mut(|data| data.insert_temp(pending_id(), memory));
+    let viewport_ids = ctx.input(|input| input.raw.viewports.keys().copied().collect::<Vec<_>>());
+    for viewport_id in viewport_ids {
+        ctx.request_repaint_of(viewport_id);
+    }
+    ctx.request_repaint_of(egui::ViewportId::ROOT);
+}
+
+/// replace the memory of `ctx` with the imported one, between the frames.
+pub(crate) fn apply_pending(ctx: &egui::Context) {
+    let Some(mut pending) = ctx.data_mut(|data| data.remove_temp::<egui::Memory>(pending_id()))
+    else {
+        return;
+    };
+    ctx.memory_mut(|memory| {
+        pending.options = memory.options.clone();
+        *memory = pending;
+    });
+}
@@ -0,0 +1,302 @@
+use anyhow::Result;
+use egui_winit::winit;
+#[cfg(target_os = "windows")]
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::accelerator::Accelerators;
+
+/// menu of the native menu bar.
+///
+/// On macOS the first menu becomes the application menu.
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub label: String,
+    pub items: Vec<MenuItem>,
+}
+
+/// item of a native menu.
+#[derive(Debug, Clone)]
+pub enum MenuItem {
+    /// item delivered as [`crate::event::Event::MenuActivated`] with `id` when clicked
+    /// or when `shortcut` is pressed.
+    /// Without `shortcut`, the shortcut registered for `id` in [`Accelerators`] is shown.
+    Action {
+        id: String,
+        label: String,
+        shortcut: Option<egui::KeyboardShortcut>,
+        enabled: bool,
+    },
+    Separator,
+    Submenu(Menu),
+}
+
+struct NativeMenuInner {
+    #[cfg(target_os = "windows")]
+    window_handle: Option<RawWindowHandle>,
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    menu_bar: Option<muda::Menu>,
+    shortcuts: Vec<(String, egui::KeyboardShortcut)>,
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    accelerators: Accelerators,
+}
+
+/// native menu bar of the main window. (Windows and macOS)
+///
+/// Shortcuts of the items are shown in the native menu.
+/// Key presses matching them are taken out of the egui input, so use
+/// [`crate::event::Event::MenuActivated`] instead of `consume_shortcut` for them.
+#[derive(Clone)]
+pub struct NativeMenu {
+    inner: Rc<RefCell<NativeMenuInner>>,
+}
+impl NativeMenu {
+    #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+    pub(crate) fn new(main_window: &winit::window::Window, accelerators: Accelerators) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(NativeMenuInner {
+                #[cfg(target_os = "windows")]
+                window_handle: main_window
+                    .window_handle()
+                    .ok()
+                    .map(|handle| handle.as_raw()),
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                menu_bar: None,
+                shortcuts: vec![],
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                accelerators,
+            })),
+        }
+    }
+
+    /// replace the menu bar.
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    pub fn set_menu_bar(&self, menus: Vec<Menu>) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        let mut shortcuts = vec![];
+        let menu_bar = muda::Menu::new();
+        for menu in &menus {
+            menu_bar.append(&build_submenu(
+                menu,
+                Some(&inner.accelerators),
+                &mut shortcuts,
+            )?)?;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let Some(RawWindowHandle::Win32(handle)) = inner.window_handle else {
+                anyhow::bail!("Main window has no Win32 window handle");
+            };
+            let hwnd = handle.hwnd.get();
+            // SAFETY: the main window is alive while the app runs.
+            unsafe {
+                if let Some(old_menu_bar) = &inner.menu_bar {
+                    old_menu_bar.remove_for_hwnd(hwnd)?;
+                }
+                menu_bar.init_for_hwnd(hwnd)?;
+            }
+        }
+        #[cfg(target_os = "macos")]
+        menu_bar.init_for_nsapp();
+
+        inner.menu_bar = Some(menu_bar);
+        inner.shortcuts = shortcuts;
+        Ok(())
+    }
+
+    /// replace the menu bar.
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    pub fn set_menu_bar(&self, _menus: Vec<Menu>) -> Result<()> {
+        anyhow::bail!("Native menu bar is not supported on this platform")
+    }
+
+    /// take key presses of menu shortcuts out of `events[start..]` and return the activated item ids.
+    pub(crate) fn take_shortcuts(
+        &self,
+        events: &mut Vec<egui::Event>,
+        start: usize,
+    ) -> Vec<String> {
+        // the native menu of macOS handles its key equivalents before the window receives them.
+        if cfg!(target_os = "macos") {
+            return vec![];
+        }
+        let inner = self.inner.borrow();
+        crate::accelerator::take_shortcuts(&inner.shortcuts, events, start)
+    }
+}
+impl std::fmt::Debug for NativeMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeMenu").finish()
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn build_submenu(
+    menu: &Menu,
+    accelerators: Option<&Accelerators>,
+    shortcuts: &mut Vec<(String, egui::KeyboardShortcut)>,
+) -> Result<muda::Submenu> {
+    let submenu = muda::Submenu::new(&menu.label, true);
+    for item in build_items(&menu.items, accelerators, shortcuts)? {
+        submenu.append(item.as_ref())?;
+    }
+    Ok(submenu)
+}
+
+/// build muda items of `items`, collecting the shortcuts of the actions.
+///
+/// Shortcuts of `accelerators` are only shown, they are dispatched by [`Accelerators`] itself.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn build_items(
+    items: &[MenuItem],
+    accelerators: Option<&Accelerators>,
+    shortcuts: &mut Vec<(String, egui::KeyboardShortcut)>,
+) -> Result<Vec<Box<dyn muda::IsMenuItem>>> {
+    let mut muda_items: Vec<Box<dyn muda::IsMenuItem>> = vec![];
+    for item in items {
+        match item {
+            MenuItem::Action {
+                id,
+                label,
+                shortcut,
+                enabled,
+            } => {
+                if let Some(shortcut) = shortcut {
+                    shortcuts.push((id.clone(), *shortcut));
+                }
+                let shown_shortcut = shortcut
+                    .or_else(|| accelerators.and_then(|accelerators| accelerators.shortcut(id)));
+                let accelerator = shown_shortcut.and_then(translate_shortcut);
+                muda_items.push(Box::new(muda::MenuItem::with_id(
+                    id.as_str(),
+                    label,
+                    *enabled,
+                    accelerator,
+                )));
+            }
+            MenuItem::Separator => {
+                muda_items.push(Box::new(muda::PredefinedMenuItem::separator()));
+            }
+            MenuItem::Submenu(menu) => {
+                muda_items.push(Box::new(build_submenu(menu, accelerators, shortcuts)?));
+            }
+        }
+    }
+    Ok(muda_items)
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn translate_shortcut(shortcut: egui::KeyboardShortcut) -> Option<muda::accelerator::Accelerator> {
+    use muda::accelerator::{Accelerator, Modifiers, CMD_OR_CTRL};
+
+    let mut modifiers = Modifiers::empty();
+    if shortcut.modifiers.command {
+        modifiers |= CMD_OR_CTRL;
+    }
+    if shortcut.modifiers.ctrl {
+        modifiers |= Modifiers::CONTROL;
+    }
+    if shortcut.modifiers.mac_cmd {
+        modifiers |= Modifiers::META;
+    }
+    if shortcut.modifiers.alt {
+        modifiers |= Modifiers::ALT;
+    }
+    if shortcut.modifiers.shift {
+        modifiers |= Modifiers::SHIFT;
+    }
+    let code = translate_key(shortcut.logical_key)?;
+    Some(Accelerator::new(modifiers, code))
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn translate_key(key: egui::Key) -> Option<muda::accelerator::Code> {
+    use egui::Key;
+    use muda::accelerator::Code;
+
+    Some(match key {
+        Key::ArrowDown => Code::ArrowDown,
+        Key::ArrowLeft => Code::ArrowLeft,
+        Key::ArrowRight => Code::ArrowRight,
+        Key::ArrowUp => Code::ArrowUp,
+
+        Key::Escape => Code::Escape,
+        Key::Tab => Code::Tab,
+        Key::Backspace => Code::Backspace,
+        Key::Enter => Code::Enter,
+        Key::Space => Code::Space,
+
+        Key::Insert => Code::Insert,
+        Key::Delete => Code::Delete,
+        Key::Home => Code::Home,
+        Key::End => Code::End,
+        Key::PageUp => Code::PageUp,
+        Key::PageDown => Code::PageDown,
+
+        Key::Comma => Code::Comma,
+        Key::Backslash => Code::Backslash,
+        Key::Slash => Code::Slash,
+        Key::OpenBracket => Code::BracketLeft,
+        Key::CloseBracket => Code::BracketRight,
+        Key::Backtick => Code::Backquote,
+        Key::Minus => Code::Minus,
+        Key::Period => Code::Period,
+        Key::Equals => Code::Equal,
+        Key::Semicolon => Code::Semicolon,
+        Key::Quote => Code::Quote,
+
+        Key::Num0 => Code::Digit0,
+        Key::Num1 => Code::Digit1,
+        Key::Num2 => Code::Digit2,
+        Key::Num3 => Code::Digit3,
+        Key::Num4 => Code::Digit4,
+        Key::Num5 => Code::Digit5,
+        Key::Num6 => Code::Digit6,
+        Key::Num7 => Code::Digit7,
+        Key::Num8 => Code::Digit8,
+        Key::Num9 => Code::Digit9,
+
+        Key::A => Code::KeyA,
+        Key::B => Code::KeyB,
+        Key::C => Code::KeyC,
+        Key::D => Code::KeyD,
+        Key::E => Code::KeyE,
+        Key::F => Code::KeyF,
+        Key::G => Code::KeyG,
+        Key::H => Code::KeyH,
+        Key::I => Code::KeyI,
+        Key::J => Code::KeyJ,
+        Key::K => Code::KeyK,
+        Key::L => Code::KeyL,
+        Key::M => Code::KeyM,
+        Key::N => Code::KeyN,
+        Key::O => Code::KeyO,
+        Key::P => Code::KeyP,
+        Key::Q => Code::KeyQ,
+        Key::R => Code::KeyR,
+        Key::S => Code::KeyS,
+        Key::T => Code::KeyT,
+        Key::U => Code::KeyU,
+        Key::V => Code::KeyV,
+        Key::W => Code::KeyW,
+        Key::X => Code::KeyX,
+        Key::Y => Code::KeyY,
+        Key::Z => Code::KeyZ,
+
+        Key::F1 => Code::F1,
+        Key::F2 => Code::F2,
+        Key::F3 => Code::F3,
+        Key::F4 => Code::F4,
+        Key::F5 => Code::F5,
+        Key::F6 => Code::F6,
+        Key::F7 => Code::F7,
+        Key::F8 => Code::F8,
+        Key::F9 => Code::F9,
+        Key::F10 => Code::F10,
+        Key::F11 => Code::F11,
+        Key::F12 => Code::F12,
+
+        _ => return None,
+    })
+}
@@ -0,0 +1,129 @@
+//! native alert and confirm dialogs that do not block the event loop.
+//!
+//! [`MessageDialogs`] shows an `rfd::AsyncMessageDialog` on a thread of its own and delivers the
+//! button the user chose as [`crate::event::Event::MessageDialogClosed`], so the viewports keep
+//! painting while it is open. With [`MessageDialogs::set_dim_viewports`] the viewports are dimmed
+//! and do not take input until the dialogs are closed, like behind an app-modal dialog.
+
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::{integration::IntegrationEvent, utils};
+
+pub use rfd::{AsyncMessageDialog, MessageButtons, MessageDialogResult, MessageLevel};
+
+/// id of a dialog opened with [`MessageDialogs::show`], to match
+/// [`crate::event::Event::MessageDialogClosed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageDialogId(u64);
+
+fn open_dialogs_id() -> egui::Id {
+    egui::Id::new("egui_ash_open_message_dialogs")
+}
+
+fn dim_id() -> egui::Id {
+    egui::Id::new("egui_ash_dim_for_message_dialogs")
+}
+
+/// shows message dialogs without blocking the event loop.
+#[derive(Clone)]
+pub struct MessageDialogs {
+    context: egui::Context,
+    event_loop_proxy: EventLoopProxy<IntegrationEvent>,
+    next_id: Arc<AtomicU64>,
+}
+impl MessageDialogs {
+    pub(crate) fn new(
+        context: egui::Context,
+        event_loop_proxy: EventLoopProxy<IntegrationEvent>,
+    ) -> Self {
+        Self {
+            context,
+            event_loop_proxy,
+            next_id: Arc::default(),
+        }
+    }
+
+    /// dim the viewports and block their input while a dialog is open. Off by default.
+    pub fn set_dim_viewports(&self, dim: bool) {
+        self.context
+            .data_mut(|data| data.insert_temp(dim_id(), dim));
+    }
+
+    /// show `dialog`, configured with its title, description, level and buttons.
+    pub fn show(&self, dialog: AsyncMessageDialog) -> MessageDialogId {
+        let id = MessageDialogId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        set_open_dialogs(&self.context, 1);
+        // the dialog is created on the calling thread, which macOS requires, and awaited on another.
+        let shown = dialog.show();
+        let context = self.context.clone();
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        let spawned = std::thread::Builder::new()
+            .name("egui-ash message dialog".to_owned())
+            .spawn(move || {
+                let result = utils::block_on(shown);
+                set_open_dialogs(&context, -1);
+                let _ =
+                    event_loop_proxy.send_event(IntegrationEvent::MessageDialogClosed(id, result));
+            });
+        if let Err(err) = spawned {
+            error!("Failed to start the message dialog thread: {err}");
+            set_open_dialogs(&self.context, -1);
+        }
+        id
+    }
+
+    /// show an alert with an Ok button.
+    pub fn alert(&self, title: &str, description: &str) -> MessageDialogId {
+        self.show(
+            AsyncMessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title(title)
+                .set_description(description)
+                .set_buttons(MessageButtons::Ok),
+        )
+    }
+
+    /// ask to confirm with Ok and Cancel buttons.
+    pub fn confirm(&self, title: &str, description: &str) -> MessageDialogId {
+        self.show(
+            AsyncMessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title(title)
+                .set_description(description)
+                .set_buttons(MessageButtons::OkCancel),
+        )
+    }
+}
+
+// count the open dialogs and repaint the viewports to dim or undim them.
+fn set_open_dialogs(ctx: &egui::Context, delta: i32) {
+    ctx.data_mut(|data| {
+        let open = data.get_temp_mut_or_default::<i32>(open_dialogs_id());
+        *open = (*open + delta).max(0);
+    });
+    let viewport_ids = ctx.input(|input| input.raw.viewports.keys().copied().collect::<Vec<_>>());
+    for viewport_id in viewport_ids {
+        ctx.request_repaint_of(viewport_id);
+    }
+    ctx.request_repaint_of(egui::ViewportId::ROOT);
+}
+
+/// dim the viewport of `ctx` while a message dialog is open, see
+/// [`MessageDialogs::set_dim_viewports`].
+pub(crate) fn paint_backdrop(ctx: &egui::Context) {
+    let (dim, open) = ctx.data(|data| {
+        (
+            data.get_temp::<bool>(dim_id()).unwrap_or(false),
+            data.get_temp::<i32>(open_dialogs_id()).unwrap_or(0),
+        )
+    });
+    if dim && open > 0 {
+        egui::Modal::new(egui::Id::new("egui_ash_message_dialog_backdrop"))
+            .frame(egui::Frame::NONE)
+            .show(ctx, |_| {});
+    }
+}
@@ -0,0 +1,183 @@
+//! mirror of a viewport on a second window, e.g. the presenter view of a slide app on a projector.
+//!
+//! The mirror shows the frames of its source viewport, copied from the swapchain image of the
+//! source after it is rendered, so the UI runs once for both windows. The mirror window takes no
+//! input, closing it stops the mirror. Surfaces whose swapchain images can not be copied from are
+//! not mirrored, which is logged.
+
+use ash::vk;
+use egui_winit::winit::{
+    dpi::LogicalSize,
+    event_loop::ActiveEventLoop,
+    window::{Fullscreen, Window},
+};
+
+fn requests_id() -> egui::Id {
+    egui::Id::new("egui_ash_mirror_requests")
+}
+
+/// how the mirrored UI is fit into the mirror window.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MirrorFit {
+    /// scale to the window keeping the aspect ratio, with black bars around it.
+    #[default]
+    Contain,
+    /// scale to the window.
+    Stretch,
+    /// scale by a fixed factor, centered in the window.
+    Scale(f32),
+}
+
+/// options of a mirror window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirrorOptions {
+    pub title: String,
+    /// part of the source viewport that is mirrored, in points. `None` mirrors all of it.
+    pub crop: Option<egui::Rect>,
+    pub fit: MirrorFit,
+    /// index of the monitor in `ActiveEventLoop::available_monitors` the mirror is fullscreen on,
+    /// e.g. a projector. `None` opens a normal window.
+    pub fullscreen_monitor: Option<usize>,
+    /// inner size of the mirror window in points, `None` for the size of the source window.
+    pub inner_size: Option<egui::Vec2>,
+}
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self {
+            title: "Mirror".to_owned(),
+            crop: None,
+            fit: MirrorFit::default(),
+            fullscreen_monitor: None,
+            inner_size: None,
+        }
+    }
+}
+
+/// start and stop mirrors of the viewports.
+#[derive(Clone)]
+pub struct Mirrors {
+    context: egui::Context,
+}
+impl Mirrors {
+    pub(crate) fn new(context: egui::Context) -> Self {
+        Self { context }
+    }
+
+    /// mirror `source` on a new window, replacing the mirror it already has.
+    pub fn start(&self, source: egui::ViewportId, options: MirrorOptions) {
+        self.request(source, Some(options));
+    }
+
+    /// close the mirror window of `source`.
+    pub fn stop(&self, source: egui::ViewportId) {
+        self.request(source, None);
+    }
+
+    fn request(&self, source: egui::ViewportId, options: Option<MirrorOptions>) {
+        self.context.data_mut(|data| {
+            data.get_temp_mut_or_default::<Vec<(egui::ViewportId, Option<MirrorOptions>)>>(
+                requests_id(),
+            )
+            .push((source, options));
+        });
+        self.context.request_repaint_of(source);
+    }
+}
+
+/// the starts and stops requested since the last call.
+pub(crate) fn take_requests(ctx: &egui::Context) -> Vec<(egui::ViewportId, Option<MirrorOptions>)> {
+    ctx.data_mut(|data| data.remove_temp(requests_id()))
+        .unwrap_or_default()
+}
+
+/// the window of a mirror.
+pub(crate) struct MirrorWindow {
+    pub(crate) window: Window,
+    pub(crate) options: MirrorOptions,
+}
+impl MirrorWindow {
+    pub(crate) fn create(
+        event_loop: &ActiveEventLoop,
+        source_window: &Window,
+        options: MirrorOptions,
+    ) -> Option<Self> {
+        let inner_size = options.inner_size.map_or_else(
+            || {
+                source_window
+                    .inner_size()
+                    .to_logical::<f64>(source_window.scale_factor())
+            },
+            |size| LogicalSize::new(size.x as f64, size.y as f64),
+        );
+        let fullscreen = options.fullscreen_monitor.map(|index| {
+            let monitor = event_loop.available_monitors().nth(index);
+            if monitor.is_none() {
+                warn!("Monitor {index} of the mirror does not exist, using the current monitor");
+            }
+            Fullscreen::Borderless(monitor)
+        });
+        let attributes = Window::default_attributes()
+            .with_title(&options.title)
+            .with_inner_size(inner_size)
+            .with_fullscreen(fullscreen);
+        match event_loop.create_window(attributes) {
+            Ok(window) => Some(Self { window, options }),
+            Err(err) => {
+                error!("Failed to create the mirror window: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// the rect of `source` that is copied and the rect of `target` it is copied to, in pixels.
+pub(crate) fn blit_offsets(
+    crop: Option<egui::Rect>,
+    fit: MirrorFit,
+    pixels_per_point: f32,
+    source: vk::Extent2D,
+    target: vk::Extent2D,
+) -> Option<([vk::Offset3D; 2], [vk::Offset3D; 2])> {
+    let source_rect = egui::Rect::from_min_size(
+        egui::Pos2::ZERO,
+        egui::vec2(source.width as f32, source.height as f32),
+    );
+    let src = crop.map_or(source_rect, |crop| {
+        (crop * pixels_per_point).intersect(source_rect)
+    });
+    let target_size = egui::vec2(target.width as f32, target.height as f32);
+    let size = match fit {
+        MirrorFit::Contain => {
+            let scale = (target_size.x / src.width()).min(target_size.y / src.height());
+            src.size() * scale
+        }
+        MirrorFit::Stretch => target_size,
+        MirrorFit::Scale(scale) => src.size() * scale,
+    };
+    let dst = egui::Rect::from_center_size((target_size / 2.0).to_pos2(), size);
+    // the parts of a scaled copy outside of the window are cut from the source.
+    let visible = dst.intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, target_size));
+    if src.width() < 1.0 || src.height() < 1.0 || visible.width() < 1.0 || visible.height() < 1.0 {
+        return None;
+    }
+    let to_src = |pos: egui::Pos2| {
+        src.min
+            + (pos - dst.min) * egui::vec2(src.width() / dst.width(), src.height() / dst.height())
+    };
+    let src = egui::Rect::from_min_max(to_src(visible.min), to_src(visible.max));
+    let offsets = |rect: egui::Rect| {
+        [
+            vk::Offset3D {
+                x: rect.min.x.round() as i32,
+                y: rect.min.y.round() as i32,
+                z: 0,
+            },
+            vk::Offset3D {
+                x: rect.max.x.round() as i32,
+                y: rect.max.y.round() as i32,
+                z: 1,
+            },
+        ]
+    };
+    Some((offsets(src), offsets(visible)))
+}
@@ -0,0 +1,123 @@
+//! monitor hotplug and mode changes, which winit does not report.
+//!
+//! The monitors are compared every few seconds. On a change the app gets
+//! [`crate::event::AppEvent::MonitorsChanged`], windows left off-screen are moved back onto a
+//! monitor and the swapchains are recreated, they may have gone suboptimal.
+
+use egui_winit::winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::Window,
+};
+use std::time::Duration;
+
+use crate::integration::IntegrationEvent;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// part of a window that must be on a monitor, to grab it by the title bar.
+const MIN_VISIBLE: i32 = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Monitor {
+    name: Option<String>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+    refresh_rate_millihertz: Option<u32>,
+}
+
+fn monitors(event_loop: &ActiveEventLoop) -> Vec<Monitor> {
+    event_loop
+        .available_monitors()
+        .map(|monitor| Monitor {
+            name: monitor.name(),
+            position: monitor.position(),
+            size: monitor.size(),
+            scale_factor: monitor.scale_factor(),
+            refresh_rate_millihertz: monitor.refresh_rate_millihertz(),
+        })
+        .collect()
+}
+
+/// the monitors seen last, to detect changes.
+pub(crate) struct MonitorWatch {
+    monitors: Vec<Monitor>,
+}
+impl MonitorWatch {
+    /// start comparing the monitors, woken up by [`IntegrationEvent::CheckMonitors`].
+    pub(crate) fn start(
+        event_loop: &ActiveEventLoop,
+        event_loop_proxy: EventLoopProxy<IntegrationEvent>,
+    ) -> Self {
+        let spawned = std::thread::Builder::new()
+            .name("egui-ash monitors".to_owned())
+            .spawn(move || loop {
+                std::thread::sleep(POLL_INTERVAL);
+                if event_loop_proxy
+                    .send_event(IntegrationEvent::CheckMonitors)
+                    .is_err()
+                {
+                    // the event loop exited.
+                    return;
+                }
+            });
+        if let Err(err) = spawned {
+            error!("Failed to start the monitor thread: {err}");
+        }
+        Self {
+            monitors: monitors(event_loop),
+        }
+    }
+
+    /// whether a monitor was connected, disconnected or changed its mode since the last call.
+    pub(crate) fn changed(&mut self, event_loop: &ActiveEventLoop) -> bool {
+        let monitors = monitors(event_loop);
+        if monitors == self.monitors {
+            return false;
+        }
+        self.monitors = monitors;
+        true
+    }
+}
+
+/// move `window` onto the nearest monitor if too little of it is on any monitor.
+pub(crate) fn clamp_to_monitors(window: &Window, event_loop: &ActiveEventLoop) {
+    // wayland does not tell the window position, the compositor places the windows.
+    let Ok(position) = window.outer_position() else {
+        return;
+    };
+    let size = window.outer_size();
+    let monitors = monitors(event_loop);
+    let visible = |monitor: &Monitor| {
+        let left = position.x.max(monitor.position.x);
+        let top = position.y.max(monitor.position.y);
+        let right =
+            (position.x + size.width as i32).min(monitor.position.x + monitor.size.width as i32);
+        let bottom =
+            (position.y + size.height as i32).min(monitor.position.y + monitor.size.height as i32);
+        right - left >= MIN_VISIBLE.min(size.width as i32) && bottom - top > 0
+    };
+    if monitors.is_empty() || monitors.iter().any(visible) {
+        return;
+    }
+    let distance = |monitor: &&Monitor| {
+        let dx = (monitor.position.x - position.x) as i64;
+        let dy = (monitor.position.y - position.y) as i64;
+        dx * dx + dy * dy
+    };
+    let Some(monitor) = monitors.iter().min_by_key(distance) else {
+        return;
+    };
+    let x = position.x.clamp(
+        monitor.position.x,
+        (monitor.position.x + monitor.size.width as i32 - size.width as i32)
+            .max(monitor.position.x),
+    );
+    let y = position.y.clamp(
+        monitor.position.y,
+        (monitor.position.y + monitor.size.height as i32 - size.height as i32)
+            .max(monitor.position.y),
+    );
+    window.set_outer_position(PhysicalPosition::new(x, y));
+}
@@ -0,0 +1,71 @@
+use egui_winit::winit::event_loop::EventLoopProxy;
+use objc2::{
+    ffi,
+    runtime::{AnyClass, AnyObject, Sel},
+    sel,
+};
+use objc2_foundation::{NSArray, NSURL};
+use std::{
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::integration::IntegrationEvent;
+
+// the delegate class is registered by winit when the event loop is created.
+const WINIT_APP_DELEGATE_CLASS: &str = "WinitApplicationDelegate";
+
+static EVENT_LOOP_PROXY: OnceLock<Mutex<EventLoopProxy<IntegrationEvent>>> = OnceLock::new();
+
+extern "C" fn application_open_urls(
+    _this: &AnyObject,
+    _cmd: Sel,
+    _application: &AnyObject,
+    urls: &NSArray<NSURL>,
+) {
+    let paths = (0..urls.count())
+        .map(|index| unsafe { urls.objectAtIndex(index) })
+        .filter(|url| unsafe { url.isFileURL() })
+        .filter_map(|url| unsafe { url.path() })
+        .map(|path| PathBuf::from(path.to_string()))
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return;
+    }
+    if let Some(event_loop_proxy) = EVENT_LOOP_PROXY.get() {
+        let _ = event_loop_proxy
+            .lock()
+            .unwrap()
+            .send_event(IntegrationEvent::OpenFiles(paths));
+    }
+}
+
+/// add `application:openURLs:` to the app delegate of winit and send the files to the event loop.
+///
+/// Must be called after the event loop is created and before it runs,
+/// so that the files of the launch are delivered too.
+pub(crate) fn install(event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+    if EVENT_LOOP_PROXY.set(Mutex::new(event_loop_proxy)).is_err() {
+        return;
+    }
+    let Some(class) = AnyClass::get(WINIT_APP_DELEGATE_CLASS) else {
+        error!("Failed to find {} class", WINIT_APP_DELEGATE_CLASS);
+        return;
+    };
+
+    type OpenUrls = extern "C" fn(&AnyObject, Sel, &AnyObject, &NSArray<NSURL>);
+    // SAFETY: the signature matches the `v@:@@` type encoding of `application:openURLs:`.
+    let added = unsafe {
+        ffi::class_addMethod(
+            class as *const AnyClass as *mut ffi::objc_class,
+            sel!(application:openURLs:).as_ptr(),
+            Some(std::mem::transmute::<OpenUrls, unsafe extern "C" fn()>(
+                application_open_urls,
+            )),
+            c"v@:@@".as_ptr(),
+        )
+    };
+    if added == ffi::NO {
+        error!("Failed to add application:openURLs: to the app delegate");
+    }
+}
@@ -0,0 +1,151 @@
+use egui_winit::winit;
+
+/// whether the mouse input of a window passes through to the windows behind it, e.g. for overlay HUDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MousePassthrough {
+    /// the window receives all mouse input.
+    #[default]
+    Disabled,
+    /// all mouse input passes through the window. Same as `egui::ViewportCommand::MousePassthrough(true)`.
+    Enabled,
+    /// mouse input passes through except over egui windows, areas, popups and tooltips, updated every frame.
+    ///
+    /// The cursor is followed with raw mouse motion while it passes through, so the window reacts
+    /// when the cursor comes back over egui. Not supported on Wayland, where windows cannot know the cursor position.
+    OutsideEgui,
+}
+
+fn pending_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_mouse_passthrough").with(viewport_id)
+}
+
+/// set the [`MousePassthrough`] of the window of `viewport_id`, applied after the current frame.
+pub fn set_mouse_passthrough(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    passthrough: MousePassthrough,
+) {
+    ctx.data_mut(|data| data.insert_temp(pending_id(viewport_id), passthrough));
+    ctx.request_repaint_of(viewport_id);
+}
+
+/// the passthrough set by [`set_mouse_passthrough`] for `viewport_id` since the last call.
+pub(crate) fn take_mouse_passthrough(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+) -> Option<MousePassthrough> {
+    ctx.data_mut(|data| data.remove_temp(pending_id(viewport_id)))
+}
+
+/// rects in points of the areas above the panels of the current viewport. Call it during the frame.
+pub(crate) fn interactive_rects(ctx: &egui::Context) -> Vec<egui::Rect> {
+    ctx.memory(|memory| {
+        memory
+            .areas()
+            .visible_layer_ids()
+            .into_iter()
+            .filter(|layer_id| layer_id.order != egui::Order::Background)
+            .filter_map(|layer_id| memory.area_rect(layer_id.id))
+            .collect()
+    })
+}
+
+/// cursor hit testing of a window for [`MousePassthrough`].
+pub(crate) struct PassthroughState {
+    passthrough: MousePassthrough,
+    interactive_rects: Vec<egui::Rect>,
+    // cursor position on the screen in physical pixels, followed with raw motion while passing through.
+    cursor: Option<winit::dpi::PhysicalPosition<f64>>,
+    // a mouse button pressed over the window, keep the input until it is released.
+    pressed: bool,
+    hittest: bool,
+}
+impl PassthroughState {
+    /// the state of a window created from `builder`.
+    pub(crate) fn new(builder: &egui::ViewportBuilder) -> Self {
+        let enabled = builder.mouse_passthrough == Some(true);
+        Self {
+            passthrough: if enabled {
+                MousePassthrough::Enabled
+            } else {
+                MousePassthrough::Disabled
+            },
+            interactive_rects: vec![],
+            cursor: None,
+            pressed: false,
+            hittest: !enabled,
+        }
+    }
+
+    pub(crate) fn passthrough(&self) -> MousePassthrough {
+        self.passthrough
+    }
+
+    pub(crate) fn set_passthrough(&mut self, passthrough: MousePassthrough) {
+        self.passthrough = passthrough;
+    }
+
+    pub(crate) fn set_interactive_rects(&mut self, interactive_rects: Vec<egui::Rect>) {
+        self.interactive_rects = interactive_rects;
+    }
+
+    pub(crate) fn cursor_moved(
+        &mut self,
+        window: &winit::window::Window,
+        position: winit::dpi::PhysicalPosition<f64>,
+    ) {
+        self.cursor = window.inner_position().ok().map(|inner| {
+            winit::dpi::PhysicalPosition::new(
+                inner.x as f64 + position.x,
+                inner.y as f64 + position.y,
+            )
+        });
+    }
+
+    pub(crate) fn mouse_input(&mut self, state: winit::event::ElementState) {
+        self.pressed = state.is_pressed();
+    }
+
+    /// follow the cursor while the window does not receive cursor events.
+    pub(crate) fn mouse_motion(&mut self, delta: (f64, f64)) {
+        if self.hittest {
+            return;
+        }
+        if let Some(cursor) = &mut self.cursor {
+            cursor.x += delta.0;
+            cursor.y += delta.1;
+        }
+    }
+
+    /// enable or disable the cursor hit testing of `window` for the passthrough and the cursor position.
+    pub(crate) fn update(&mut self, window: &winit::window::Window, pixels_per_point: f32) {
+        let hittest = match self.passthrough {
+            MousePassthrough::Disabled => true,
+            MousePassthrough::Enabled => false,
+            MousePassthrough::OutsideEgui => {
+                self.pressed || self.cursor_over_egui(window, pixels_per_point)
+            }
+        };
+        if hittest == self.hittest {
+            return;
+        }
+        match window.set_cursor_hittest(hittest) {
+            Ok(()) => self.hittest = hittest,
+            Err(err) => {
+                warn!("Failed to set mouse passthrough: {err}");
+                self.passthrough = MousePassthrough::Disabled;
+            }
+        }
+    }
+
+    fn cursor_over_egui(&self, window: &winit::window::Window, pixels_per_point: f32) -> bool {
+        let (Some(cursor), Ok(inner)) = (self.cursor, window.inner_position()) else {
+            return true;
+        };
+        let pos = egui::pos2(
+            ((cursor.x - inner.x as f64) / pixels_per_point as f64) as f32,
+            ((cursor.y - inner.y as f64) / pixels_per_point as f64) as f32,
+        );
+        self.interactive_rects.iter().any(|rect| rect.contains(pos))
+    }
+}
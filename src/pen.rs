@@ -0,0 +1,48 @@
+//! pen state of touch input, read from the pointer API of Windows.
+//!
+//! winit reports pen contacts as `WindowEvent::Touch` with the pointer id as the touch id and
+//! dispatches the event while it handles the pointer message, so the pen info of the message
+//! can still be queried.
+
+use windows_sys::Win32::UI::{
+    Input::Pointer::{
+        GetPointerPenInfo, POINTER_FLAG_INCONTACT, POINTER_FLAG_INRANGE, POINTER_PEN_INFO,
+    },
+    WindowsAndMessaging::{
+        PEN_FLAG_BARREL, PEN_FLAG_ERASER, PEN_FLAG_INVERTED, PEN_MASK_ROTATION, PEN_MASK_TILT_X,
+        PEN_MASK_TILT_Y,
+    },
+};
+
+use crate::event::{PenInfo, PenTool};
+
+/// pen state of the touch `id`, `None` if it is a finger or the pointer message is gone.
+pub(crate) fn pen_info(id: u64) -> Option<PenInfo> {
+    let mut info = std::mem::MaybeUninit::<POINTER_PEN_INFO>::uninit();
+    // SAFETY: `info` is written by `GetPointerPenInfo` when it succeeds.
+    let info = unsafe {
+        if GetPointerPenInfo(id as u32, info.as_mut_ptr()) == 0 {
+            return None;
+        }
+        info.assume_init()
+    };
+    let has = |mask| info.penMask & mask != 0;
+    let tool = if info.penFlags & (PEN_FLAG_INVERTED | PEN_FLAG_ERASER) != 0 {
+        PenTool::Eraser
+    } else {
+        PenTool::Pen
+    };
+    // degrees in `-90..=90`, 0 if the pen does not report the axis.
+    let tilt = (has(PEN_MASK_TILT_X) || has(PEN_MASK_TILT_Y))
+        .then(|| [info.tiltX, info.tiltY].map(|degrees| (degrees as f32).to_radians()));
+    let twist = has(PEN_MASK_ROTATION).then(|| (info.rotation as f32).to_radians());
+    let pointer_flags = info.pointerInfo.pointerFlags;
+    Some(PenInfo {
+        tool,
+        tilt,
+        twist,
+        barrel_button: info.penFlags & PEN_FLAG_BARREL != 0,
+        hovering: pointer_flags & POINTER_FLAG_INRANGE != 0
+            && pointer_flags & POINTER_FLAG_INCONTACT == 0,
+    })
+}
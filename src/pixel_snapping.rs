@@ -0,0 +1,37 @@
+fn snapping_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_pixel_snapping").with(viewport_id)
+}
+
+/// snap the solid shapes of `viewport_id` to the pixel grid, so that 1px lines and the edges of rects
+/// that land between two pixels are drawn sharp instead of blurred over both, e.g. on 100% scale displays.
+///
+/// Text and images keep their positions, the glyphs are not stretched.
+pub fn set_pixel_snapping(ctx: &egui::Context, viewport_id: egui::ViewportId, enabled: bool) {
+    ctx.data_mut(|data| data.insert_temp(snapping_id(viewport_id), enabled));
+    ctx.request_repaint_of(viewport_id);
+}
+
+/// whether the shapes of `viewport_id` are snapped to the pixel grid, see [`set_pixel_snapping`].
+pub fn pixel_snapping(ctx: &egui::Context, viewport_id: egui::ViewportId) -> bool {
+    ctx.data(|data| data.get_temp(snapping_id(viewport_id)))
+        .unwrap_or(false)
+}
+
+/// round the positions of the untextured vertices to whole pixels.
+pub(crate) fn snap(clipped_primitives: &mut [egui::ClippedPrimitive], pixels_per_point: f32) {
+    for clipped_primitive in clipped_primitives {
+        let egui::epaint::Primitive::Mesh(mesh) = &mut clipped_primitive.primitive else {
+            continue;
+        };
+        if mesh.texture_id != egui::TextureId::default() {
+            continue;
+        }
+        // solid shapes sample the white texel of the font atlas, glyphs the rest of it.
+        for vertex in &mut mesh.vertices {
+            if vertex.uv == egui::epaint::WHITE_UV {
+                vertex.pos =
+                    (vertex.pos.to_vec2() * pixels_per_point).round().to_pos2() / pixels_per_point;
+            }
+        }
+    }
+}
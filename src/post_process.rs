@@ -0,0 +1,136 @@
+//! full-screen post pass over the egui layer of a viewport, e.g. to check the UI with a simulated
+//! color vision deficiency or to dim it at night.
+//!
+//! egui renders to an intermediate image, like with [`crate::RunOption::render_scale`], which is
+//! drawn to the swapchain image through the post pass. The clear color and the layers below egui
+//! are not filtered. Turning the pass on or off and changing its shader recreates the swapchain,
+//! the filter settings apply from the current frame on.
+
+use std::sync::Arc;
+
+fn post_process_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_post_process").with(viewport_id)
+}
+
+/// simulated color vision deficiency, after Machado et al. 2009 with full severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorBlindness {
+    /// no red cones.
+    Protanopia,
+    /// no green cones.
+    Deuteranopia,
+    /// no blue cones.
+    Tritanopia,
+    /// no color vision, the luminance only.
+    Achromatopsia,
+}
+
+/// gamma, contrast and brightness of the egui layer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorFilter {
+    /// values above 1 brighten the mid tones, below 1 darken them.
+    pub gamma: f32,
+    /// scale of the distance to mid gray.
+    pub contrast: f32,
+    /// scale of the colors, e.g. 0.6 to dim the UI at night.
+    pub brightness: f32,
+}
+impl Default for ColorFilter {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            contrast: 1.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+/// the post pass of a viewport.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostProcess {
+    ColorBlindness(ColorBlindness),
+    Filter(ColorFilter),
+    /// a fragment shader of your own, as SPIR-V.
+    ///
+    /// It samples the egui layer at set 0 binding 0 with the uv at location 1, and gets
+    /// `push_constants` at offset 16 of the push constants, see `src/shaders/src/post.frag`. The
+    /// colors are premultiplied and blended with `ONE, ONE_MINUS_SRC_ALPHA`. Each shader is kept
+    /// until the app exits.
+    Shader {
+        spirv: Arc<[u32]>,
+        push_constants: [f32; 24],
+    },
+}
+impl PostProcess {
+    /// the custom shader, `None` for the built-in color transform.
+    pub(crate) fn shader(&self) -> Option<Arc<[u32]>> {
+        match self {
+            Self::Shader { spirv, .. } => Some(spirv.clone()),
+            _ => None,
+        }
+    }
+
+    /// the fragment push constants: a column-major color matrix with the offsets in the last
+    /// column, the exponent decoding the colors before it and the one encoding them after it.
+    pub(crate) fn push_constants(&self) -> [f32; 24] {
+        let (matrix, offset, decode, encode) = match self {
+            Self::ColorBlindness(color_blindness) => {
+                let matrix = match color_blindness {
+                    ColorBlindness::Protanopia => [
+                        [0.152_286, 1.052_583, -0.204_868],
+                        [0.114_503, 0.786_281, 0.099_216],
+                        [-0.003_882, -0.048_116, 1.051_998],
+                    ],
+                    ColorBlindness::Deuteranopia => [
+                        [0.367_322, 0.860_646, -0.227_968],
+                        [0.280_085, 0.672_501, 0.047_413],
+                        [-0.011_820, 0.042_940, 0.968_881],
+                    ],
+                    ColorBlindness::Tritanopia => [
+                        [1.255_528, -0.076_749, -0.178_779],
+                        [-0.078_411, 0.930_809, 0.147_602],
+                        [0.004_733, 0.691_367, 0.303_900],
+                    ],
+                    ColorBlindness::Achromatopsia => [[0.2126, 0.7152, 0.0722]; 3],
+                };
+                // the matrices apply to linear colors.
+                (matrix, 0.0, 2.2, 1.0 / 2.2)
+            }
+            Self::Filter(filter) => {
+                let scale = filter.brightness * filter.contrast;
+                let matrix = [[scale, 0.0, 0.0], [0.0, scale, 0.0], [0.0, 0.0, scale]];
+                let offset = filter.brightness * 0.5 * (1.0 - filter.contrast);
+                (matrix, offset, 1.0, 1.0 / filter.gamma.max(f32::EPSILON))
+            }
+            Self::Shader { push_constants, .. } => return *push_constants,
+        };
+        let mut constants = [0.0; 24];
+        for column in 0..3 {
+            for row in 0..3 {
+                constants[column * 4 + row] = matrix[row][column];
+            }
+        }
+        constants[12..16].copy_from_slice(&[offset, offset, offset, 1.0]);
+        constants[16..20].copy_from_slice(&[decode, decode, decode, 1.0]);
+        constants[20..24].copy_from_slice(&[encode, encode, encode, 1.0]);
+        constants
+    }
+}
+
+/// set the post pass of the egui layer of `viewport_id`, `None` to draw it as it is.
+pub fn set_post_process(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    post_process: Option<PostProcess>,
+) {
+    ctx.data_mut(|data| match post_process {
+        Some(post_process) => data.insert_temp(post_process_id(viewport_id), post_process),
+        None => data.remove::<PostProcess>(post_process_id(viewport_id)),
+    });
+    ctx.request_repaint_of(viewport_id);
+}
+
+/// the post pass of `viewport_id`, see [`set_post_process`].
+pub fn post_process(ctx: &egui::Context, viewport_id: egui::ViewportId) -> Option<PostProcess> {
+    ctx.data(|data| data.get_temp(post_process_id(viewport_id)))
+}
@@ -0,0 +1,232 @@
+//! power source and power saver mode of the system, delivered as
+//! [`crate::event::AppEvent::PowerChanged`].
+//!
+//! The status is polled on a thread of its own, changes arrive within a few seconds. Set
+//! [`crate::RunOption::battery_max_fps`] to cap the frame rate while the system saves power.
+
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::time::Duration;
+
+use crate::integration::IntegrationEvent;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// where the system takes its power from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    /// mains power, also desktops without a battery.
+    Ac,
+    Battery,
+    /// the platform does not report it.
+    Unknown,
+}
+
+/// power status of the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    /// power saver mode, low power mode on macOS, battery saver on Windows.
+    pub power_saver: bool,
+}
+impl PowerStatus {
+    /// whether the app should save power, on battery or in power saver mode.
+    pub fn saving_power(&self) -> bool {
+        self.source == PowerSource::Battery || self.power_saver
+    }
+}
+
+/// send the status once and then on every change to the event loop.
+pub(crate) fn watch(event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+    let spawned = std::thread::Builder::new()
+        .name("egui-ash power".to_owned())
+        .spawn(move || {
+            let mut last = None;
+            loop {
+                let status = platform::power_status();
+                if last != Some(status) {
+                    last = Some(status);
+                    if event_loop_proxy
+                        .send_event(IntegrationEvent::PowerChanged(status))
+                        .is_err()
+                    {
+                        // the event loop exited.
+                        return;
+                    }
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        });
+    if let Err(err) = spawned {
+        error!("Failed to start the power status thread: {err}");
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod platform {
+    use super::{PowerSource, PowerStatus};
+
+    fn read(path: &std::path::Path) -> Option<String> {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|contents| contents.trim().to_owned())
+    }
+
+    pub(super) fn power_status() -> PowerStatus {
+        let (mut has_mains, mut mains_online, mut discharging) = (false, false, false);
+        if let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") {
+            for supply in supplies.flatten() {
+                let path = supply.path();
+                match read(&path.join("type")).as_deref() {
+                    Some("Mains") => {
+                        has_mains = true;
+                        mains_online |= read(&path.join("online")).as_deref() == Some("1");
+                    }
+                    Some("Battery") => {
+                        discharging |= read(&path.join("status")).as_deref() == Some("Discharging");
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // without a mains supply, e.g. in some VMs, the battery tells whether it is charged.
+        let on_battery = if has_mains {
+            !mains_online
+        } else {
+            discharging
+        };
+        let source = if on_battery {
+            PowerSource::Battery
+        } else {
+            PowerSource::Ac
+        };
+        // power-profiles-daemon sets the platform profile to low-power in power saver mode.
+        let power_saver = read(std::path::Path::new("/sys/firmware/acpi/platform_profile"))
+            .as_deref()
+            == Some("low-power");
+        PowerStatus {
+            source,
+            power_saver,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{PowerSource, PowerStatus};
+
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(non_snake_case)]
+    struct SYSTEM_POWER_STATUS {
+        ACLineStatus: u8,
+        BatteryFlag: u8,
+        BatteryLifePercent: u8,
+        SystemStatusFlag: u8,
+        BatteryLifeTime: u32,
+        BatteryFullLifeTime: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SYSTEM_POWER_STATUS) -> i32;
+    }
+
+    pub(super) fn power_status() -> PowerStatus {
+        let mut status = SYSTEM_POWER_STATUS::default();
+        // SAFETY: `status` is a valid SYSTEM_POWER_STATUS to write to.
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return PowerStatus {
+                source: PowerSource::Unknown,
+                power_saver: false,
+            };
+        }
+        let source = match status.ACLineStatus {
+            0 => PowerSource::Battery,
+            1 => PowerSource::Ac,
+            _ => PowerSource::Unknown,
+        };
+        PowerStatus {
+            source,
+            power_saver: status.SystemStatusFlag == 1,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::{class, msg_send, runtime::AnyObject, sel};
+    use objc2_foundation::NSString;
+    use std::ffi::c_void;
+
+    use super::{PowerSource, PowerStatus};
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPSCopyPowerSourcesInfo() -> *const c_void;
+        fn IOPSGetProvidingPowerSourceType(snapshot: *const c_void) -> *const NSString;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    pub(super) fn power_status() -> PowerStatus {
+        // SAFETY: the snapshot is released after the type, a CFString owned by it, is read.
+        let source = unsafe {
+            let snapshot = IOPSCopyPowerSourcesInfo();
+            if snapshot.is_null() {
+                PowerSource::Unknown
+            } else {
+                let source_type = IOPSGetProvidingPowerSourceType(snapshot);
+                let source = match source_type
+                    .as_ref()
+                    .map(|source_type| source_type.to_string())
+                {
+                    Some(source_type) if source_type == "AC Power" => PowerSource::Ac,
+                    Some(source_type) if source_type == "Battery Power" => PowerSource::Battery,
+                    _ => PowerSource::Unknown,
+                };
+                CFRelease(snapshot);
+                source
+            }
+        };
+        // SAFETY: `isLowPowerModeEnabled` is only sent when NSProcessInfo responds to it (macOS 12+).
+        let power_saver = unsafe {
+            let process_info: *mut AnyObject = msg_send![class!(NSProcessInfo), processInfo];
+            let responds: bool =
+                msg_send![process_info, respondsToSelector: sel!(isLowPowerModeEnabled)];
+            responds && msg_send![process_info, isLowPowerModeEnabled]
+        };
+        PowerStatus {
+            source,
+            power_saver,
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "windows",
+    target_os = "macos"
+)))]
+mod platform {
+    use super::{PowerSource, PowerStatus};
+
+    pub(super) fn power_status() -> PowerStatus {
+        PowerStatus {
+            source: PowerSource::Unknown,
+            power_saver: false,
+        }
+    }
+}
@@ -1,35 +1,164 @@
 use anyhow::Result;
 use ash::{vk, Device, Entry, Instance};
 use egui_winit::winit;
-use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
-use std::collections::{hash_map::Entry as HashMapEntry, HashMap};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use std::{
+    collections::{hash_map::Entry as HashMapEntry, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use crate::{
+    allocator::Allocator,
+    app::AshRenderState,
+    crash_diagnostics::{self, Breadcrumbs, GpuCrashDiagnostics},
+    device::{DeviceCapabilities, DeviceFeature},
+    inspector::{Inspector, SwapchainInfo},
+    mirror::{self, MirrorFit},
     renderer::{EguiCommand, SwapchainUpdateInfo},
+    stats::FrameStatistics,
+    submit::{FinalPassInfo, SubmitHooks, SubmitInfo, SubmittedFrame},
     utils,
 };
 
+/// unsignaled fences to reuse for the next submissions.
+type FencePool = Arc<Mutex<Vec<vk::Fence>>>;
+
+/// viewports whose frame did not finish within the GPU hang timeout, to notify the app.
+pub(crate) type GpuHangs = Arc<Mutex<Vec<egui::ViewportId>>>;
+
+/// viewports whose swapchain went suboptimal or out of date, to repaint them with a new one.
+pub(crate) type StaleSwapchains = Arc<Mutex<Vec<egui::ViewportId>>>;
+
+/// fence of a queue submission, shared by the presenters submitted together.
+///
+/// The fence returns to the pool when dropped.
+struct SubmitFence {
+    device: Device,
+    fence: vk::Fence,
+    pool: FencePool,
+}
+impl SubmitFence {
+    fn new(device: &Device, pool: &FencePool) -> Result<Self> {
+        let fence = match pool.lock().unwrap().pop() {
+            Some(fence) => fence,
+            None => unsafe { device.create_fence(&vk::FenceCreateInfo::default(), None)? },
+        };
+        Ok(Self {
+            device: device.clone(),
+            fence,
+            pool: pool.clone(),
+        })
+    }
+}
+impl Drop for SubmitFence {
+    fn drop(&mut self) {
+        // the presenters wait for their fences before dropping them, so the fence is
+        // signaled or it has never been submitted.
+        let reset = unsafe {
+            match self.device.get_fence_status(self.fence) {
+                Ok(true) => self.device.reset_fences(std::slice::from_ref(&self.fence)),
+                Ok(false) => Ok(()),
+                Err(err) => Err(err),
+            }
+        };
+        match reset {
+            Ok(()) => self.pool.lock().unwrap().push(self.fence),
+            Err(_) => unsafe { self.device.destroy_fence(self.fence, None) },
+        }
+    }
+}
+
+/// `vkCmdPipelineBarrier2` and `vkQueueSubmit2` of [`DeviceFeature::Synchronization2`], core in
+/// Vulkan 1.3 or from `VK_KHR_synchronization2`.
+enum Synchronization2 {
+    Core(Box<Device>),
+    Extension(ash::khr::synchronization2::Device),
+}
+impl Synchronization2 {
+    fn new(
+        instance: &Instance,
+        device: &Device,
+        capabilities: &DeviceCapabilities,
+    ) -> Option<Self> {
+        if !capabilities.is_enabled(DeviceFeature::Synchronization2) {
+            return None;
+        }
+        Some(if capabilities.api_version >= vk::API_VERSION_1_3 {
+            Self::Core(Box::new(device.clone()))
+        } else {
+            Self::Extension(ash::khr::synchronization2::Device::new(instance, device))
+        })
+    }
+
+    fn cmd_pipeline_barrier2(&self, cmd: vk::CommandBuffer, dependency_info: &vk::DependencyInfo) {
+        unsafe {
+            match self {
+                Self::Core(device) => device.cmd_pipeline_barrier2(cmd, dependency_info),
+                Self::Extension(loader) => loader.cmd_pipeline_barrier2(cmd, dependency_info),
+            }
+        }
+    }
+
+    fn queue_submit2(
+        &self,
+        queue: vk::Queue,
+        submits: &[vk::SubmitInfo2],
+        fence: vk::Fence,
+    ) -> ash::prelude::VkResult<()> {
+        unsafe {
+            match self {
+                Self::Core(device) => device.queue_submit2(queue, submits, fence),
+                Self::Extension(loader) => loader.queue_submit2(queue, submits, fence),
+            }
+        }
+    }
+}
+
+/// how the swapchain of a [`Presenter`] is created, kept to recreate it.
+#[derive(Clone, Copy)]
+struct SwapchainOptions {
+    present_mode: vk::PresentModeKHR,
+    max_image_count: Option<u32>,
+    // the window is transparent, the swapchain blends with what is behind it.
+    transparent: bool,
+}
+
 struct Presenter {
     width: u32,
     height: u32,
 
     _device: Device,
     surface: vk::SurfaceKHR,
-    present_mode: vk::PresentModeKHR,
+    options: SwapchainOptions,
+    // the swapchain images can be copied to a mirror.
+    copyable: bool,
+    // the clear color of the last recorded frame.
+    clear_color: [f32; 4],
 
     swapchain: vk::SwapchainKHR,
     swapchain_images: Vec<vk::Image>,
     swapchain_format: vk::Format,
     swapchain_extent: vk::Extent2D,
 
+    // each presenter records on its own pool, so that the viewports can record in parallel.
+    command_pool: vk::CommandPool,
     render_command_buffers: Vec<vk::CommandBuffer>,
+    // begin and end timestamps of each frame slot, if the queue supports timestamps.
+    query_pool: Option<vk::QueryPool>,
 
-    in_flight_fences: Vec<vk::Fence>,
+    in_flight_fences: Vec<Option<Arc<SubmitFence>>>,
     image_available_semaphores: Vec<vk::Semaphore>,
     render_finished_semaphores: Vec<vk::Semaphore>,
     current_frame: usize,
 
     dirty_flag: bool,
+    // a frame has been drawn to the swapchain, so unchanged frames can be skipped.
+    presented: bool,
+    // a frame has been presented since the presenter was created, so the window can be shown.
+    has_presented: bool,
+    // the frame in the current slot did not finish within the GPU hang timeout.
+    hung: bool,
 }
 impl Presenter {
     fn create_swapchain(
@@ -39,8 +168,13 @@ impl Presenter {
         surface: vk::SurfaceKHR,
         surface_loader: &ash::khr::surface::Instance,
         swapchain_loader: &ash::khr::swapchain::Device,
-        present_mode: vk::PresentModeKHR,
+        options: SwapchainOptions,
     ) -> Result<(vk::SwapchainKHR, Vec<vk::Image>, vk::Format, vk::Extent2D)> {
+        let SwapchainOptions {
+            present_mode,
+            max_image_count,
+            transparent,
+        } = options;
         let surface_capabilities = unsafe {
             surface_loader.get_physical_device_surface_capabilities(physical_device, surface)?
         };
@@ -66,6 +200,27 @@ impl Presenter {
             .find(|&&mode| mode == present_mode)
             .unwrap_or(&vk::PresentModeKHR::FIFO);
 
+        // select composite alpha. egui outputs premultiplied colors.
+        let composite_alpha = if transparent {
+            [
+                vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+                vk::CompositeAlphaFlagsKHR::INHERIT,
+                vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+            ]
+            .into_iter()
+            .find(|&alpha| {
+                surface_capabilities
+                    .supported_composite_alpha
+                    .contains(alpha)
+            })
+            .unwrap_or_else(|| {
+                warn!("Surface does not support transparency, the window is opaque");
+                vk::CompositeAlphaFlagsKHR::OPAQUE
+            })
+        } else {
+            vk::CompositeAlphaFlagsKHR::OPAQUE
+        };
+
         // calculate extent
         let surface_extent = if surface_capabilities.current_extent.width == u32::MAX {
             vk::Extent2D {
@@ -89,6 +244,11 @@ impl Presenter {
         } else {
             image_count
         };
+        let image_count = max_image_count.map_or(image_count, |max| {
+            image_count
+                .min(max)
+                .max(surface_capabilities.min_image_count)
+        });
 
         // create swapchain
         let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
@@ -97,10 +257,16 @@ impl Presenter {
             .image_color_space(surface_format.color_space)
             .image_format(surface_format.format)
             .image_extent(surface_extent)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
+            .image_usage(
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    // mirrors copy from the swapchain images
+                    | (surface_capabilities.supported_usage_flags
+                        & vk::ImageUsageFlags::TRANSFER_SRC),
+            )
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
             .pre_transform(surface_capabilities.current_transform)
-            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .composite_alpha(composite_alpha)
             .present_mode(*surface_present_mode)
             .image_array_layers(1)
             .clipped(true);
@@ -131,18 +297,29 @@ impl Presenter {
         Ok(command_buffers)
     }
 
+    fn create_query_pool(device: &Device, len: u32) -> Result<vk::QueryPool> {
+        let query_pool = unsafe {
+            device.create_query_pool(
+                &vk::QueryPoolCreateInfo::default()
+                    .query_type(vk::QueryType::TIMESTAMP)
+                    .query_count(2 * len),
+                None,
+            )?
+        };
+        Ok(query_pool)
+    }
+
+    #[allow(clippy::type_complexity)]
     fn create_sync_objects(
         device: &Device,
         len: u32,
-    ) -> Result<(Vec<vk::Fence>, Vec<vk::Semaphore>, Vec<vk::Semaphore>)> {
-        // create fences
-        let fence_create_info =
-            vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
-        let mut in_flight_fences = vec![];
-        for _ in 0..len {
-            let fence = unsafe { device.create_fence(&fence_create_info, None)? };
-            in_flight_fences.push(fence);
-        }
+    ) -> Result<(
+        Vec<Option<Arc<SubmitFence>>>,
+        Vec<vk::Semaphore>,
+        Vec<vk::Semaphore>,
+    )> {
+        // the fences are created on submit
+        let in_flight_fences = vec![None; len as usize];
 
         // create semaphores
         let mut image_available_semaphores = vec![];
@@ -173,12 +350,14 @@ impl Presenter {
         device: Device,
         surface_loader: &ash::khr::surface::Instance,
         swapchain_loader: &ash::khr::swapchain::Device,
-        command_pool: vk::CommandPool,
-        window: &winit::window::Window,
-        present_mode: vk::PresentModeKHR,
+        queue_family_index: u32,
+        window: &(impl HasDisplayHandle + HasWindowHandle),
+        size: winit::dpi::PhysicalSize<u32>,
+        options: SwapchainOptions,
+        timestamps: bool,
     ) -> Option<Self> {
-        let width = window.inner_size().width;
-        let height = window.inner_size().height;
+        let width = size.width;
+        let height = size.height;
 
         // if window is minimized, return empty presenter
         if width == 0 || height == 0 {
@@ -202,6 +381,14 @@ impl Presenter {
             )
             .expect("Failed to create surface")
         };
+        let copyable = unsafe {
+            surface_loader.get_physical_device_surface_capabilities(physical_device, surface)
+        }
+        .is_ok_and(|capabilities| {
+            capabilities
+                .supported_usage_flags
+                .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+        });
 
         // create swapchain
         let (swapchain, swapchain_images, swapchain_format, swapchain_extent) =
@@ -212,9 +399,24 @@ impl Presenter {
                 surface,
                 surface_loader,
                 swapchain_loader,
-                present_mode,
+                options,
             )
             .expect("Failed to create swapchain");
+        debug!(
+            "Created swapchain {width}x{height} {swapchain_format:?} with {} images",
+            swapchain_images.len()
+        );
+
+        // create command pool
+        let command_pool = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::default()
+                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+                    .queue_family_index(queue_family_index),
+                None,
+            )
+        }
+        .expect("Failed to create command pool");
 
         // create render command buffers
         let render_command_buffers = Self::create_render_command_buffers(
@@ -224,6 +426,12 @@ impl Presenter {
         )
         .expect("Failed to create render command buffers");
 
+        // create timestamp queries
+        let query_pool = timestamps.then(|| {
+            Self::create_query_pool(&device, swapchain_images.len() as u32)
+                .expect("Failed to create query pool")
+        });
+
         // create sync objects
         let (in_flight_fences, image_available_semaphores, render_finished_semaphores) =
             Self::create_sync_objects(&device, swapchain_images.len() as u32)
@@ -235,14 +443,18 @@ impl Presenter {
 
             _device: device,
             surface,
-            present_mode,
+            options,
+            copyable,
+            clear_color: [0.0; 4],
 
             swapchain,
             swapchain_images,
             swapchain_format,
             swapchain_extent,
 
+            command_pool,
             render_command_buffers,
+            query_pool,
 
             in_flight_fences,
             image_available_semaphores,
@@ -250,6 +462,9 @@ impl Presenter {
             current_frame: 0,
 
             dirty_flag: true,
+            presented: false,
+            has_presented: false,
+            hung: false,
         })
     }
 
@@ -259,16 +474,16 @@ impl Presenter {
         device: &Device,
         surface_loader: &ash::khr::surface::Instance,
         swapchain_loader: &ash::khr::swapchain::Device,
-        command_pool: vk::CommandPool,
-        window: &winit::window::Window,
+        size: winit::dpi::PhysicalSize<u32>,
     ) {
-        let width = window.inner_size().width;
-        let height = window.inner_size().height;
+        let width = size.width;
+        let height = size.height;
 
         // if window is minimized, do nothing
         if width == 0 || height == 0 {
             return;
         }
+        span!("recreate_swapchain", width, height);
 
         // wait device idle
         unsafe {
@@ -277,20 +492,8 @@ impl Presenter {
                 .expect("Failed to wait device idle");
         };
 
-        // cleanup old swapchain and sync objects
+        // cleanup old swapchain. The command buffers and sync objects are reused.
         unsafe {
-            for &fence in &self.in_flight_fences {
-                device.destroy_fence(fence, None);
-            }
-            for &semaphore in &self.image_available_semaphores {
-                device.destroy_semaphore(semaphore, None);
-            }
-            for &semaphore in &self.render_finished_semaphores {
-                device.destroy_semaphore(semaphore, None);
-            }
-            for cmd in &self.render_command_buffers {
-                device.free_command_buffers(command_pool, std::slice::from_ref(cmd));
-            }
             swapchain_loader.destroy_swapchain(self.swapchain, None);
         }
 
@@ -303,22 +506,17 @@ impl Presenter {
                 self.surface,
                 surface_loader,
                 swapchain_loader,
-                self.present_mode,
+                self.options,
             )
             .expect("Failed to create swapchain");
+        debug!(
+            "Recreated swapchain {width}x{height} {swapchain_format:?} with {} images",
+            swapchain_images.len()
+        );
 
-        // create render command buffers
-        let render_command_buffers = Self::create_render_command_buffers(
-            device,
-            command_pool,
-            swapchain_images.len() as u32,
-        )
-        .expect("Failed to create render command buffers");
-
-        // create sync objects
-        let (in_flight_fences, image_available_semaphores, render_finished_semaphores) =
-            Self::create_sync_objects(device, swapchain_images.len() as u32)
-                .expect("Failed to create sync objects");
+        // one command buffer and sync objects per swapchain image
+        self.resize_frames(device, swapchain_images.len())
+            .expect("Failed to create sync objects");
 
         // update self
         self.width = width;
@@ -327,29 +525,70 @@ impl Presenter {
         self.swapchain_images = swapchain_images;
         self.swapchain_format = swapchain_format;
         self.swapchain_extent = swapchain_extent;
-        self.render_command_buffers = render_command_buffers;
-        self.in_flight_fences = in_flight_fences;
-        self.image_available_semaphores = image_available_semaphores;
-        self.render_finished_semaphores = render_finished_semaphores;
         self.current_frame = 0;
+        self.presented = false;
     }
 
-    fn present(
+    /// grow or shrink the command buffers and semaphores to `len` frames,
+    /// keeping the ones of the previous swapchain. The device must be idle.
+    fn resize_frames(&mut self, device: &Device, len: usize) -> Result<()> {
+        let current_len = self.render_command_buffers.len();
+        if len > current_len {
+            let extra = (len - current_len) as u32;
+            let render_command_buffers =
+                Self::create_render_command_buffers(device, self.command_pool, extra)?;
+            let (_, image_available_semaphores, render_finished_semaphores) =
+                Self::create_sync_objects(device, extra)?;
+            self.render_command_buffers.extend(render_command_buffers);
+            self.image_available_semaphores
+                .extend(image_available_semaphores);
+            self.render_finished_semaphores
+                .extend(render_finished_semaphores);
+        } else if len < current_len {
+            unsafe {
+                device.free_command_buffers(self.command_pool, &self.render_command_buffers[len..]);
+                for &semaphore in self.image_available_semaphores[len..]
+                    .iter()
+                    .chain(&self.render_finished_semaphores[len..])
+                {
+                    device.destroy_semaphore(semaphore, None);
+                }
+            }
+            self.render_command_buffers.truncate(len);
+            self.image_available_semaphores.truncate(len);
+            self.render_finished_semaphores.truncate(len);
+        }
+        if let Some(query_pool) = self.query_pool {
+            if len != current_len {
+                unsafe { device.destroy_query_pool(query_pool, None) };
+                self.query_pool = Some(Self::create_query_pool(device, len as u32)?);
+            }
+        }
+        // no frame is in flight, the fences return to the pool.
+        self.in_flight_fences = vec![None; len];
+        Ok(())
+    }
+
+    /// wait for the frame slot and acquire the next swapchain image.
+    ///
+    /// `None` if the swapchain is out of date.
+    /// Fails with `vk::Result::TIMEOUT` if the frame slot is not free within `hang_timeout`.
+    fn acquire(
         &mut self,
-        mut egui_cmd: EguiCommand,
         device: &Device,
         swapchain_loader: &ash::khr::swapchain::Device,
-        queue: vk::Queue,
-    ) -> anyhow::Result<()> {
+        hang_timeout: Option<Duration>,
+    ) -> Result<Option<usize>> {
+        profile_function!();
         // Wait for the resources at this index to be completed on the GPU before requesting an available image.
         // Otherwise, the `image_available_semaphores` below may not be ready for reuse.
-        unsafe {
-            device.wait_for_fences(
-                std::slice::from_ref(&self.in_flight_fences[self.current_frame]),
-                true,
-                u64::MAX,
-            )
-        }?;
+        if let Some(fence) = &self.in_flight_fences[self.current_frame] {
+            let timeout = hang_timeout.map_or(u64::MAX, |timeout| {
+                u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX)
+            });
+            unsafe { device.wait_for_fences(std::slice::from_ref(&fence.fence), true, timeout) }?;
+        }
+        self.hung = false;
 
         // acquire next image
         let result = unsafe {
@@ -360,15 +599,27 @@ impl Presenter {
                 vk::Fence::null(),
             )
         };
-        let index = match result {
-            Ok((index, _)) => index as usize,
+        match result {
+            Ok((index, _)) => Ok(Some(index as usize)),
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
                 self.dirty_flag = true;
-                return Ok(());
+                Ok(None)
             }
-            Err(error) => return Err(anyhow::anyhow!(error)),
-        };
+            Err(error) => Err(anyhow::anyhow!(error)),
+        }
+    }
 
+    /// begin the command buffer of the frame slot, update the swapchain and the textures of `egui_cmd`.
+    ///
+    /// These submit to the queue, so the frames are begun one after another.
+    fn begin_record(
+        &mut self,
+        egui_cmd: &mut EguiCommand,
+        device: &Device,
+        synchronization2: Option<&Synchronization2>,
+        index: usize,
+    ) -> Result<()> {
+        profile_function!();
         // clear command buffer
         unsafe {
             device.reset_command_buffer(
@@ -401,76 +652,317 @@ impl Presenter {
 
         // Ensure that the image layout is correct for egui (i.e., `COLOR_ATTACHMENT_OPTIMAL`).
         // This includes converting `UNDEFINED` or `PRESENT_SRC_KHR` layouts to `COLOR_ATTACHMENT_OPTIMAL`, as well as a no-op when it is already in the correct layout.
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1)
+            .level_count(1);
+        // egui does not paint everywhere, e.g. behind transparent panels, so start from a cleared image.
+        self.clear_color = egui_cmd.clear_color();
+        self.clear(device, index, subresource_range);
+        match synchronization2 {
+            Some(synchronization2) => {
+                let barrier = vk::ImageMemoryBarrier2::default()
+                    .src_stage_mask(vk::PipelineStageFlags2::CLEAR)
+                    .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                    .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+                    .dst_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_READ)
+                    .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                    .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(self.swapchain_images[index])
+                    .subresource_range(subresource_range);
+                synchronization2.cmd_pipeline_barrier2(
+                    self.render_command_buffers[self.current_frame],
+                    &vk::DependencyInfo::default()
+                        .image_memory_barriers(std::slice::from_ref(&barrier)),
+                );
+            }
+            None => utils::insert_image_memory_barrier(
+                device,
+                self.render_command_buffers[self.current_frame],
+                self.swapchain_images[index],
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                subresource_range,
+            ),
+        }
+
+        egui_cmd.update_textures();
+        self.presented = true;
+        Ok(())
+    }
+
+    /// clear the swapchain image to the clear color, leaving it in `TRANSFER_DST_OPTIMAL`.
+    fn clear(&self, device: &Device, index: usize, subresource_range: vk::ImageSubresourceRange) {
+        let command_buffer = self.render_command_buffers[self.current_frame];
         utils::insert_image_memory_barrier(
             device,
-            self.render_command_buffers[self.current_frame],
+            command_buffer,
             self.swapchain_images[index],
             vk::QUEUE_FAMILY_IGNORED,
             vk::QUEUE_FAMILY_IGNORED,
             vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-            vk::AccessFlags::COLOR_ATTACHMENT_READ,
+            vk::AccessFlags::TRANSFER_WRITE,
             vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            vk::ImageSubresourceRange::default()
-                .aspect_mask(vk::ImageAspectFlags::COLOR)
-                .layer_count(1)
-                .level_count(1),
+            vk::PipelineStageFlags::TRANSFER,
+            subresource_range,
         );
+        unsafe {
+            device.cmd_clear_color_image(
+                command_buffer,
+                self.swapchain_images[index],
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: self.clear_color,
+                },
+                &[subresource_range],
+            );
+        }
+    }
 
-        // record egui cmd
-        egui_cmd.record(self.render_command_buffers[self.current_frame], index);
+    /// record the draw commands of `egui_cmd`, the final pass of the app, the copy to the mirror and
+    /// end the command buffer.
+    fn end_record(
+        &self,
+        egui_cmd: EguiCommand,
+        device: &Device,
+        index: usize,
+        mirror: Option<(&Mirror, usize)>,
+        breadcrumbs: Option<(&Breadcrumbs, u32)>,
+        final_pass: (&SubmitHooks, egui::ViewportId),
+    ) -> Result<()> {
+        profile_function!();
+        let cmd = self.render_command_buffers[self.current_frame];
+        let first_query = 2 * self.current_frame as u32;
+
+        // record egui cmd between the timestamps of the frame slot
+        if let Some(query_pool) = self.query_pool {
+            unsafe {
+                device.cmd_reset_query_pool(cmd, query_pool, first_query, 2);
+                device.cmd_write_timestamp(
+                    cmd,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    query_pool,
+                    first_query,
+                );
+            }
+        }
+        if let Some((breadcrumbs, marker)) = breadcrumbs {
+            breadcrumbs.begin(cmd, marker);
+        }
+        egui_cmd.record(cmd, index);
+        let (submit_hooks, viewport_id) = final_pass;
+        let final_pass = submit_hooks.final_pass(&FinalPassInfo {
+            viewport_id,
+            command_buffer: cmd,
+            image: self.swapchain_images[index],
+            image_index: index,
+            format: self.swapchain_format,
+            extent: self.swapchain_extent,
+        });
+        if let Some((mirror, mirror_index)) = mirror {
+            if final_pass {
+                // the final pass may write the image in any stage.
+                unsafe {
+                    device.cmd_pipeline_barrier(
+                        cmd,
+                        vk::PipelineStageFlags::ALL_COMMANDS,
+                        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                        vk::DependencyFlags::empty(),
+                        &[vk::MemoryBarrier::default()
+                            .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+                            .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)],
+                        &[],
+                        &[],
+                    );
+                }
+            }
+            self.record_mirror(device, index, mirror, mirror_index);
+        }
+        if let Some((breadcrumbs, marker)) = breadcrumbs {
+            breadcrumbs.end(cmd, marker);
+        }
+        if let Some(query_pool) = self.query_pool {
+            unsafe {
+                device.cmd_write_timestamp(
+                    cmd,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    query_pool,
+                    first_query + 1,
+                );
+            }
+        }
 
         // end command buffer
-        unsafe { device.end_command_buffer(self.render_command_buffers[self.current_frame]) }?;
+        unsafe { device.end_command_buffer(cmd) }?;
+        Ok(())
+    }
 
-        // reset fence
-        unsafe {
-            device.reset_fences(std::slice::from_ref(
-                &self.in_flight_fences[self.current_frame],
-            ))
-        }?;
-
-        // submit command buffer
-        let buffers_to_submit = [self.render_command_buffers[self.current_frame]];
-        let submit_info = vk::SubmitInfo::default()
-            .command_buffers(&buffers_to_submit)
-            .wait_semaphores(std::slice::from_ref(
-                &self.image_available_semaphores[self.current_frame],
-            ))
-            .wait_dst_stage_mask(&[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT])
-            .signal_semaphores(std::slice::from_ref(
-                &self.render_finished_semaphores[self.current_frame],
-            ));
+    /// copy the swapchain image at `index` to the image of `mirror` at `mirror_index`, leaving both
+    /// in `PRESENT_SRC_KHR`.
+    fn record_mirror(&self, device: &Device, index: usize, mirror: &Mirror, mirror_index: usize) {
+        let cmd = self.render_command_buffers[self.current_frame];
+        let image = self.swapchain_images[index];
+        let mirror_image = mirror.presenter.swapchain_images[mirror_index];
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1)
+            .level_count(1);
+        let subresource_layers = vk::ImageSubresourceLayers::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1);
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::AccessFlags::TRANSFER_READ,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            subresource_range,
+        );
+        // the acquire of the mirror image is waited for at the transfer stage.
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            mirror_image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER,
+            subresource_range,
+        );
         unsafe {
-            device.queue_submit(
-                queue,
-                std::slice::from_ref(&submit_info),
-                self.in_flight_fences[self.current_frame],
-            )?;
-        };
+            // black bars around the copy
+            device.cmd_clear_color_image(
+                cmd,
+                mirror_image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+                &[subresource_range],
+            );
+        }
+        let offsets = mirror::blit_offsets(
+            mirror.crop,
+            mirror.fit,
+            mirror.pixels_per_point,
+            self.swapchain_extent,
+            mirror.presenter.swapchain_extent,
+        );
+        if let Some((src_offsets, dst_offsets)) = offsets {
+            utils::insert_image_memory_barrier(
+                device,
+                cmd,
+                mirror_image,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                subresource_range,
+            );
+            let region = vk::ImageBlit::default()
+                .src_subresource(subresource_layers)
+                .src_offsets(src_offsets)
+                .dst_subresource(subresource_layers)
+                .dst_offsets(dst_offsets);
+            unsafe {
+                device.cmd_blit_image(
+                    cmd,
+                    image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    mirror_image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[region],
+                    vk::Filter::LINEAR,
+                );
+            }
+        }
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::TRANSFER_READ,
+            vk::AccessFlags::empty(),
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            subresource_range,
+        );
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            mirror_image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::empty(),
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            subresource_range,
+        );
+    }
 
-        // present swapchain image
-        let image_indices = [index as u32];
-        let present_info = vk::PresentInfoKHR::default()
-            .wait_semaphores(std::slice::from_ref(
-                &self.render_finished_semaphores[self.current_frame],
-            ))
-            .swapchains(std::slice::from_ref(&self.swapchain))
-            .image_indices(&image_indices);
-        let result = unsafe { swapchain_loader.queue_present(queue, &present_info) };
-        let is_dirty_swapchain = match result {
-            Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR) => true,
-            Err(error) => panic!("Failed to present queue. Cause: {error}"),
-            _ => false,
-        };
-        self.dirty_flag = is_dirty_swapchain;
+    /// update the frame slot after its frame was presented with `result`.
+    fn frame_presented(&mut self, result: vk::Result, fence: &Arc<SubmitFence>) {
+        self.dirty_flag = matches!(
+            result,
+            vk::Result::SUBOPTIMAL_KHR | vk::Result::ERROR_OUT_OF_DATE_KHR
+        );
+        self.has_presented |= matches!(result, vk::Result::SUCCESS | vk::Result::SUBOPTIMAL_KHR);
+        self.in_flight_fences[self.current_frame] = Some(fence.clone());
 
         // update current_frame
         self.current_frame = (self.current_frame + 1) % self.in_flight_fences.len();
+    }
 
-        Ok(())
+    /// GPU time of the frame last submitted in the current frame slot.
+    ///
+    /// Call after [`Self::acquire`] waited for its fence.
+    fn gpu_time(&self, device: &Device, timestamp_period: f32) -> Option<Duration> {
+        let query_pool = self.query_pool?;
+        self.in_flight_fences[self.current_frame].as_ref()?;
+        let mut timestamps = [0u64; 2];
+        unsafe {
+            device.get_query_pool_results(
+                query_pool,
+                2 * self.current_frame as u32,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        }
+        .ok()?;
+        let ticks = timestamps[1].saturating_sub(timestamps[0]);
+        Some(Duration::from_nanos(
+            (ticks as f64 * f64::from(timestamp_period)) as u64,
+        ))
     }
 
     fn destroy(
@@ -478,7 +970,6 @@ impl Presenter {
         device: &Device,
         surface_loader: &ash::khr::surface::Instance,
         swapchain_loader: &ash::khr::swapchain::Device,
-        command_pool: vk::CommandPool,
     ) {
         // wait device idle
         unsafe {
@@ -487,26 +978,58 @@ impl Presenter {
                 .expect("Failed to wait device idle");
         };
 
-        // cleanup old swapchain and sync objects
+        // cleanup old swapchain and sync objects. The fences return to the pool when dropped.
         unsafe {
-            for &fence in &self.in_flight_fences {
-                device.destroy_fence(fence, None);
-            }
             for &semaphore in &self.image_available_semaphores {
                 device.destroy_semaphore(semaphore, None);
             }
             for &semaphore in &self.render_finished_semaphores {
                 device.destroy_semaphore(semaphore, None);
             }
-            for cmd in &self.render_command_buffers {
-                device.free_command_buffers(command_pool, std::slice::from_ref(cmd));
+            if let Some(query_pool) = self.query_pool {
+                device.destroy_query_pool(query_pool, None);
             }
+            device.destroy_command_pool(self.command_pool, None);
             swapchain_loader.destroy_swapchain(self.swapchain, None);
             surface_loader.destroy_surface(self.surface, None);
         }
     }
 }
 
+// the window of a mirror, showing copies of the swapchain images of its source viewport.
+struct Mirror {
+    presenter: Presenter,
+    crop: Option<egui::Rect>,
+    fit: MirrorFit,
+    // of the source viewport, to crop in points.
+    pixels_per_point: f32,
+}
+
+/// a recorded frame with its swapchain image index, and the mirror it is copied to.
+type RecordedFrame<'a> = (&'a mut Presenter, usize, Option<(&'a mut Mirror, usize)>);
+
+/// options of the [`Presenters`], from [`crate::RunOption`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PresenterOptions {
+    pub(crate) present_mode: vk::PresentModeKHR,
+    pub(crate) skip_unchanged_frames: bool,
+    pub(crate) max_swapchain_images: Option<u32>,
+    pub(crate) gpu_crash_diagnostics: Option<GpuCrashDiagnostics>,
+    pub(crate) gpu_hang_timeout: Option<Duration>,
+}
+impl PresenterOptions {
+    /// presenting with `present_mode`, without the optional behaviors.
+    pub(crate) fn new(present_mode: vk::PresentModeKHR) -> Self {
+        Self {
+            present_mode,
+            skip_unchanged_frames: false,
+            max_swapchain_images: None,
+            gpu_crash_diagnostics: None,
+            gpu_hang_timeout: None,
+        }
+    }
+}
+
 pub struct Presenters {
     entry: Entry,
     instance: Instance,
@@ -515,34 +1038,93 @@ pub struct Presenters {
     surface_loader: ash::khr::surface::Instance,
     swapchain_loader: ash::khr::swapchain::Device,
     queue: vk::Queue,
-    command_pool: vk::CommandPool,
+    queue_family_index: u32,
     presenters: HashMap<egui::ViewportId, Presenter>,
-    present_mode: vk::PresentModeKHR,
+    // by source viewport.
+    mirrors: HashMap<egui::ViewportId, Mirror>,
+    options: PresenterOptions,
+    fence_pool: FencePool,
+    // nanoseconds per timestamp tick, `None` if the queue does not support timestamps.
+    timestamp_period: Option<f32>,
+    frame_stats: FrameStatistics,
+    inspector: Inspector,
+    breadcrumbs: Option<Breadcrumbs>,
+    gpu_hangs: GpuHangs,
+    stale_swapchains: StaleSwapchains,
+    submit_hooks: SubmitHooks,
+    synchronization2: Option<Synchronization2>,
 }
 impl Presenters {
-    #[allow(clippy::too_many_arguments)]
-    pub(crate) fn new(
-        entry: Entry,
-        instance: Instance,
-        physical_device: vk::PhysicalDevice,
-        device: Device,
-        surface_loader: ash::khr::surface::Instance,
-        swapchain_loader: ash::khr::swapchain::Device,
-        queue: vk::Queue,
-        command_pool: vk::CommandPool,
-        present_mode: vk::PresentModeKHR,
+    pub(crate) fn new<A: Allocator + 'static>(
+        render_state: &AshRenderState<A>,
+        options: PresenterOptions,
+        frame_stats: FrameStatistics,
+        inspector: Inspector,
+        submit_hooks: SubmitHooks,
     ) -> Self {
+        let instance = render_state.instance.clone();
+        let physical_device = render_state.physical_device;
+        let device = render_state.device.clone();
+        let queue_family_index = render_state.queue_family_index;
+        let timestamp_period = unsafe {
+            let limits = instance
+                .get_physical_device_properties(physical_device)
+                .limits;
+            let queue_families =
+                instance.get_physical_device_queue_family_properties(physical_device);
+            queue_families
+                .get(queue_family_index as usize)
+                .filter(|queue_family| queue_family.timestamp_valid_bits > 0)
+                .filter(|_| limits.timestamp_period > 0.0)
+                .map(|_| limits.timestamp_period)
+        };
+        let breadcrumbs = options.gpu_crash_diagnostics.and_then(|diagnostics| {
+            Breadcrumbs::new(&instance, physical_device, device.clone(), diagnostics)
+                .inspect_err(|err| error!("Failed to create GPU crash breadcrumbs: {err}"))
+                .ok()
+        });
+        let synchronization2 =
+            Synchronization2::new(&instance, &device, &DeviceCapabilities::of(&device));
         Self {
-            entry,
+            entry: render_state.entry.clone(),
             instance,
             physical_device,
             device,
-            surface_loader,
-            swapchain_loader,
-            queue,
-            command_pool,
+            surface_loader: render_state.surface_loader.clone(),
+            swapchain_loader: render_state.swapchain_loader.clone(),
+            queue: render_state.queue,
+            queue_family_index,
             presenters: HashMap::new(),
-            present_mode,
+            mirrors: HashMap::new(),
+            options,
+            fence_pool: Arc::new(Mutex::new(vec![])),
+            timestamp_period,
+            frame_stats,
+            inspector,
+            breadcrumbs,
+            gpu_hangs: Arc::new(Mutex::new(vec![])),
+            stale_swapchains: Arc::new(Mutex::new(vec![])),
+            submit_hooks,
+            synchronization2,
+        }
+    }
+
+    /// the viewports whose frames hung, taken by the integration to notify the app.
+    pub(crate) fn gpu_hangs(&self) -> GpuHangs {
+        self.gpu_hangs.clone()
+    }
+
+    /// the viewports whose swapchain must be recreated, taken by the integration to repaint them.
+    pub(crate) fn stale_swapchains(&self) -> StaleSwapchains {
+        self.stale_swapchains.clone()
+    }
+
+    /// log the breadcrumbs of the frames if `err` is a device lost.
+    fn report_device_lost(&self, err: &anyhow::Error) {
+        if let Some(breadcrumbs) = &self.breadcrumbs {
+            if crash_diagnostics::is_device_lost(err) {
+                breadcrumbs.dump(self.queue);
+            }
         }
     }
 
@@ -555,7 +1137,9 @@ impl Presenters {
     pub(crate) fn recreate_swapchain_if_needed(
         &mut self,
         viewport_id: egui::ViewportId,
-        window: &winit::window::Window,
+        window: &(impl HasDisplayHandle + HasWindowHandle),
+        size: winit::dpi::PhysicalSize<u32>,
+        transparent: bool,
     ) {
         self.presenters.entry(viewport_id).and_modify(|p| {
             if p.dirty_flag {
@@ -564,8 +1148,7 @@ impl Presenters {
                     &self.device,
                     &self.surface_loader,
                     &self.swapchain_loader,
-                    self.command_pool,
-                    window,
+                    size,
                 );
             }
         });
@@ -578,33 +1161,365 @@ impl Presenters {
                 self.device.clone(),
                 &self.surface_loader,
                 &self.swapchain_loader,
-                self.command_pool,
+                self.queue_family_index,
                 window,
-                self.present_mode,
+                size,
+                SwapchainOptions {
+                    present_mode: self.options.present_mode,
+                    max_image_count: self.options.max_swapchain_images,
+                    transparent,
+                },
+                self.timestamp_period.is_some(),
             ) {
                 entry.insert(presenter);
             }
         }
     }
 
+    /// whether a frame of the viewport has been presented.
+    pub(crate) fn has_presented(&self, viewport_id: egui::ViewportId) -> bool {
+        self.presenters
+            .get(&viewport_id)
+            .is_some_and(|presenter| presenter.has_presented)
+    }
+
     pub(crate) fn destroy_swapchain_if_needed(&mut self, viewport_id: egui::ViewportId) {
         if let Some(presenter) = self.presenters.remove(&viewport_id) {
-            presenter.destroy(
-                &self.device,
-                &self.surface_loader,
-                &self.swapchain_loader,
-                self.command_pool,
-            );
+            presenter.destroy(&self.device, &self.surface_loader, &self.swapchain_loader);
+        }
+    }
+
+    /// create the swapchain of the mirror of `source` on `window`, or recreate it if it is dirty.
+    ///
+    /// Returns whether the swapchain was created, so that the mirror needs a frame of its source.
+    pub(crate) fn update_mirror(
+        &mut self,
+        source: egui::ViewportId,
+        window: &(impl HasDisplayHandle + HasWindowHandle),
+        size: winit::dpi::PhysicalSize<u32>,
+        pixels_per_point: f32,
+        crop: Option<egui::Rect>,
+        fit: MirrorFit,
+    ) -> bool {
+        if let Some(mirror) = self.mirrors.get_mut(&source) {
+            mirror.crop = crop;
+            mirror.fit = fit;
+            mirror.pixels_per_point = pixels_per_point;
+            let recreate = mirror.presenter.dirty_flag && size.width > 0 && size.height > 0;
+            if recreate {
+                mirror.presenter.recreate(
+                    self.physical_device,
+                    &self.device,
+                    &self.surface_loader,
+                    &self.swapchain_loader,
+                    size,
+                );
+            }
+            return recreate;
+        }
+        if self
+            .presenters
+            .get(&source)
+            .is_some_and(|presenter| !presenter.copyable)
+        {
+            warn!("The swapchain images of {source:?} can not be copied, it is not mirrored");
+        }
+        let Some(presenter) = Presenter::create(
+            &self.entry,
+            &self.instance,
+            self.physical_device,
+            self.device.clone(),
+            &self.surface_loader,
+            &self.swapchain_loader,
+            self.queue_family_index,
+            window,
+            size,
+            SwapchainOptions {
+                present_mode: self.options.present_mode,
+                max_image_count: self.options.max_swapchain_images,
+                transparent: false,
+            },
+            false,
+        ) else {
+            return false;
+        };
+        self.mirrors.insert(
+            source,
+            Mirror {
+                presenter,
+                crop,
+                fit,
+                pixels_per_point,
+            },
+        );
+        true
+    }
+
+    pub(crate) fn dirty_mirror(&mut self, source: egui::ViewportId) {
+        if let Some(mirror) = self.mirrors.get_mut(&source) {
+            mirror.presenter.dirty_flag = true;
         }
     }
 
-    pub(crate) fn present_egui(&mut self, viewport_id: egui::ViewportId, egui_cmd: EguiCommand) {
-        if let Some(presenter) = self.presenters.get_mut(&viewport_id) {
-            // ignore Err to presenting swapchain image
-            let _ = presenter.present(egui_cmd, &self.device, &self.swapchain_loader, self.queue);
+    pub(crate) fn stop_mirror(&mut self, source: egui::ViewportId) {
+        if let Some(mirror) = self.mirrors.remove(&source) {
+            mirror
+                .presenter
+                .destroy(&self.device, &self.surface_loader, &self.swapchain_loader);
         }
     }
 
+    /// record the frames of the viewports in parallel, then submit and present them together.
+    pub(crate) fn present_egui(&mut self, frames: Vec<(egui::ViewportId, EguiCommand)>) {
+        profile_function!();
+        span!("present_egui", frames = frames.len());
+        // begin in the order of the frames, so that their texture updates stay in order.
+        let mut begun = HashMap::new();
+        for (viewport_id, mut egui_cmd) in frames {
+            let presenter = self
+                .presenters
+                .get_mut(&viewport_id)
+                .filter(|_| !begun.contains_key(&viewport_id));
+            let Some(presenter) = presenter else {
+                // the textures are shared by all viewports, keep them up to date.
+                egui_cmd.update_textures();
+                continue;
+            };
+            let mirror = self
+                .mirrors
+                .get_mut(&viewport_id)
+                .filter(|mirror| presenter.copyable && !mirror.presenter.dirty_flag);
+            // skip acquire and present when the image on screen is already up to date.
+            if self.options.skip_unchanged_frames
+                && egui_cmd.unchanged()
+                && presenter.presented
+                && !presenter.dirty_flag
+                && presenter.clear_color == egui_cmd.clear_color()
+                && mirror
+                    .as_ref()
+                    .is_none_or(|mirror| mirror.presenter.presented)
+            {
+                egui_cmd.update_textures();
+                continue;
+            }
+            let result = presenter
+                .acquire(
+                    &self.device,
+                    &self.swapchain_loader,
+                    self.options.gpu_hang_timeout,
+                )
+                .and_then(|index| {
+                    let Some(index) = index else {
+                        return Ok(None);
+                    };
+                    if let Some(timestamp_period) = self.timestamp_period {
+                        let gpu_time = presenter.gpu_time(&self.device, timestamp_period);
+                        self.frame_stats
+                            .update(viewport_id, |stats| stats.gpu_time = gpu_time);
+                    }
+                    presenter.begin_record(
+                        &mut egui_cmd,
+                        &self.device,
+                        self.synchronization2.as_ref(),
+                        index,
+                    )?;
+                    Ok(Some(index))
+                });
+            match result {
+                Ok(Some(index)) => {
+                    let mirror_index = mirror.and_then(|mirror| {
+                        mirror
+                            .presenter
+                            .acquire(
+                                &self.device,
+                                &self.swapchain_loader,
+                                self.options.gpu_hang_timeout,
+                            )
+                            .inspect_err(|err| {
+                                warn!(
+                                    "Failed to acquire the mirror image of {viewport_id:?}: {err}"
+                                );
+                            })
+                            .ok()
+                            .flatten()
+                    });
+                    begun.insert(viewport_id, (egui_cmd, index, mirror_index));
+                }
+                Ok(None) => {
+                    self.stale_swapchains.lock().unwrap().push(viewport_id);
+                    egui_cmd.update_textures();
+                }
+                Err(err) if err.downcast_ref::<vk::Result>() == Some(&vk::Result::TIMEOUT) => {
+                    // skip the frame instead of blocking, it is acquired again once the GPU catches up.
+                    if !presenter.hung {
+                        presenter.hung = true;
+                        error!(
+                            "Frame of {viewport_id:?} did not finish on the GPU within {:?}",
+                            self.options.gpu_hang_timeout.unwrap_or_default()
+                        );
+                        if let Some(breadcrumbs) = &self.breadcrumbs {
+                            breadcrumbs.dump(self.queue);
+                        }
+                        self.gpu_hangs.lock().unwrap().push(viewport_id);
+                    }
+                    egui_cmd.update_textures();
+                }
+                Err(err) => {
+                    error!("Failed to record frame of {viewport_id:?}: {err}");
+                    self.report_device_lost(&err);
+                    egui_cmd.update_textures();
+                }
+            }
+        }
+        let mut mirrors = self
+            .mirrors
+            .iter_mut()
+            .filter_map(|(source, mirror)| {
+                let &(_, _, mirror_index) = begun.get(source)?;
+                Some((*source, (mirror, mirror_index?)))
+            })
+            .collect::<HashMap<_, _>>();
+        let recording = self
+            .presenters
+            .iter_mut()
+            .filter_map(|(viewport_id, presenter)| {
+                let (egui_cmd, index, _) = begun.remove(viewport_id)?;
+                let mirror = mirrors.remove(viewport_id);
+                Some((*viewport_id, presenter, egui_cmd, index, mirror))
+            })
+            .collect::<Vec<_>>();
+
+        let device = &self.device;
+        let frame_stats = &self.frame_stats;
+        let breadcrumbs = self.breadcrumbs.as_ref();
+        let submit_hooks = &self.submit_hooks;
+        let end_record = |viewport_id,
+                          presenter: &Presenter,
+                          egui_cmd,
+                          index,
+                          mirror: Option<(&Mirror, usize)>| {
+            let start = Instant::now();
+            let breadcrumbs =
+                breadcrumbs.map(|breadcrumbs| (breadcrumbs, breadcrumbs.next_marker(viewport_id)));
+            let result = presenter.end_record(
+                egui_cmd,
+                device,
+                index,
+                mirror,
+                breadcrumbs,
+                (submit_hooks, viewport_id),
+            );
+            let record_time = start.elapsed();
+            frame_stats.update(viewport_id, |stats| stats.record_time = record_time);
+            result
+        };
+        let recorded = if recording.len() == 1 {
+            recording
+                .into_iter()
+                .map(|(viewport_id, presenter, egui_cmd, index, mirror)| {
+                    let mirror_ref = mirror.as_ref().map(|(mirror, index)| (&**mirror, *index));
+                    let result = end_record(viewport_id, presenter, egui_cmd, index, mirror_ref);
+                    (viewport_id, presenter, index, mirror, result)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            std::thread::scope(|scope| {
+                let handles = recording
+                    .into_iter()
+                    .map(|(viewport_id, presenter, egui_cmd, index, mirror)| {
+                        scope.spawn(move || {
+                            let mirror_ref =
+                                mirror.as_ref().map(|(mirror, index)| (&**mirror, *index));
+                            let result =
+                                end_record(viewport_id, presenter, egui_cmd, index, mirror_ref);
+                            (viewport_id, presenter, index, mirror, result)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                    })
+                    .collect()
+            })
+        };
+        let (viewport_ids, recorded): (Vec<_>, Vec<_>) = recorded
+            .into_iter()
+            .filter_map(
+                |(viewport_id, presenter, index, mirror, result)| match result {
+                    Ok(()) => Some((viewport_id, (presenter, index, mirror))),
+                    Err(err) => {
+                        error!("Failed to record frame: {err}");
+                        None
+                    }
+                },
+            )
+            .unzip();
+        if recorded.is_empty() {
+            return;
+        }
+
+        // the frames are dropped on Err, the next frames present again
+        let start = Instant::now();
+        let submission = Submission {
+            device,
+            swapchain_loader: &self.swapchain_loader,
+            queue: self.queue,
+            fence_pool: &self.fence_pool,
+            synchronization2: self.synchronization2.as_ref(),
+            breadcrumbs,
+            submit_hooks: &self.submit_hooks,
+        };
+        if let Err(err) = submission.submit_and_present(&viewport_ids, recorded) {
+            error!("Failed to submit frames: {err}");
+            self.report_device_lost(&err);
+        }
+        let submit_time = start.elapsed();
+        for viewport_id in viewport_ids {
+            frame_stats.update(viewport_id, |stats| stats.submit_time = submit_time);
+            if self
+                .presenters
+                .get(&viewport_id)
+                .is_some_and(|presenter| presenter.dirty_flag)
+            {
+                self.stale_swapchains.lock().unwrap().push(viewport_id);
+            }
+        }
+
+        if self.inspector.is_shown() {
+            self.update_inspector();
+        }
+    }
+
+    fn update_inspector(&self) {
+        let swapchains = self
+            .presenters
+            .iter()
+            .map(|(&viewport_id, presenter)| {
+                let frames_in_flight = presenter
+                    .in_flight_fences
+                    .iter()
+                    .flatten()
+                    .filter(|fence| {
+                        (unsafe { self.device.get_fence_status(fence.fence) }) == Ok(false)
+                    })
+                    .count();
+                let swapchain = SwapchainInfo {
+                    format: presenter.swapchain_format,
+                    extent: presenter.swapchain_extent,
+                    image_count: presenter.swapchain_images.len(),
+                    present_mode: presenter.options.present_mode,
+                    frames_in_flight,
+                };
+                (viewport_id, swapchain)
+            })
+            .collect();
+        self.inspector.set_swapchains(swapchains);
+    }
+
     pub(crate) fn destroy_viewports(&mut self, active_viewport_ids: &egui::ViewportIdSet) {
         let remove_viewports = self
             .presenters
@@ -616,24 +1531,213 @@ impl Presenters {
 
         for id in remove_viewports {
             if let Some(presenter) = self.presenters.remove(&id) {
-                presenter.destroy(
-                    &self.device,
-                    &self.surface_loader,
-                    &self.swapchain_loader,
-                    self.command_pool,
-                );
+                presenter.destroy(&self.device, &self.surface_loader, &self.swapchain_loader);
             }
+            self.stop_mirror(id);
         }
     }
 
     pub(crate) fn destroy_root(&mut self) {
         for (_, presenter) in self.presenters.drain() {
-            presenter.destroy(
-                &self.device,
-                &self.surface_loader,
-                &self.swapchain_loader,
-                self.command_pool,
-            );
+            presenter.destroy(&self.device, &self.surface_loader, &self.swapchain_loader);
+        }
+        for (_, mirror) in self.mirrors.drain() {
+            mirror
+                .presenter
+                .destroy(&self.device, &self.surface_loader, &self.swapchain_loader);
+        }
+        for fence in self.fence_pool.lock().unwrap().drain(..) {
+            unsafe { self.device.destroy_fence(fence, None) };
+        }
+        if let Some(breadcrumbs) = self.breadcrumbs.take() {
+            breadcrumbs.destroy();
+        }
+    }
+}
+
+// the parts of the presenters a submission uses, borrowed beside the recorded frames.
+struct Submission<'a> {
+    device: &'a Device,
+    swapchain_loader: &'a ash::khr::swapchain::Device,
+    queue: vk::Queue,
+    fence_pool: &'a FencePool,
+    synchronization2: Option<&'a Synchronization2>,
+    breadcrumbs: Option<&'a Breadcrumbs>,
+    submit_hooks: &'a SubmitHooks,
+}
+impl Submission<'_> {
+    fn submit_and_present(
+        &self,
+        viewport_ids: &[egui::ViewportId],
+        mut recorded: Vec<RecordedFrame>,
+    ) -> Result<()> {
+        let Self {
+            device,
+            swapchain_loader,
+            queue,
+            fence_pool,
+            synchronization2,
+            breadcrumbs,
+            submit_hooks,
+        } = *self;
+        profile_function!();
+        // submit command buffers
+        let fence = Arc::new(SubmitFence::new(device, fence_pool)?);
+        let frames = viewport_ids
+            .iter()
+            .zip(&recorded)
+            .map(|(&viewport_id, (presenter, _, _))| SubmittedFrame {
+                viewport_id,
+                command_buffer: presenter.render_command_buffers[presenter.current_frame],
+                wait_semaphore: presenter.image_available_semaphores[presenter.current_frame],
+                signal_semaphore: presenter.render_finished_semaphores[presenter.current_frame],
+            })
+            .collect::<Vec<_>>();
+        // a frame copied to a mirror also waits for the mirror image and signals its present.
+        let semaphores = frames
+            .iter()
+            .zip(&recorded)
+            .map(|(frame, (_, _, mirror))| {
+                let mut waits = vec![frame.wait_semaphore];
+                let mut signals = vec![frame.signal_semaphore];
+                if let Some((mirror, _)) = mirror {
+                    let current_frame = mirror.presenter.current_frame;
+                    waits.push(mirror.presenter.image_available_semaphores[current_frame]);
+                    signals.push(mirror.presenter.render_finished_semaphores[current_frame]);
+                }
+                (waits, signals)
+            })
+            .collect::<Vec<_>>();
+        // the egui render pass waits for the frame image, the copy for the mirror image.
+        let wait_stages = [
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+        ];
+        let wait_stages2 = [
+            vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags2::TRANSFER,
+        ];
+        let submit_info = SubmitInfo {
+            queue,
+            frames: &frames,
+            fence: fence.fence,
+        };
+        submit_hooks.pre_submit(&submit_info);
+        match synchronization2 {
+            Some(synchronization2) => {
+                let submits = frames
+                    .iter()
+                    .zip(&semaphores)
+                    .map(|(frame, (waits, signals))| {
+                        (
+                            waits
+                                .iter()
+                                .zip(wait_stages2)
+                                .map(|(&semaphore, stage)| {
+                                    vk::SemaphoreSubmitInfo::default()
+                                        .semaphore(semaphore)
+                                        .stage_mask(stage)
+                                })
+                                .collect::<Vec<_>>(),
+                            vk::CommandBufferSubmitInfo::default()
+                                .command_buffer(frame.command_buffer),
+                            signals
+                                .iter()
+                                .map(|&semaphore| {
+                                    vk::SemaphoreSubmitInfo::default()
+                                        .semaphore(semaphore)
+                                        .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+                                })
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let submit_infos = submits
+                    .iter()
+                    .map(|(waits, command_buffer, signals)| {
+                        vk::SubmitInfo2::default()
+                            .wait_semaphore_infos(waits)
+                            .command_buffer_infos(std::slice::from_ref(command_buffer))
+                            .signal_semaphore_infos(signals)
+                    })
+                    .collect::<Vec<_>>();
+                synchronization2.queue_submit2(queue, &submit_infos, fence.fence)?;
+            }
+            None => {
+                let submit_infos = frames
+                    .iter()
+                    .zip(&semaphores)
+                    .map(|(frame, (waits, signals))| {
+                        vk::SubmitInfo::default()
+                            .command_buffers(std::slice::from_ref(&frame.command_buffer))
+                            .wait_semaphores(waits)
+                            .wait_dst_stage_mask(&wait_stages[..waits.len()])
+                            .signal_semaphores(signals)
+                    })
+                    .collect::<Vec<_>>();
+                unsafe { device.queue_submit(queue, &submit_infos, fence.fence)? };
+            }
         }
+        submit_hooks.post_submit(&submit_info);
+
+        // present swapchain images, the mirrors after the frames
+        let mirrors = recorded
+            .iter()
+            .filter_map(|(_, _, mirror)| mirror.as_ref())
+            .map(|(mirror, index)| (&mirror.presenter, *index));
+        let presents = recorded
+            .iter()
+            .map(|(presenter, index, _)| (&**presenter, *index))
+            .chain(mirrors)
+            .collect::<Vec<_>>();
+        let wait_semaphores = presents
+            .iter()
+            .map(|(presenter, _)| presenter.render_finished_semaphores[presenter.current_frame])
+            .collect::<Vec<_>>();
+        let swapchains = presents
+            .iter()
+            .map(|(presenter, _)| presenter.swapchain)
+            .collect::<Vec<_>>();
+        let image_indices = presents
+            .iter()
+            .map(|&(_, index)| index as u32)
+            .collect::<Vec<_>>();
+        let mut results = vec![vk::Result::SUCCESS; presents.len()];
+        let present_info = vk::PresentInfoKHR::default()
+            .wait_semaphores(&wait_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices)
+            .results(&mut results);
+        let result = unsafe { swapchain_loader.queue_present(queue, &present_info) };
+        match result {
+            Ok(_) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR) => (),
+            Err(error) => {
+                error!("Failed to present queue: {error}");
+                if let Some(breadcrumbs) =
+                    breadcrumbs.filter(|_| error == vk::Result::ERROR_DEVICE_LOST)
+                {
+                    breadcrumbs.dump(queue);
+                }
+                panic!("Failed to present queue. Cause: {error}");
+            }
+        }
+
+        // the results of the mirrors follow the ones of the frames.
+        let mut results = results.into_iter();
+        for ((presenter, _, _), result) in recorded.iter_mut().zip(results.by_ref()) {
+            presenter.frame_presented(result, &fence);
+        }
+        for ((_, _, mirror), result) in recorded
+            .iter_mut()
+            .filter(|(_, _, mirror)| mirror.is_some())
+            .zip(results)
+        {
+            if let Some((mirror, _)) = mirror {
+                mirror.presenter.presented = true;
+                mirror.presenter.frame_presented(result, &fence);
+            }
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,27 @@
+//! puffin scopes of the integration, compiled out without the `puffin` feature.
+
+/// profile the rest of the enclosing block as a puffin scope.
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($name);
+    };
+    ($name:expr, $data:expr) => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($name, $data);
+    };
+}
+
+/// profile the rest of the enclosing function as a puffin scope named after it.
+macro_rules! profile_function {
+    () => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!();
+    };
+}
+
+/// end the puffin frame after the viewports of an event loop iteration are painted.
+pub(crate) fn finish_frame() {
+    #[cfg(feature = "puffin")]
+    puffin::GlobalProfiler::lock().new_frame();
+}
@@ -0,0 +1,497 @@
+use ash::vk;
+use egui_winit::winit;
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
+    RawWindowHandle, WindowHandle,
+};
+use std::{
+    ffi::{CStr, CString},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    app::{App, AshRenderState, HandleRedraw},
+    clear_color,
+    focus::{self, FocusBoundary},
+    inspector::Inspector,
+    layer,
+    presenters::{PresenterOptions, Presenters},
+    renderer::{self, EguiFrame, ImageRegistry, ImageRegistryReceiver, Renderer, RendererOptions},
+    repaint::{self, RepaintCause},
+    stats::FrameStatistics,
+    submit::SubmitHooks,
+    timestep::FrameClock,
+    zoom, Allocator,
+};
+
+pub use crate::focus::TabDirection;
+
+/// get required instance extensions for a raw display handle.
+pub fn required_instance_extensions(display_handle: RawDisplayHandle) -> Vec<CString> {
+    ash_window::enumerate_required_extensions(display_handle)
+        .unwrap()
+        .iter()
+        .map(|&ext| unsafe { CStr::from_ptr(ext).to_owned() })
+        .collect()
+}
+
+/// get required device extensions for [`RawIntegration`].
+pub fn required_device_extensions() -> Vec<CString> {
+    vec![ash::khr::swapchain::NAME.to_owned()]
+}
+
+/// output of [`RawIntegration::run`].
+pub struct RawOutput {
+    /// platform output to apply in the host windowing layer. (cursor, clipboard, ime, urls, etc.)
+    pub platform_output: egui::PlatformOutput,
+
+    /// time until egui wants to be repainted.
+    pub repaint_delay: Duration,
+
+    /// viewport commands for the root window.
+    pub viewport_commands: Vec<egui::ViewportCommand>,
+
+    /// a frame did not finish on the GPU within the hang timeout, see [`crate::event::Event::GpuHang`].
+    pub gpu_hang: bool,
+
+    /// rect of the focused widget in physical pixels, to forward to the OS so that magnifiers and
+    /// on-screen keyboards follow the focus.
+    pub focused_rect: Option<egui::Rect>,
+
+    /// the focus left egui with Tab or Shift+Tab, move it to the next or previous native control.
+    ///
+    /// Only set with [`RawIntegration::set_tab_out`].
+    pub focus_exit: Option<TabDirection>,
+}
+
+pub(crate) struct RawWindow {
+    pub(crate) display_handle: RawDisplayHandle,
+    pub(crate) window_handle: RawWindowHandle,
+}
+impl HasDisplayHandle for RawWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        // SAFETY: validity is guaranteed by the caller of `RawIntegration::new`.
+        Ok(unsafe { DisplayHandle::borrow_raw(self.display_handle) })
+    }
+}
+impl HasWindowHandle for RawWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // SAFETY: validity is guaranteed by the caller of `RawIntegration::new`.
+        Ok(unsafe { WindowHandle::borrow_raw(self.window_handle) })
+    }
+}
+
+/// options of a [`RawIntegration`] created by egui-ash, from [`crate::RunOption`].
+pub(crate) struct RawOptions {
+    pub(crate) presenter: PresenterOptions,
+    pub(crate) renderer: RendererOptions,
+    pub(crate) frame_stats: FrameStatistics,
+    pub(crate) submit_hooks: SubmitHooks,
+}
+impl RawOptions {
+    /// presenting with `present_mode`, with the default options otherwise.
+    pub(crate) fn new(present_mode: vk::PresentModeKHR) -> Self {
+        Self {
+            presenter: PresenterOptions::new(present_mode),
+            renderer: RendererOptions::default(),
+            frame_stats: FrameStatistics::default(),
+            submit_hooks: SubmitHooks::default(),
+        }
+    }
+}
+
+/// egui-ash integration for windows created outside of egui-ash.
+///
+/// The host supplies the raw window handles, the window size and the egui input,
+/// and calls [`RawIntegration::run`] once per frame.
+/// Child viewports are embedded in the window.
+pub struct RawIntegration<A: Allocator + 'static> {
+    context: egui::Context,
+    window: RawWindow,
+    size: winit::dpi::PhysicalSize<u32>,
+    native_pixels_per_point: f32,
+    transparent: bool,
+    egui_input: egui::RawInput,
+    beginning: Instant,
+    frame_clock: FrameClock,
+    image_registry: ImageRegistry,
+    presenters: Presenters,
+    renderer: Arc<Mutex<Renderer<A>>>,
+    frame_stats: FrameStatistics,
+    submit_hooks: SubmitHooks,
+    inspector: Inspector,
+    focus_boundary: FocusBoundary,
+    // causes of the next frame, besides input and repaint requests.
+    repaint_causes: Vec<RepaintCause>,
+}
+impl<A: Allocator + 'static> RawIntegration<A> {
+    /// create integration for a raw window.
+    ///
+    /// `size` is the size of the window surface in physical pixels.
+    ///
+    /// # Safety
+    ///
+    /// `display_handle` and `window_handle` must stay valid until [`RawIntegration::destroy`] is called.
+    pub unsafe fn new(
+        context: egui::Context,
+        display_handle: RawDisplayHandle,
+        window_handle: RawWindowHandle,
+        size: winit::dpi::PhysicalSize<u32>,
+        native_pixels_per_point: f32,
+        render_state: AshRenderState<A>,
+        present_mode: vk::PresentModeKHR,
+    ) -> Self {
+        Self::from_parts(
+            context,
+            RawWindow {
+                display_handle,
+                window_handle,
+            },
+            size,
+            native_pixels_per_point,
+            render_state,
+            ImageRegistry::new(),
+            RawOptions::new(present_mode),
+        )
+    }
+
+    pub(crate) unsafe fn from_parts(
+        context: egui::Context,
+        window: RawWindow,
+        size: winit::dpi::PhysicalSize<u32>,
+        native_pixels_per_point: f32,
+        render_state: AshRenderState<A>,
+        (image_registry, receiver): (ImageRegistry, ImageRegistryReceiver),
+        options: RawOptions,
+    ) -> Self {
+        let RawOptions {
+            presenter,
+            renderer,
+            frame_stats,
+            submit_hooks,
+        } = options;
+        // raw window has only one native window.
+        context.set_embed_viewports(true);
+        let inspector = Inspector::install(&context);
+
+        let limits = unsafe {
+            render_state
+                .instance
+                .get_physical_device_properties(render_state.physical_device)
+                .limits
+        };
+        let max_texture_side = renderer::max_texture_side(&limits);
+        let presenters = Presenters::new(
+            &render_state,
+            presenter,
+            frame_stats.clone(),
+            inspector.clone(),
+            submit_hooks.clone(),
+        );
+        // the raw integration keeps its resources, the host owns the suspend.
+        let renderer = Renderer::new(
+            &render_state,
+            &limits,
+            receiver,
+            RendererOptions {
+                keep_image_copies: false,
+                ..renderer
+            },
+            frame_stats.clone(),
+            inspector.clone(),
+        );
+
+        Self {
+            context,
+            window,
+            size,
+            native_pixels_per_point,
+            transparent: false,
+            egui_input: egui::RawInput {
+                focused: true,
+                max_texture_side: Some(max_texture_side),
+                ..Default::default()
+            },
+            beginning: Instant::now(),
+            frame_clock: FrameClock::default(),
+            image_registry,
+            presenters,
+            renderer,
+            frame_stats,
+            submit_hooks,
+            inspector,
+            focus_boundary: FocusBoundary::default(),
+            repaint_causes: vec![RepaintCause::Window],
+        }
+    }
+
+    /// egui context
+    pub fn context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    /// user texture image registry for egui-ash
+    pub fn image_registry(&self) -> ImageRegistry {
+        self.image_registry.clone()
+    }
+
+    /// metrics of the last frames. The event handling time is measured by the host.
+    pub fn frame_stats(&self) -> FrameStatistics {
+        self.frame_stats.clone()
+    }
+
+    /// callbacks around the queue submissions of the frames.
+    pub fn submit_hooks(&self) -> SubmitHooks {
+        self.submit_hooks.clone()
+    }
+
+    /// current window size in physical pixels.
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.size
+    }
+
+    /// notify the window size or the native scale factor has changed.
+    pub fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>, native_pixels_per_point: f32) {
+        if self.size != size {
+            self.presenters.dirty_swapchain(egui::ViewportId::ROOT);
+        }
+        if self.size != size || self.native_pixels_per_point != native_pixels_per_point {
+            repaint::push_cause(&mut self.repaint_causes, RepaintCause::Window);
+        }
+        self.size = size;
+        self.native_pixels_per_point = native_pixels_per_point;
+    }
+
+    /// render into another window from now on, e.g. after the host recreated the window of a plugin
+    /// editor under a new parent. The swapchain of the old window is destroyed.
+    ///
+    /// # Safety
+    ///
+    /// The handles must stay valid until [`RawIntegration::destroy`] or the next `set_window`.
+    pub unsafe fn set_window(
+        &mut self,
+        display_handle: RawDisplayHandle,
+        window_handle: RawWindowHandle,
+        size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        self.presenters
+            .destroy_swapchain_if_needed(egui::ViewportId::ROOT);
+        self.window = RawWindow {
+            display_handle,
+            window_handle,
+        };
+        self.size = size;
+        repaint::push_cause(&mut self.repaint_causes, RepaintCause::Window);
+    }
+
+    /// blend the window with what is behind it where egui does not paint, for windows with a transparent visual.
+    ///
+    /// Takes effect when the swapchain is created, call it before the first [`RawIntegration::run`].
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+        clear_color::set_transparent(&self.context, egui::ViewportId::ROOT, transparent);
+    }
+
+    /// push an input event for the next frame.
+    pub fn push_event(&mut self, event: egui::Event) {
+        self.egui_input.events.push(event);
+    }
+
+    /// notify a file is hovered over the window.
+    pub fn hover_file(&mut self, path: impl Into<PathBuf>) {
+        self.egui_input.hovered_files.push(egui::HoveredFile {
+            path: Some(path.into()),
+            ..Default::default()
+        });
+    }
+
+    /// notify hovered files have left the window without being dropped.
+    pub fn cancel_hovered_files(&mut self) {
+        self.egui_input.hovered_files.clear();
+    }
+
+    /// notify a file is dropped on the window.
+    pub fn drop_file(&mut self, path: impl Into<PathBuf>) {
+        self.egui_input.hovered_files.clear();
+        self.egui_input.dropped_files.push(egui::DroppedFile {
+            path: Some(path.into()),
+            ..Default::default()
+        });
+    }
+
+    /// move the focus out of egui with Tab on its last widget and Shift+Tab on its first one, instead of
+    /// wrapping around, for a window with native controls next to egui. See [`RawOutput::focus_exit`].
+    pub fn set_tab_out(&mut self, tab_out: bool) {
+        self.focus_boundary.enabled = tab_out;
+    }
+
+    /// the focus moved into egui from a native control, focus the first widget (`Forward`) or the last
+    /// widget (`Backward`) in the next frame.
+    pub fn focus_enter(&mut self, direction: TabDirection) {
+        self.focus_boundary.enter(direction);
+        self.egui_input.focused = true;
+        repaint::push_cause(&mut self.repaint_causes, RepaintCause::Window);
+    }
+
+    /// input for the next frame. (modifiers, focus, etc.)
+    pub fn egui_input_mut(&mut self) -> &mut egui::RawInput {
+        &mut self.egui_input
+    }
+
+    /// run egui for the app and paint the result.
+    pub fn run(&mut self, app: &mut impl App) -> RawOutput {
+        profile_function!();
+        let pixels_per_point = self.native_pixels_per_point * self.context.zoom_factor();
+        let screen_size_in_points =
+            egui::vec2(self.size.width as f32, self.size.height as f32) / pixels_per_point;
+        self.egui_input.screen_rect = (screen_size_in_points.x > 0.0
+            && screen_size_in_points.y > 0.0)
+            .then(|| egui::Rect::from_min_size(egui::Pos2::ZERO, screen_size_in_points));
+        self.egui_input.time = Some(self.beginning.elapsed().as_secs_f64());
+        self.egui_input.viewport_id = egui::ViewportId::ROOT;
+        self.egui_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(self.native_pixels_per_point);
+        let mut raw_input = self.egui_input.take();
+        app.on_raw_input(egui::ViewportId::ROOT, &mut raw_input);
+        let mut causes = std::mem::take(&mut self.repaint_causes);
+        if !raw_input.events.is_empty() {
+            causes.insert(
+                0,
+                RepaintCause::Input {
+                    events: raw_input.events.len(),
+                },
+            );
+        }
+
+        let mut focus_exit = None;
+        let egui::FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            mut viewport_output,
+        } = {
+            let frame_info =
+                self.frame_clock
+                    .next_frame(&self.context, egui::ViewportId::ROOT, self.size, None);
+            profile_scope!("run_ui");
+            let start = Instant::now();
+            let inspector = &self.inspector;
+            let focus_boundary = &mut self.focus_boundary;
+            #[cfg(feature = "persistence")]
+            crate::memory::apply_pending(&self.context);
+            let full_output = self.context.run(raw_input, |ctx| {
+                let mut causes = causes.clone();
+                for cause in RepaintCause::requested(ctx) {
+                    repaint::push_cause(&mut causes, cause);
+                }
+                if inspector.is_shown() {
+                    inspector.set_repaint_causes(egui::ViewportId::ROOT, &causes);
+                }
+                repaint::set_repaint_causes(ctx, causes);
+                focus_boundary.begin_pass(ctx);
+                app.ui(ctx, &frame_info);
+                focus_exit = focus_boundary.end_pass(ctx);
+                zoom::handle_zoom(ctx);
+                layer::paint_layer_split(ctx);
+            });
+            let egui_run_time = start.elapsed();
+            self.frame_stats.update(egui::ViewportId::ROOT, |stats| {
+                stats.egui_run_time = egui_run_time;
+            });
+            app.on_full_output(egui::ViewportId::ROOT, &full_output);
+            full_output
+        };
+        #[cfg(feature = "accesskit")]
+        if let Some(update) = &platform_output.accesskit_update {
+            crate::access_tree::store(&self.context, egui::ViewportId::ROOT, update);
+        }
+
+        let (repaint_delay, viewport_commands) = viewport_output
+            .remove(&egui::ViewportId::ROOT)
+            .map(|output| (output.repaint_delay, output.commands))
+            .unwrap_or((Duration::MAX, vec![]));
+        if !viewport_commands.is_empty() {
+            repaint::push_cause(&mut self.repaint_causes, RepaintCause::ViewportCommand);
+        }
+
+        let frame = EguiFrame {
+            shapes,
+            pixels_per_point,
+            textures_delta,
+        };
+        // if window is minimized, skip painting but keep the textures of egui up to date.
+        if self.size.width == 0 || self.size.height == 0 {
+            self.renderer
+                .lock()
+                .unwrap()
+                .create_egui_cmd(
+                    egui::ViewportId::ROOT,
+                    &self.context,
+                    frame,
+                    pixels_per_point,
+                    self.size,
+                )
+                .update_textures();
+        } else {
+            match app.request_redraw(egui::ViewportId::ROOT) {
+                HandleRedraw::Auto => {
+                    self.presenters.recreate_swapchain_if_needed(
+                        egui::ViewportId::ROOT,
+                        &self.window,
+                        self.size,
+                        self.transparent,
+                    );
+                    let egui_cmd = self.renderer.lock().unwrap().create_egui_cmd(
+                        egui::ViewportId::ROOT,
+                        &self.context,
+                        frame,
+                        pixels_per_point,
+                        self.size,
+                    );
+                    self.presenters
+                        .present_egui(vec![(egui::ViewportId::ROOT, egui_cmd)]);
+                }
+                HandleRedraw::Handle(handler) => {
+                    self.presenters
+                        .destroy_swapchain_if_needed(egui::ViewportId::ROOT);
+                    let egui_cmd = self.renderer.lock().unwrap().create_egui_cmd(
+                        egui::ViewportId::ROOT,
+                        &self.context,
+                        frame,
+                        pixels_per_point,
+                        self.size,
+                    );
+                    handler(self.size, egui_cmd);
+                }
+            }
+        }
+
+        crate::profiling::finish_frame();
+        let gpu_hangs = self.presenters.gpu_hangs();
+        let gpu_hang = !std::mem::take(&mut *gpu_hangs.lock().unwrap()).is_empty();
+        RawOutput {
+            platform_output,
+            repaint_delay,
+            viewport_commands,
+            gpu_hang,
+            focused_rect: focus::focused_rect(&self.context),
+            focus_exit,
+        }
+    }
+
+    /// destroy vulkan objects of the integration.
+    ///
+    /// Call this before destroying the device and the window.
+    pub fn destroy(self) {
+        let mut presenters = self.presenters;
+        let mut renderer = self.renderer.lock().unwrap();
+        presenters.destroy_root();
+        renderer.destroy_root();
+    }
+}
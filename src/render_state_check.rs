@@ -0,0 +1,127 @@
+//! check of the render state returned by [`crate::AppCreator::create`], see
+//! [`crate::RunOption::check_render_state`].
+//!
+//! A missing extension or a queue that can not present otherwise fails later in the swapchain
+//! code, with a Vulkan error that does not tell what the creator got wrong.
+
+use ash::vk;
+use egui_winit::winit::window::Window;
+use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
+use std::ffi::{CStr, CString};
+
+use crate::{allocator::Allocator, app::AshRenderState};
+
+fn names(properties: &[vk::ExtensionProperties]) -> Vec<&CStr> {
+    properties
+        .iter()
+        .filter_map(|properties| properties.extension_name_as_c_str().ok())
+        .collect()
+}
+
+/// check that the extensions are supported and enabled, and that the queue presents to `window`.
+pub(crate) fn check_render_state<A: Allocator + 'static>(
+    render_state: &AshRenderState<A>,
+    window: &Window,
+    instance_extensions: &[CString],
+    device_extensions: &[CString],
+) -> anyhow::Result<()> {
+    let mut problems = vec![];
+
+    let properties = unsafe {
+        render_state
+            .entry
+            .enumerate_instance_extension_properties(None)?
+    };
+    let supported = names(&properties);
+    for extension in instance_extensions {
+        if !supported.contains(&extension.as_c_str()) {
+            problems.push(format!(
+                "instance extension {extension:?} is not supported by the Vulkan implementation"
+            ));
+        }
+    }
+
+    let properties = unsafe {
+        render_state
+            .instance
+            .enumerate_device_extension_properties(render_state.physical_device)?
+    };
+    let supported = names(&properties);
+    for extension in device_extensions {
+        if !supported.contains(&extension.as_c_str()) {
+            problems.push(format!(
+                "device extension {extension:?} is not supported by the physical device"
+            ));
+        }
+    }
+    // the device returns no commands of extensions that were not enabled.
+    let create_swapchain = unsafe {
+        (render_state.instance.fp_v1_0().get_device_proc_addr)(
+            render_state.device.handle(),
+            c"vkCreateSwapchainKHR".as_ptr(),
+        )
+    };
+    if create_swapchain.is_none() {
+        problems.push(format!(
+            "device extension {:?} is not enabled on the device",
+            ash::khr::swapchain::NAME
+        ));
+    }
+
+    let queue_family_count = unsafe {
+        render_state
+            .instance
+            .get_physical_device_queue_family_properties(render_state.physical_device)
+            .len()
+    };
+    if render_state.queue_family_index as usize >= queue_family_count {
+        problems.push(format!(
+            "queue family {} does not exist, the physical device has {queue_family_count}",
+            render_state.queue_family_index
+        ));
+    } else {
+        // a surface of its own, the one of the root window is created with the swapchain.
+        let surface = unsafe {
+            ash_window::create_surface(
+                &render_state.entry,
+                &render_state.instance,
+                window.display_handle()?.as_raw(),
+                window.window_handle()?.as_raw(),
+                None,
+            )
+        };
+        match surface {
+            Ok(surface) => {
+                let supported = unsafe {
+                    render_state
+                        .surface_loader
+                        .get_physical_device_surface_support(
+                            render_state.physical_device,
+                            render_state.queue_family_index,
+                            surface,
+                        )
+                };
+                unsafe { render_state.surface_loader.destroy_surface(surface, None) };
+                if !supported? {
+                    problems.push(format!(
+                        "queue family {} can not present to the window",
+                        render_state.queue_family_index
+                    ));
+                }
+            }
+            Err(err) => problems.push(format!(
+                "no surface can be created for the window ({err}), \
+                 are the required instance extensions enabled on the instance?"
+            )),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "the render state does not meet the requirements of egui-ash:\n- {}",
+            problems.join("\n- ")
+        ))
+    }
+}
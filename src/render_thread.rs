@@ -0,0 +1,125 @@
+use egui_winit::winit;
+use std::{
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread::JoinHandle,
+};
+
+use crate::{presenters::Presenters, renderer::EguiCommand, RedrawHandler};
+
+// frames queued before the event loop waits for the render thread.
+const MAX_QUEUED_FRAMES: usize = 2;
+
+enum RenderJob {
+    Present(Vec<(egui::ViewportId, EguiCommand)>),
+    Handle {
+        handler: RedrawHandler,
+        size: winit::dpi::PhysicalSize<u32>,
+        egui_cmd: EguiCommand,
+    },
+    Exit,
+}
+
+/// records and presents the frames of the viewports on a dedicated thread.
+///
+/// Jobs run in the order they are queued, so the texture updates of the frames stay in order.
+/// Wait for [`Self::wait_idle`] before destroying resources the queued frames use.
+#[derive(Clone)]
+pub(crate) struct RenderThread {
+    sender: mpsc::SyncSender<RenderJob>,
+    queued: Arc<(Mutex<usize>, Condvar)>,
+    handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+impl RenderThread {
+    pub(crate) fn spawn(presenters: Arc<Mutex<Presenters>>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<RenderJob>(MAX_QUEUED_FRAMES);
+        let queued = Arc::new((Mutex::new(0), Condvar::new()));
+        let handle = std::thread::Builder::new()
+            .name("egui-ash render".to_owned())
+            .spawn({
+                let queued = queued.clone();
+                move || {
+                    while let Ok(job) = receiver.recv() {
+                        let _done = JobDone(&queued);
+                        match job {
+                            RenderJob::Present(frames) => {
+                                let mut presenters = presenters.lock().unwrap();
+                                presenters.present_egui(frames);
+                            }
+                            RenderJob::Handle {
+                                handler,
+                                size,
+                                egui_cmd,
+                            } => handler(size, egui_cmd),
+                            RenderJob::Exit => break,
+                        }
+                    }
+                }
+            })
+            .expect("Failed to spawn render thread");
+        Self {
+            sender,
+            queued,
+            handle: Arc::new(Mutex::new(Some(handle))),
+        }
+    }
+
+    /// queue the frames of the viewports drawn by egui-ash.
+    pub(crate) fn present(&self, frames: Vec<(egui::ViewportId, EguiCommand)>) {
+        self.send(RenderJob::Present(frames));
+    }
+
+    /// queue the frame of a viewport drawn by [`crate::HandleRedraw::Handle`].
+    pub(crate) fn handle(
+        &self,
+        handler: RedrawHandler,
+        size: winit::dpi::PhysicalSize<u32>,
+        egui_cmd: EguiCommand,
+    ) {
+        self.send(RenderJob::Handle {
+            handler,
+            size,
+            egui_cmd,
+        });
+    }
+
+    /// block until the queued frames are recorded and submitted.
+    pub(crate) fn wait_idle(&self) {
+        let (queued, idle) = &*self.queued;
+        let queued = queued.lock().unwrap();
+        drop(idle.wait_while(queued, |queued| *queued > 0).unwrap());
+    }
+
+    /// finish the queued frames and stop the thread.
+    pub(crate) fn shutdown(&self) {
+        let Some(handle) = self.handle.lock().unwrap().take() else {
+            return;
+        };
+        self.send(RenderJob::Exit);
+        if handle.join().is_err() {
+            error!("Render thread panicked");
+        }
+    }
+
+    fn send(&self, job: RenderJob) {
+        *self.queued.0.lock().unwrap() += 1;
+        if self.sender.send(job).is_err() {
+            error!("Failed to send frame to render thread");
+            JobDone(&self.queued);
+        }
+    }
+}
+
+// counts a job as done when dropped, also when the job panics.
+struct JobDone<'a>(&'a (Mutex<usize>, Condvar));
+impl Drop for JobDone<'_> {
+    fn drop(&mut self) {
+        let (queued, idle) = self.0;
+        let mut queued = queued
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *queued -= 1;
+        if *queued == 0 {
+            idle.notify_all();
+        }
+    }
+}
@@ -3,18 +3,31 @@ use bytemuck::bytes_of;
 use egui_winit::winit;
 use std::fmt::Debug;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     ffi::CString,
     fmt::Formatter,
+    ops::Range,
     sync::{
         atomic::{AtomicU64, Ordering},
         mpsc::{self, Receiver, Sender},
-        Arc, Mutex,
+        Arc, Mutex, OnceLock,
     },
+    time::{Duration, Instant},
 };
 
-use crate::allocator::{Allocation, AllocationCreateInfo, Allocator, MemoryLocation};
-use crate::utils;
+use crate::allocator::{
+    Allocation, AllocationCreateInfo, Allocator, AllocatorDebugOptions, MemoryLocation,
+};
+use crate::app::AshRenderState;
+use crate::clear_color;
+use crate::device;
+use crate::inspector::{Inspector, RegisteredTexture, TextureInfo};
+use crate::layer::{self, EguiLayer};
+use crate::pixel_snapping;
+use crate::post_process::{self, PostProcess};
+use crate::resource_usage::ResourceUsage;
+use crate::stats::FrameStatistics;
+use crate::utils::{self, QueueFamilies};
 
 struct ViewportRendererState<A: Allocator + 'static> {
     width: u32,
@@ -31,35 +44,358 @@ struct ViewportRendererState<A: Allocator + 'static> {
     scale_factor: f32,
     physical_width: u32,
     physical_height: u32,
+    // tessellation generation in the vertex and index buffers of each swapchain image.
+    uploaded_generations: Vec<Option<u64>>,
+    scaled_target: Option<ScaledTarget<A>>,
+    // custom shader and pipeline of the post pass, drawing the scaled render target.
+    post_pipeline: Option<(Option<Arc<[u32]>>, vk::Pipeline)>,
 }
 
 #[derive(Clone)]
 struct ViewportRenderer<A: Allocator + 'static> {
+    viewport_id: egui::ViewportId,
     device: Device,
     descriptor_set_layout: vk::DescriptorSetLayout,
+    pipelines: Pipelines,
     allocator: A,
+    queue_families: QueueFamilies,
+    render_scale: f32,
+    max_image_dimension: u32,
+    managed_textures: Arc<Mutex<ManagedTextures<A>>>,
+    user_textures: Arc<Mutex<UserTextures>>,
+    frame_stats: FrameStatistics,
     state: Arc<Mutex<Option<ViewportRendererState<A>>>>,
+    // post pass of the last frame, a change of its settings redraws unchanged frames.
+    last_post_process: Arc<Mutex<Option<PostProcess>>>,
+}
+// tessellated primitives of a frame, shared by the frames which draw the same shapes.
+struct Tessellation {
+    clipped_primitives: Arc<Vec<egui::ClippedPrimitive>>,
+    generation: u64,
+    // the primitives are the ones of the previous frame.
+    reused: bool,
 }
-struct VertexIndexBuffers<A: Allocator + 'static>(Vec<vk::Buffer>, Vec<A::Allocation>, Vec<vk::Buffer>, Vec<A::Allocation>);
 
-impl<A: Allocator + 'static> ViewportRenderer<A> {
-    fn new(device: Device, descriptor_set_layout: vk::DescriptorSetLayout, allocator: A) -> Self {
+// last tessellated frame of a viewport.
+struct CachedTessellation {
+    shapes: Vec<egui::epaint::ClippedShape>,
+    pixels_per_point: f32,
+    pixel_snapping: bool,
+    clipped_primitives: Arc<Vec<egui::ClippedPrimitive>>,
+    generation: u64,
+}
+
+// intermediate render target of a viewport with a render scale other than 1 or a post pass, one image
+// per swapchain image.
+//
+// egui renders to it and the frame is drawn scaled to the swapchain image with linear filtering,
+// through the post pass if there is one.
+struct ScaledTarget<A: Allocator + 'static> {
+    scale: f32,
+    width: u32,
+    height: u32,
+    render_pass: vk::RenderPass,
+    images: Vec<vk::Image>,
+    allocations: Vec<A::Allocation>,
+    image_views: Vec<vk::ImageView>,
+    framebuffers: Vec<vk::Framebuffer>,
+    sampler: vk::Sampler,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+impl<A: Allocator + 'static> ScaledTarget<A> {
+    fn create_render_pass(device: &Device, surface_format: vk::Format) -> vk::RenderPass {
+        unsafe {
+            device.create_render_pass(
+                &vk::RenderPassCreateInfo::default()
+                    .attachments(std::slice::from_ref(
+                        &vk::AttachmentDescription::default()
+                            .format(surface_format)
+                            .samples(vk::SampleCountFlags::TYPE_1)
+                            .load_op(vk::AttachmentLoadOp::CLEAR)
+                            .store_op(vk::AttachmentStoreOp::STORE)
+                            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                            .initial_layout(vk::ImageLayout::UNDEFINED)
+                            .final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+                    ))
+                    .subpasses(std::slice::from_ref(
+                        &vk::SubpassDescription::default()
+                            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                            .color_attachments(std::slice::from_ref(
+                                &vk::AttachmentReference::default()
+                                    .attachment(0)
+                                    .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
+                            )),
+                    ))
+                    .dependencies(&[
+                        vk::SubpassDependency::default()
+                            .src_subpass(vk::SUBPASS_EXTERNAL)
+                            .dst_subpass(0)
+                            .src_access_mask(vk::AccessFlags::SHADER_READ)
+                            .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                            .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+                            .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT),
+                        vk::SubpassDependency::default()
+                            .src_subpass(0)
+                            .dst_subpass(vk::SUBPASS_EXTERNAL)
+                            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                            .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER),
+                    ]),
+                None,
+            )
+        }
+        .expect("Failed to create render pass.")
+    }
+
+    // at the render scale of `viewport_renderer`, with its device and queue families.
+    fn new(
+        viewport_renderer: &ViewportRenderer<A>,
+        allocator: &A,
+        width: u32,
+        height: u32,
+        surface_format: vk::Format,
+        image_count: usize,
+    ) -> Self {
+        let device = &viewport_renderer.device;
+        let queue_families = &viewport_renderer.queue_families;
+        let descriptor_set_layout = viewport_renderer.descriptor_set_layout;
+        // the target is at most `maxImageDimension2D` texels wide and high
+        let max_scale =
+            viewport_renderer.max_image_dimension as f32 / width.max(height).max(1) as f32;
+        let scale = viewport_renderer.render_scale.min(max_scale);
+        let width = ((width as f32 * scale).round() as u32).max(1);
+        let height = ((height as f32 * scale).round() as u32).max(1);
+        let render_pass = Self::create_render_pass(device, surface_format);
+
+        let mut images = vec![];
+        let mut allocations = vec![];
+        for _ in 0..image_count {
+            let image = unsafe {
+                device.create_image(
+                    &vk::ImageCreateInfo::default()
+                        .array_layers(1)
+                        .extent(vk::Extent3D {
+                            width,
+                            height,
+                            depth: 1,
+                        })
+                        .format(surface_format)
+                        .image_type(vk::ImageType::TYPE_2D)
+                        .initial_layout(vk::ImageLayout::UNDEFINED)
+                        .mip_levels(1)
+                        .samples(vk::SampleCountFlags::TYPE_1)
+                        .sharing_mode(queue_families.sharing_mode())
+                        .queue_family_indices(queue_families.indices())
+                        .tiling(vk::ImageTiling::OPTIMAL)
+                        .usage(
+                            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+                        ),
+                    None,
+                )
+            }
+            .expect("Failed to create scaled render target.");
+            let requirements = unsafe { device.get_image_memory_requirements(image) };
+            let allocation = allocator
+                .allocate(A::AllocationCreateInfo::new(
+                    Some("egui-ash scaled render target"),
+                    requirements,
+                    MemoryLocation::gpu_only(),
+                    false,
+                ))
+                .expect("Failed to allocate scaled render target.");
+            unsafe {
+                device
+                    .bind_image_memory(image, allocation.memory(), allocation.offset())
+                    .expect("Failed to bind scaled render target memory.");
+            }
+            images.push(image);
+            allocations.push(allocation);
+        }
+
+        let (framebuffers, image_views) = ViewportRenderer::<A>::create_framebuffers(
+            device,
+            &images,
+            render_pass,
+            surface_format,
+            width,
+            height,
+        );
+
+        let sampler = unsafe {
+            device.create_sampler(
+                &vk::SamplerCreateInfo::default()
+                    .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .anisotropy_enable(false)
+                    .min_filter(vk::Filter::LINEAR)
+                    .mag_filter(vk::Filter::LINEAR)
+                    .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+                    .min_lod(0.0)
+                    .max_lod(0.0),
+                None,
+            )
+        }
+        .expect("Failed to create sampler.");
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .max_sets(image_count as u32)
+                    .pool_sizes(std::slice::from_ref(
+                        &vk::DescriptorPoolSize::default()
+                            .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                            .descriptor_count(image_count as u32),
+                    )),
+                None,
+            )
+        }
+        .expect("Failed to create descriptor pool.");
+        let descriptor_sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&vec![descriptor_set_layout; image_count]),
+            )
+        }
+        .expect("Failed to allocate descriptor set");
+        for (&descriptor_set, &image_view) in descriptor_sets.iter().zip(&image_views) {
+            unsafe {
+                device.update_descriptor_sets(
+                    std::slice::from_ref(
+                        &vk::WriteDescriptorSet::default()
+                            .dst_set(descriptor_set)
+                            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                            .dst_array_element(0_u32)
+                            .dst_binding(0_u32)
+                            .image_info(std::slice::from_ref(
+                                &vk::DescriptorImageInfo::default()
+                                    .image_view(image_view)
+                                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                                    .sampler(sampler),
+                            )),
+                    ),
+                    &[],
+                );
+            }
+        }
+
+        Self {
+            scale,
+            width,
+            height,
+            render_pass,
+            images,
+            allocations,
+            image_views,
+            framebuffers,
+            sampler,
+            descriptor_pool,
+            descriptor_sets,
+        }
+    }
+
+    fn destroy(self, device: &Device, allocator: &A) {
+        unsafe {
+            for framebuffer in self.framebuffers {
+                device.destroy_framebuffer(framebuffer, None);
+            }
+            for image_view in self.image_views {
+                device.destroy_image_view(image_view, None);
+            }
+            for image in self.images {
+                device.destroy_image(image, None);
+            }
+            for allocation in self.allocations {
+                allocator
+                    .free(allocation)
+                    .expect("Failed to free scaled render target allocation.");
+            }
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+            device.destroy_sampler(self.sampler, None);
+            device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+// where a mesh of the frame is in the vertex and index buffers.
+struct MeshLayout {
+    first_index: u32,
+    index_count: u32,
+    // index counts of the tile groups of a tiled texture, in the order of its tiles.
+    tile_index_counts: Option<Vec<u32>>,
+}
+
+// indexed draw of consecutive meshes with the same texture and clip rect.
+struct DrawCall {
+    descriptor_set: vk::DescriptorSet,
+    scissor: vk::Rect2D,
+    first_index: u32,
+    index_count: u32,
+}
+
+struct VertexIndexBuffers<A: Allocator + 'static>(
+    Vec<vk::Buffer>,
+    Vec<A::Allocation>,
+    Vec<vk::Buffer>,
+    Vec<A::Allocation>,
+);
+
+// surface format and sample count a render pass and pipeline are built for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    format: vk::Format,
+    samples: vk::SampleCountFlags,
+}
+
+type PipelineEntry = Arc<OnceLock<(vk::RenderPass, vk::Pipeline)>>;
+
+// key of a post pass pipeline, with its custom shader.
+type PostPipelineKey = (PipelineKey, Option<Arc<[u32]>>);
+
+// render passes and pipelines shared by all viewports, built once per key.
+//
+// Builds started with `prepare` run on a background thread, so a new window does not wait for them.
+#[derive(Clone)]
+struct Pipelines {
+    device: Device,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline_cache: vk::PipelineCache,
+    entries: Arc<Mutex<HashMap<PipelineKey, PipelineEntry>>>,
+    post_entries: Arc<Mutex<HashMap<PostPipelineKey, vk::Pipeline>>>,
+    // `framebufferColorSampleCounts` of the device, the sample counts of the keys are clamped to.
+    sample_counts: vk::SampleCountFlags,
+}
+impl Pipelines {
+    fn new(
+        device: Device,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        sample_counts: vk::SampleCountFlags,
+    ) -> Self {
+        let pipeline_layout = Self::create_pipeline_layout(&device, descriptor_set_layout);
+        let pipeline_cache =
+            unsafe { device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None) }
+                .expect("Failed to create pipeline cache.");
         Self {
             device,
-            descriptor_set_layout,
-            allocator,
-            state: Arc::new(Mutex::new(None)),
+            pipeline_layout,
+            pipeline_cache,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            post_entries: Arc::new(Mutex::new(HashMap::new())),
+            sample_counts,
         }
     }
 
-    fn create_render_pass(device: &Device, surface_format: vk::Format) -> vk::RenderPass {
+    fn create_render_pass(device: &Device, key: PipelineKey) -> vk::RenderPass {
         unsafe {
             device.create_render_pass(
                 &vk::RenderPassCreateInfo::default()
                     .attachments(std::slice::from_ref(
                         &vk::AttachmentDescription::default()
-                            .format(surface_format)
-                            .samples(vk::SampleCountFlags::TYPE_1)
+                            .format(key.format)
+                            .samples(key.samples)
                             .load_op(vk::AttachmentLoadOp::LOAD)
                             .store_op(vk::AttachmentStoreOp::STORE)
                             .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
@@ -97,12 +433,17 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
             device.create_pipeline_layout(
                 &vk::PipelineLayoutCreateInfo::default()
                     .set_layouts(&[descriptor_set_layout])
-                    .push_constant_ranges(std::slice::from_ref(
-                        &vk::PushConstantRange::default()
+                    .push_constant_ranges(&[
+                        vk::PushConstantRange::default()
                             .stage_flags(vk::ShaderStageFlags::VERTEX)
                             .offset(0)
                             .size(std::mem::size_of::<f32>() as u32 * 2),
-                    )),
+                        // the color transform of the post pass
+                        vk::PushConstantRange::default()
+                            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+                            .offset(16)
+                            .size(std::mem::size_of::<f32>() as u32 * 24),
+                    ]),
                 None,
             )
         }
@@ -113,6 +454,9 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         device: &Device,
         render_pass: vk::RenderPass,
         pipeline_layout: vk::PipelineLayout,
+        pipeline_cache: vk::PipelineCache,
+        samples: vk::SampleCountFlags,
+        fragment_code: &[u8],
     ) -> vk::Pipeline {
         let attributes = [
             // position
@@ -146,10 +490,9 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                 .expect("Failed to create vertex shader module.")
         };
         let fragment_shader_module = {
-            let bytes_code = include_bytes!("shaders/spv/frag.spv");
             let shader_module_create_info = vk::ShaderModuleCreateInfo {
-                code_size: bytes_code.len(),
-                p_code: bytes_code.as_ptr().cast::<u32>(),
+                code_size: fragment_code.len(),
+                p_code: fragment_code.as_ptr().cast::<u32>(),
                 ..Default::default()
             };
             unsafe { device.create_shader_module(&shader_module_create_info, None) }
@@ -201,12 +544,12 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
         let dynamic_state_info =
             vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
-        let multisample_info = vk::PipelineMultisampleStateCreateInfo::default()
-            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+        let multisample_info =
+            vk::PipelineMultisampleStateCreateInfo::default().rasterization_samples(samples);
 
         let pipeline = unsafe {
             device.create_graphics_pipelines(
-                vk::PipelineCache::null(),
+                pipeline_cache,
                 std::slice::from_ref(
                     &vk::GraphicsPipelineCreateInfo::default()
                         .stages(&pipeline_shader_stages)
@@ -262,6 +605,89 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         pipeline
     }
 
+    fn entry(&self, key: PipelineKey) -> PipelineEntry {
+        self.entries.lock().unwrap().entry(key).or_default().clone()
+    }
+
+    fn build(&self, key: PipelineKey) -> (vk::RenderPass, vk::Pipeline) {
+        let key = PipelineKey {
+            samples: device::clamp_sample_count(key.samples, self.sample_counts),
+            ..key
+        };
+        let render_pass = Self::create_render_pass(&self.device, key);
+        let pipeline = Self::create_pipeline(
+            &self.device,
+            render_pass,
+            self.pipeline_layout,
+            self.pipeline_cache,
+            key.samples,
+            include_bytes!("shaders/spv/frag.spv"),
+        );
+        (render_pass, pipeline)
+    }
+
+    // start building the render pass and pipeline for `key` on a background thread.
+    fn prepare(&self, key: PipelineKey) {
+        let entry = self.entry(key);
+        if entry.get().is_some() {
+            return;
+        }
+        let pipelines = self.clone();
+        std::thread::spawn(move || {
+            entry.get_or_init(|| pipelines.build(key));
+        });
+    }
+
+    // render pass and pipeline for `key`, waits for the build if it is still running.
+    fn get(&self, key: PipelineKey) -> (vk::RenderPass, vk::Pipeline) {
+        *self.entry(key).get_or_init(|| self.build(key))
+    }
+
+    // pipeline of the post pass for `key`, with `shader` or else the built-in color transform.
+    fn get_post(&self, key: PipelineKey, shader: Option<Arc<[u32]>>) -> vk::Pipeline {
+        let (render_pass, _) = self.get(key);
+        let mut post_entries = self.post_entries.lock().unwrap();
+        *post_entries
+            .entry((key, shader.clone()))
+            .or_insert_with(|| {
+                let fragment_code = shader.as_deref().map_or(
+                    &include_bytes!("shaders/spv/post.spv")[..],
+                    bytemuck::cast_slice,
+                );
+                Self::create_pipeline(
+                    &self.device,
+                    render_pass,
+                    self.pipeline_layout,
+                    self.pipeline_cache,
+                    device::clamp_sample_count(key.samples, self.sample_counts),
+                    fragment_code,
+                )
+            })
+    }
+
+    fn destroy(&mut self) {
+        for (_, pipeline) in self.post_entries.lock().unwrap().drain() {
+            unsafe { self.device.destroy_pipeline(pipeline, None) };
+        }
+        let entries = self.entries.lock().unwrap().drain().collect::<Vec<_>>();
+        for (key, entry) in entries {
+            // builds still running on a background thread are waited for.
+            let (render_pass, pipeline) = *entry.get_or_init(|| self.build(key));
+            unsafe {
+                self.device.destroy_pipeline(pipeline, None);
+                self.device.destroy_render_pass(render_pass, None);
+            }
+        }
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_pipeline_cache(self.pipeline_cache, None);
+        }
+    }
+}
+
+impl<A: Allocator + 'static> ViewportRenderer<A> {
     fn create_framebuffers(
         device: &Device,
         swap_images: &[vk::Image],
@@ -317,6 +743,7 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         device: &Device,
         swapchain_count: usize,
         allocator: &A,
+        queue_families: &QueueFamilies,
     ) -> VertexIndexBuffers<A> {
         let mut vertex_buffers = vec![];
         let mut vertex_buffer_allocations = vec![];
@@ -328,7 +755,8 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                     .create_buffer(
                         &vk::BufferCreateInfo::default()
                             .usage(vk::BufferUsageFlags::VERTEX_BUFFER)
-                            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                            .sharing_mode(queue_families.sharing_mode())
+                            .queue_family_indices(queue_families.indices())
                             .size(Self::vertex_buffer_size()),
                         None,
                     )
@@ -359,7 +787,8 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                     .create_buffer(
                         &vk::BufferCreateInfo::default()
                             .usage(vk::BufferUsageFlags::INDEX_BUFFER)
-                            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                            .sharing_mode(queue_families.sharing_mode())
+                            .queue_family_indices(queue_families.indices())
                             .size(Self::index_buffer_size()),
                         None,
                     )
@@ -391,7 +820,12 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
             index_buffer_allocations.push(index_buffer_allocation);
         }
 
-        VertexIndexBuffers(vertex_buffers, vertex_buffer_allocations, index_buffers, index_buffer_allocations)
+        VertexIndexBuffers(
+            vertex_buffers,
+            vertex_buffer_allocations,
+            index_buffers,
+            index_buffer_allocations,
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -403,8 +837,11 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         surface_format: vk::Format,
         scale_factor: f32,
         physical_size: winit::dpi::PhysicalSize<u32>,
+        post_shader: Option<Option<Arc<[u32]>>>,
         allocator: A,
     ) {
+        profile_function!();
+        span!("update_swapchain", width, height, format = ?surface_format);
         // wait device idle
         unsafe {
             self.device
@@ -413,11 +850,14 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         }
 
         // cleanup framebuffers and others
-        let (render_pass, pipeline_layout, pipeline) = {
+        {
             let Ok(mut state) = self.state.lock() else {
                 panic!("Failed to lock state.");
             };
             if let Some(mut state) = state.take() {
+                if let Some(scaled_target) = state.scaled_target.take() {
+                    scaled_target.destroy(&self.device, &self.allocator);
+                }
                 unsafe {
                     for vertex_buffer in state.vertex_buffers.drain(..) {
                         self.device.destroy_buffer(vertex_buffer, None);
@@ -442,16 +882,20 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                         self.device.destroy_image_view(image_view, None);
                     }
                 }
-
-                (state.render_pass, state.pipeline_layout, state.pipeline)
-            } else {
-                let render_pass = Self::create_render_pass(&self.device, surface_format);
-                let pipeline_layout =
-                    Self::create_pipeline_layout(&self.device, self.descriptor_set_layout);
-                let pipeline = Self::create_pipeline(&self.device, render_pass, pipeline_layout);
-                (render_pass, pipeline_layout, pipeline)
             }
+        }
+
+        // the render pass and pipeline are shared with the other viewports of the same format
+        let pipeline_key = PipelineKey {
+            format: surface_format,
+            samples: vk::SampleCountFlags::TYPE_1,
         };
+        let (render_pass, pipeline) = self.pipelines.get(pipeline_key);
+        let pipeline_layout = self.pipelines.pipeline_layout;
+        let post_pipeline = post_shader.map(|shader| {
+            let pipeline = self.pipelines.get_post(pipeline_key, shader.clone());
+            (shader, pipeline)
+        });
 
         // Create Framebuffers
         let (framebuffers, swapchain_image_views) = Self::create_framebuffers(
@@ -463,8 +907,29 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
             height,
         );
 
-        let VertexIndexBuffers(vertex_buffers, vertex_buffer_allocations, index_buffers, index_buffer_allocations) =
-            Self::create_buffers(&self.device, swapchain_images.len(), &allocator);
+        let VertexIndexBuffers(
+            vertex_buffers,
+            vertex_buffer_allocations,
+            index_buffers,
+            index_buffer_allocations,
+        ) = Self::create_buffers(
+            &self.device,
+            swapchain_images.len(),
+            &allocator,
+            &self.queue_families,
+        );
+
+        // Create the intermediate render target
+        let scaled_target = (self.render_scale != 1.0 || post_pipeline.is_some()).then(|| {
+            ScaledTarget::new(
+                self,
+                &allocator,
+                width,
+                height,
+                surface_format,
+                swapchain_images.len(),
+            )
+        });
 
         // update self
         let mut state = self.state.lock().expect("Failed to lock state.");
@@ -483,6 +948,9 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
             scale_factor,
             physical_width: physical_size.width,
             physical_height: physical_size.height,
+            uploaded_generations: vec![None; swapchain_images.len()],
+            scaled_target,
+            post_pipeline,
         });
     }
 
@@ -496,21 +964,87 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
         1024 * 1024 * 4
     }
 
+    // the quad drawing the scaled render target is at the end of the vertex buffer
+    fn quad_first_vertex() -> usize {
+        Self::vertex_buffer_size() as usize / std::mem::size_of::<egui::epaint::Vertex>() - 4
+    }
+
+    // and at the end of the index buffer
+    fn quad_first_index() -> usize {
+        Self::index_buffer_size() as usize / std::mem::size_of::<u32>() - 6
+    }
+
     fn create_egui_cmd(
         &self,
-        clipped_primitives: Vec<egui::ClippedPrimitive>,
+        tessellation: Tessellation,
         textures_delta: egui::TexturesDelta,
-        managed_textures: Arc<Mutex<ManagedTextures<A>>>,
-        user_textures: Arc<Mutex<UserTextures>>,
+        context: egui::Context,
         scale_factor: f32,
         physical_size: winit::dpi::PhysicalSize<u32>,
+        post_process: Option<PostProcess>,
     ) -> EguiCommand {
+        let Tessellation {
+            clipped_primitives,
+            generation,
+            reused,
+        } = tessellation;
+        let managed_textures = self.managed_textures.clone();
+        let user_textures = self.user_textures.clone();
+        let post_process_unchanged = {
+            let mut last_post_process = self.last_post_process.lock().unwrap();
+            let unchanged = *last_post_process == post_process;
+            last_post_process.clone_from(&post_process);
+            unchanged
+        };
+        let post_shader = post_process.as_ref().map(PostProcess::shader);
+        let post_constants = post_process.as_ref().map(PostProcess::push_constants);
+        let layer_split = layer::split_index(&clipped_primitives);
+        let primitive_count = clipped_primitives.len();
+        let texture_ids = clipped_primitives
+            .iter()
+            .filter_map(|clipped_primitive| match &clipped_primitive.primitive {
+                egui::epaint::Primitive::Mesh(mesh) => Some(mesh.texture_id),
+                egui::epaint::Primitive::Callback(_) => None,
+            })
+            .collect::<HashSet<_>>();
+        // the swapchain images already show this frame.
+        // user textures are native images which can be updated in place without a texture delta.
+        let unchanged = reused
+            && post_process_unchanged
+            && textures_delta.is_empty()
+            && managed_textures.lock().unwrap().pending_uploads.is_empty()
+            && !texture_ids
+                .iter()
+                .any(|texture_id| matches!(texture_id, egui::TextureId::User(_)))
+            && self.state.lock().unwrap().as_ref().is_some_and(|state| {
+                state.scale_factor == scale_factor
+                    && state.physical_width == physical_size.width
+                    && state.physical_height == physical_size.height
+            });
+        // descriptor sets of the textures the frame draws, looked up when its textures are updated.
+        let texture_desc_sets = Arc::new(Mutex::new(HashMap::new()));
+        // uv rects of the atlas images the frame draws, in their atlas page.
+        let atlas_rects = Arc::new(Mutex::new(HashMap::new()));
+        // descriptor sets and uv rects of the tiles of the textures the frame draws.
+        let tile_sets = Arc::new(Mutex::new(HashMap::new()));
+        // the user textures the frame samples, looked up with the descriptor sets.
+        let texture_usages = Arc::new(Mutex::new(Vec::new()));
+        // where the meshes of the frame are in the buffers, known once they are uploaded.
+        let mesh_layouts = Arc::new(Mutex::new(None));
+
         EguiCommand {
+            unchanged,
+            layer_split,
+            primitive_count,
+            clear_color: [0.0; 4],
+            texture_usages: Some(texture_usages.clone()),
             swapchain_recreate_required: {
                 let this = self.clone();
                 let state = this.state.lock().unwrap();
                 if let Some(state) = &*state {
                     state.scale_factor != scale_factor
+                        || state.post_pipeline.as_ref().map(|(shader, _)| shader)
+                            != post_shader.as_ref()
                 } else {
                     false
                 }
@@ -531,15 +1065,21 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                         surface_format,
                         scale_factor,
                         physical_size,
+                        post_shader,
                         this.allocator.clone(),
                     );
                 }
             })),
-            recorder: Box::new({
-                let this = self.clone();
-                move |cmd, index: usize| {
-                    let state = this.state.lock().expect("Failed to lock state mutex.");
-                    let state = state.as_ref().expect("State is none.");
+            texture_updater: Some(Box::new({
+                let texture_desc_sets = texture_desc_sets.clone();
+                let atlas_rects = atlas_rects.clone();
+                let tile_sets = tile_sets.clone();
+                let frame_stats = self.frame_stats.clone();
+                let texture_usages = texture_usages.clone();
+                let viewport_id = self.viewport_id;
+                move || {
+                    profile_scope!("update_textures");
+                    let pass_nr = context.cumulative_pass_nr_for(viewport_id);
                     let mut managed_textures =
                         managed_textures.lock().expect("Failed to lock textures.");
                     let mut user_textures =
@@ -547,36 +1087,289 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
 
                     // update textures
                     managed_textures.update_textures(textures_delta);
-                    user_textures.update_textures();
+                    let atlas_commands = user_textures.update_textures();
+                    managed_textures.update_atlas(atlas_commands);
+                    let drawn = texture_ids
+                        .iter()
+                        .map(|id| {
+                            managed_textures
+                                .atlas
+                                .images
+                                .get(id)
+                                .map_or(*id, |&(page_id, _)| page_id)
+                        })
+                        .collect::<HashSet<_>>();
+                    managed_textures.upload_pending(&drawn);
+                    if !managed_textures.pending_uploads.is_empty() {
+                        // continue uploading on the next frame
+                        context.request_repaint();
+                    }
+                    let texture_memory = managed_textures.memory_size();
+                    frame_stats.update(viewport_id, |stats| stats.texture_memory = texture_memory);
+
+                    // recording does not lock the textures, so that viewports can record in parallel.
+                    let mut texture_desc_sets = texture_desc_sets.lock().unwrap();
+                    let mut atlas_rects = atlas_rects.lock().unwrap();
+                    let mut tile_sets = tile_sets.lock().unwrap();
+                    let mut texture_usages = texture_usages.lock().unwrap();
+                    for texture_id in texture_ids {
+                        let desc_set = match texture_id {
+                            egui::TextureId::Managed(_) => {
+                                if let Some(tiled) = managed_textures.tiles.get(&texture_id) {
+                                    let sets = tiled
+                                        .tiles
+                                        .iter()
+                                        .zip(tiled.uv_rects())
+                                        .map(|(tile, rects)| {
+                                            let desc_set = managed_textures
+                                                .texture_desc_sets
+                                                .get(&tile.texture_id)
+                                                .copied();
+                                            (desc_set, rects)
+                                        })
+                                        .collect::<Vec<_>>();
+                                    tile_sets.insert(texture_id, sets);
+                                }
+                                managed_textures.texture_desc_sets.get(&texture_id)
+                            }
+                            egui::TextureId::User(id) => {
+                                user_textures.last_used.insert(id, Instant::now());
+                                user_textures.last_drawn.insert(id, pass_nr);
+                                if let Some(&(page_id, uv_rect)) =
+                                    managed_textures.atlas.images.get(&texture_id)
+                                {
+                                    atlas_rects.insert(texture_id, uv_rect);
+                                    managed_textures.texture_desc_sets.get(&page_id)
+                                } else {
+                                    let image = user_textures
+                                        .infos
+                                        .get(&id)
+                                        .map_or(vk::Image::null(), |info| info.image);
+                                    texture_usages
+                                        .push(ResourceUsage::user_texture(texture_id, image));
+                                    user_textures.texture_desc_sets.get(&id)
+                                }
+                            }
+                        };
+                        if let Some(&desc_set) = desc_set {
+                            texture_desc_sets.insert(texture_id, desc_set);
+                        }
+                    }
+                }
+            })),
+            uploader: Box::new({
+                let this = self.clone();
+                let clipped_primitives = clipped_primitives.clone();
+                let tile_sets = tile_sets.clone();
+                let mesh_layouts = mesh_layouts.clone();
+                move |index: usize| {
+                    let mut state = this.state.lock().expect("Failed to lock state mutex.");
+                    let state = state.as_mut().expect("State is none.");
+                    let mut mesh_layouts = mesh_layouts.lock().unwrap();
+                    // the buffers of this image already hold the vertices of this frame, or of an unchanged frame.
+                    let uploaded = state.uploaded_generations[index] == Some(generation);
+                    if uploaded && mesh_layouts.is_some() {
+                        return;
+                    }
+                    profile_scope!("upload_egui");
+                    let atlas_rects = atlas_rects.lock().unwrap();
+                    let tile_sets = tile_sets.lock().unwrap();
 
                     // get buffer ptr
                     let mut vertex_buffer_ptr = state.vertex_buffer_allocations[index]
                         .mapped_ptr()
                         .unwrap()
-                        .as_ptr().cast::<u8>();
-                    let vertex_buffer_ptr_end =
-                        unsafe { vertex_buffer_ptr.add(Self::vertex_buffer_size() as usize) };
+                        .as_ptr()
+                        .cast::<u8>();
+                    let vertex_buffer_ptr_end = unsafe {
+                        vertex_buffer_ptr.add(
+                            Self::quad_first_vertex() * std::mem::size_of::<egui::epaint::Vertex>(),
+                        )
+                    };
                     let mut index_buffer_ptr = state.index_buffer_allocations[index]
                         .mapped_ptr()
                         .unwrap()
-                        .as_ptr().cast::<u8>();
-                    let index_buffer_ptr_end =
-                        unsafe { index_buffer_ptr.add(Self::index_buffer_size() as usize) };
+                        .as_ptr()
+                        .cast::<u8>();
+                    let index_buffer_ptr_end = unsafe {
+                        index_buffer_ptr.add(Self::quad_first_index() * std::mem::size_of::<u32>())
+                    };
 
-                    // begin render pass
-                    unsafe {
-                        this.device.cmd_begin_render_pass(
-                            cmd,
-                            &vk::RenderPassBeginInfo::default()
-                                .render_pass(state.render_pass)
-                                .framebuffer(state.framebuffers[index])
-                                .clear_values(&[])
-                                .render_area(
-                                    vk::Rect2D::default().extent(
-                                        vk::Extent2D::default()
-                                            .width(state.width)
-                                            .height(state.height),
-                                    ),
+                    let mut vertex_base = 0;
+                    let mut index_base = 0;
+                    let mut layouts = Vec::with_capacity(clipped_primitives.len());
+                    for egui::ClippedPrimitive { primitive, .. } in clipped_primitives.iter() {
+                        let mesh = match primitive {
+                            egui::epaint::Primitive::Mesh(mesh) => mesh,
+                            egui::epaint::Primitive::Callback(callback)
+                                if layer::is_layer_split(callback) =>
+                            {
+                                layouts.push(None);
+                                continue;
+                            }
+                            egui::epaint::Primitive::Callback(_) => todo!(),
+                        };
+                        if mesh.vertices.is_empty() || mesh.indices.is_empty() {
+                            layouts.push(None);
+                            continue;
+                        }
+
+                        // the triangles of tiled textures get their own vertices, grouped by tile.
+                        let groups = tile_sets.get(&mesh.texture_id).map(|tiles| {
+                            let rects = tiles.iter().map(|&(_, rects)| rects).collect::<Vec<_>>();
+                            tile_vertices(&rects, mesh)
+                        });
+                        let (vertex_count, index_count) = match &groups {
+                            Some(groups) => {
+                                let count = groups.iter().map(Vec::len).sum();
+                                (count, count)
+                            }
+                            None => (mesh.vertices.len(), mesh.indices.len()),
+                        };
+
+                        let v_slice = &mesh.vertices;
+                        let v_size = std::mem::size_of::<egui::epaint::Vertex>();
+                        let v_copy_size = vertex_count * v_size;
+
+                        let i_slice = &mesh.indices;
+                        let i_size = std::mem::size_of::<u32>();
+                        let i_copy_size = index_count * i_size;
+
+                        let vertex_buffer_ptr_next = unsafe { vertex_buffer_ptr.add(v_copy_size) };
+                        let index_buffer_ptr_next = unsafe { index_buffer_ptr.add(i_copy_size) };
+
+                        if vertex_buffer_ptr_next >= vertex_buffer_ptr_end
+                            || index_buffer_ptr_next >= index_buffer_ptr_end
+                        {
+                            panic!("egui paint out of memory");
+                        }
+
+                        // map memory
+                        // indices are offset by the vertices of the previous meshes, so that consecutive meshes can share a draw.
+                        if !uploaded {
+                            if let Some(groups) = &groups {
+                                // the vertices are sampled from the tile of their triangle
+                                let vertex_ptr = vertex_buffer_ptr.cast::<egui::epaint::Vertex>();
+                                for (i, vertex) in groups.iter().flatten().enumerate() {
+                                    unsafe {
+                                        vertex_ptr.add(i).write_unaligned(*vertex);
+                                    };
+                                }
+                            } else if let Some(uv_rect) = atlas_rects.get(&mesh.texture_id) {
+                                // atlas images are sampled from their rect in the atlas page
+                                let vertex_ptr = vertex_buffer_ptr.cast::<egui::epaint::Vertex>();
+                                for (i, vertex) in v_slice.iter().enumerate() {
+                                    let uv = uv_rect.min + vertex.uv.to_vec2() * uv_rect.size();
+                                    unsafe {
+                                        vertex_ptr.add(i).write_unaligned(egui::epaint::Vertex {
+                                            uv,
+                                            ..*vertex
+                                        });
+                                    };
+                                }
+                            } else {
+                                unsafe {
+                                    vertex_buffer_ptr
+                                        .copy_from(v_slice.as_ptr().cast::<u8>(), v_copy_size);
+                                };
+                            }
+                            let index_ptr = index_buffer_ptr.cast::<u32>();
+                            if groups.is_some() {
+                                // the vertices of tiled textures are in the order they are drawn
+                                for i in 0..index_count {
+                                    unsafe {
+                                        index_ptr.add(i).write_unaligned(vertex_base + i as u32);
+                                    };
+                                }
+                            } else {
+                                for (i, &vertex_index) in i_slice.iter().enumerate() {
+                                    unsafe {
+                                        index_ptr
+                                            .add(i)
+                                            .write_unaligned(vertex_base + vertex_index);
+                                    };
+                                }
+                            }
+                        }
+
+                        vertex_buffer_ptr = vertex_buffer_ptr_next;
+                        index_buffer_ptr = index_buffer_ptr_next;
+
+                        // the buffer layout does not depend on the textures and clip rects, so that it stays valid for the next frames.
+                        // Only the tiles of textures larger than `maxImageDimension2D` change it, which tessellates the frame again.
+                        layouts.push(Some(MeshLayout {
+                            first_index: index_base,
+                            index_count: index_count as u32,
+                            tile_index_counts: groups.map(|groups| {
+                                groups.iter().map(|group| group.len() as u32).collect()
+                            }),
+                        }));
+                        vertex_base += vertex_count as u32;
+                        index_base += index_count as u32;
+                    }
+                    state.uploaded_generations[index] = Some(generation);
+                    *mesh_layouts = Some(layouts);
+                    this.frame_stats.update(this.viewport_id, |stats| {
+                        stats.vertices = vertex_base;
+                        stats.indices = index_base;
+                    });
+                }
+            }),
+            recorder: Box::new({
+                let this = self.clone();
+                move |cmd, index: usize, range: Range<usize>| {
+                    profile_scope!("record_egui");
+                    let state = this.state.lock().expect("Failed to lock state mutex.");
+                    let state = state.as_ref().expect("State is none.");
+                    let texture_desc_sets = texture_desc_sets.lock().unwrap();
+                    let tile_sets = tile_sets.lock().unwrap();
+                    let mesh_layouts = mesh_layouts.lock().unwrap();
+                    let mesh_layouts = mesh_layouts
+                        .as_ref()
+                        .expect("The meshes have not been uploaded.");
+
+                    // egui renders to the scaled render target if there is one
+                    let render_scale = state
+                        .scaled_target
+                        .as_ref()
+                        .map_or(1.0, |target| target.scale);
+                    let pixels_per_point = state.scale_factor * render_scale;
+                    let target_width = state.physical_width as f32 * render_scale;
+                    let target_height = state.physical_height as f32 * render_scale;
+
+                    // begin render pass
+                    unsafe {
+                        let (render_pass, framebuffer, width, height) = match &state.scaled_target {
+                            Some(target) => (
+                                target.render_pass,
+                                target.framebuffers[index],
+                                target.width,
+                                target.height,
+                            ),
+                            None => (
+                                state.render_pass,
+                                state.framebuffers[index],
+                                state.width,
+                                state.height,
+                            ),
+                        };
+                        // the scaled render target is cleared to transparent
+                        let clear_values = [vk::ClearValue::default()];
+                        let clear_values = if state.scaled_target.is_some() {
+                            &clear_values[..]
+                        } else {
+                            &[]
+                        };
+                        this.device.cmd_begin_render_pass(
+                            cmd,
+                            &vk::RenderPassBeginInfo::default()
+                                .render_pass(render_pass)
+                                .framebuffer(framebuffer)
+                                .clear_values(clear_values)
+                                .render_area(
+                                    vk::Rect2D::default().extent(
+                                        vk::Extent2D::default().width(width).height(height),
+                                    ),
                                 ),
                             vk::SubpassContents::INLINE,
                         );
@@ -620,37 +1413,123 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                     }
 
                     // render meshes
-                    let mut vertex_base = 0;
-                    let mut index_base = 0;
-                    for egui::ClippedPrimitive {
-                        clip_rect,
-                        primitive,
-                    } in clipped_primitives
+                    unsafe {
+                        this.device.cmd_set_viewport(
+                            cmd,
+                            0,
+                            std::slice::from_ref(
+                                &vk::Viewport::default()
+                                    .x(0.0)
+                                    .y(0.0)
+                                    .width(target_width)
+                                    .height(target_height)
+                                    .min_depth(0.0)
+                                    .max_depth(1.0),
+                            ),
+                        );
+                    }
+                    let draw = |draw_call: &DrawCall| unsafe {
+                        this.device.cmd_bind_descriptor_sets(
+                            cmd,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            state.pipeline_layout,
+                            0,
+                            &[draw_call.descriptor_set],
+                            &[],
+                        );
+                        this.device.cmd_set_scissor(
+                            cmd,
+                            0,
+                            std::slice::from_ref(&draw_call.scissor),
+                        );
+                        this.device.cmd_draw_indexed(
+                            cmd,
+                            draw_call.index_count,
+                            1,
+                            draw_call.first_index,
+                            0,
+                            0,
+                        );
+                    };
+                    let mut draw_calls = 0;
+                    let mut pending_draw: Option<DrawCall> = None;
+                    for (
+                        i,
+                        egui::ClippedPrimitive {
+                            clip_rect,
+                            primitive,
+                        },
+                    ) in clipped_primitives.iter().enumerate()
                     {
-                        let mesh = match primitive {
-                            egui::epaint::Primitive::Mesh(mesh) => mesh,
-                            egui::epaint::Primitive::Callback(_) => todo!(),
+                        // the meshes of the other layer are uploaded, but not drawn
+                        if !range.contains(&i) {
+                            continue;
+                        }
+                        let (egui::epaint::Primitive::Mesh(mesh), Some(layout)) =
+                            (primitive, &mesh_layouts[i])
+                        else {
+                            continue;
                         };
-                        if mesh.vertices.is_empty() || mesh.indices.is_empty() {
+
+                        // cull meshes outside of the framebuffer
+                        let min = clip_rect.min;
+                        let min = egui::Pos2 {
+                            x: min.x * pixels_per_point,
+                            y: min.y * pixels_per_point,
+                        };
+                        let min = egui::Pos2 {
+                            x: f32::clamp(min.x, 0.0, target_width),
+                            y: f32::clamp(min.y, 0.0, target_height),
+                        };
+                        let max = clip_rect.max;
+                        let max = egui::Pos2 {
+                            x: max.x * pixels_per_point,
+                            y: max.y * pixels_per_point,
+                        };
+                        let max = egui::Pos2 {
+                            x: f32::clamp(max.x, min.x, target_width),
+                            y: f32::clamp(max.y, min.y, target_height),
+                        };
+                        let scissor = vk::Rect2D::default()
+                            .offset(vk::Offset2D {
+                                x: min.x.round() as i32,
+                                y: min.y.round() as i32,
+                            })
+                            .extent(vk::Extent2D {
+                                width: (max.x.round() - min.x) as u32,
+                                height: (max.y.round() - min.y) as u32,
+                            });
+                        if scissor.extent.width == 0 || scissor.extent.height == 0 {
                             continue;
                         }
 
-                        unsafe {
-                            match mesh.texture_id {
-                                egui::TextureId::User(id) => {
+                        // the descriptor sets drawing the mesh, with their first index and index count.
+                        let parts = if let (Some(tiles), Some(index_counts)) =
+                            (tile_sets.get(&mesh.texture_id), &layout.tile_index_counts)
+                        {
+                            let mut first_index = layout.first_index;
+                            let mut parts = vec![];
+                            for (&(descriptor_set, _), &index_count) in
+                                tiles.iter().zip(index_counts)
+                            {
+                                // tiles waiting for the upload budget are skipped
+                                if let Some(descriptor_set) =
+                                    descriptor_set.filter(|_| index_count != 0)
+                                {
+                                    parts.push((descriptor_set, first_index, index_count));
+                                }
+                                first_index += index_count;
+                            }
+                            parts
+                        } else {
+                            let descriptor_set = match mesh.texture_id {
+                                egui::TextureId::User(_) => {
                                     if let Some(&descriptor_set) =
-                                        user_textures.texture_desc_sets.get(&id)
+                                        texture_desc_sets.get(&mesh.texture_id)
                                     {
-                                        this.device.cmd_bind_descriptor_sets(
-                                            cmd,
-                                            vk::PipelineBindPoint::GRAPHICS,
-                                            state.pipeline_layout,
-                                            0,
-                                            &[descriptor_set],
-                                            &[],
-                                        );
+                                        descriptor_set
                                     } else {
-                                        log::error!(
+                                        error!(
                                             "This UserTexture has already been unregistered: {:?}",
                                             mesh.texture_id
                                         );
@@ -658,119 +1537,192 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                                     }
                                 }
                                 egui::TextureId::Managed(_) => {
-                                    this.device.cmd_bind_descriptor_sets(
-                                        cmd,
-                                        vk::PipelineBindPoint::GRAPHICS,
-                                        state.pipeline_layout,
-                                        0,
-                                        &[*managed_textures
-                                            .texture_desc_sets
-                                            .get(&mesh.texture_id)
-                                            .unwrap()],
-                                        &[],
-                                    );
+                                    // waiting for the upload budget
+                                    let Some(&descriptor_set) =
+                                        texture_desc_sets.get(&mesh.texture_id)
+                                    else {
+                                        continue;
+                                    };
+                                    descriptor_set
                                 }
-                            }
-                        }
-                        let v_slice = &mesh.vertices;
-                        let v_size = std::mem::size_of::<egui::epaint::Vertex>();
-                        let v_copy_size = v_slice.len() * v_size;
-
-                        let i_slice = &mesh.indices;
-                        let i_size = std::mem::size_of::<u32>();
-                        let i_copy_size = i_slice.len() * i_size;
-
-                        let vertex_buffer_ptr_next = unsafe { vertex_buffer_ptr.add(v_copy_size) };
-                        let index_buffer_ptr_next = unsafe { index_buffer_ptr.add(i_copy_size) };
-
-                        if vertex_buffer_ptr_next >= vertex_buffer_ptr_end
-                            || index_buffer_ptr_next >= index_buffer_ptr_end
-                        {
-                            panic!("egui paint out of memory");
-                        }
-
-                        // map memory
-                        unsafe {
-                            vertex_buffer_ptr.copy_from(v_slice.as_ptr().cast::<u8>(), v_copy_size);
-                        };
-                        unsafe {
-                            index_buffer_ptr.copy_from(i_slice.as_ptr().cast::<u8>(), i_copy_size);
+                            };
+                            vec![(descriptor_set, layout.first_index, layout.index_count)]
                         };
 
-                        vertex_buffer_ptr = vertex_buffer_ptr_next;
-                        index_buffer_ptr = index_buffer_ptr_next;
-
-                        // record draw commands
-                        unsafe {
-                            let min = clip_rect.min;
-                            let min = egui::Pos2 {
-                                x: min.x * state.scale_factor,
-                                y: min.y * state.scale_factor,
-                            };
-                            let min = egui::Pos2 {
-                                x: f32::clamp(min.x, 0.0, state.physical_width as f32),
-                                y: f32::clamp(min.y, 0.0, state.physical_height as f32),
-                            };
-                            let max = clip_rect.max;
-                            let max = egui::Pos2 {
-                                x: max.x * state.scale_factor,
-                                y: max.y * state.scale_factor,
-                            };
-                            let max = egui::Pos2 {
-                                x: f32::clamp(max.x, min.x, state.physical_width as f32),
-                                y: f32::clamp(max.y, min.y, state.physical_height as f32),
-                            };
-                            this.device.cmd_set_scissor(
-                                cmd,
-                                0,
-                                std::slice::from_ref(
-                                    &vk::Rect2D::default()
-                                        .offset(vk::Offset2D {
-                                            x: min.x.round() as i32,
-                                            y: min.y.round() as i32,
-                                        })
-                                        .extent(vk::Extent2D {
-                                            width: (max.x.round() - min.x) as u32,
-                                            height: (max.y.round() - min.y) as u32,
-                                        }),
-                                ),
-                            );
-                            this.device.cmd_set_viewport(
-                                cmd,
-                                0,
-                                std::slice::from_ref(
-                                    &vk::Viewport::default()
-                                        .x(0.0)
-                                        .y(0.0)
-                                        .width(state.physical_width as f32)
-                                        .height(state.physical_height as f32)
-                                        .min_depth(0.0)
-                                        .max_depth(1.0),
-                                ),
-                            );
-                            this.device.cmd_draw_indexed(
-                                cmd,
-                                mesh.indices.len() as u32,
-                                1,
-                                index_base,
-                                vertex_base,
-                                0,
-                            );
+                        for (descriptor_set, first_index, index_count) in parts {
+                            // merge with the previous mesh if it has the same texture and clip rect
+                            if let Some(draw_call) = &mut pending_draw {
+                                if draw_call.descriptor_set == descriptor_set
+                                    && draw_call.scissor == scissor
+                                    && draw_call.first_index + draw_call.index_count == first_index
+                                {
+                                    draw_call.index_count += index_count;
+                                    continue;
+                                }
+                            }
+                            if let Some(draw_call) = pending_draw.replace(DrawCall {
+                                descriptor_set,
+                                scissor,
+                                first_index,
+                                index_count,
+                            }) {
+                                draw(&draw_call);
+                                draw_calls += 1;
+                            }
                         }
-
-                        vertex_base += mesh.vertices.len() as i32;
-                        index_base += mesh.indices.len() as u32;
                     }
+                    if let Some(draw_call) = pending_draw.take() {
+                        draw(&draw_call);
+                        draw_calls += 1;
+                    }
+                    this.frame_stats.update(this.viewport_id, |stats| {
+                        // the foreground layer adds to the draws of the background layer
+                        stats.draw_calls = if range.start == 0 {
+                            draw_calls
+                        } else {
+                            stats.draw_calls + draw_calls
+                        };
+                    });
 
                     // end render pass
                     unsafe {
                         this.device.cmd_end_render_pass(cmd);
                     }
+
+                    // draw the scaled render target to the swapchain image
+                    if let Some(target) = &state.scaled_target {
+                        this.record_scaled_target(cmd, state, target, index, post_constants);
+                    }
                 }
             }),
         }
     }
 
+    fn record_scaled_target(
+        &self,
+        cmd: vk::CommandBuffer,
+        state: &ViewportRendererState<A>,
+        target: &ScaledTarget<A>,
+        index: usize,
+        post_constants: Option<[f32; 24]>,
+    ) {
+        // the post pass draws the quad if the frame has one and the swapchain was created with it
+        let post = state.post_pipeline.as_ref().zip(post_constants);
+        let pipeline = post.map_or(state.pipeline, |((_, pipeline), _)| *pipeline);
+        // the quad covers the swapchain image, in pixels
+        let width = state.physical_width as f32;
+        let height = state.physical_height as f32;
+        let vertex = |x: f32, y: f32| egui::epaint::Vertex {
+            pos: egui::pos2(x * width, y * height),
+            uv: egui::pos2(x, y),
+            color: egui::Color32::WHITE,
+        };
+        let vertices = [
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+            vertex(0.0, 1.0),
+        ];
+        let indices: [u32; 6] = [0, 1, 2, 0, 2, 3];
+        unsafe {
+            let vertex_buffer_ptr = state.vertex_buffer_allocations[index]
+                .mapped_ptr()
+                .unwrap()
+                .as_ptr()
+                .cast::<egui::epaint::Vertex>()
+                .add(Self::quad_first_vertex());
+            vertex_buffer_ptr.copy_from(vertices.as_ptr(), vertices.len());
+            let index_buffer_ptr = state.index_buffer_allocations[index]
+                .mapped_ptr()
+                .unwrap()
+                .as_ptr()
+                .cast::<u32>()
+                .add(Self::quad_first_index());
+            index_buffer_ptr.copy_from(indices.as_ptr(), indices.len());
+        }
+
+        unsafe {
+            self.device.cmd_begin_render_pass(
+                cmd,
+                &vk::RenderPassBeginInfo::default()
+                    .render_pass(state.render_pass)
+                    .framebuffer(state.framebuffers[index])
+                    .clear_values(&[])
+                    .render_area(
+                        vk::Rect2D::default().extent(
+                            vk::Extent2D::default()
+                                .width(state.width)
+                                .height(state.height),
+                        ),
+                    ),
+                vk::SubpassContents::INLINE,
+            );
+            self.device
+                .cmd_bind_pipeline(cmd, vk::PipelineBindPoint::GRAPHICS, pipeline);
+            self.device.cmd_push_constants(
+                cmd,
+                state.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                bytes_of(&width),
+            );
+            if let Some((_, constants)) = &post {
+                self.device.cmd_push_constants(
+                    cmd,
+                    state.pipeline_layout,
+                    vk::ShaderStageFlags::FRAGMENT,
+                    16,
+                    bytes_of(constants),
+                );
+            }
+            self.device.cmd_push_constants(
+                cmd,
+                state.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX,
+                4,
+                bytes_of(&height),
+            );
+            self.device.cmd_bind_descriptor_sets(
+                cmd,
+                vk::PipelineBindPoint::GRAPHICS,
+                state.pipeline_layout,
+                0,
+                &[target.descriptor_sets[index]],
+                &[],
+            );
+            self.device.cmd_set_viewport(
+                cmd,
+                0,
+                std::slice::from_ref(
+                    &vk::Viewport::default()
+                        .x(0.0)
+                        .y(0.0)
+                        .width(width)
+                        .height(height)
+                        .min_depth(0.0)
+                        .max_depth(1.0),
+                ),
+            );
+            self.device.cmd_set_scissor(
+                cmd,
+                0,
+                std::slice::from_ref(&vk::Rect2D::default().extent(vk::Extent2D {
+                    width: state.width,
+                    height: state.height,
+                })),
+            );
+            self.device.cmd_draw_indexed(
+                cmd,
+                indices.len() as u32,
+                1,
+                Self::quad_first_index() as u32,
+                Self::quad_first_vertex() as i32,
+                0,
+            );
+            self.device.cmd_end_render_pass(cmd);
+        }
+    }
+
     fn destroy(&mut self) {
         let mut state = self.state.lock().expect("Failed to lock state mutex.");
         if let Some(mut state) = state.take() {
@@ -782,11 +1734,13 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
             };
 
             // destroy state
+            if let Some(scaled_target) = state.scaled_target.take() {
+                scaled_target.destroy(&self.device, &self.allocator);
+            }
             unsafe {
                 for image_view in state.swapchain_image_views.drain(..) {
                     self.device.destroy_image_view(image_view, None);
                 }
-                self.device.destroy_render_pass(state.render_pass, None);
                 for vertex_buffer in state.vertex_buffers.drain(..) {
                     self.device.destroy_buffer(vertex_buffer, None);
                 }
@@ -809,11 +1763,454 @@ impl<A: Allocator + 'static> ViewportRenderer<A> {
                 for image_view in state.swapchain_image_views.drain(..) {
                     self.device.destroy_image_view(image_view, None);
                 }
-                self.device.destroy_pipeline(state.pipeline, None);
+            }
+        }
+    }
+}
+
+/// default number of descriptor sets in the first pool of a `DescriptorSets` chain.
+pub(crate) const DEFAULT_DESCRIPTOR_POOL_SIZE: u32 = 1024;
+
+/// most descriptor sets in a pool on `limits`: a pool holds no more combined image samplers than
+/// a descriptor set may use.
+fn max_descriptor_pool_size(limits: &vk::PhysicalDeviceLimits) -> u32 {
+    limits
+        .max_descriptor_set_samplers
+        .min(limits.max_descriptor_set_sampled_images)
+}
+
+/// texture descriptor sets, allocated from a chain of pools which grows when they run out.
+///
+/// Freed sets are recycled for new textures instead of returned to their pool.
+struct DescriptorSets {
+    device: Device,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    // pools with the number of sets they hold.
+    pools: Vec<(vk::DescriptorPool, u32)>,
+    current_pool: usize,
+    // sets the current pool can still allocate.
+    remaining: u32,
+    pool_size: u32,
+    // most sets a pool holds, see `max_descriptor_pool_size`.
+    max_pool_size: u32,
+    // sets no frame uses anymore.
+    free: Vec<vk::DescriptorSet>,
+    // sets freed while frames in flight may still use them.
+    retired: Vec<vk::DescriptorSet>,
+}
+impl DescriptorSets {
+    fn new(
+        device: Device,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        pool_size: u32,
+        max_pool_size: u32,
+    ) -> Self {
+        Self {
+            device,
+            descriptor_set_layout,
+            pools: vec![],
+            current_pool: 0,
+            remaining: 0,
+            pool_size: pool_size.clamp(1, max_pool_size.max(1)),
+            max_pool_size: max_pool_size.max(1),
+            free: vec![],
+            retired: vec![],
+        }
+    }
+
+    fn create_pool(device: &Device, max_sets: u32) -> vk::DescriptorPool {
+        unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .max_sets(max_sets)
+                    .pool_sizes(std::slice::from_ref(
+                        &vk::DescriptorPoolSize::default()
+                            .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                            .descriptor_count(max_sets),
+                    )),
+                None,
+            )
+        }
+        .expect("Failed to create descriptor pool.")
+    }
+
+    fn allocate(&mut self) -> vk::DescriptorSet {
+        if let Some(set) = self.free.pop() {
+            return set;
+        }
+        if self.remaining == 0 && self.current_pool + 1 < self.pools.len() {
+            self.current_pool += 1;
+            self.remaining = self.pools[self.current_pool].1;
+        }
+        if self.remaining == 0 && !self.retired.is_empty() {
+            // wait for the frames which may still use the retired sets before rewriting them.
+            unsafe {
                 self.device
-                    .destroy_pipeline_layout(state.pipeline_layout, None);
+                    .device_wait_idle()
+                    .expect("Failed to wait device idle");
+            }
+            self.free.append(&mut self.retired);
+            return self.free.pop().unwrap();
+        }
+        if self.remaining == 0 {
+            // each pool of the chain is twice as large as the previous one, up to the device limits.
+            let max_sets = self
+                .pool_size
+                .saturating_mul(1 << self.pools.len().min(8))
+                .min(self.max_pool_size);
+            self.pools
+                .push((Self::create_pool(&self.device, max_sets), max_sets));
+            self.current_pool = self.pools.len() - 1;
+            self.remaining = max_sets;
+        }
+        let (pool, max_sets) = self.pools[self.current_pool];
+        let first_set = self.remaining == max_sets;
+        self.remaining -= 1;
+        let result = unsafe {
+            self.device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(pool)
+                    .set_layouts(&[self.descriptor_set_layout]),
+            )
+        };
+        match result {
+            Ok(sets) => sets[0],
+            // some drivers run out of pool memory before `max_sets`, continue in the next pool.
+            Err(vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL)
+                if !first_set =>
+            {
+                self.remaining = 0;
+                self.allocate()
+            }
+            Err(err) => panic!("Failed to allocate descriptor set: {err}"),
+        }
+    }
+
+    /// recycle `set` once the frames in flight are done with it.
+    fn free(&mut self, set: vk::DescriptorSet) {
+        self.retired.push(set);
+    }
+
+    /// return all sets to their pools, which are reused from the first one.
+    ///
+    /// No frame may use the sets anymore.
+    fn reset(&mut self) {
+        for &(pool, _) in &self.pools {
+            unsafe {
+                self.device
+                    .reset_descriptor_pool(pool, vk::DescriptorPoolResetFlags::empty())
+                    .expect("Failed to reset descriptor pool.");
+            }
+        }
+        self.free.clear();
+        self.retired.clear();
+        self.current_pool = 0;
+        self.remaining = self.pools.first().map_or(0, |&(_, max_sets)| max_sets);
+    }
+
+    fn destroy(&mut self) {
+        for (pool, _) in self.pools.drain(..) {
+            unsafe {
+                self.device.destroy_descriptor_pool(pool, None);
+            }
+        }
+        self.free.clear();
+        self.retired.clear();
+        self.current_pool = 0;
+        self.remaining = 0;
+    }
+}
+
+/// limit of the texture data uploaded to the GPU per frame.
+///
+/// Uploads over the budget wait for the next frames. The font atlas is uploaded first and
+/// always without waiting, then the textures drawn in the frame, then the other textures.
+/// At least one texture is uploaded per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadBudget {
+    /// bytes of texture data per frame.
+    pub bytes_per_frame: usize,
+    /// time spent uploading textures per frame.
+    pub time_per_frame: Duration,
+}
+impl Default for UploadBudget {
+    fn default() -> Self {
+        Self {
+            bytes_per_frame: 16 * 1024 * 1024,
+            time_per_frame: Duration::from_millis(4),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum UploadPriority {
+    FontAtlas,
+    Visible,
+    Prefetch,
+}
+
+/// side of the atlas pages small user images are packed into.
+const ATLAS_PAGE_SIZE: usize = 2048;
+// transparent gap between the images of an atlas page, so that linear filtering does not bleed.
+const ATLAS_PADDING: usize = 1;
+
+// atlas page packing images in rows from top to bottom.
+//
+// The space of unregistered images is reused only after all images of the page are unregistered.
+struct AtlasPage {
+    texture_id: egui::TextureId,
+    size: [usize; 2],
+    cursor: [usize; 2],
+    row_height: usize,
+    image_count: usize,
+}
+impl AtlasPage {
+    fn allocate(&mut self, size: [usize; 2]) -> Option<[usize; 2]> {
+        let [width, height] = [size[0] + ATLAS_PADDING, size[1] + ATLAS_PADDING];
+        if self.cursor[0] + width > self.size[0] {
+            // next row
+            self.cursor = [0, self.cursor[1] + self.row_height];
+            self.row_height = 0;
+        }
+        if self.cursor[0] + width > self.size[0] || self.cursor[1] + height > self.size[1] {
+            return None;
+        }
+        let pos = self.cursor;
+        self.cursor[0] += width;
+        self.row_height = self.row_height.max(height);
+        self.image_count += 1;
+        Some(pos)
+    }
+}
+
+// user images packed into atlas pages, which are managed textures with ids counting down from `u64::MAX`.
+#[derive(Default)]
+struct TextureAtlas {
+    pages: Vec<AtlasPage>,
+    // atlas page and uv rect of each image
+    images: HashMap<egui::TextureId, (egui::TextureId, egui::Rect)>,
+    next_page: u64,
+}
+
+/// smallest `max_texture_side` egui is told about, so that the font atlas has room for the glyphs
+/// on devices with a small `maxImageDimension2D`.
+const MIN_MAX_TEXTURE_SIDE: usize = 8192;
+// texels shared by neighboring tiles, so that most glyphs crossing a tile border are drawn from one tile
+// without clipping them.
+const TILE_OVERLAP: usize = 256;
+
+/// `max_texture_side` of egui. Textures larger than `maxImageDimension2D` are split into tiles.
+pub(crate) fn max_texture_side(limits: &vk::PhysicalDeviceLimits) -> usize {
+    (limits.max_image_dimension2_d as usize).max(MIN_MAX_TEXTURE_SIDE)
+}
+
+// tile of a texture larger than `maxImageDimension2D`, a managed texture with an id counting down from `u64::MAX / 2`.
+struct TextureTile {
+    texture_id: egui::TextureId,
+    pos: [usize; 2],
+    size: [usize; 2],
+}
+
+// tiles of a texture larger than `maxImageDimension2D`, overlapping by `TILE_OVERLAP` texels.
+struct TiledTexture {
+    size: [usize; 2],
+    tiles: Vec<TextureTile>,
+}
+impl TiledTexture {
+    fn new(size: [usize; 2], max_side: usize, next_tile: &mut u64) -> Self {
+        let stride = max_side - TILE_OVERLAP.min(max_side / 2);
+        let starts = |len: usize| {
+            let mut starts = vec![0];
+            while starts[starts.len() - 1] + max_side < len {
+                starts.push(starts[starts.len() - 1] + stride);
+            }
+            starts
+        };
+        let mut tiles = vec![];
+        for y in starts(size[1]) {
+            for x in starts(size[0]) {
+                tiles.push(TextureTile {
+                    texture_id: egui::TextureId::Managed(u64::MAX / 2 - *next_tile),
+                    pos: [x, y],
+                    size: [max_side.min(size[0] - x), max_side.min(size[1] - y)],
+                });
+                *next_tile += 1;
             }
         }
+        Self { size, tiles }
+    }
+
+    // uv rect of each tile in the texture, and of its cell: the part of the texture up to the next tile.
+    // The tiles overlap, their cells do not.
+    fn uv_rects(&self) -> Vec<TileRects> {
+        let [width, height] = self.size.map(|side| side as f32);
+        let cell_end = |axis: usize, start: usize| {
+            self.tiles
+                .iter()
+                .map(|tile| tile.pos[axis])
+                .filter(|&pos| pos > start)
+                .min()
+                .unwrap_or(self.size[axis])
+        };
+        self.tiles
+            .iter()
+            .map(|tile| {
+                let min = egui::pos2(tile.pos[0] as f32 / width, tile.pos[1] as f32 / height);
+                TileRects {
+                    uv_rect: egui::Rect::from_min_max(
+                        min,
+                        egui::pos2(
+                            (tile.pos[0] + tile.size[0]) as f32 / width,
+                            (tile.pos[1] + tile.size[1]) as f32 / height,
+                        ),
+                    ),
+                    cell: egui::Rect::from_min_max(
+                        min,
+                        egui::pos2(
+                            cell_end(0, tile.pos[0]) as f32 / width,
+                            cell_end(1, tile.pos[1]) as f32 / height,
+                        ),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    // the updates of the tiles `delta` overlaps.
+    fn split(
+        &self,
+        delta: &egui::epaint::ImageDelta,
+    ) -> Vec<(egui::TextureId, egui::epaint::ImageDelta)> {
+        let egui::ImageData::Color(image) = &delta.image;
+        let [x, y] = delta.pos.unwrap_or([0, 0]);
+        let [width, height] = image.size;
+        self.tiles
+            .iter()
+            .filter_map(|tile| {
+                let min = [x.max(tile.pos[0]), y.max(tile.pos[1])];
+                let max = [
+                    (x + width).min(tile.pos[0] + tile.size[0]),
+                    (y + height).min(tile.pos[1] + tile.size[1]),
+                ];
+                if min[0] >= max[0] || min[1] >= max[1] {
+                    return None;
+                }
+                let pixels = (min[1]..max[1])
+                    .flat_map(|row| {
+                        let start = (row - y) * width + min[0] - x;
+                        image.pixels[start..start + max[0] - min[0]].iter().copied()
+                    })
+                    .collect();
+                let tile_image = egui::ColorImage::new([max[0] - min[0], max[1] - min[1]], pixels);
+                let tile_delta = match delta.pos {
+                    Some(_) => egui::epaint::ImageDelta::partial(
+                        [min[0] - tile.pos[0], min[1] - tile.pos[1]],
+                        tile_image,
+                        delta.options,
+                    ),
+                    None => egui::epaint::ImageDelta::full(tile_image, delta.options),
+                };
+                Some((tile.texture_id, tile_delta))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TileRects {
+    uv_rect: egui::Rect,
+    cell: egui::Rect,
+}
+
+// the vertices of the triangles of `mesh` grouped by the tile they are drawn from, with uvs in the tile.
+// A triangle is drawn from the first tile with the uvs of all three vertices, else it is clipped to the
+// cells of the tiles it crosses, e.g. a large glyph crossing the border of two tiles of the font atlas.
+fn tile_vertices(tiles: &[TileRects], mesh: &egui::Mesh) -> Vec<Vec<egui::epaint::Vertex>> {
+    let to_tile = |vertex: egui::epaint::Vertex, uv_rect: egui::Rect| egui::epaint::Vertex {
+        uv: ((vertex.uv - uv_rect.min) / uv_rect.size()).to_pos2(),
+        ..vertex
+    };
+    let mut groups = vec![vec![]; tiles.len()];
+    for triangle in mesh.indices.as_chunks::<3>().0 {
+        let vertices = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+        let uvs = vertices.map(|vertex| vertex.uv);
+        let tile = tiles
+            .iter()
+            .position(|tile| uvs.iter().all(|&uv| tile.uv_rect.contains(uv)));
+        // without uv area there is nothing to clip, draw it from the tile with its center.
+        let (u, v) = (uvs[1] - uvs[0], uvs[2] - uvs[0]);
+        let uv_area = (u.x * v.y - u.y * v.x).abs();
+        let tile = tile.or_else(|| {
+            (uv_area <= f32::EPSILON).then(|| {
+                let center = egui::pos2(
+                    (uvs[0].x + uvs[1].x + uvs[2].x) / 3.0,
+                    (uvs[0].y + uvs[1].y + uvs[2].y) / 3.0,
+                );
+                tiles
+                    .iter()
+                    .position(|tile| tile.cell.contains(center))
+                    .unwrap_or(0)
+            })
+        });
+        if let Some(tile) = tile {
+            let uv_rect = tiles[tile].uv_rect;
+            groups[tile].extend(vertices.map(|vertex| to_tile(vertex, uv_rect)));
+            continue;
+        }
+        for (group, tile) in groups.iter_mut().zip(tiles) {
+            let polygon = clip_to_cell(&vertices, tile.cell);
+            // the clipped polygon is convex, draw it as a fan.
+            for i in 1..polygon.len().saturating_sub(1) {
+                group.extend(
+                    [polygon[0], polygon[i], polygon[i + 1]]
+                        .map(|vertex| to_tile(vertex, tile.uv_rect)),
+                );
+            }
+        }
+    }
+    groups
+}
+
+// the part of `triangle` whose uvs are in `cell`, by clipping it to each side of the cell.
+fn clip_to_cell(
+    triangle: &[egui::epaint::Vertex; 3],
+    cell: egui::Rect,
+) -> Vec<egui::epaint::Vertex> {
+    let mut polygon = triangle.to_vec();
+    for (axis, sign, bound) in [
+        (0, 1.0, cell.min.x),
+        (0, -1.0, cell.max.x),
+        (1, 1.0, cell.min.y),
+        (1, -1.0, cell.max.y),
+    ] {
+        let distance = |vertex: &egui::epaint::Vertex| sign * (vertex.uv[axis] - bound);
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let (current_distance, next_distance) = (distance(current), distance(next));
+            if current_distance >= 0.0 {
+                clipped.push(*current);
+            }
+            if (current_distance >= 0.0) != (next_distance >= 0.0) {
+                let t = current_distance / (current_distance - next_distance);
+                clipped.push(lerp_vertex(current, next, t));
+            }
+        }
+        polygon = clipped;
+        if polygon.is_empty() {
+            break;
+        }
+    }
+    polygon
+}
+
+fn lerp_vertex(a: &egui::epaint::Vertex, b: &egui::epaint::Vertex, t: f32) -> egui::epaint::Vertex {
+    let color =
+        [0, 1, 2, 3].map(|i| egui::lerp(a.color[i] as f32..=b.color[i] as f32, t).round() as u8);
+    egui::epaint::Vertex {
+        pos: a.pos.lerp(b.pos, t),
+        uv: a.uv.lerp(b.uv, t),
+        color: egui::Color32::from_rgba_premultiplied(color[0], color[1], color[2], color[3]),
     }
 }
 
@@ -821,8 +2218,8 @@ struct ManagedTextures<A: Allocator + 'static> {
     device: Device,
     queue: vk::Queue,
     queue_family_index: u32,
-    descriptor_pool: vk::DescriptorPool,
-    descriptor_set_layout: vk::DescriptorSetLayout,
+    queue_families: QueueFamilies,
+    descriptor_sets: DescriptorSets,
     sampler: vk::Sampler,
     allocator: A,
 
@@ -830,6 +2227,21 @@ struct ManagedTextures<A: Allocator + 'static> {
     texture_images: HashMap<egui::TextureId, vk::Image>,
     texture_allocations: HashMap<egui::TextureId, A::Allocation>,
     texture_image_views: HashMap<egui::TextureId, vk::ImageView>,
+    texture_sizes: HashMap<egui::TextureId, [usize; 2]>,
+
+    // copies of the textures to upload them again after `release`.
+    image_copies: Option<HashMap<egui::TextureId, egui::epaint::ImageDelta>>,
+    released: bool,
+    atlas: TextureAtlas,
+
+    // `maxImageDimension2D`, larger textures are split into tiles.
+    max_image_dimension: usize,
+    tiles: HashMap<egui::TextureId, TiledTexture>,
+    next_tile: u64,
+
+    upload_budget: Option<UploadBudget>,
+    // texture updates waiting for the upload budget, in the order they were made.
+    pending_uploads: VecDeque<(egui::TextureId, egui::epaint::ImageDelta)>,
 }
 impl<A: Allocator + 'static> ManagedTextures<A> {
     fn create_sampler(device: &Device) -> vk::Sampler {
@@ -852,31 +2264,47 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
     }
 
     fn new(
-        device: Device,
-        queue: vk::Queue,
-        queue_family_index: u32,
-        descriptor_pool: vk::DescriptorPool,
-        descriptor_set_layout: vk::DescriptorSetLayout,
-        allocator: A,
+        render_state: &AshRenderState<A>,
+        queue_families: QueueFamilies,
+        descriptor_sets: DescriptorSets,
+        max_image_dimension: usize,
+        options: &RendererOptions,
     ) -> Arc<Mutex<Self>> {
+        let device = render_state.device.clone();
         let sampler = Self::create_sampler(&device);
 
         Arc::new(Mutex::new(Self {
             device,
-            queue,
-            queue_family_index,
-            descriptor_pool,
-            descriptor_set_layout,
+            queue: render_state.queue,
+            queue_family_index: render_state.queue_family_index,
+            queue_families,
+            descriptor_sets,
             sampler,
-            allocator,
+            allocator: render_state.allocator.clone(),
             texture_desc_sets: HashMap::new(),
             texture_images: HashMap::new(),
             texture_allocations: HashMap::new(),
             texture_image_views: HashMap::new(),
+            texture_sizes: HashMap::new(),
+            image_copies: options.keep_image_copies.then(HashMap::new),
+            released: false,
+            atlas: TextureAtlas::default(),
+            max_image_dimension,
+            tiles: HashMap::new(),
+            next_tile: 0,
+            upload_budget: options.upload_budget,
+            pending_uploads: VecDeque::new(),
         }))
     }
 
     fn update_texture(&mut self, texture_id: egui::TextureId, delta: egui::epaint::ImageDelta) {
+        profile_function!();
+        if let Some(tiled) = self.tiles.get(&texture_id) {
+            for (tile_id, tile_delta) in tiled.split(&delta) {
+                self.update_texture(tile_id, tile_delta);
+            }
+            return;
+        }
         // Extract pixel data from egui
         let data: Vec<u8> = match &delta.image {
             egui::ImageData::Color(image) => {
@@ -945,7 +2373,11 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
             };
             (texture_buffer, allocation)
         };
-        let ptr = staging_allocation.mapped_ptr().unwrap().as_ptr().cast::<u8>();
+        let ptr = staging_allocation
+            .mapped_ptr()
+            .unwrap()
+            .as_ptr()
+            .cast::<u8>();
         unsafe {
             ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
         }
@@ -966,7 +2398,8 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
                         .initial_layout(vk::ImageLayout::UNDEFINED)
                         .mip_levels(1)
                         .samples(vk::SampleCountFlags::TYPE_1)
-                        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                        .sharing_mode(self.queue_families.sharing_mode())
+                        .queue_family_indices(self.queue_families.indices())
                         .tiling(vk::ImageTiling::OPTIMAL)
                         .usage(
                             vk::ImageUsageFlags::SAMPLED
@@ -1272,17 +2705,7 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
             // Otherwise save the newly created texture
 
             // update dsc set
-            let dsc_set = {
-                unsafe {
-                    self.device
-                        .allocate_descriptor_sets(
-                            &vk::DescriptorSetAllocateInfo::default()
-                                .descriptor_pool(self.descriptor_pool)
-                                .set_layouts(&[self.descriptor_set_layout]),
-                        )
-                        .unwrap()[0]
-                }
-            };
+            let dsc_set = self.descriptor_sets.allocate();
             unsafe {
                 self.device.update_descriptor_sets(
                     std::slice::from_ref(
@@ -1318,7 +2741,11 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
                 .insert(texture_id, texture_allocation);
             self.texture_image_views
                 .insert(texture_id, texture_image_view);
-            self.texture_desc_sets.insert(texture_id, dsc_set);
+            self.texture_sizes
+                .insert(texture_id, [delta.image.width(), delta.image.height()]);
+            if let Some(old_dsc_set) = self.texture_desc_sets.insert(texture_id, dsc_set) {
+                self.descriptor_sets.free(old_dsc_set);
+            }
         }
         // cleanup
         unsafe {
@@ -1330,7 +2757,14 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
     }
 
     fn free_texture(&mut self, id: egui::TextureId) {
-        self.texture_desc_sets.remove_entry(&id);
+        if let Some(tiled) = self.tiles.remove(&id) {
+            for tile in tiled.tiles {
+                self.free_texture(tile.texture_id);
+            }
+        }
+        if let Some(desc_set) = self.texture_desc_sets.remove(&id) {
+            self.descriptor_sets.free(desc_set);
+        }
         if let Some((_, image)) = self.texture_images.remove_entry(&id) {
             unsafe {
                 self.device.destroy_image(image, None);
@@ -1344,15 +2778,319 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
         if let Some((_, allocation)) = self.texture_allocations.remove_entry(&id) {
             self.allocator.free(allocation).unwrap();
         }
+        self.texture_sizes.remove(&id);
+    }
+
+    fn update_textures(&mut self, textures_delta: egui::TexturesDelta) {
+        if std::mem::take(&mut self.released) {
+            for (id, image_delta) in self.image_copies.clone().unwrap_or_default() {
+                self.update_texture(id, image_delta);
+            }
+        }
+        for (id, image_delta) in textures_delta.set {
+            self.update_image_copy(id, &image_delta);
+            self.update_tiles(id, &image_delta);
+            self.pending_uploads.push_back((id, image_delta));
+        }
+        for id in textures_delta.free {
+            if let Some(image_copies) = &mut self.image_copies {
+                image_copies.remove(&id);
+            }
+            self.pending_uploads
+                .retain(|(pending_id, _)| *pending_id != id);
+            self.free_texture(id);
+        }
+    }
+
+    /// split a texture into tiles when its size is set larger than `maxImageDimension2D`.
+    ///
+    /// The tiles are laid out before the upload, so that the frame draws them with their uvs.
+    fn update_tiles(&mut self, id: egui::TextureId, image_delta: &egui::epaint::ImageDelta) {
+        if image_delta.pos.is_some() {
+            return;
+        }
+        let size = image_delta.image.size();
+        let tiled = size.iter().any(|&side| side > self.max_image_dimension);
+        match self.tiles.get(&id) {
+            Some(tiles) if tiled && tiles.size == size => return,
+            None if !tiled => return,
+            // the texture was uploaded untiled or with other tiles
+            _ => self.free_texture(id),
+        }
+        if tiled {
+            let tiles = TiledTexture::new(size, self.max_image_dimension, &mut self.next_tile);
+            self.tiles.insert(id, tiles);
+        }
+    }
+
+    /// upload the pending texture updates within the upload budget, prioritizing the textures `drawn` in the frame.
+    fn upload_pending(&mut self, drawn: &HashSet<egui::TextureId>) {
+        profile_function!();
+        let priority = |id: &egui::TextureId| {
+            if *id == egui::TextureId::default() {
+                UploadPriority::FontAtlas
+            } else if drawn.contains(id) {
+                UploadPriority::Visible
+            } else {
+                UploadPriority::Prefetch
+            }
+        };
+        // the stable sort keeps the updates of each texture in order.
+        let mut pending = Vec::from(std::mem::take(&mut self.pending_uploads));
+        pending.sort_by_key(|(id, _)| priority(id));
+
+        let start = Instant::now();
+        let mut uploaded_bytes = 0;
+        let mut deferred = HashSet::new();
+        for (id, image_delta) in pending {
+            let over_budget = self.upload_budget.is_some_and(|budget| {
+                uploaded_bytes > 0
+                    && (uploaded_bytes >= budget.bytes_per_frame
+                        || start.elapsed() >= budget.time_per_frame)
+            });
+            if deferred.contains(&id) || (over_budget && priority(&id) != UploadPriority::FontAtlas)
+            {
+                deferred.insert(id);
+                self.pending_uploads.push_back((id, image_delta));
+                continue;
+            }
+            uploaded_bytes += image_delta.image.width() * image_delta.image.height() * 4;
+            self.update_texture(id, image_delta);
+        }
+    }
+
+    fn update_atlas(&mut self, commands: Vec<RegistryCommand>) {
+        for command in commands {
+            match command {
+                RegistryCommand::RegisterAtlasImage { image, id } => {
+                    self.register_atlas_image(id, image);
+                }
+                RegistryCommand::UnregisterUserTexture { id } => self.unregister_atlas_image(id),
+                RegistryCommand::ReadBack {
+                    id,
+                    user_image,
+                    sender,
+                } => {
+                    let _ = sender.send(self.read_back(id, user_image.as_ref()));
+                }
+                RegistryCommand::RegisterUserTexture { .. }
+                | RegistryCommand::DescribeUserTexture { .. } => {}
+            }
+        }
+    }
+
+    fn register_atlas_image(&mut self, id: egui::TextureId, image: egui::ColorImage) {
+        let size = image.size;
+        let allocated = self.atlas.pages.iter_mut().find_map(|page| {
+            let pos = page.allocate(size)?;
+            Some((page.texture_id, page.size, pos))
+        });
+        let (page_id, page_size, pos) = if let Some(allocated) = allocated {
+            allocated
+        } else {
+            // images larger than a page get a page of their own.
+            let page_size = [
+                ATLAS_PAGE_SIZE.max(size[0] + ATLAS_PADDING),
+                ATLAS_PAGE_SIZE.max(size[1] + ATLAS_PADDING),
+            ];
+            let texture_id = egui::TextureId::Managed(u64::MAX - self.atlas.next_page);
+            self.atlas.next_page += 1;
+            let page_image = egui::ColorImage::from_rgba_unmultiplied(
+                page_size,
+                &vec![0; page_size[0] * page_size[1] * 4],
+            );
+            let delta = egui::epaint::ImageDelta::full(page_image, egui::TextureOptions::LINEAR);
+            self.update_image_copy(texture_id, &delta);
+            self.pending_uploads.push_back((texture_id, delta));
+
+            let mut page = AtlasPage {
+                texture_id,
+                size: page_size,
+                cursor: [0, 0],
+                row_height: 0,
+                image_count: 0,
+            };
+            let pos = page
+                .allocate(size)
+                .expect("The image does not fit in an empty atlas page.");
+            self.atlas.pages.push(page);
+            (texture_id, page_size, pos)
+        };
+
+        let delta = egui::epaint::ImageDelta::partial(pos, image, egui::TextureOptions::LINEAR);
+        self.update_image_copy(page_id, &delta);
+        self.pending_uploads.push_back((page_id, delta));
+
+        let uv_rect = egui::Rect::from_min_size(
+            egui::pos2(
+                pos[0] as f32 / page_size[0] as f32,
+                pos[1] as f32 / page_size[1] as f32,
+            ),
+            egui::vec2(
+                size[0] as f32 / page_size[0] as f32,
+                size[1] as f32 / page_size[1] as f32,
+            ),
+        );
+        self.atlas.images.insert(id, (page_id, uv_rect));
+    }
+
+    fn unregister_atlas_image(&mut self, id: egui::TextureId) {
+        let Some((page_id, _)) = self.atlas.images.remove(&id) else {
+            return;
+        };
+        let Some(index) = self
+            .atlas
+            .pages
+            .iter()
+            .position(|page| page.texture_id == page_id)
+        else {
+            return;
+        };
+        self.atlas.pages[index].image_count -= 1;
+        if self.atlas.pages[index].image_count == 0 {
+            self.atlas.pages.remove(index);
+            if let Some(image_copies) = &mut self.image_copies {
+                image_copies.remove(&page_id);
+            }
+            self.pending_uploads
+                .retain(|(pending_id, _)| *pending_id != page_id);
+            self.free_texture(page_id);
+        }
+    }
+
+    fn update_image_copy(&mut self, id: egui::TextureId, image_delta: &egui::epaint::ImageDelta) {
+        let Some(image_copies) = &mut self.image_copies else {
+            return;
+        };
+        let Some([x, y]) = image_delta.pos else {
+            image_copies.insert(id, image_delta.clone());
+            return;
+        };
+        let Some(image_copy) = image_copies.get_mut(&id) else {
+            return;
+        };
+        let (egui::ImageData::Color(image_copy), egui::ImageData::Color(delta_image)) =
+            (&mut image_copy.image, &image_delta.image);
+        let image_copy = Arc::make_mut(image_copy);
+        let copy_width = image_copy.width();
+        let delta_width = delta_image.width();
+        for row in 0..delta_image.height() {
+            let start = (y + row) * copy_width + x;
+            image_copy.pixels[start..start + delta_width]
+                .copy_from_slice(&delta_image.pixels[row * delta_width..(row + 1) * delta_width]);
+        }
+    }
+
+    /// copy the pixels of a texture into a color image.
+    fn read_back(
+        &self,
+        id: egui::TextureId,
+        user_image: Option<&UserTextureInfo>,
+    ) -> anyhow::Result<egui::ColorImage> {
+        profile_function!();
+        if let Some(info) = user_image {
+            return self.copy_to_cpu(info.image, info.format, [0, 0], info.size);
+        }
+        if let Some(&(page_id, uv_rect)) = self.atlas.images.get(&id) {
+            let page = self
+                .atlas
+                .pages
+                .iter()
+                .find(|page| page.texture_id == page_id)
+                .ok_or_else(|| anyhow::anyhow!("The atlas page of {id:?} is missing"))?;
+            let &image = self
+                .texture_images
+                .get(&page_id)
+                .ok_or_else(|| anyhow::anyhow!("The atlas page of {id:?} is not uploaded"))?;
+            let offset = [
+                (uv_rect.min.x * page.size[0] as f32).round() as u32,
+                (uv_rect.min.y * page.size[1] as f32).round() as u32,
+            ];
+            let size = self.atlas_image_size(id).unwrap_or_default();
+            return self.copy_to_cpu(image, vk::Format::R8G8B8A8_UNORM, offset, size);
+        }
+        if let Some(tiled) = self.tiles.get(&id) {
+            // the tiles overlap, the overlap is copied twice with the same texels.
+            let [width, height] = tiled.size;
+            let mut pixels = vec![egui::Color32::TRANSPARENT; width * height];
+            for tile in &tiled.tiles {
+                let &image = self
+                    .texture_images
+                    .get(&tile.texture_id)
+                    .ok_or_else(|| anyhow::anyhow!("A tile of {id:?} is not uploaded"))?;
+                let tile_size = [tile.size[0] as u32, tile.size[1] as u32];
+                let tile_image =
+                    self.copy_to_cpu(image, vk::Format::R8G8B8A8_UNORM, [0, 0], tile_size)?;
+                for (row, tile_row) in tile_image.pixels.chunks_exact(tile.size[0]).enumerate() {
+                    let start = (tile.pos[1] + row) * width + tile.pos[0];
+                    pixels[start..start + tile.size[0]].copy_from_slice(tile_row);
+                }
+            }
+            return Ok(egui::ColorImage::new([width, height], pixels));
+        }
+        let (Some(&image), Some(&size)) =
+            (self.texture_images.get(&id), self.texture_sizes.get(&id))
+        else {
+            anyhow::bail!("No texture {id:?} to read back");
+        };
+        let size = [size[0] as u32, size[1] as u32];
+        self.copy_to_cpu(image, vk::Format::R8G8B8A8_UNORM, [0, 0], size)
+    }
+
+    /// copy a rect of an image in `SHADER_READ_ONLY_OPTIMAL` layout through a staging buffer,
+    /// waiting for the copy on the GPU.
+    fn copy_to_cpu(
+        &self,
+        image: vk::Image,
+        format: vk::Format,
+        offset: [u32; 2],
+        size: [u32; 2],
+    ) -> anyhow::Result<egui::ColorImage> {
+        copy_to_cpu(
+            &self.device,
+            self.queue,
+            self.queue_family_index,
+            &self.allocator,
+            image,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            format,
+            offset,
+            size,
+        )
     }
 
-    fn update_textures(&mut self, textures_delta: egui::TexturesDelta) {
-        for (id, image_delta) in textures_delta.set {
-            self.update_texture(id, image_delta);
-        }
-        for id in textures_delta.free {
+    /// size of the atlas image `id` in its atlas page.
+    fn atlas_image_size(&self, id: egui::TextureId) -> Option<[u32; 2]> {
+        let (page_id, uv_rect) = self.atlas.images.get(&id)?;
+        let page = self
+            .atlas
+            .pages
+            .iter()
+            .find(|page| page.texture_id == *page_id)?;
+        Some([
+            (uv_rect.width() * page.size[0] as f32).round() as u32,
+            (uv_rect.height() * page.size[1] as f32).round() as u32,
+        ])
+    }
+
+    /// destroy the textures. They are uploaded again from the copies on the next update.
+    // memory of the uploaded textures in bytes.
+    fn memory_size(&self) -> u64 {
+        self.texture_allocations
+            .values()
+            .map(Allocation::size)
+            .sum()
+    }
+
+    fn release(&mut self) {
+        for id in self.texture_images.keys().copied().collect::<Vec<_>>() {
             self.free_texture(id);
         }
+        self.texture_desc_sets.clear();
+        self.descriptor_sets.reset();
+        // the copies hold the pending updates, which are uploaded with them.
+        self.pending_uploads.clear();
+        self.released = true;
     }
 
     fn destroy(&mut self, device: &Device, allocator: &A) {
@@ -1376,6 +3114,9 @@ impl<A: Allocator + 'static> ManagedTextures<A> {
             }
             self.device.destroy_sampler(self.sampler, None);
         }
+        self.texture_desc_sets.clear();
+        self.tiles.clear();
+        self.descriptor_sets.destroy();
     }
 }
 
@@ -1398,7 +3139,7 @@ impl ImageRegistry {
         )
     }
 
-    #[must_use] 
+    #[must_use]
     pub fn register_user_texture(
         &self,
         image_view: vk::ImageView,
@@ -1415,11 +3156,84 @@ impl ImageRegistry {
         id
     }
 
+    /// pack a small image into an atlas page shared with other images, instead of
+    /// creating an image and a descriptor set for it.
+    ///
+    /// The returned id is drawn like the ids of `register_user_texture`, the renderer maps
+    /// the uvs to the rect of the image in the atlas page. Unregister it with `unregister_user_texture`.
+    #[must_use]
+    pub fn register_atlas_image(&self, image: egui::ColorImage) -> egui::TextureId {
+        let id = egui::TextureId::User(self.counter.fetch_add(1, Ordering::SeqCst));
+        self.sender
+            .send(RegistryCommand::RegisterAtlasImage { image, id })
+            .expect("Failed to send register atlas image command.");
+        id
+    }
+
     pub fn unregister_user_texture(&self, id: egui::TextureId) {
         let _ = self
             .sender
             .send(RegistryCommand::UnregisterUserTexture { id });
     }
+
+    /// describe a registered user texture for [`crate::texture_debugger_ui`].
+    ///
+    /// Atlas images are described by the renderer, a label can be set here.
+    pub fn describe_user_texture(&self, id: egui::TextureId, info: UserTextureInfo) {
+        let _ = self
+            .sender
+            .send(RegistryCommand::DescribeUserTexture { id, info });
+    }
+
+    /// download the pixels of a texture to the CPU, e.g. to export a canvas painted by a paint callback.
+    ///
+    /// The copy is made with the texture updates of the next frame. Egui textures, atlas images and user
+    /// textures described with their [`UserTextureInfo::image`] in an 8-bit RGBA or BGRA format can be
+    /// read back.
+    pub fn read_back(&self, id: egui::TextureId) -> TextureReadback {
+        let (sender, receiver) = mpsc::channel();
+        let _ = self.sender.send(RegistryCommand::ReadBack {
+            id,
+            user_image: None,
+            sender,
+        });
+        TextureReadback {
+            receiver,
+            result: None,
+        }
+    }
+}
+
+/// pixels of a texture on their way to the CPU, see [`ImageRegistry::read_back`].
+#[derive(Debug)]
+pub struct TextureReadback {
+    receiver: Receiver<anyhow::Result<egui::ColorImage>>,
+    result: Option<anyhow::Result<egui::ColorImage>>,
+}
+impl TextureReadback {
+    /// the pixels once they are copied, `None` before.
+    pub fn poll(&mut self) -> Option<&anyhow::Result<egui::ColorImage>> {
+        if self.result.is_none() {
+            self.result = match self.receiver.try_recv() {
+                Ok(result) => Some(result),
+                Err(mpsc::TryRecvError::Empty) => None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    Some(Err(anyhow::anyhow!("The renderer was destroyed")))
+                }
+            };
+        }
+        self.result.as_ref()
+    }
+
+    /// block until the pixels are copied. Waiting on the thread that paints the frames never returns.
+    pub fn wait(mut self) -> anyhow::Result<egui::ColorImage> {
+        if let Some(result) = self.result.take() {
+            return result;
+        }
+        self.receiver
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("The renderer was destroyed")))
+    }
 }
 impl Debug for ImageRegistry {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -1433,57 +3247,78 @@ pub(crate) enum RegistryCommand {
         sampler: vk::Sampler,
         id: egui::TextureId,
     },
+    RegisterAtlasImage {
+        image: egui::ColorImage,
+        id: egui::TextureId,
+    },
     UnregisterUserTexture {
         id: egui::TextureId,
     },
+    DescribeUserTexture {
+        id: egui::TextureId,
+        info: UserTextureInfo,
+    },
+    ReadBack {
+        id: egui::TextureId,
+        user_image: Option<UserTextureInfo>,
+        sender: Sender<anyhow::Result<egui::ColorImage>>,
+    },
+}
+
+/// description of a user texture, see [`ImageRegistry::describe_user_texture`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserTextureInfo {
+    pub label: Option<String>,
+    /// size in pixels.
+    pub size: [u32; 2],
+    pub format: vk::Format,
+    /// image of the image view, in `SHADER_READ_ONLY_OPTIMAL` layout between frames, to read the texture
+    /// back with [`ImageRegistry::read_back`]. Null if it can not be read back.
+    pub image: vk::Image,
+    /// memory of the image in bytes.
+    pub memory: u64,
 }
 
 struct UserTextures {
     device: Device,
-    descriptor_pool: vk::DescriptorPool,
-    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_sets: DescriptorSets,
     texture_desc_sets: HashMap<u64, vk::DescriptorSet>,
+    last_used: HashMap<u64, Instant>,
+    // egui pass of the viewport the texture was last drawn in.
+    last_drawn: HashMap<u64, u64>,
+    infos: HashMap<u64, UserTextureInfo>,
     receiver: ImageRegistryReceiver,
 }
 impl UserTextures {
     fn new(
         device: Device,
-        descriptor_pool: vk::DescriptorPool,
-        descriptor_set_layout: vk::DescriptorSetLayout,
+        descriptor_sets: DescriptorSets,
         receiver: ImageRegistryReceiver,
     ) -> Arc<Mutex<Self>> {
         let texture_desc_sets = HashMap::new();
 
         Arc::new(Mutex::new(Self {
             device,
-            descriptor_pool,
-            descriptor_set_layout,
+            descriptor_sets,
             texture_desc_sets,
+            last_used: HashMap::new(),
+            last_drawn: HashMap::new(),
+            infos: HashMap::new(),
             receiver,
         }))
     }
 
     fn register_user_texture(&mut self, id: u64, image_view: vk::ImageView, sampler: vk::Sampler) {
-        let dsc_set = {
-            unsafe {
-                self.texture_desc_sets.insert(
-                    id,
-                    self.device
-                        .allocate_descriptor_sets(
-                            &vk::DescriptorSetAllocateInfo::default()
-                                .descriptor_pool(self.descriptor_pool)
-                                .set_layouts(&[self.descriptor_set_layout]),
-                        )
-                        .expect("Failed to allocate descriptor set")[0],
-                );
-            }
-            self.texture_desc_sets.get(&id).unwrap()
-        };
+        let dsc_set = self.descriptor_sets.allocate();
+        if let Some(old_dsc_set) = self.texture_desc_sets.insert(id, dsc_set) {
+            self.descriptor_sets.free(old_dsc_set);
+        }
+        self.last_used.insert(id, Instant::now());
         unsafe {
             self.device.update_descriptor_sets(
                 std::slice::from_ref(
                     &vk::WriteDescriptorSet::default()
-                        .dst_set(*dsc_set)
+                        .dst_set(dsc_set)
                         .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
                         .dst_array_element(0_u32)
                         .dst_binding(0_u32)
@@ -1500,19 +3335,41 @@ impl UserTextures {
     }
 
     fn unregister_user_texture(&mut self, id: u64) {
+        self.forget(id);
         if let Some(desc_set) = self.texture_desc_sets.remove(&id) {
-            unsafe {
-                self.device
-                    .device_wait_idle()
-                    .expect("Failed to wait device idle");
-                self.device
-                    .free_descriptor_sets(self.descriptor_pool, &[desc_set])
-                    .expect("Failed to free descriptor set.");
-            }
+            self.descriptor_sets.free(desc_set);
         }
     }
 
-    fn update_textures(&mut self) {
+    // forget the usage and info of an unregistered user texture or atlas image.
+    fn forget(&mut self, id: u64) {
+        self.last_used.remove(&id);
+        self.last_drawn.remove(&id);
+        self.infos.remove(&id);
+    }
+
+    /// textures not drawn for `unused_for`, least recently used first.
+    fn unused_textures(&self, unused_for: Duration) -> Vec<egui::TextureId> {
+        let mut unused = self
+            .last_used
+            .iter()
+            .filter(|(_, last_used)| last_used.elapsed() >= unused_for)
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, &last_used)| last_used);
+        unused
+            .into_iter()
+            .map(|(&id, _)| egui::TextureId::User(id))
+            .collect()
+    }
+
+    fn destroy(&mut self) {
+        self.texture_desc_sets.clear();
+        self.descriptor_sets.destroy();
+    }
+
+    /// apply the registry commands. The commands of atlas images are returned for `ManagedTextures`.
+    fn update_textures(&mut self) -> Vec<RegistryCommand> {
+        let mut atlas_commands = vec![];
         for command in self.receiver.try_iter().collect::<Vec<_>>() {
             match command {
                 RegistryCommand::RegisterUserTexture {
@@ -1527,47 +3384,99 @@ impl UserTextures {
                         self.register_user_texture(id, image_view, sampler);
                     }
                 },
+                command @ RegistryCommand::RegisterAtlasImage { .. } => {
+                    atlas_commands.push(command);
+                }
                 RegistryCommand::UnregisterUserTexture { id } => match id {
                     egui::TextureId::Managed(_) => {
                         panic!("This texture id is not for user texture: {id:?}")
                     }
-                    egui::TextureId::User(id) => {
-                        self.unregister_user_texture(id);
+                    egui::TextureId::User(raw_id) => {
+                        if self.texture_desc_sets.contains_key(&raw_id) {
+                            self.unregister_user_texture(raw_id);
+                        } else {
+                            self.forget(raw_id);
+                            atlas_commands.push(RegistryCommand::UnregisterUserTexture { id });
+                        }
                     }
                 },
+                RegistryCommand::DescribeUserTexture { id, info } => {
+                    if let egui::TextureId::User(id) = id {
+                        self.infos.insert(id, info);
+                    }
+                }
+                RegistryCommand::ReadBack { id, sender, .. } => {
+                    // the image of a user texture is only known from its description.
+                    let user_image = match id {
+                        egui::TextureId::User(raw_id) => self
+                            .infos
+                            .get(&raw_id)
+                            .filter(|info| info.image != vk::Image::null())
+                            .cloned(),
+                        egui::TextureId::Managed(_) => None,
+                    };
+                    atlas_commands.push(RegistryCommand::ReadBack {
+                        id,
+                        user_image,
+                        sender,
+                    });
+                }
             }
         }
+        atlas_commands
+    }
+}
+
+/// options of the [`Renderer`], from [`crate::RunOption`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RendererOptions {
+    // keep a copy of the managed textures to upload them again after the resources are released.
+    pub(crate) keep_image_copies: bool,
+    pub(crate) descriptor_pool_size: u32,
+    pub(crate) render_scale: f32,
+    pub(crate) upload_budget: Option<UploadBudget>,
+    pub(crate) allocator_debug: AllocatorDebugOptions,
+}
+impl Default for RendererOptions {
+    fn default() -> Self {
+        Self {
+            keep_image_copies: false,
+            descriptor_pool_size: DEFAULT_DESCRIPTOR_POOL_SIZE,
+            render_scale: 1.0,
+            upload_budget: None,
+            allocator_debug: AllocatorDebugOptions::default(),
+        }
     }
 }
 
+/// shapes and texture changes of an egui frame, to draw with [`Renderer::create_egui_cmd`].
+pub(crate) struct EguiFrame {
+    pub(crate) shapes: Vec<egui::epaint::ClippedShape>,
+    pub(crate) pixels_per_point: f32,
+    pub(crate) textures_delta: egui::TexturesDelta,
+}
+
 pub(crate) struct Renderer<A: Allocator + 'static> {
     device: Device,
-    descriptor_pool: vk::DescriptorPool,
     descriptor_set_layout: vk::DescriptorSetLayout,
+    pipelines: Pipelines,
     allocator: A,
+    queue_families: QueueFamilies,
     viewport_renderers: HashMap<egui::ViewportId, ViewportRenderer<A>>,
+    render_scale: f32,
+    // `maxImageDimension2D` of the device.
+    max_image_dimension: u32,
 
     managed_textures: Arc<Mutex<ManagedTextures<A>>>,
     user_textures: Arc<Mutex<UserTextures>>,
+
+    tessellations: HashMap<egui::ViewportId, CachedTessellation>,
+    next_generation: u64,
+    frame_stats: FrameStatistics,
+    inspector: Inspector,
+    allocator_debug: AllocatorDebugOptions,
 }
 impl<A: Allocator + 'static> Renderer<A> {
-    fn create_descriptor_pool(device: &Device) -> vk::DescriptorPool {
-        unsafe {
-            device.create_descriptor_pool(
-                &vk::DescriptorPoolCreateInfo::default()
-                    .flags(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
-                    .max_sets(1024)
-                    .pool_sizes(std::slice::from_ref(
-                        &vk::DescriptorPoolSize::default()
-                            .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-                            .descriptor_count(1024),
-                    )),
-                None,
-            )
-        }
-        .expect("Failed to create descriptor pool.")
-    }
-
     fn create_descriptor_set_layout(device: &Device) -> vk::DescriptorSetLayout {
         unsafe {
             device.create_descriptor_set_layout(
@@ -1585,63 +3494,294 @@ impl<A: Allocator + 'static> Renderer<A> {
     }
 
     pub(crate) fn new(
-        device: Device,
-        queue: vk::Queue,
-        queue_family_index: u32,
-        allocator: A,
+        render_state: &AshRenderState<A>,
+        limits: &vk::PhysicalDeviceLimits,
         receiver: Receiver<RegistryCommand>,
+        options: RendererOptions,
+        frame_stats: FrameStatistics,
+        inspector: Inspector,
     ) -> Arc<Mutex<Self>> {
-        let descriptor_pool = Self::create_descriptor_pool(&device);
+        let device = render_state.device.clone();
+        let allocator = render_state.allocator.clone();
         let descriptor_set_layout = Self::create_descriptor_set_layout(&device);
+        let queue_families = QueueFamilies::new(
+            render_state.queue_family_index,
+            &render_state.concurrent_queue_family_indices,
+        );
+        frame_stats.set_allocator(allocator.clone());
+        let pipelines = Pipelines::new(
+            device.clone(),
+            descriptor_set_layout,
+            limits.framebuffer_color_sample_counts,
+        );
+        let max_pool_size = max_descriptor_pool_size(limits);
+        // the surface format the presenters prefer, ready by the time the first window opens.
+        pipelines.prepare(PipelineKey {
+            format: vk::Format::B8G8R8A8_UNORM,
+            samples: vk::SampleCountFlags::TYPE_1,
+        });
         Arc::new(Mutex::new(Self {
             device: device.clone(),
-            descriptor_pool,
             descriptor_set_layout,
-            allocator: allocator.clone(),
+            pipelines,
+            allocator,
+            queue_families: queue_families.clone(),
             viewport_renderers: HashMap::new(),
+            render_scale: options.render_scale,
+            max_image_dimension: limits.max_image_dimension2_d,
             managed_textures: ManagedTextures::new(
-                device.clone(),
-                queue,
-                queue_family_index,
-                descriptor_pool,
-                descriptor_set_layout,
-                allocator,
+                render_state,
+                queue_families,
+                DescriptorSets::new(
+                    device.clone(),
+                    descriptor_set_layout,
+                    options.descriptor_pool_size,
+                    max_pool_size,
+                ),
+                limits.max_image_dimension2_d as usize,
+                &options,
             ),
             user_textures: UserTextures::new(
-                device,
-                descriptor_pool,
-                descriptor_set_layout,
+                device.clone(),
+                DescriptorSets::new(
+                    device,
+                    descriptor_set_layout,
+                    options.descriptor_pool_size,
+                    max_pool_size,
+                ),
                 receiver,
             ),
+            tessellations: HashMap::new(),
+            next_generation: 0,
+            frame_stats,
+            inspector,
+            allocator_debug: options.allocator_debug,
         }))
     }
 
+    // reuse the primitives of the last frame of the viewport if its shapes did not change.
+    fn tessellate(
+        &mut self,
+        viewport_id: egui::ViewportId,
+        context: &egui::Context,
+        shapes: Vec<egui::epaint::ClippedShape>,
+        pixels_per_point: f32,
+        textures_delta: &egui::TexturesDelta,
+    ) -> Tessellation {
+        profile_function!();
+        // the font atlas was rebuilt, the uvs of the cached glyphs may have moved. The tiles of
+        // textures larger than `maxImageDimension2D` move when they are resized.
+        let max_image_dimension = self.max_image_dimension as usize;
+        let font_changed = textures_delta.set.iter().any(|(id, delta)| {
+            *id == egui::TextureId::default()
+                || (delta.pos.is_none()
+                    && delta
+                        .image
+                        .size()
+                        .iter()
+                        .any(|&side| side > max_image_dimension))
+        });
+        let pixel_snapping = pixel_snapping::pixel_snapping(context, viewport_id);
+        if let Some(cached) = self.tessellations.get(&viewport_id) {
+            if !font_changed
+                && cached.pixels_per_point == pixels_per_point
+                && cached.pixel_snapping == pixel_snapping
+                && cached.shapes == shapes
+            {
+                self.frame_stats.update(viewport_id, |stats| {
+                    stats.tessellation_time = Duration::ZERO
+                });
+                return Tessellation {
+                    clipped_primitives: cached.clipped_primitives.clone(),
+                    generation: cached.generation,
+                    reused: true,
+                };
+            }
+        }
+
+        let start = Instant::now();
+        let mut clipped_primitives = context.tessellate(shapes.clone(), pixels_per_point);
+        if pixel_snapping {
+            // the grid of the render target, which the render scale resizes.
+            pixel_snapping::snap(
+                &mut clipped_primitives,
+                pixels_per_point * self.render_scale,
+            );
+        }
+        let clipped_primitives = Arc::new(clipped_primitives);
+        let tessellation_time = start.elapsed();
+        self.frame_stats.update(viewport_id, |stats| {
+            stats.tessellation_time = tessellation_time
+        });
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        self.tessellations.insert(
+            viewport_id,
+            CachedTessellation {
+                shapes,
+                pixels_per_point,
+                pixel_snapping,
+                clipped_primitives: clipped_primitives.clone(),
+                generation,
+            },
+        );
+        Tessellation {
+            clipped_primitives,
+            generation,
+            reused: false,
+        }
+    }
+
     pub(crate) fn create_egui_cmd(
         &mut self,
         viewport_id: egui::ViewportId,
-        clipped_primitives: Vec<egui::ClippedPrimitive>,
-        textures_delta: egui::TexturesDelta,
+        context: &egui::Context,
+        frame: EguiFrame,
         scale_factor: f32,
         physical_size: winit::dpi::PhysicalSize<u32>,
     ) -> EguiCommand {
+        let EguiFrame {
+            shapes,
+            pixels_per_point,
+            textures_delta,
+        } = frame;
+        let tessellation = self.tessellate(
+            viewport_id,
+            context,
+            shapes,
+            pixels_per_point,
+            &textures_delta,
+        );
+        if self.inspector.is_shown() {
+            self.update_inspector();
+        }
         let viewport_renderer = self
             .viewport_renderers
             .entry(viewport_id)
-            .or_insert_with(|| {
-                ViewportRenderer::new(
-                    self.device.clone(),
-                    self.descriptor_set_layout,
-                    self.allocator.clone(),
-                )
+            .or_insert_with(|| ViewportRenderer {
+                viewport_id,
+                device: self.device.clone(),
+                descriptor_set_layout: self.descriptor_set_layout,
+                pipelines: self.pipelines.clone(),
+                allocator: self.allocator.clone(),
+                queue_families: self.queue_families.clone(),
+                render_scale: self.render_scale,
+                max_image_dimension: self.max_image_dimension,
+                managed_textures: self.managed_textures.clone(),
+                user_textures: self.user_textures.clone(),
+                frame_stats: self.frame_stats.clone(),
+                state: Arc::new(Mutex::new(None)),
+                last_post_process: Arc::new(Mutex::new(None)),
             });
-        viewport_renderer.create_egui_cmd(
-            clipped_primitives,
+        let mut egui_cmd = viewport_renderer.create_egui_cmd(
+            tessellation,
             textures_delta,
-            self.managed_textures.clone(),
-            self.user_textures.clone(),
+            context.clone(),
             scale_factor,
             physical_size,
-        )
+            post_process::post_process(context, viewport_id),
+        );
+        egui_cmd.clear_color = clear_color::clear_color(context, viewport_id);
+        egui_cmd
+    }
+
+    fn update_inspector(&self) {
+        let mut managed_textures = self.managed_textures.lock().unwrap();
+        let mut user_textures = self.user_textures.lock().unwrap();
+        let buffer_memory = self
+            .viewport_renderers
+            .values()
+            .filter_map(|viewport_renderer| {
+                let state = viewport_renderer.state.lock().unwrap();
+                let state = state.as_ref()?;
+                let memory = state
+                    .vertex_buffer_allocations
+                    .iter()
+                    .chain(&state.index_buffer_allocations)
+                    .map(Allocation::size)
+                    .sum::<u64>();
+                Some(memory)
+            })
+            .sum();
+        self.inspector.set_textures(TextureInfo {
+            managed_textures: managed_textures.texture_images.len(),
+            user_textures: user_textures.texture_desc_sets.len(),
+            atlas_images: managed_textures.atlas.images.len(),
+            atlas_pages: managed_textures.atlas.pages.len(),
+            texture_memory: managed_textures.memory_size(),
+            buffer_memory,
+            allocator: self.allocator.report(),
+            budget: self.allocator.budget(),
+        });
+
+        for id in self.inspector.take_evicted() {
+            if let egui::TextureId::User(raw_id) = id {
+                if user_textures.texture_desc_sets.contains_key(&raw_id) {
+                    user_textures.unregister_user_texture(raw_id);
+                } else {
+                    user_textures.forget(raw_id);
+                    managed_textures.unregister_atlas_image(id);
+                }
+            }
+        }
+        if self.inspector.is_texture_debugger_shown() {
+            let user_ids = user_textures.texture_desc_sets.keys().copied();
+            let atlas_ids = managed_textures
+                .atlas
+                .images
+                .keys()
+                .filter_map(|id| match id {
+                    egui::TextureId::User(id) => Some(*id),
+                    egui::TextureId::Managed(_) => None,
+                });
+            let mut registered = user_ids
+                .map(|id| (id, false))
+                .chain(atlas_ids.map(|id| (id, true)))
+                .map(|(id, atlas)| {
+                    let texture_id = egui::TextureId::User(id);
+                    let mut info = user_textures.infos.get(&id).cloned();
+                    if let Some(size) = managed_textures.atlas_image_size(texture_id) {
+                        let info = info.get_or_insert_with(UserTextureInfo::default);
+                        info.size = size;
+                        info.format = vk::Format::R8G8B8A8_UNORM;
+                        info.memory = u64::from(size[0]) * u64::from(size[1]) * 4;
+                    }
+                    RegisteredTexture {
+                        id: texture_id,
+                        atlas,
+                        info,
+                        last_drawn: user_textures.last_drawn.get(&id).copied(),
+                        last_used: user_textures.last_used.get(&id).copied(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            registered.sort_by_key(|texture| texture.id);
+            self.inspector.set_registered_textures(registered);
+        }
+    }
+
+    /// user textures not drawn for `unused_for`, least recently used first.
+    pub(crate) fn unused_user_textures(&self, unused_for: Duration) -> Vec<egui::TextureId> {
+        self.user_textures
+            .lock()
+            .unwrap()
+            .unused_textures(unused_for)
+    }
+
+    /// destroy the render targets of all viewports and the egui textures.
+    ///
+    /// They are created again on the next frame. Requires `keep_image_copies`.
+    pub(crate) fn release_resources(&mut self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle");
+        };
+
+        for (_, mut viewport_renderer) in self.viewport_renderers.drain() {
+            viewport_renderer.destroy();
+        }
+        self.managed_textures.lock().unwrap().release();
     }
 
     pub(crate) fn destroy_viewports(&mut self, active_viewport_ids: &egui::ViewportIdSet) {
@@ -1654,12 +3794,44 @@ impl<A: Allocator + 'static> Renderer<A> {
             .collect::<Vec<_>>();
 
         for id in remove_viewports {
+            self.tessellations.remove(&id);
+            self.frame_stats.remove(id);
             if let Some(mut viewport_renderer) = self.viewport_renderers.remove(&id) {
                 viewport_renderer.destroy();
             }
         }
     }
 
+    pub(crate) fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle");
+        };
+    }
+
+    // the allocations of the app are still alive, only the ones named by egui-ash are leaks.
+    fn log_leaks(&self) {
+        let Some(report) = self.allocator.report() else {
+            return;
+        };
+        let leaks = report
+            .allocations
+            .iter()
+            .filter(|allocation| allocation.name.starts_with("egui-ash "))
+            .collect::<Vec<_>>();
+        if leaks.is_empty() {
+            return;
+        }
+        warn!(
+            "{} allocations of egui-ash are still alive after destroying the integration",
+            leaks.len()
+        );
+        for leak in leaks {
+            warn!("Leaked {} of {} bytes", leak.name, leak.size);
+        }
+    }
+
     pub(crate) fn destroy_root(&mut self) {
         // wait device idle
         unsafe {
@@ -1672,18 +3844,215 @@ impl<A: Allocator + 'static> Renderer<A> {
             .lock()
             .unwrap()
             .destroy(&self.device, &self.allocator);
+        self.user_textures.lock().unwrap().destroy();
         for (_, mut viewport_renderer) in self.viewport_renderers.drain() {
             viewport_renderer.destroy();
         }
+        self.tessellations.clear();
+        if self.allocator_debug.log_leaks_on_shutdown {
+            self.log_leaks();
+        }
+        self.frame_stats.clear_allocator();
+        self.pipelines.destroy();
         unsafe {
             self.device
                 .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
-            self.device
-                .destroy_descriptor_pool(self.descriptor_pool, None);
         }
     }
 }
 
+/// copy a rect of an image in `layout` through a staging buffer, waiting for the copy on the GPU.
+///
+/// The image is back in `layout` afterwards.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn copy_to_cpu<A: Allocator>(
+    device: &Device,
+    queue: vk::Queue,
+    queue_family_index: u32,
+    allocator: &A,
+    image: vk::Image,
+    layout: vk::ImageLayout,
+    format: vk::Format,
+    offset: [u32; 2],
+    size: [u32; 2],
+) -> anyhow::Result<egui::ColorImage> {
+    let bgra = match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+        _ => anyhow::bail!("Reading back {format:?} images is not supported"),
+    };
+    let [width, height] = size;
+    if width == 0 || height == 0 {
+        return Ok(egui::ColorImage::new([0, 0], vec![]));
+    }
+    // the rows are tightly packed, 4 bytes per texel.
+    let buffer_size = u64::from(width) * u64::from(height) * 4;
+    let subresource_range = vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1,
+    };
+
+    unsafe {
+        let staging_buffer = device.create_buffer(
+            &vk::BufferCreateInfo::default()
+                .size(buffer_size)
+                .usage(vk::BufferUsageFlags::TRANSFER_DST),
+            None,
+        )?;
+        let requirements = device.get_buffer_memory_requirements(staging_buffer);
+        let staging_allocation = match allocator.allocate(A::AllocationCreateInfo::new(
+            Some("egui-ash readback buffer"),
+            requirements,
+            MemoryLocation::gpu_to_cpu(),
+            true,
+        )) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                device.destroy_buffer(staging_buffer, None);
+                return Err(err);
+            }
+        };
+        device.bind_buffer_memory(
+            staging_buffer,
+            staging_allocation.memory(),
+            staging_allocation.offset(),
+        )?;
+        let cmd_pool = device.create_command_pool(
+            &vk::CommandPoolCreateInfo::default().queue_family_index(queue_family_index),
+            None,
+        )?;
+        let cmd = device.allocate_command_buffers(
+            &vk::CommandBufferAllocateInfo::default()
+                .command_buffer_count(1u32)
+                .command_pool(cmd_pool)
+                .level(vk::CommandBufferLevel::PRIMARY),
+        )?[0];
+        let cmd_fence = device.create_fence(&vk::FenceCreateInfo::default(), None)?;
+
+        device.begin_command_buffer(
+            cmd,
+            &vk::CommandBufferBeginInfo::default()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+        )?;
+        // after the writes of the frames submitted before, e.g. a paint callback drawing into it.
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::MEMORY_WRITE,
+            vk::AccessFlags::TRANSFER_READ,
+            layout,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+            vk::PipelineStageFlags::TRANSFER,
+            subresource_range,
+        );
+        device.cmd_copy_image_to_buffer(
+            cmd,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            staging_buffer,
+            std::slice::from_ref(
+                &vk::BufferImageCopy::default()
+                    .buffer_offset(0)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                        mip_level: 0,
+                    })
+                    .image_offset(vk::Offset3D {
+                        x: offset[0] as i32,
+                        y: offset[1] as i32,
+                        z: 0,
+                    })
+                    .image_extent(vk::Extent3D {
+                        width,
+                        height,
+                        depth: 1,
+                    }),
+            ),
+        );
+        utils::insert_image_memory_barrier(
+            device,
+            cmd,
+            image,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::QUEUE_FAMILY_IGNORED,
+            vk::AccessFlags::TRANSFER_READ,
+            vk::AccessFlags::SHADER_READ,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            layout,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            subresource_range,
+        );
+        device.cmd_pipeline_barrier(
+            cmd,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[],
+            std::slice::from_ref(
+                &vk::BufferMemoryBarrier::default()
+                    .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                    .dst_access_mask(vk::AccessFlags::HOST_READ)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .buffer(staging_buffer)
+                    .size(vk::WHOLE_SIZE),
+            ),
+            &[],
+        );
+        device.end_command_buffer(cmd)?;
+        let cmd_buffs = [cmd];
+        let submitted = device
+            .queue_submit(
+                queue,
+                std::slice::from_ref(&vk::SubmitInfo::default().command_buffers(&cmd_buffs)),
+                cmd_fence,
+            )
+            .and_then(|()| device.wait_for_fences(&[cmd_fence], true, u64::MAX));
+
+        let result = submitted.map_err(anyhow::Error::from).and_then(|()| {
+            let ptr = staging_allocation
+                .mapped_ptr()
+                .ok_or_else(|| anyhow::anyhow!("The readback buffer is not mapped"))?;
+            let bytes = std::slice::from_raw_parts(ptr.as_ptr().cast::<u8>(), buffer_size as usize);
+            let pixels = bytes
+                .as_chunks::<4>()
+                .0
+                .iter()
+                .map(|texel| {
+                    let [r, g, b, a] = if bgra {
+                        [texel[2], texel[1], texel[0], texel[3]]
+                    } else {
+                        [texel[0], texel[1], texel[2], texel[3]]
+                    };
+                    egui::Color32::from_rgba_premultiplied(r, g, b, a)
+                })
+                .collect();
+            Ok(egui::ColorImage::new(
+                [width as usize, height as usize],
+                pixels,
+            ))
+        });
+
+        device.destroy_fence(cmd_fence, None);
+        device.destroy_command_pool(cmd_pool, None);
+        device.destroy_buffer(staging_buffer, None);
+        allocator.free(staging_allocation)?;
+        result
+    }
+}
+
 /// struct to pass to `EguiCommand::update_swapchain` method.
 pub struct SwapchainUpdateInfo {
     pub width: u32,
@@ -1698,8 +4067,18 @@ pub struct SwapchainUpdateInfo {
 /// You also must call `update_swapchain` method when first time to record commands.
 pub struct EguiCommand {
     swapchain_updater: Option<Box<dyn FnOnce(SwapchainUpdateInfo) + Send>>,
-    recorder: Box<dyn FnOnce(vk::CommandBuffer, usize) + Send>,
+    texture_updater: Option<Box<dyn FnOnce() + Send>>,
+    uploader: Box<dyn FnMut(usize) + Send>,
+    recorder: Box<dyn FnMut(vk::CommandBuffer, usize, Range<usize>) + Send>,
     swapchain_recreate_required: bool,
+    unchanged: bool,
+    // index of the primitive splitting the background and foreground layers.
+    layer_split: Option<usize>,
+    primitive_count: usize,
+    clear_color: [f32; 4],
+    // the user textures sampled by the frame, known once its textures are updated.
+    // None if the command records nothing.
+    texture_usages: Option<Arc<Mutex<Vec<ResourceUsage>>>>,
 }
 impl EguiCommand {
     /// You must call this method once when first time to record commands
@@ -1710,13 +4089,88 @@ impl EguiCommand {
         ))(info);
     }
 
+    /// apply the texture updates of the frame, which submit to the queue.
+    ///
+    /// Called by `record` if not called before. Call it in the order the frames were created.
+    pub(crate) fn update_textures(&mut self) {
+        if let Some(texture_updater) = self.texture_updater.take() {
+            texture_updater();
+        }
+    }
+
+    /// write the vertices and indices of the frame to the buffers of the swapchain image.
+    ///
+    /// Called by `record_into` and `record_layer` if not called before. The upload happens once
+    /// per swapchain image, recording the frame again only records the draws.
+    pub fn upload(&mut self, swapchain_index: usize) {
+        self.update_textures();
+        (self.uploader)(swapchain_index);
+    }
+
     /// record commands to command buffer.
-    pub fn record(self, cmd: vk::CommandBuffer, swapchain_index: usize) {
-        (self.recorder)(cmd, swapchain_index);
+    pub fn record(mut self, cmd: vk::CommandBuffer, swapchain_index: usize) {
+        self.record_into(cmd, swapchain_index);
+    }
+
+    /// record commands to command buffer, without consuming the command so that the frame can be
+    /// recorded again, e.g. into the command buffers of several submissions.
+    ///
+    /// The command buffers must not be executing at the same time as the buffers of the swapchain
+    /// image are rewritten, which happens on the next frame of the viewport.
+    pub fn record_into(&mut self, cmd: vk::CommandBuffer, swapchain_index: usize) {
+        self.upload(swapchain_index);
+        (self.recorder)(cmd, swapchain_index, 0..self.primitive_count);
+    }
+
+    /// record the commands of one layer of the frame, e.g. to render a 3D scene between the
+    /// background panels and the foreground windows.
+    ///
+    /// Record [`EguiLayer::Background`] before [`EguiLayer::Foreground`].
+    /// Like `record`, each layer leaves the swapchain image in `PRESENT_SRC_KHR` and expects it in
+    /// `COLOR_ATTACHMENT_OPTIMAL`. Frames of immediate viewports only have a foreground layer.
+    pub fn record_layer(
+        &mut self,
+        cmd: vk::CommandBuffer,
+        swapchain_index: usize,
+        layer: EguiLayer,
+    ) {
+        self.upload(swapchain_index);
+        let range = match (layer, self.layer_split) {
+            (EguiLayer::Background, Some(split)) => 0..split,
+            (EguiLayer::Background, None) => 0..0,
+            (EguiLayer::Foreground, Some(split)) => split + 1..self.primitive_count,
+            (EguiLayer::Foreground, None) => 0..self.primitive_count,
+        };
+        (self.recorder)(cmd, swapchain_index, range);
+    }
+
+    /// the images the commands access, with their stages, accesses and layouts, e.g. to register the
+    /// frame as a pass of a frame graph which places the barriers around it.
+    ///
+    /// Applies the texture updates of the frame like `record`, so call it before recording.
+    pub fn resource_usages(&mut self) -> Vec<ResourceUsage> {
+        self.update_textures();
+        let Some(texture_usages) = &self.texture_usages else {
+            return Vec::new();
+        };
+        std::iter::once(ResourceUsage::target())
+            .chain(texture_usages.lock().unwrap().iter().copied())
+            .collect()
+    }
+
+    /// whether the frame draws the same as the previous frame of the viewport without user textures,
+    /// which `Presenters` does not present again.
+    pub(crate) fn unchanged(&self) -> bool {
+        self.unchanged
+    }
+
+    /// the color `Presenters` clears the swapchain image to before recording.
+    pub(crate) fn clear_color(&self) -> [f32; 4] {
+        self.clear_color
     }
 
     /// Returns whether swapchain recreation is required.
-    #[must_use] 
+    #[must_use]
     pub fn swapchain_recreate_required(&self) -> bool {
         self.swapchain_recreate_required
     }
@@ -1725,8 +4179,15 @@ impl Default for EguiCommand {
     fn default() -> Self {
         Self {
             swapchain_updater: None,
-            recorder: Box::new(|_, _| {}),
+            texture_updater: None,
+            uploader: Box::new(|_| {}),
+            recorder: Box::new(|_, _, _| {}),
             swapchain_recreate_required: false,
+            unchanged: false,
+            layer_split: None,
+            primitive_count: 0,
+            clear_color: [0.0; 4],
+            texture_usages: None,
         }
     }
 }
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+
+fn causes_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new(("egui_ash_repaint_causes", viewport_id))
+}
+
+/// why a frame of a viewport was painted, see [`repaint_causes`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RepaintCause {
+    /// input events of the viewport, e.g. pointer moves or key presses.
+    Input { events: usize },
+    /// `egui::Context::request_repaint` at `file:line`.
+    ///
+    /// egui requests repaints for animations, tooltips and viewport commands from its own sources.
+    RequestRepaint {
+        file: String,
+        line: u32,
+        reason: String,
+    },
+    /// the app sent viewport commands in the previous frame.
+    ViewportCommand,
+    /// the window was created, resized, rescaled or became visible again.
+    Window,
+}
+impl RepaintCause {
+    /// the repaints egui requested for the current pass of `ctx`.
+    pub(crate) fn requested(ctx: &egui::Context) -> impl Iterator<Item = Self> {
+        ctx.repaint_causes()
+            .into_iter()
+            .map(|cause| Self::RequestRepaint {
+                file: cause.file.to_owned(),
+                line: cause.line,
+                reason: cause.reason.to_string(),
+            })
+    }
+}
+impl Display for RepaintCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input { events } => write!(f, "input ({events} events)"),
+            Self::RequestRepaint { file, line, reason } => write!(f, "{file}:{line} {reason}"),
+            Self::ViewportCommand => write!(f, "viewport command"),
+            Self::Window => write!(f, "window changed"),
+        }
+    }
+}
+
+/// repaint requests from other threads, e.g. when a worker finished loading data.
+///
+/// Wakes the event loop up. Unlike a clone of the `egui::Context`, it only gives access to the
+/// repaints.
+#[derive(Clone)]
+pub struct RepaintSignal {
+    context: egui::Context,
+}
+impl RepaintSignal {
+    pub(crate) fn new(context: egui::Context) -> Self {
+        Self { context }
+    }
+
+    /// repaint the root viewport.
+    #[track_caller]
+    pub fn request_repaint(&self) {
+        self.context.request_repaint_of(egui::ViewportId::ROOT);
+    }
+
+    /// repaint `viewport_id`.
+    #[track_caller]
+    pub fn request_repaint_of(&self, viewport_id: egui::ViewportId) {
+        self.context.request_repaint_of(viewport_id);
+    }
+
+    /// repaint `viewport_id` in `delay` at the latest, e.g. when the next value of a poll is due.
+    #[track_caller]
+    pub fn request_repaint_after(&self, viewport_id: egui::ViewportId, delay: std::time::Duration) {
+        self.context.request_repaint_after_for(delay, viewport_id);
+    }
+}
+
+/// add `cause` to `causes` unless it is already there.
+pub(crate) fn push_cause(causes: &mut Vec<RepaintCause>, cause: RepaintCause) {
+    if !causes.contains(&cause) {
+        causes.push(cause);
+    }
+}
+
+/// store the causes of the current pass of `ctx` for [`repaint_causes`].
+pub(crate) fn set_repaint_causes(ctx: &egui::Context, causes: Vec<RepaintCause>) {
+    let id = causes_id(ctx.viewport_id());
+    ctx.data_mut(|data| data.insert_temp(id, causes));
+}
+
+/// why the current frame of the viewport of `ctx` was painted.
+///
+/// Call it in [`crate::App::ui`], e.g. to find out why an idle app keeps repainting.
+pub fn repaint_causes(ctx: &egui::Context) -> Vec<RepaintCause> {
+    ctx.data(|data| data.get_temp(causes_id(ctx.viewport_id())))
+        .unwrap_or_default()
+}
+
+/// show the [`repaint_causes`] of the current frame in the bottom right corner of the viewport.
+pub fn repaint_cause_overlay(ctx: &egui::Context) {
+    let causes = repaint_causes(ctx);
+    egui::Area::new(egui::Id::new("egui_ash_repaint_cause_overlay"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+        .order(egui::Order::Debug)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("pass {}", ctx.cumulative_pass_nr()));
+                if causes.is_empty() {
+                    ui.weak("no cause recorded");
+                }
+                for cause in &causes {
+                    ui.monospace(cause.to_string());
+                }
+            });
+        });
+}
@@ -0,0 +1,134 @@
+//! recording of the egui input of the app to a file and its replay, e.g. to replay the session of a
+//! crash report or to test a UI flow again. (winit backend)
+//!
+//! The recording is the `egui::RawInput` of each frame of the root and deferred viewports in order,
+//! with the time and the window state of the frame. The replay feeds the frames to egui at the times
+//! they were recorded, in the same order, and drops the live input until it finishes.
+//!
+//! Immediate viewports are not recorded. A replay only matches the recording if the app state at the
+//! start was the same.
+
+use anyhow::Result;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Frame {
+    viewport_id: egui::ViewportId,
+    input: egui::RawInput,
+}
+
+/// writes the input of the frames to a file.
+pub(crate) struct InputRecorder {
+    writer: BufWriter<File>,
+}
+impl InputRecorder {
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        rmp_serde::encode::write_named(&mut writer, &Header { version: VERSION })?;
+        Ok(Self { writer })
+    }
+
+    /// append the input of a frame, flushed so that it survives a crash.
+    pub(crate) fn record(
+        &mut self,
+        viewport_id: egui::ViewportId,
+        input: &egui::RawInput,
+    ) -> Result<()> {
+        profile_function!();
+        let frame = Frame {
+            viewport_id,
+            input: input.clone(),
+        };
+        rmp_serde::encode::write_named(&mut self.writer, &frame)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// feeds the frames of a recording to egui.
+pub(crate) struct InputReplay {
+    frames: VecDeque<Frame>,
+    start: Instant,
+    // time of the first frame, the replay starts right away.
+    time_offset: f64,
+}
+impl InputReplay {
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header: Header = rmp_serde::from_read(&mut reader)?;
+        if header.version != VERSION {
+            anyhow::bail!(
+                "The input recording has version {}, expected {VERSION}",
+                header.version
+            );
+        }
+        let mut frames = VecDeque::new();
+        loop {
+            match rmp_serde::from_read::<_, Frame>(&mut reader) {
+                Ok(frame) => frames.push_back(frame),
+                Err(rmp_serde::decode::Error::InvalidMarkerRead(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                // the last frame of a crashed session may be cut off.
+                Err(err) => {
+                    warn!("Input recording ends with an invalid frame: {err}");
+                    break;
+                }
+            }
+        }
+        let time_offset = frames
+            .front()
+            .and_then(|frame| frame.input.time)
+            .unwrap_or_default();
+        debug!("Replaying {} frames of input", frames.len());
+        Ok(Self {
+            frames,
+            start: Instant::now(),
+            time_offset,
+        })
+    }
+
+    /// the recorded input of the next frame if it is a frame of `viewport_id` and its time has come.
+    /// Otherwise the viewport of the next frame is repainted at its time.
+    pub(crate) fn next_input(
+        &mut self,
+        ctx: &egui::Context,
+        viewport_id: egui::ViewportId,
+    ) -> Option<egui::RawInput> {
+        let frame = self.frames.front()?;
+        let delay = self.delay(frame);
+        if frame.viewport_id != viewport_id || !delay.is_zero() {
+            ctx.request_repaint_after_for(delay, frame.viewport_id);
+            return None;
+        }
+        let frame = self.frames.pop_front()?;
+        if let Some(next) = self.frames.front() {
+            ctx.request_repaint_after_for(self.delay(next), next.viewport_id);
+        }
+        Some(frame.input)
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    fn delay(&self, frame: &Frame) -> Duration {
+        let time = frame.input.time.unwrap_or_default() - self.time_offset;
+        Duration::from_secs_f64((time - self.start.elapsed().as_secs_f64()).max(0.0))
+    }
+}
@@ -0,0 +1,53 @@
+use ash::vk;
+
+/// image accessed by a frame of egui, see [`ResourceUsage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EguiResource {
+    /// the image of `swapchain_index` the frame is recorded into.
+    Target,
+    /// an image registered with `ImageRegistry::register_user_texture` that the frame samples.
+    /// `image` is the one of [`crate::UserTextureInfo`], null if the texture was not described.
+    UserTexture {
+        id: egui::TextureId,
+        image: vk::Image,
+    },
+}
+
+/// how the commands of a frame of egui access an image, to register [`crate::EguiCommand`] as a
+/// pass of a frame graph instead of a black box.
+///
+/// The textures egui-ash manages itself, the font atlas and the scaled render target are not
+/// listed: they are only touched by egui-ash, and their uploads are finished before recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceUsage {
+    pub resource: EguiResource,
+    pub stage: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+    /// layout the image must be in when the commands run.
+    pub layout: vk::ImageLayout,
+    /// layout the commands leave the image in.
+    pub final_layout: vk::ImageLayout,
+}
+impl ResourceUsage {
+    pub(crate) fn target() -> Self {
+        Self {
+            resource: EguiResource::Target,
+            stage: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            // the render pass loads the image, egui draws over it.
+            access: vk::AccessFlags2::COLOR_ATTACHMENT_READ
+                | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+        }
+    }
+
+    pub(crate) fn user_texture(id: egui::TextureId, image: vk::Image) -> Self {
+        Self {
+            resource: EguiResource::UserTexture { id, image },
+            stage: vk::PipelineStageFlags2::FRAGMENT_SHADER,
+            access: vk::AccessFlags2::SHADER_SAMPLED_READ,
+            layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+/// system clipboard with rich text formats.
+///
+/// egui itself only copies and pastes plain text.
+/// Use this to exchange HTML with word processors and browsers.
+/// RTF is not supported by the underlying clipboard backend.
+#[derive(Clone, Default)]
+pub struct RichClipboard {
+    clipboard: Arc<Mutex<Option<arboard::Clipboard>>>,
+}
+impl RichClipboard {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_clipboard<T>(
+        &self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T> {
+        let mut clipboard = self.clipboard.lock().unwrap();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+        Ok(f(clipboard.as_mut().unwrap())?)
+    }
+
+    /// get plain text from the clipboard.
+    pub fn get_text(&self) -> Result<String> {
+        self.with_clipboard(|clipboard| clipboard.get_text())
+    }
+
+    /// set plain text to the clipboard.
+    pub fn set_text(&self, text: &str) -> Result<()> {
+        self.with_clipboard(|clipboard| clipboard.set_text(text))
+    }
+
+    /// get HTML from the clipboard.
+    pub fn get_html(&self) -> Result<String> {
+        self.with_clipboard(|clipboard| clipboard.get().html())
+    }
+
+    /// set HTML to the clipboard, with plain text alternative for apps that do not accept HTML.
+    pub fn set_html(&self, html: &str, alt_text: Option<&str>) -> Result<()> {
+        self.with_clipboard(|clipboard| clipboard.set_html(html, alt_text))
+    }
+}
+impl std::fmt::Debug for RichClipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RichClipboard").finish()
+    }
+}
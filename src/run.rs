@@ -2,68 +2,356 @@ use egui_winit::winit::{
     self,
     application::ApplicationHandler,
     event_loop::{ActiveEventLoop, EventLoop},
-    window::{Theme, WindowAttributes},
+    window::Theme,
 };
 use raw_window_handle::HasDisplayHandle as _;
 use std::{
+    collections::HashMap,
     ffi::{CStr, CString},
     mem::ManuallyDrop,
+    panic::AssertUnwindSafe,
     process::ExitCode,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+#[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+use crate::drag::{DragRequestReceiver, DragSource};
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{self, GamepadNavigation};
+#[cfg(feature = "global-hotkey")]
+use crate::hotkey::{self, GlobalHotkeys};
+#[cfg(feature = "menu")]
+use crate::menu::NativeMenu;
+#[cfg(feature = "tray")]
+use crate::tray::Tray;
 use crate::{
-    app::{App, AppCreator, CreationContext},
+    accelerator::Accelerators,
+    activation,
+    app::{App, AppCreator, CloseResponse, CreationContext, EventFilter},
+    clear_color,
+    crash_diagnostics::GpuCrashDiagnostics,
+    cursor::CustomCursors,
+    documents::{DocumentWindows, Documents},
     event,
-    integration::{Integration, IntegrationEvent},
-    renderer::ImageRegistry,
-    Allocator,
+    fonts::Fonts,
+    handler::{ComposedHandler, HandlerOrder},
+    integration::{Integration, IntegrationEvent, IntegrationOptions},
+    monitors::MonitorWatch,
+    passthrough::{self, MousePassthrough},
+    presenters::PresenterOptions,
+    render_state_check,
+    renderer::{ImageRegistry, RendererOptions, UploadBudget, DEFAULT_DESCRIPTOR_POOL_SIZE},
+    scroll::ScrollOptions,
+    show::{self, ShowPolicy},
+    single_instance::SingleInstance,
+    stats::FrameStatistics,
+    submit::SubmitHooks,
+    theme,
+    timestep::{FixedTimestep, FrameLimiter, FramePacer, FrameStepper},
+    zoom::{self, ZoomOptions},
+    Allocator, AllocatorDebugOptions,
 };
 #[cfg(feature = "persistence")]
-use crate::{storage, utils};
+use crate::{layout::LayoutProfiles, storage, utils};
+
+/// how cursor moves are passed to egui and [`App::handle_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerMovePolicy {
+    /// pass every cursor move as it arrives.
+    DeliverAll,
+    /// pass only the last cursor move of each window per frame.
+    ///
+    /// [`event::Event::PointerMoved`] still reports every sample.
+    CoalescePerFrame,
+}
+
+/// what happens to the GPU resources of egui-ash while the app is suspended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuspendPolicy {
+    /// keep all resources.
+    KeepResources,
+    /// destroy the swapchains, the render targets and the egui textures on suspend,
+    /// and create them again on the first frame after resuming.
+    ///
+    /// The egui textures, e.g. the font atlas, are uploaded again from copies kept in CPU memory.
+    /// Registered user textures stay registered, the app recreates its own resources
+    /// on [`event::AppEvent::Suspended`] and [`event::AppEvent::Resumed`].
+    ReleaseResources,
+}
 
 /// egui-ash run option.
 pub struct RunOption {
-    /// window clear color.
+    /// color the windows are cleared to before egui paints, premultiplied and in gamma space.
+    /// Opaque black by default. Transparent windows are cleared to transparent unless their color
+    /// is set with [`crate::set_clear_color`].
+    ///
+    /// Change it per viewport while the app runs with [`crate::set_clear_color`].
     pub clear_color: [f32; 4],
     /// viewport builder for root window.
     pub viewport_builder: Option<egui::ViewportBuilder>,
-    /// follow system theme.
+    /// mouse passthrough of the root window, see [`crate::set_mouse_passthrough`]. (winit backend only)
+    pub mouse_passthrough: MousePassthrough,
+    /// when the root window and the windows of new viewports are shown.
+    pub show_policy: ShowPolicy,
+    /// follow system theme, also when it changes while the app runs.
+    ///
+    /// Change it at runtime with [`crate::set_theme_preference`].
     pub follow_system_theme: bool,
-    /// default theme.
+    /// theme used when not following the system theme, or when the system theme is unknown.
     pub default_theme: Theme,
+    /// style of the light theme instead of egui's default, for all viewports.
+    ///
+    /// To only change the colors, use `egui::Style { visuals: my_light_visuals, ..Default::default() }`.
+    pub light_style: Option<egui::Style>,
+    /// style of the dark theme instead of egui's default, for all viewports.
+    pub dark_style: Option<egui::Style>,
+    /// use the UI font of the OS with fallbacks for other scripts, see [`crate::system_fonts`].
+    /// Installed before [`AppCreator::create`], which can still replace the fonts.
+    #[cfg(feature = "system-fonts")]
+    pub system_fonts: bool,
     #[cfg(feature = "persistence")]
     pub persistent_windows: bool,
     #[cfg(feature = "persistence")]
     pub persistent_egui_memory: bool,
+    /// save the app state when a window loses the focus, in addition to [`App::auto_save_interval`].
+    #[cfg(feature = "persistence")]
+    pub save_on_focus_loss: bool,
+    /// backend of the app state storage. `None` saves a file in the data directory of the app.
+    /// The storage options below apply only to that file.
+    #[cfg(feature = "persistence")]
+    pub storage_backend: Option<Box<dyn storage::StorageBackend>>,
+    /// serialization format of the storage file.
+    #[cfg(feature = "persistence")]
+    pub storage_format: storage::StorageFormat,
+    /// directory of the storage file instead of the data directory derived from the app id.
+    #[cfg(feature = "persistence")]
+    pub storage_dir: Option<std::path::PathBuf>,
+    /// name of the storage file instead of `app.<extension of storage_format>`.
+    #[cfg(feature = "persistence")]
+    pub storage_file_name: Option<String>,
+    /// version of the persisted app state. Raise it when the saved values change.
+    #[cfg(feature = "persistence")]
+    pub storage_version: u32,
+    /// called with the stored version when it is lower than `storage_version`, before the app is created.
+    #[cfg(feature = "persistence")]
+    pub storage_migration: Option<storage::StorageMigration>,
     /// `vk::PresentModeKHR`
     pub present_mode: ash::vk::PresentModeKHR,
+    /// cap of the swapchain images, at least the minimum of the surface. `None` uses one more
+    /// than the minimum.
+    pub max_swapchain_images: Option<u32>,
+    /// cursor move coalescing for high polling rate mice. (winit backend only)
+    pub pointer_move_policy: PointerMovePolicy,
+    /// scroll line height, pixel delta multiplier and smoothing.
+    pub scroll_options: ScrollOptions,
+    /// zoom keybindings and the persistence of the zoom factor.
+    pub zoom: ZoomOptions,
+    /// on a panic in the app or the renderer, save the app state and destroy the Vulkan objects
+    /// before the panic continues. (winit backend only)
+    pub cleanup_on_panic: bool,
+    /// check the render state returned by [`AppCreator::create`] against the required extensions
+    /// and the surface of the root window, and panic with what is missing instead of failing
+    /// later with a Vulkan error. Off by default. (winit backend only)
+    pub check_render_state: bool,
+    /// run one instance of the app per user. A second launch passes its arguments to the running
+    /// instance as [`event::AppEvent::SecondInstance`] and exits. (winit backend only)
+    pub single_instance: bool,
+    /// GPU resources while the app is suspended. (winit backend only)
+    pub suspend_policy: SuspendPolicy,
+    /// splash window shown while [`AppCreator::create`] runs, closed when the root window is shown.
+    /// (winit backend only)
+    #[cfg(feature = "splash")]
+    pub splash: Option<crate::splash::SplashOptions>,
+    /// interval of [`App::fixed_update`], independent of the repaints. `None` disables it, zero is
+    /// ignored.
+    pub fixed_timestep: Option<Duration>,
+    /// frame rate cap, independent of the present mode. `None` disables it.
+    /// Change it while the app runs with [`FrameLimiter`].
+    pub max_fps: Option<f32>,
+    /// frame rate cap while the system is on battery or in power saver mode, applied on top of
+    /// [`Self::max_fps`]. `None` keeps the frame rate. (winit backend only)
+    #[cfg(feature = "power")]
+    pub battery_max_fps: Option<f32>,
+    /// paint only the frames stepped with [`FrameStepper::step_frame`], with a manual clock.
+    /// (winit backend only)
+    pub manual_stepping: bool,
+    /// record and present the frames on a dedicated thread, so that long GPU frames do not block
+    /// the event loop. [`App::request_redraw`] handlers run on that thread. (winit backend only)
+    pub render_thread: bool,
+    /// do not acquire and present frames drawing the same as the previous frame. Off by default.
+    /// Frames drawing user textures are always presented.
+    pub skip_unchanged_frames: bool,
+    /// number of texture descriptor sets in the first descriptor pool. When it runs out,
+    /// another pool twice as large is chained to it.
+    pub descriptor_pool_size: u32,
+    /// scale of the resolution egui renders at, relative to the window. Values above 1 supersample
+    /// the UI, values below 1 render it faster at a lower resolution. The frame is drawn
+    /// to the window with linear filtering.
+    pub render_scale: f32,
+    /// limit of the texture data uploaded per frame, to avoid hitches when many images load.
+    /// `None` uploads all texture updates in the frame they are made.
+    pub upload_budget: Option<UploadBudget>,
+    /// breadcrumbs around the frames of egui-ash, logged when the device is lost.
+    /// Its extension is added to [`CreationContext::required_device_extensions`],
+    /// check the support with [`GpuCrashDiagnostics::supported`].
+    pub gpu_crash_diagnostics: Option<GpuCrashDiagnostics>,
+    /// time to wait for a frame to finish on the GPU before it counts as hung, see
+    /// [`event::Event::GpuHang`]. `None` waits forever.
+    pub gpu_hang_timeout: Option<Duration>,
+    /// debug options for the allocator, passed as [`CreationContext::allocator_debug`].
+    /// Leaked allocations of egui-ash are logged when the integration is destroyed.
+    pub allocator_debug: AllocatorDebugOptions,
+    /// record the egui input of the frames to this file, see [`crate::replay`]. (winit backend only)
+    #[cfg(feature = "replay")]
+    pub record_input: Option<std::path::PathBuf>,
+    /// replay the egui input recorded to this file, dropping the live input until it finishes.
+    /// (winit backend only)
+    #[cfg(feature = "replay")]
+    pub replay_input: Option<std::path::PathBuf>,
 }
 impl Default for RunOption {
     fn default() -> Self {
         Self {
             clear_color: [0.0, 0.0, 0.0, 1.0],
             viewport_builder: None,
+            mouse_passthrough: MousePassthrough::Disabled,
+            show_policy: ShowPolicy::AfterFirstPresent,
             follow_system_theme: true,
             default_theme: Theme::Light,
+            light_style: None,
+            dark_style: None,
+            #[cfg(feature = "system-fonts")]
+            system_fonts: false,
             #[cfg(feature = "persistence")]
             persistent_windows: true,
             #[cfg(feature = "persistence")]
             persistent_egui_memory: true,
+            #[cfg(feature = "persistence")]
+            save_on_focus_loss: true,
+            #[cfg(feature = "persistence")]
+            storage_backend: None,
+            #[cfg(feature = "persistence")]
+            storage_format: storage::StorageFormat::Ron,
+            #[cfg(feature = "persistence")]
+            storage_dir: None,
+            #[cfg(feature = "persistence")]
+            storage_file_name: None,
+            #[cfg(feature = "persistence")]
+            storage_version: 0,
+            #[cfg(feature = "persistence")]
+            storage_migration: None,
+            present_mode: ash::vk::PresentModeKHR::FIFO,
+            max_swapchain_images: None,
+            pointer_move_policy: PointerMovePolicy::DeliverAll,
+            scroll_options: ScrollOptions::default(),
+            zoom: ZoomOptions::default(),
+            cleanup_on_panic: false,
+            check_render_state: false,
+            single_instance: false,
+            suspend_policy: SuspendPolicy::KeepResources,
+            #[cfg(feature = "splash")]
+            splash: None,
+            fixed_timestep: None,
+            max_fps: None,
+            #[cfg(feature = "power")]
+            battery_max_fps: None,
+            manual_stepping: false,
+            render_thread: false,
+            skip_unchanged_frames: false,
+            descriptor_pool_size: DEFAULT_DESCRIPTOR_POOL_SIZE,
+            render_scale: 1.0,
+            upload_budget: None,
+            gpu_crash_diagnostics: None,
+            gpu_hang_timeout: None,
+            allocator_debug: AllocatorDebugOptions::default(),
+            #[cfg(feature = "replay")]
+            record_input: None,
+            #[cfg(feature = "replay")]
+            replay_input: None,
+        }
+    }
+}
+impl RunOption {
+    /// options for an overlay, e.g. a game HUD or a streaming overlay.
+    ///
+    /// The root window is built with [`overlay_viewport_builder`]. Paint the panels with
+    /// `egui::Frame::NONE` to see what is behind the window.
+    pub fn overlay(mouse_passthrough: MousePassthrough) -> Self {
+        Self {
+            viewport_builder: Some(overlay_viewport_builder()),
+            mouse_passthrough,
+            clear_color: [0.0; 4],
+            ..Default::default()
+        }
+    }
+
+    /// options for software rasterizers like lavapipe and SwiftShader, e.g. on CI machines without
+    /// a GPU. Select the device with [`crate::device::DeviceRequirements::software`].
+    ///
+    /// Presents with FIFO, the only mode these ICDs guarantee, with two swapchain images, and
+    /// enables nothing that needs a vendor extension or a dedicated thread. The GPU hang timeout
+    /// is off, since frames rendered on the CPU of a busy CI machine may take seconds.
+    pub fn software() -> Self {
+        Self {
             present_mode: ash::vk::PresentModeKHR::FIFO,
+            max_swapchain_images: Some(2),
+            render_thread: false,
+            render_scale: 1.0,
+            gpu_crash_diagnostics: None,
+            gpu_hang_timeout: None,
+            ..Default::default()
         }
     }
+
+    pub(crate) fn presenter_options(&self) -> PresenterOptions {
+        PresenterOptions {
+            present_mode: self.present_mode,
+            skip_unchanged_frames: self.skip_unchanged_frames,
+            max_swapchain_images: self.max_swapchain_images,
+            gpu_crash_diagnostics: self.gpu_crash_diagnostics,
+            gpu_hang_timeout: self.gpu_hang_timeout,
+        }
+    }
+
+    pub(crate) fn renderer_options(&self) -> RendererOptions {
+        RendererOptions {
+            keep_image_copies: self.suspend_policy == SuspendPolicy::ReleaseResources,
+            descriptor_pool_size: self.descriptor_pool_size,
+            render_scale: self.render_scale,
+            upload_budget: self.upload_budget,
+            allocator_debug: self.allocator_debug,
+        }
+    }
+}
+
+/// viewport builder of a transparent, undecorated and always on top window hidden from the taskbar,
+/// for overlay viewports.
+pub fn overlay_viewport_builder() -> egui::ViewportBuilder {
+    default_viewport_builder()
+        .with_transparent(true)
+        .with_decorations(false)
+        .with_window_level(egui::WindowLevel::AlwaysOnTop)
+        .with_taskbar(false)
 }
 
 /// exit signal sender for exit app.
 #[derive(Debug, Clone)]
 pub struct ExitSignal {
-    tx: std::sync::mpsc::Sender<ExitCode>,
+    pub(crate) tx: std::sync::mpsc::Sender<ExitCode>,
+    /// wakes the event loop of [`run`].
+    pub(crate) event_loop_proxy: Option<winit::event_loop::EventLoopProxy<IntegrationEvent>>,
 }
 impl ExitSignal {
     /// send exit signal.
+    ///
+    /// The app exits after the current event with the code of the first signal:
+    /// [`event::AppEvent::LoopExiting`] is delivered, the app state is saved,
+    /// the frames in flight finish and then the Vulkan objects of egui-ash are destroyed.
     pub fn send(&self, exit_code: ExitCode) {
         self.tx.send(exit_code).unwrap();
+        if let Some(event_loop_proxy) = &self.event_loop_proxy {
+            let _ = event_loop_proxy.send_event(IntegrationEvent::Exit);
+        }
     }
 }
 
@@ -78,40 +366,147 @@ pub fn run<C: AppCreator<A> + 'static, A: Allocator + 'static>(
     creator: C,
     run_option: RunOption,
 ) -> ExitCode {
-    let app_id: String = app_id.into();
+    run_composed(app_id.into(), creator, run_option, None)
+}
+
+/// [`run`] with a winit `ApplicationHandler` of the app composed with the one of egui-ash, to
+/// drive other winit based subsystems on the event loop of egui-ash, e.g. a softbuffer window.
+///
+/// `handler` receives every event of the event loop before or after egui-ash, depending on
+/// `order`, including the ones of the windows of egui-ash. The windows it creates are its own,
+/// egui-ash ignores their events.
+pub fn run_with_handler<C: AppCreator<A> + 'static, A: Allocator + 'static>(
+    app_id: impl Into<String>,
+    creator: C,
+    run_option: RunOption,
+    handler: impl ApplicationHandler + 'static,
+    order: HandlerOrder,
+) -> ExitCode {
+    run_composed(
+        app_id.into(),
+        creator,
+        run_option,
+        Some((Box::new(handler), order)),
+    )
+}
+
+fn run_composed<C: AppCreator<A> + 'static, A: Allocator + 'static>(
+    app_id: String,
+    creator: C,
+    run_option: RunOption,
+    handler: Option<(Box<dyn ApplicationHandler>, HandlerOrder)>,
+) -> ExitCode {
+    let single_instance = if run_option.single_instance {
+        match SingleInstance::acquire(&app_id) {
+            Ok(Some(single_instance)) => Some(single_instance),
+            Ok(None) => {
+                debug!("Passed the arguments to the running instance");
+                return ExitCode::SUCCESS;
+            }
+            Err(err) => {
+                error!("Failed to check for a running instance: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     let event_loop = EventLoop::<IntegrationEvent>::with_user_event()
         .build()
         .expect("Failed to create event loop");
 
-    /*match run_option.default_theme {
-        Theme::Light => {
-            context.set_visuals(egui::Visuals::light());
-        }
-        Theme::Dark => {
-            context.set_visuals(egui::Visuals::dark());
-        }
-    }*/
-
     let (exit_signal_tx, exit_signal_rx) = std::sync::mpsc::channel();
-    let exit_signal = ExitSignal { tx: exit_signal_tx };
+    let exit_signal = ExitSignal {
+        tx: exit_signal_tx,
+        event_loop_proxy: Some(event_loop.create_proxy()),
+    };
+
+    let custom_cursors = CustomCursors::new();
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    let (drag_source, drag_requests) = DragSource::new();
+    #[cfg(feature = "gamepad")]
+    gamepad::spawn(event_loop.create_proxy());
+    #[cfg(feature = "global-hotkey")]
+    hotkey::set_event_handler(event_loop.create_proxy());
+    #[cfg(all(feature = "menu", any(target_os = "windows", target_os = "macos")))]
+    {
+        let event_loop_proxy = event_loop.create_proxy();
+        muda::MenuEvent::set_event_handler(Some(move |event: muda::MenuEvent| {
+            let _ = event_loop_proxy.send_event(IntegrationEvent::MenuActivated(event.id.0));
+        }));
+    }
+    #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+    crate::tray::set_event_handler(event_loop.create_proxy());
+    #[cfg(all(feature = "open-files", target_os = "macos"))]
+    crate::open_files::install(event_loop.create_proxy());
+    #[cfg(feature = "power")]
+    crate::power::watch(event_loop.create_proxy());
 
+    let fixed_timestep = run_option.fixed_timestep.and_then(FixedTimestep::new);
+    let frame_limiter = FrameLimiter::new(run_option.max_fps);
+    let frame_stepper = FrameStepper::new(run_option.manual_stepping, event_loop.create_proxy());
+    let frame_stats = FrameStatistics::default();
     let mut state = State {
         app_id,
         run_option,
         exit_signal,
         creator,
         app: None,
-        #[cfg(feature = "accesskit")]
+        custom_cursors,
         event_loop_proxy: event_loop.create_proxy(),
+        #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+        drag_source,
+        #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+        drag_requests,
+        #[cfg(feature = "gamepad")]
+        gamepad_navigation: GamepadNavigation::new(),
+        pending_cursor_moved: HashMap::new(),
+        #[cfg(feature = "global-hotkey")]
+        global_hotkeys: GlobalHotkeys::new(),
+        #[cfg(feature = "tray")]
+        tray: Tray::new(),
+        #[cfg(feature = "splash")]
+        splash: None,
+        #[cfg(target_os = "macos")]
+        pending_open_files: vec![],
+        #[cfg(feature = "power")]
+        max_fps_before_battery: None,
+        fixed_timestep,
+        frame_pacer: FramePacer::new(frame_limiter.clone()),
+        frame_limiter,
+        frame_stepper,
+        frame_stats,
+        submit_hooks: SubmitHooks::default(),
+        documents: Documents::new(event_loop.create_proxy()),
+        single_instance,
+        monitor_watch: None,
         integration: None,
+        document_windows: None,
     };
 
-    event_loop
-        .run_app(&mut state)
-        .expect("Failed to run event loop");
+    let cleanup_on_panic = state.run_option.cleanup_on_panic;
+    let mut handler = ComposedHandler {
+        state: &mut state,
+        handler,
+    };
+    if cleanup_on_panic {
+        let result =
+            std::panic::catch_unwind(AssertUnwindSafe(|| event_loop.run_app(&mut handler)));
+        match result {
+            Ok(result) => result.expect("Failed to run event loop"),
+            Err(payload) => {
+                handler.state.cleanup_after_panic();
+                std::panic::resume_unwind(payload);
+            }
+        }
+    } else {
+        event_loop
+            .run_app(&mut handler)
+            .expect("Failed to run event loop");
+    }
 
-    exit_signal_rx.recv_timeout(Duration::from_secs(1)).unwrap()
+    exit_signal_rx.try_recv().unwrap_or(ExitCode::SUCCESS)
 }
 
 struct State<C, A>
@@ -125,8 +520,37 @@ where
     creator: C,
     app: Option<C::App>,
     integration: Option<ManuallyDrop<Integration<A>>>,
-    #[cfg(feature = "accesskit")]
+    documents: Documents,
+    document_windows: Option<DocumentWindows<A>>,
+    // listens for the next launches once the app is created.
+    single_instance: Option<SingleInstance>,
+    monitor_watch: Option<MonitorWatch>,
+    custom_cursors: CustomCursors,
     event_loop_proxy: winit::event_loop::EventLoopProxy<IntegrationEvent>,
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    drag_source: DragSource,
+    #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+    drag_requests: DragRequestReceiver,
+    #[cfg(feature = "gamepad")]
+    gamepad_navigation: GamepadNavigation,
+    pending_cursor_moved: HashMap<winit::window::WindowId, winit::event::WindowEvent>,
+    #[cfg(feature = "global-hotkey")]
+    global_hotkeys: GlobalHotkeys,
+    #[cfg(feature = "tray")]
+    tray: Tray,
+    #[cfg(feature = "splash")]
+    splash: Option<crate::splash::SplashHandle>,
+    #[cfg(target_os = "macos")]
+    pending_open_files: Vec<std::path::PathBuf>,
+    // the frame rate cap replaced by `RunOption::battery_max_fps` while the system saves power.
+    #[cfg(feature = "power")]
+    max_fps_before_battery: Option<Option<f32>>,
+    fixed_timestep: Option<FixedTimestep>,
+    frame_limiter: FrameLimiter,
+    frame_pacer: FramePacer,
+    frame_stepper: FrameStepper,
+    frame_stats: FrameStatistics,
+    submit_hooks: SubmitHooks,
 }
 
 impl<C, A> State<C, A>
@@ -141,14 +565,12 @@ where
         context: &egui::Context,
         storage: &storage::Storage,
     ) -> winit::window::Window {
-        let Some(ref viewport_builder) = self.run_option.viewport_builder else {
-            let window_attributes = WindowAttributes::default()
-                .with_visible(false)
-                .with_title("egui-ash");
-            return event_loop.create_window(window_attributes).unwrap();
-        };
+        let mut viewport_builder = self
+            .run_option
+            .viewport_builder
+            .clone()
+            .unwrap_or_else(default_viewport_builder);
 
-        #[cfg(feature = "persistence")]
         if self.run_option.persistent_windows {
             let egui_zoom_factor = context.zoom_factor();
             let window_settings = storage
@@ -164,58 +586,104 @@ where
                 });
 
             if let Some(window_settings) = window_settings {
-                self.run_option.viewport_builder =
-                    Some(window_settings.initialize_viewport_builder(
-                        egui_zoom_factor,
-                        event_loop,
-                        viewport_builder.clone(),
-                    ));
+                viewport_builder = window_settings.initialize_viewport_builder(
+                    egui_zoom_factor,
+                    event_loop,
+                    viewport_builder,
+                );
             }
         }
 
-        let window_attributes = WindowAttributes::default().with_visible(false);
-        event_loop.create_window(window_attributes).unwrap()
+        self.run_option.viewport_builder = Some(viewport_builder.clone());
+        create_root_window(event_loop, context, viewport_builder)
     }
 
     #[cfg(not(feature = "persistence"))]
     fn create_window(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _context: &egui::Context,
+        context: &egui::Context,
     ) -> winit::window::Window {
-        let window_attributes = WindowAttributes::default()
-            .with_visible(false)
-            .with_title("egui-ash");
-        event_loop.create_window(window_attributes).unwrap();
+        let viewport_builder = self
+            .run_option
+            .viewport_builder
+            .clone()
+            .unwrap_or_else(default_viewport_builder);
 
-        let window_attributes = WindowAttributes::default().with_visible(false);
-        event_loop.create_window(window_attributes).unwrap()
+        self.run_option.viewport_builder = Some(viewport_builder.clone());
+        create_root_window(event_loop, context, viewport_builder)
     }
 
     fn initial_setup(&mut self, event_loop: &ActiveEventLoop) {
         #[cfg(feature = "persistence")]
-        let storage =
-            storage::Storage::from_app_id(&self.app_id).expect("Failed to create storage");
+        let storage = storage::Storage::from_run_option(&mut self.run_option, &self.app_id)
+            .expect("Failed to create storage");
 
         let context = egui::Context::default();
+        context.set_theme(if self.run_option.follow_system_theme {
+            egui::ThemePreference::System
+        } else {
+            theme::egui_theme(self.run_option.default_theme).into()
+        });
         #[cfg(feature = "persistence")]
         if self.run_option.persistent_egui_memory {
             if let Some(memory) = storage.get_egui_memory() {
+                // also restores the theme preference set at runtime in the last session.
                 context.memory_mut(|m| *m = memory);
             }
         }
+        zoom::set_zoom_options(&context, self.run_option.zoom);
+        #[cfg(feature = "persistence")]
+        zoom::restore_zoom_factor(&context, &storage);
+        theme::set_styles(
+            &context,
+            self.run_option.light_style.as_ref(),
+            self.run_option.dark_style.as_ref(),
+        );
+        let fonts = Fonts::new(context.clone());
+        #[cfg(feature = "system-fonts")]
+        if self.run_option.system_fonts {
+            fonts.set_base(crate::system_fonts::font_definitions());
+        }
+        // the windows take the theme of the preference with the first frame.
+        theme::set_theme_preference(&context, theme::theme_preference(&context));
+        clear_color::set_default_clear_color(&context, self.run_option.clear_color);
+        show::set_show_policy(&context, self.run_option.show_policy);
+        if self.run_option.mouse_passthrough != MousePassthrough::Disabled {
+            passthrough::set_mouse_passthrough(
+                &context,
+                egui::ViewportId::ROOT,
+                self.run_option.mouse_passthrough,
+            );
+        }
 
         context.set_embed_viewports(false);
 
+        #[cfg(feature = "splash")]
+        if let Some(options) = self.run_option.splash.take() {
+            self.splash =
+                crate::splash::SplashHandle::new(event_loop, &context, &self.app_id, options);
+        }
+
         #[cfg(feature = "persistence")]
         let main_window = self.create_window(event_loop, &context, &storage);
         #[cfg(not(feature = "persistence"))]
         let main_window = self.create_window(event_loop, &context);
 
         let (image_registry, image_registry_receiver) = ImageRegistry::new();
+        let accelerators = Accelerators::new();
+        #[cfg(feature = "persistence")]
+        let layout_profiles = LayoutProfiles::new(storage.clone());
+        #[cfg(feature = "menu")]
+        let native_menu = NativeMenu::new(&main_window, accelerators.clone());
 
         let instance_extensions = required_instance_extensions(event_loop);
-        let device_extensions = vec![ash::khr::swapchain::NAME.to_owned()];
+        let mut device_extensions = vec![ash::khr::swapchain::NAME.to_owned()];
+        if let Some(diagnostics) = self.run_option.gpu_crash_diagnostics {
+            device_extensions.push(diagnostics.device_extension().to_owned());
+        }
+
+        let required_extensions = (instance_extensions.clone(), device_extensions.clone());
 
         let cc = CreationContext {
             //Display handle, Window handle
@@ -225,33 +693,204 @@ where
             required_device_extensions: device_extensions,
             image_registry,
             exit_signal: self.exit_signal.clone(),
+            repaint_signal: crate::RepaintSignal::new(context.clone()),
+            documents: self.documents.clone(),
+            #[cfg(feature = "persistence")]
+            storage: &storage,
+            #[cfg(feature = "persistence")]
+            layout_profiles: layout_profiles.clone(),
+            custom_cursors: self.custom_cursors.clone(),
+            fonts,
+            accelerators: accelerators.clone(),
+            taskbar: crate::Taskbar::new(context.clone()),
+            mirrors: crate::Mirrors::new(context.clone()),
+            #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+            drag_source: self.drag_source.clone(),
+            #[cfg(feature = "file-dialog")]
+            file_dialogs: crate::file_dialog::FileDialogs::new(self.event_loop_proxy.clone()),
+            #[cfg(feature = "global-hotkey")]
+            global_hotkeys: self.global_hotkeys.clone(),
+            #[cfg(feature = "menu")]
+            native_menu: native_menu.clone(),
+            #[cfg(feature = "message-dialog")]
+            message_dialogs: crate::message_dialog::MessageDialogs::new(
+                context.clone(),
+                self.event_loop_proxy.clone(),
+            ),
+            #[cfg(feature = "rich-clipboard")]
+            rich_clipboard: crate::rich_clipboard::RichClipboard::new(),
+            #[cfg(feature = "tray")]
+            tray: self.tray.clone(),
+            #[cfg(feature = "splash")]
+            splash: self.splash.clone(),
+            frame_limiter: self.frame_limiter.clone(),
+            frame_stepper: self.frame_stepper.clone(),
+            frame_stats: self.frame_stats.clone(),
+            submit_hooks: self.submit_hooks.clone(),
+            allocator_debug: self.run_option.allocator_debug,
         };
         let (app, render_state) = self.creator.create(cc);
+        if self.run_option.check_render_state {
+            let (instance_extensions, device_extensions) = required_extensions;
+            if let Err(err) = render_state_check::check_render_state(
+                &render_state,
+                &main_window,
+                &instance_extensions,
+                &device_extensions,
+            ) {
+                panic!("Invalid render state returned by AppCreator::create: {err}");
+            }
+        }
+        self.document_windows = Some(DocumentWindows::new(
+            self.documents.clone(),
+            render_state.clone(),
+            self.run_option.present_mode,
+            self.run_option.max_swapchain_images,
+        ));
+        let system_theme = main_window.theme().or(Some(self.run_option.default_theme));
 
         // ManuallyDrop is required because the integration object needs to be dropped before
         // the app drops for gpu_allocator drop order reasons.
-        let integration = ManuallyDrop::new(Integration::new(
-            &self.app_id,
+        #[allow(unused_mut)] // for replay
+        let mut integration = ManuallyDrop::new(Integration::new(
             event_loop,
             context,
             main_window,
             render_state,
-            self.run_option.present_mode,
             image_registry_receiver,
-            Some(self.run_option.default_theme),
-            #[cfg(feature = "accesskit")]
             &self.event_loop_proxy,
-            #[cfg(feature = "persistence")]
-            storage,
-            #[cfg(feature = "persistence")]
-            self.run_option.persistent_windows,
-            #[cfg(feature = "persistence")]
-            self.run_option.persistent_egui_memory,
+            IntegrationOptions {
+                app_id: self.app_id.clone(),
+                root_builder: self.run_option.viewport_builder.clone().unwrap_or_default(),
+                theme: system_theme,
+                custom_cursors: self.custom_cursors.clone(),
+                scroll_options: self.run_option.scroll_options,
+                accelerators,
+                suspend_policy: self.run_option.suspend_policy,
+                render_thread: self.run_option.render_thread,
+                presenter: self.run_option.presenter_options(),
+                renderer: self.run_option.renderer_options(),
+                frame_stats: self.frame_stats.clone(),
+                submit_hooks: self.submit_hooks.clone(),
+                frame_stepper: self.frame_stepper.clone(),
+                #[cfg(feature = "menu")]
+                native_menu,
+                #[cfg(feature = "persistence")]
+                storage,
+                #[cfg(feature = "persistence")]
+                persistent_windows: self.run_option.persistent_windows,
+                #[cfg(feature = "persistence")]
+                persistent_egui_memory: self.run_option.persistent_egui_memory,
+                #[cfg(feature = "persistence")]
+                save_on_focus_loss: self.run_option.save_on_focus_loss,
+                #[cfg(feature = "persistence")]
+                layout_profiles,
+            },
         ));
+        #[cfg(feature = "replay")]
+        integration.set_input_replay(
+            self.run_option.record_input.as_deref(),
+            self.run_option.replay_input.as_deref(),
+        );
 
         self.integration = Some(integration);
         self.app = Some(app);
     }
+
+    /// best-effort save and teardown after a panic unwound out of the event loop.
+    fn cleanup_after_panic(&mut self) {
+        let Some(mut integration) = self.integration.take() else {
+            return;
+        };
+        #[cfg(feature = "persistence")]
+        let app = self.app.as_mut();
+        let document_windows = self.document_windows.as_mut();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(move || {
+            if let Some(document_windows) = document_windows {
+                document_windows.destroy();
+            }
+            integration.clear_poison();
+            #[cfg(feature = "persistence")]
+            if let Some(app) = app {
+                integration.save(app);
+            }
+            integration.destroy();
+            ManuallyDrop::into_inner(integration);
+        }));
+        if result.is_err() {
+            error!("Failed to save and destroy after panic");
+        }
+    }
+
+    fn dispatch_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        let start = Instant::now();
+        let (integration, app) = (
+            self.integration.as_mut().unwrap(),
+            self.app.as_mut().unwrap(),
+        );
+
+        let consumed = integration.handle_window_event(window_id, &event, event_loop, app);
+        if let Some(viewport_id) = integration.viewport_id_from_window_id(window_id) {
+            match &event {
+                winit::event::WindowEvent::Touch(touch) => {
+                    app.handle_event(touch_input_event(viewport_id, touch));
+                }
+                winit::event::WindowEvent::KeyboardInput {
+                    device_id,
+                    event: key_event,
+                    ..
+                } => {
+                    let key_input_event = event::Event::KeyInput {
+                        viewport_id,
+                        device_id: *device_id,
+                        physical_key: key_event.physical_key,
+                        logical_key: key_event.logical_key.clone(),
+                        state: key_event.state,
+                        repeat: key_event.repeat,
+                    };
+                    app.handle_event(key_input_event);
+                }
+                _ => {}
+            }
+        }
+        if !consumed {
+            if let Some(viewport_id) = integration.viewport_id_from_window_id(window_id) {
+                let viewport_event = event::Event::ViewportEvent { viewport_id, event };
+                app.handle_event(viewport_event);
+            }
+        }
+        integration.add_event_handling_time(window_id, start.elapsed());
+    }
+}
+
+fn default_viewport_builder() -> egui::ViewportBuilder {
+    egui::ViewportBuilder::default().with_title("egui-ash")
+}
+
+// the root window is shown with the show policy, like the other viewports.
+fn create_root_window(
+    event_loop: &ActiveEventLoop,
+    context: &egui::Context,
+    viewport_builder: egui::ViewportBuilder,
+) -> winit::window::Window {
+    // with AccessKit, the window is shown once its adapter is created.
+    let viewport_builder = viewport_builder.with_visible(
+        show::show_policy(context) == ShowPolicy::Immediately && !cfg!(feature = "accesskit"),
+    );
+    let window_attributes = activation::apply_startup_token(
+        egui_winit::create_winit_window_attributes(context, viewport_builder.clone()),
+        event_loop,
+    );
+    let window = event_loop
+        .create_window(window_attributes)
+        .expect("Failed to create window");
+    egui_winit::apply_viewport_builder_to_window(context, &window, &viewport_builder);
+    window
 }
 
 fn required_instance_extensions(event_loop: &ActiveEventLoop) -> Vec<CString> {
@@ -269,13 +908,80 @@ fn required_instance_extensions(event_loop: &ActiveEventLoop) -> Vec<CString> {
         .collect::<Vec<_>>()
 }
 
+fn touch_input_event(
+    viewport_id: egui::ViewportId,
+    touch: &winit::event::Touch,
+) -> event::Event<'static> {
+    let (pressure, altitude_angle) = match touch.force {
+        Some(winit::event::Force::Calibrated {
+            force,
+            max_possible_force,
+            altitude_angle,
+        }) => (Some((force / max_possible_force) as f32), altitude_angle),
+        Some(winit::event::Force::Normalized(force)) => (Some(force as f32), None),
+        None => (None, None),
+    };
+    #[cfg(target_os = "windows")]
+    let pen = crate::pen::pen_info(touch.id);
+    #[cfg(not(target_os = "windows"))]
+    let pen: Option<event::PenInfo> = None;
+    let altitude_angle =
+        altitude_angle.or_else(|| pen.and_then(|pen| pen.altitude_angle()).map(f64::from));
+    event::Event::TouchInput {
+        viewport_id,
+        device_id: touch.device_id,
+        id: touch.id,
+        phase: touch.phase,
+        location: touch.location,
+        pressure,
+        altitude_angle,
+        pen,
+        time: Instant::now(),
+    }
+}
+
+fn is_input_event(event: &winit::event::WindowEvent) -> bool {
+    use winit::event::WindowEvent;
+
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::PanGesture { .. }
+            | WindowEvent::DoubleTapGesture { .. }
+            | WindowEvent::RotationGesture { .. }
+            | WindowEvent::TouchpadPressure { .. }
+            | WindowEvent::AxisMotion { .. }
+            | WindowEvent::Touch(_)
+    )
+}
+
 impl<C, A> ApplicationHandler<IntegrationEvent> for State<C, A>
 where
     C: AppCreator<A> + 'static,
     A: Allocator + 'static,
 {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        self.initial_setup(event_loop);
+        match self.integration.as_mut() {
+            Some(integration) => integration.resume(),
+            None => self.initial_setup(event_loop),
+        }
+        if let Some(single_instance) = self.single_instance.take() {
+            single_instance.listen(self.event_loop_proxy.clone());
+        }
+        if self.monitor_watch.is_none() {
+            self.monitor_watch = Some(MonitorWatch::start(
+                event_loop,
+                self.event_loop_proxy.clone(),
+            ));
+        }
 
         // ------- HANDLE RESUMED
         let app_event = event::Event::AppEvent {
@@ -286,6 +992,14 @@ where
             self.app.as_mut().unwrap(),
         );
         app.handle_event(app_event);
+        // files of the launch may arrive before the app is created.
+        #[cfg(target_os = "macos")]
+        if !self.pending_open_files.is_empty() {
+            let app_event = event::Event::AppEvent {
+                event: event::AppEvent::OpenFiles(std::mem::take(&mut self.pending_open_files)),
+            };
+            app.handle_event(app_event);
+        }
         integration.paint_all(event_loop, app);
     }
 
@@ -295,27 +1009,64 @@ where
         window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        if let Some(document_windows) = &mut self.document_windows {
+            if document_windows.handle_window_event(window_id, &event) {
+                return;
+            }
+        }
         let (integration, app) = (
             self.integration.as_mut().unwrap(),
             self.app.as_mut().unwrap(),
         );
 
-        let consumed = integration.handle_window_event(
-            window_id,
-            &event,
-            event_loop,
-            self.run_option.follow_system_theme,
-            app,
-        );
-        if consumed {
+        if integration.handle_mirror_window_event(window_id, &event) {
             return;
         }
-
         let Some(viewport_id) = integration.viewport_id_from_window_id(window_id) else {
             return;
         };
-        let viewport_event = event::Event::ViewportEvent { viewport_id, event };
-        app.handle_event(viewport_event);
+        #[cfg(feature = "tray")]
+        if viewport_id == egui::ViewportId::ROOT
+            && event == winit::event::WindowEvent::CloseRequested
+            && self.tray.hide_on_close()
+        {
+            integration.set_viewports_visible(false);
+            return;
+        }
+        if event == winit::event::WindowEvent::CloseRequested
+            && app.on_close_requested(viewport_id) == CloseResponse::Deny
+        {
+            return;
+        }
+        if is_input_event(&event) && app.filter_event(viewport_id, &event) == EventFilter::Consumed
+        {
+            return;
+        }
+
+        if let winit::event::WindowEvent::CursorMoved {
+            device_id,
+            position,
+        } = &event
+        {
+            let pointer_moved_event = event::Event::PointerMoved {
+                viewport_id,
+                device_id: *device_id,
+                position: *position,
+                time: Instant::now(),
+            };
+            app.handle_event(pointer_moved_event);
+
+            if self.run_option.pointer_move_policy == PointerMovePolicy::CoalescePerFrame {
+                self.pending_cursor_moved.insert(window_id, event);
+                return;
+            }
+        }
+
+        // keep the event order, the pending move must arrive before e.g. a click.
+        if let Some(cursor_moved) = self.pending_cursor_moved.remove(&window_id) {
+            self.dispatch_window_event(event_loop, window_id, cursor_moved);
+        }
+        self.dispatch_window_event(event_loop, window_id, event);
     }
 
     fn device_event(
@@ -324,55 +1075,264 @@ where
         device_id: winit::event::DeviceId,
         event: winit::event::DeviceEvent,
     ) {
+        if let Some(integration) = self.integration.as_mut() {
+            integration.handle_device_event(&event);
+        }
         let app = self.app.as_mut().unwrap();
 
         let device_event = event::Event::DeviceEvent { device_id, event };
         app.handle_event(device_event);
     }
 
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: IntegrationEvent) {
-        #[cfg(feature = "accesskit")]
-        {
-            let (integration, app) = (
-                self.integration.as_mut().unwrap(),
-                self.app.as_mut().unwrap(),
-            );
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: IntegrationEvent) {
+        match event {
+            // `exiting` saves and destroys after the event loop stopped.
+            IntegrationEvent::Exit => event_loop.exit(),
+            // the repaint is scheduled in `about_to_wait`.
+            IntegrationEvent::RequestRepaint => (),
+            IntegrationEvent::CheckMonitors => {
+                let Some(monitor_watch) = self.monitor_watch.as_mut() else {
+                    return;
+                };
+                if !monitor_watch.changed(event_loop) {
+                    return;
+                }
+                let (Some(integration), Some(app)) = (self.integration.as_mut(), self.app.as_mut())
+                else {
+                    return;
+                };
 
-            integration.handle_accesskit_event(&_event.accesskit, _event_loop, app);
-            let user_event = event::Event::AccessKitActionRequest(_event.accesskit);
-            app.handle_event(user_event);
+                integration.monitors_changed(event_loop, app);
+            }
+            #[cfg(feature = "accesskit")]
+            IntegrationEvent::AccessKit(accesskit_event) => {
+                let (integration, app) = (
+                    self.integration.as_mut().unwrap(),
+                    self.app.as_mut().unwrap(),
+                );
+
+                integration.handle_accesskit_event(&accesskit_event);
+                let user_event = event::Event::AccessKitActionRequest(accesskit_event);
+                app.handle_event(user_event);
+            }
+            #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+            IntegrationEvent::DragFinished(result) => {
+                let app = self.app.as_mut().unwrap();
+
+                let drag_event = event::Event::DragFinished { result };
+                app.handle_event(drag_event);
+            }
+            #[cfg(feature = "file-dialog")]
+            IntegrationEvent::FileDialogClosed(id, paths) => {
+                let (Some(integration), Some(app)) = (self.integration.as_mut(), self.app.as_mut())
+                else {
+                    return;
+                };
+
+                app.handle_event(event::Event::FileDialogClosed { id, paths });
+                // the app shows the paths in the next frame.
+                integration.context.request_repaint();
+            }
+            #[cfg(feature = "global-hotkey")]
+            IntegrationEvent::GlobalHotkey(hotkey_event) => {
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                let hotkey_event = event::Event::GlobalHotkey {
+                    id: hotkey_event.id,
+                    state: hotkey_event.state,
+                };
+                app.handle_event(hotkey_event);
+            }
+            #[cfg(all(feature = "menu", any(target_os = "windows", target_os = "macos")))]
+            IntegrationEvent::MenuActivated(id) => {
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                let menu_event = event::Event::MenuActivated { id };
+                app.handle_event(menu_event);
+            }
+            #[cfg(feature = "message-dialog")]
+            IntegrationEvent::MessageDialogClosed(id, result) => {
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                app.handle_event(event::Event::MessageDialogClosed { id, result });
+                // the dialog closed on another thread, which repainted the viewports.
+            }
+            #[cfg(target_os = "macos")]
+            IntegrationEvent::OpenFiles(paths) => {
+                let Some(app) = self.app.as_mut() else {
+                    self.pending_open_files.extend(paths);
+                    return;
+                };
+
+                let app_event = event::Event::AppEvent {
+                    event: event::AppEvent::OpenFiles(paths),
+                };
+                app.handle_event(app_event);
+            }
+            #[cfg(feature = "power")]
+            IntegrationEvent::PowerChanged(status) => {
+                if let Some(battery_max_fps) = self.run_option.battery_max_fps {
+                    if status.saving_power() {
+                        if self.max_fps_before_battery.is_none() {
+                            let max_fps = self.frame_limiter.max_fps();
+                            self.max_fps_before_battery = Some(max_fps);
+                            let capped = max_fps
+                                .map_or(battery_max_fps, |max_fps| max_fps.min(battery_max_fps));
+                            self.frame_limiter.set_max_fps(Some(capped));
+                        }
+                    } else if let Some(max_fps) = self.max_fps_before_battery.take() {
+                        self.frame_limiter.set_max_fps(max_fps);
+                    }
+                }
+
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                app.handle_event(event::Event::AppEvent {
+                    event: event::AppEvent::PowerChanged(status),
+                });
+            }
+            IntegrationEvent::SecondInstance(forwarded) => {
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                let app_event = event::AppEvent::SecondInstance {
+                    args: forwarded.args,
+                    cwd: forwarded.cwd,
+                };
+                app.handle_event(event::Event::AppEvent { event: app_event });
+            }
+            #[cfg(all(feature = "tray", any(target_os = "windows", target_os = "macos")))]
+            IntegrationEvent::TrayIcon(tray_event) => {
+                let Some(app) = self.app.as_mut() else {
+                    return;
+                };
+
+                let tray_event = event::Event::TrayIconEvent(tray_event);
+                app.handle_event(tray_event);
+            }
+            #[cfg(feature = "gamepad")]
+            IntegrationEvent::Gamepad(gamepad_event) => {
+                // the gamepad thread starts before the app is created.
+                let (Some(integration), Some(app)) = (self.integration.as_mut(), self.app.as_mut())
+                else {
+                    return;
+                };
+
+                let events = self.gamepad_navigation.translate(&gamepad_event);
+                integration.handle_gamepad_events(events);
+                let gamepad_event = event::Event::GamepadEvent(gamepad_event);
+                app.handle_event(gamepad_event);
+            }
         }
     }
 
     fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
-        let app = self.app.as_mut().unwrap();
+        let (integration, app) = (
+            self.integration.as_mut().unwrap(),
+            self.app.as_mut().unwrap(),
+        );
 
         let app_event = event::Event::AppEvent {
             event: event::AppEvent::Suspended,
         };
         app.handle_event(app_event);
+        integration.suspend();
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        for (window_id, cursor_moved) in std::mem::take(&mut self.pending_cursor_moved) {
+            self.dispatch_window_event(event_loop, window_id, cursor_moved);
+        }
+
         let (integration, app) = (
             self.integration.as_mut().unwrap(),
             self.app.as_mut().unwrap(),
         );
 
+        for viewport_id in integration.take_gpu_hangs() {
+            app.handle_event(event::Event::GpuHang { viewport_id });
+        }
+        integration.repaint_stale_swapchains();
+        integration.update_mirrors(event_loop);
         let app_event = event::Event::AppEvent {
             event: event::AppEvent::AboutToWait,
         };
         app.handle_event(app_event);
-        integration.paint_all(event_loop, app);
+        let mut wake_up = None;
+        if let Some(fixed_timestep) = &mut self.fixed_timestep {
+            fixed_timestep.tick(&integration.context, app);
+            wake_up = Some(fixed_timestep.next_step());
+        }
+        #[cfg(feature = "tray")]
+        if let Some(visible) = self.tray.take_visibility_request() {
+            integration.set_viewports_visible(visible);
+        }
+        #[cfg(feature = "splash")]
+        if self.splash.is_some() && integration.is_root_shown() {
+            if let Some(splash) = self.splash.take() {
+                splash.close();
+            }
+        }
+        integration.paint_steps(event_loop, app);
+        // a frame that is not due yet is painted when the loop wakes up for it.
+        let deferred_frame = if integration.repaint_due() {
+            self.frame_pacer.defer_frame()
+        } else {
+            None
+        };
+        if deferred_frame.is_none() {
+            integration.paint_all(event_loop, app);
+        }
+        let next_frame = deferred_frame.or_else(|| integration.next_repaint());
+        wake_up = match (wake_up, next_frame) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(document_windows) = &mut self.document_windows {
+            document_windows.update(event_loop, app);
+            let next_document = document_windows.paint(app);
+            wake_up = match (wake_up, next_document) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        event_loop.set_control_flow(match wake_up {
+            Some(wake_up) => winit::event_loop::ControlFlow::WaitUntil(wake_up),
+            None => winit::event_loop::ControlFlow::Wait,
+        });
+        #[cfg(feature = "persistence")]
+        integration.handle_layout_requests(event_loop, app);
+        // save after painting, so that a slow save does not delay the frame.
+        #[cfg(feature = "persistence")]
+        integration.maybe_autosave(app);
+
+        // start drags outside of window event handling, the platform drag loop may dispatch events.
+        #[cfg(all(feature = "drag", any(target_os = "windows", target_os = "macos")))]
+        while let Ok(request) = self.drag_requests.try_recv() {
+            integration.start_drag(request, &self.event_loop_proxy);
+        }
     }
 
     fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {
-        let app = self.app.as_mut().unwrap();
+        let (integration, app) = (
+            self.integration.as_mut().unwrap(),
+            self.app.as_mut().unwrap(),
+        );
 
         let app_event = event::Event::AppEvent {
             event: event::AppEvent::MemoryWarning,
         };
         app.handle_event(app_event);
+        integration.trim_memory(app);
     }
 
     fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
@@ -387,11 +1347,15 @@ where
         app.handle_event(app_event);
         #[cfg(feature = "persistence")]
         integration.save(app);
+        if let Some(document_windows) = &mut self.document_windows {
+            document_windows.destroy();
+        }
         integration.destroy();
         unsafe {
             ManuallyDrop::drop(integration);
         }
-        self.exit_signal.send(ExitCode::SUCCESS);
+        // a panic after this point must not destroy the integration again.
+        self.integration = None;
     }
 }
 
@@ -0,0 +1,64 @@
+// egui animates wheel steps of this many points or more over several frames.
+const EGUI_SMOOTH_SCROLL_THRESHOLD: f32 = 8.0;
+
+/// mouse wheel and touchpad scroll settings, applied to all viewports before the events reach egui.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollOptions {
+    /// points scrolled per line, e.g. per mouse wheel notch.
+    pub line_height: f32,
+    /// multiplier for pixel deltas of touchpads and high resolution wheels. `1.0` passes them through.
+    pub pixel_delta_multiplier: f32,
+    /// animate large scroll steps over a few frames. Otherwise the whole step is applied at once.
+    pub smooth: bool,
+}
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        Self {
+            // same as egui's `InputOptions::line_scroll_speed`
+            line_height: 40.0,
+            pixel_delta_multiplier: 1.0,
+            smooth: true,
+        }
+    }
+}
+impl ScrollOptions {
+    /// apply the options to the mouse wheel events in `events[start..]`.
+    pub(crate) fn apply(&self, events: &mut Vec<egui::Event>, start: usize) {
+        let new_events = events.split_off(start);
+        for event in new_events {
+            let egui::Event::MouseWheel {
+                unit,
+                delta,
+                modifiers,
+            } = event
+            else {
+                events.push(event);
+                continue;
+            };
+            let delta = match unit {
+                egui::MouseWheelUnit::Line => delta * self.line_height,
+                egui::MouseWheelUnit::Point => delta * self.pixel_delta_multiplier,
+                egui::MouseWheelUnit::Page => {
+                    events.push(event);
+                    continue;
+                }
+            };
+
+            // egui applies small steps immediately, so split large steps to disable the animation.
+            let steps = if self.smooth {
+                1
+            } else {
+                (delta.length() / (EGUI_SMOOTH_SCROLL_THRESHOLD * 0.9))
+                    .ceil()
+                    .max(1.0) as usize
+            };
+            for _ in 0..steps {
+                events.push(egui::Event::MouseWheel {
+                    unit: egui::MouseWheelUnit::Point,
+                    delta: delta / steps as f32,
+                    modifiers,
+                });
+            }
+        }
+    }
+}
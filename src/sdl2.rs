@@ -0,0 +1,1045 @@
+use egui_winit::winit;
+use raw_window_handle::{HasDisplayHandle as _, HasWindowHandle as _};
+use sdl2::{
+    event::{Event as SdlEvent, WindowEvent as SdlWindowEvent},
+    keyboard::{Keycode, Mod, Scancode},
+    mouse::{Cursor, MouseButton, SystemCursor},
+};
+use std::{
+    ffi::CString,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "persistence")]
+use crate::storage;
+use crate::{
+    app::{App, AshRenderState, CloseResponse},
+    clear_color, event,
+    fonts::Fonts,
+    raw::{self, RawIntegration, RawOptions, RawWindow},
+    renderer::ImageRegistry,
+    stats::FrameStatistics,
+    submit::SubmitHooks,
+    theme,
+    timestep::{FixedTimestep, FrameLimiter, FramePacer},
+    window_level, zoom, Allocator, AllocatorDebugOptions, ExitSignal, RunOption,
+};
+
+/// passed to [`Sdl2AppCreator::create()`] for creating egui-ash app on SDL2.
+pub struct Sdl2CreationContext<'a> {
+    /// SDL context
+    pub sdl: &'a sdl2::Sdl,
+
+    /// SDL video subsystem
+    pub video: &'a sdl2::VideoSubsystem,
+
+    /// root window
+    pub main_window: &'a sdl2::video::Window,
+
+    /// egui context
+    pub context: egui::Context,
+
+    /// required instance extensions for ash vulkan
+    pub required_instance_extensions: Vec<CString>,
+
+    /// required device extensions for ash vulkan
+    pub required_device_extensions: Vec<CString>,
+
+    /// user texture image registry for egui-ash
+    pub image_registry: ImageRegistry,
+
+    /// exit signal sender
+    pub exit_signal: ExitSignal,
+
+    /// persisted app state, to restore the state saved in [`App::save`]
+    #[cfg(feature = "persistence")]
+    pub storage: &'a storage::Storage,
+
+    /// fonts registered on top of the default fonts
+    pub fonts: Fonts,
+
+    /// frame rate cap
+    pub frame_limiter: FrameLimiter,
+
+    /// metrics of the last frames
+    pub frame_stats: FrameStatistics,
+
+    /// callbacks around the queue submissions of egui-ash
+    pub submit_hooks: SubmitHooks,
+
+    /// debug options to create the allocator with
+    pub allocator_debug: AllocatorDebugOptions,
+}
+
+/// egui-ash app creator trait for the SDL2 backend.
+pub trait Sdl2AppCreator<A: Allocator + 'static> {
+    type App: App;
+
+    /// create egui-ash app.
+    fn create(&self, cc: Sdl2CreationContext) -> (Self::App, AshRenderState<A>);
+}
+
+/// egui-ash run function for the SDL2 backend.
+///
+/// The SDL2 backend drives a single window. Child viewports are embedded in the root window.
+/// SDL events are delivered to [`App::handle_event`] as [`event::Event::Sdl2Event`]
+/// when egui did not consume them.
+pub fn run<C: Sdl2AppCreator<A> + 'static, A: Allocator + 'static>(
+    app_id: impl Into<String>,
+    creator: C,
+    run_option: RunOption,
+) -> ExitCode {
+    let app_id: String = app_id.into();
+
+    // egui draws the composition text, but the candidate list needs the native IME window on Windows.
+    sdl2::hint::set("SDL_IME_SHOW_UI", "1");
+    let sdl = sdl2::init().expect("Failed to initialize SDL2");
+    let video = sdl
+        .video()
+        .expect("Failed to initialize SDL2 video subsystem");
+    let mut event_pump = sdl.event_pump().expect("Failed to create SDL2 event pump");
+
+    #[cfg(feature = "persistence")]
+    let mut run_option = run_option;
+    #[cfg(feature = "persistence")]
+    let mut storage = storage::Storage::from_run_option(&mut run_option, &app_id)
+        .expect("Failed to create storage");
+
+    let context = egui::Context::default();
+    #[cfg(feature = "persistence")]
+    if run_option.persistent_egui_memory {
+        if let Some(memory) = storage.get_egui_memory() {
+            context.memory_mut(|m| *m = memory);
+        }
+    }
+    zoom::set_zoom_options(&context, run_option.zoom);
+    #[cfg(feature = "persistence")]
+    zoom::restore_zoom_factor(&context, &storage);
+    theme::set_styles(
+        &context,
+        run_option.light_style.as_ref(),
+        run_option.dark_style.as_ref(),
+    );
+    clear_color::set_default_clear_color(&context, run_option.clear_color);
+    let fonts = Fonts::new(context.clone());
+    #[cfg(feature = "system-fonts")]
+    if run_option.system_fonts {
+        fonts.set_base(crate::system_fonts::font_definitions());
+    }
+    let mut window = create_window(&video, &app_id, &run_option);
+    if let Some(level) = run_option
+        .viewport_builder
+        .as_ref()
+        .and_then(|builder| builder.window_level)
+    {
+        window_level::store_window_level(&context, egui::ViewportId::ROOT, level);
+    }
+
+    let (image_registry, image_registry_receiver) = ImageRegistry::new();
+    let (exit_signal_tx, exit_signal_rx) = std::sync::mpsc::channel();
+    let exit_signal = ExitSignal {
+        tx: exit_signal_tx,
+        event_loop_proxy: None,
+    };
+
+    let required_instance_extensions = raw::required_instance_extensions(
+        window
+            .display_handle()
+            .expect("Unable to retrieve a display handle")
+            .as_raw(),
+    );
+
+    let mut required_device_extensions = raw::required_device_extensions();
+    if let Some(diagnostics) = run_option.gpu_crash_diagnostics {
+        required_device_extensions.push(diagnostics.device_extension().to_owned());
+    }
+
+    let frame_limiter = FrameLimiter::new(run_option.max_fps);
+    let frame_stats = FrameStatistics::default();
+    let submit_hooks = SubmitHooks::default();
+    let cc = Sdl2CreationContext {
+        sdl: &sdl,
+        video: &video,
+        main_window: &window,
+        context: context.clone(),
+        required_instance_extensions,
+        required_device_extensions,
+        image_registry: image_registry.clone(),
+        exit_signal,
+        #[cfg(feature = "persistence")]
+        storage: &storage,
+        fonts,
+        frame_limiter: frame_limiter.clone(),
+        frame_stats: frame_stats.clone(),
+        submit_hooks: submit_hooks.clone(),
+        allocator_debug: run_option.allocator_debug,
+    };
+    let (mut app, render_state) = creator.create(cc);
+
+    let (width, height) = window.drawable_size();
+    // SAFETY: the window outlives the integration, which is destroyed at the end of this function.
+    let mut integration = unsafe {
+        RawIntegration::from_parts(
+            context.clone(),
+            RawWindow {
+                display_handle: window
+                    .display_handle()
+                    .expect("Unable to retrieve a display handle")
+                    .as_raw(),
+                window_handle: window
+                    .window_handle()
+                    .expect("Unable to retrieve a window handle")
+                    .as_raw(),
+            },
+            winit::dpi::PhysicalSize::new(width, height),
+            native_pixels_per_point(&window),
+            render_state,
+            (image_registry, image_registry_receiver),
+            RawOptions {
+                presenter: run_option.presenter_options(),
+                renderer: run_option.renderer_options(),
+                frame_stats: frame_stats.clone(),
+                submit_hooks,
+            },
+        )
+    };
+
+    let mut input = Sdl2Input::new(&sdl);
+    let mut fixed_timestep = run_option.fixed_timestep.and_then(FixedTimestep::new);
+    let mut frame_pacer = FramePacer::new(frame_limiter);
+    #[cfg(feature = "persistence")]
+    let mut last_auto_save = Instant::now();
+    #[cfg(feature = "persistence")]
+    let mut save_requested = false;
+
+    app.handle_event(event::Event::AppEvent {
+        event: event::AppEvent::Resumed,
+    });
+
+    let mut repaint_delay = Duration::ZERO;
+    let exit_code = 'main: loop {
+        // wait for events until the next repaint is due
+        let first_event = if repaint_delay.is_zero() {
+            event_pump.poll_event()
+        } else {
+            let timeout = repaint_delay.min(Duration::from_secs(1));
+            event_pump.wait_event_timeout(timeout.as_millis() as u32)
+        };
+        let events = first_event
+            .into_iter()
+            .chain(event_pump.poll_iter())
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        for sdl_event in events {
+            if let SdlEvent::Quit { .. } = sdl_event {
+                if app.on_close_requested(egui::ViewportId::ROOT) == CloseResponse::Deny {
+                    continue;
+                }
+                break 'main ExitCode::SUCCESS;
+            }
+            #[cfg(feature = "persistence")]
+            if let SdlEvent::Window {
+                win_event: SdlWindowEvent::FocusLost,
+                ..
+            } = sdl_event
+            {
+                save_requested |= run_option.save_on_focus_loss;
+            }
+
+            let egui_input = integration.egui_input_mut();
+            let event_count = egui_input.events.len();
+            let consumed = input.on_event(&context, egui_input, &sdl_event);
+            run_option
+                .scroll_options
+                .apply(&mut egui_input.events, event_count);
+            if !consumed {
+                app.handle_event(event::Event::Sdl2Event(sdl_event));
+            }
+        }
+        let event_handling_time = start.elapsed();
+        frame_stats.update(egui::ViewportId::ROOT, |stats| {
+            stats.event_handling_time = event_handling_time;
+        });
+
+        if let Ok(exit_code) = exit_signal_rx.try_recv() {
+            break 'main exit_code;
+        }
+
+        let (width, height) = window.drawable_size();
+        integration.resize(
+            winit::dpi::PhysicalSize::new(width, height),
+            native_pixels_per_point(&window),
+        );
+
+        frame_pacer.wait_for_frame();
+        if let Some(fixed_timestep) = &mut fixed_timestep {
+            fixed_timestep.tick(&context, &mut app);
+        }
+        let raw::RawOutput {
+            platform_output,
+            repaint_delay: delay,
+            viewport_commands,
+            gpu_hang,
+            ..
+        } = integration.run(&mut app);
+        repaint_delay = delay;
+        if gpu_hang {
+            app.handle_event(event::Event::GpuHang {
+                viewport_id: egui::ViewportId::ROOT,
+            });
+        }
+        if let Some(fixed_timestep) = &fixed_timestep {
+            let until_next_step = fixed_timestep
+                .next_step()
+                .saturating_duration_since(Instant::now());
+            repaint_delay = repaint_delay.min(until_next_step);
+        }
+
+        input.handle_platform_output(&context, integration.egui_input_mut(), platform_output);
+        for command in &viewport_commands {
+            if process_viewport_command(&context, &mut window, &sdl.mouse(), command) {
+                break 'main ExitCode::SUCCESS;
+            }
+        }
+
+        // autosave
+        #[cfg(feature = "persistence")]
+        if save_requested || last_auto_save.elapsed() >= app.auto_save_interval() {
+            save(&context, &mut storage, &run_option, &mut app);
+            save_requested = false;
+            last_auto_save = Instant::now();
+        }
+    };
+
+    app.handle_event(event::Event::AppEvent {
+        event: event::AppEvent::LoopExiting,
+    });
+    #[cfg(feature = "persistence")]
+    save(&context, &mut storage, &run_option, &mut app);
+
+    // destroy integration objects before the app for gpu_allocator drop order reasons.
+    integration.destroy();
+    drop(app);
+
+    exit_code
+}
+
+fn native_pixels_per_point(window: &sdl2::video::Window) -> f32 {
+    window.drawable_size().0 as f32 / window.size().0.max(1) as f32
+}
+
+fn create_window(
+    video: &sdl2::VideoSubsystem,
+    app_id: &str,
+    run_option: &RunOption,
+) -> sdl2::video::Window {
+    let builder = run_option.viewport_builder.clone().unwrap_or_default();
+    let title = builder.title.clone().unwrap_or_else(|| app_id.to_owned());
+    let inner_size = builder
+        .inner_size
+        .unwrap_or_else(|| egui::vec2(800.0, 600.0));
+
+    let mut window_builder = video.window(
+        &title,
+        inner_size.x.round() as u32,
+        inner_size.y.round() as u32,
+    );
+    window_builder.vulkan().allow_highdpi().position_centered();
+    if builder.resizable.unwrap_or(true) {
+        window_builder.resizable();
+    }
+    if builder.decorations == Some(false) {
+        window_builder.borderless();
+    }
+    if builder.maximized == Some(true) {
+        window_builder.maximized();
+    }
+    if builder.fullscreen == Some(true) {
+        window_builder.fullscreen_desktop();
+    }
+    let mut window = window_builder
+        .build()
+        .expect("Failed to create SDL2 window");
+
+    if let Some(min_inner_size) = builder.min_inner_size {
+        let _ = window.set_minimum_size(
+            min_inner_size.x.round() as u32,
+            min_inner_size.y.round() as u32,
+        );
+    }
+    if builder.window_level == Some(egui::WindowLevel::AlwaysOnTop) {
+        window.set_always_on_top(true);
+    }
+    window
+}
+
+// returns true if the viewport requests close.
+fn process_viewport_command(
+    context: &egui::Context,
+    window: &mut sdl2::video::Window,
+    mouse: &sdl2::mouse::MouseUtil,
+    command: &egui::ViewportCommand,
+) -> bool {
+    match command {
+        // SDL releases the grab while the window is not focused.
+        egui::ViewportCommand::CursorGrab(grab) => {
+            let (grab, relative) = match grab {
+                egui::viewport::CursorGrab::None => (false, false),
+                egui::viewport::CursorGrab::Confined => (true, false),
+                egui::viewport::CursorGrab::Locked => (false, true),
+            };
+            window.set_mouse_grab(grab);
+            mouse.set_relative_mouse_mode(relative);
+        }
+        egui::ViewportCommand::CursorVisible(visible) => mouse.show_cursor(*visible),
+        egui::ViewportCommand::Close => return true,
+        egui::ViewportCommand::Title(title) => {
+            let _ = window.set_title(title);
+        }
+        egui::ViewportCommand::InnerSize(size) => {
+            let _ = window.set_size(size.x.round() as u32, size.y.round() as u32);
+        }
+        egui::ViewportCommand::Minimized(true) => window.minimize(),
+        egui::ViewportCommand::Maximized(true) => window.maximize(),
+        egui::ViewportCommand::Minimized(false) | egui::ViewportCommand::Maximized(false) => {
+            window.restore();
+        }
+        egui::ViewportCommand::Fullscreen(fullscreen) => {
+            let _ = window.set_fullscreen(if *fullscreen {
+                sdl2::video::FullscreenType::Desktop
+            } else {
+                sdl2::video::FullscreenType::Off
+            });
+        }
+        egui::ViewportCommand::Focus => window.raise(),
+        egui::ViewportCommand::WindowLevel(level) => {
+            window.set_always_on_top(*level == egui::WindowLevel::AlwaysOnTop);
+            window_level::store_window_level(context, egui::ViewportId::ROOT, *level);
+        }
+        _ => {}
+    }
+    false
+}
+
+#[cfg(feature = "persistence")]
+fn save(
+    context: &egui::Context,
+    storage: &mut storage::Storage,
+    run_option: &RunOption,
+    app: &mut impl App,
+) {
+    if run_option.persistent_egui_memory {
+        storage.set_egui_memory(&context.memory(|m| m.clone()));
+    }
+    zoom::save_zoom_factor(context, storage);
+    app.save(storage);
+    storage.flush();
+}
+
+/// translates SDL2 events into `egui::RawInput`.
+struct Sdl2Input {
+    mouse: sdl2::mouse::MouseUtil,
+    text_input: sdl2::keyboard::TextInputUtil,
+    clipboard: sdl2::clipboard::ClipboardUtil,
+    pointer_pos_in_points: Option<egui::Pos2>,
+    ime_enabled: bool,
+    cursor: Option<(egui::CursorIcon, Cursor)>,
+}
+impl Sdl2Input {
+    fn new(sdl: &sdl2::Sdl) -> Self {
+        let video = sdl
+            .video()
+            .expect("Failed to initialize SDL2 video subsystem");
+        Self {
+            mouse: sdl.mouse(),
+            text_input: video.text_input(),
+            clipboard: video.clipboard(),
+            pointer_pos_in_points: None,
+            ime_enabled: false,
+            cursor: None,
+        }
+    }
+
+    // returns true if the event is consumed by egui.
+    fn on_event(
+        &mut self,
+        context: &egui::Context,
+        egui_input: &mut egui::RawInput,
+        event: &SdlEvent,
+    ) -> bool {
+        // SDL window coordinates are (logical) window units, egui points additionally include zoom.
+        let to_points = |x: i32, y: i32| egui::pos2(x as f32, y as f32) / context.zoom_factor();
+
+        match event {
+            SdlEvent::Window { win_event, .. } => {
+                match win_event {
+                    SdlWindowEvent::FocusGained | SdlWindowEvent::FocusLost => {
+                        let focused = matches!(win_event, SdlWindowEvent::FocusGained);
+                        egui_input.focused = focused;
+                        egui_input.events.push(egui::Event::WindowFocused(focused));
+                    }
+                    SdlWindowEvent::Leave => {
+                        self.pointer_pos_in_points = None;
+                        egui_input.events.push(egui::Event::PointerGone);
+                    }
+                    SdlWindowEvent::Close => {
+                        egui_input
+                            .viewports
+                            .entry(egui::ViewportId::ROOT)
+                            .or_default()
+                            .events
+                            .push(egui::ViewportEvent::Close);
+                    }
+                    _ => {}
+                }
+                false
+            }
+            SdlEvent::MouseMotion { x, y, .. } => {
+                let pos = to_points(*x, *y);
+                self.pointer_pos_in_points = Some(pos);
+                egui_input.events.push(egui::Event::PointerMoved(pos));
+                context.is_using_pointer()
+            }
+            SdlEvent::MouseButtonDown {
+                mouse_btn, x, y, ..
+            }
+            | SdlEvent::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                if let Some(button) = translate_mouse_button(*mouse_btn) {
+                    let pos = to_points(*x, *y);
+                    self.pointer_pos_in_points = Some(pos);
+                    egui_input.events.push(egui::Event::PointerButton {
+                        pos,
+                        button,
+                        pressed: matches!(event, SdlEvent::MouseButtonDown { .. }),
+                        modifiers: egui_input.modifiers,
+                    });
+                }
+                context.wants_pointer_input()
+            }
+            SdlEvent::MouseWheel {
+                precise_x,
+                precise_y,
+                direction,
+                ..
+            } => {
+                let flip = if *direction == sdl2::mouse::MouseWheelDirection::Flipped {
+                    -1.0
+                } else {
+                    1.0
+                };
+                let delta = egui::vec2(-*precise_x, *precise_y) * flip;
+                let modifiers = egui_input.modifiers;
+                if modifiers.ctrl || modifiers.command {
+                    // Treat as zoom instead:
+                    let factor = (delta.y / 10.0).exp();
+                    egui_input.events.push(egui::Event::Zoom(factor));
+                } else {
+                    egui_input.events.push(egui::Event::MouseWheel {
+                        unit: egui::MouseWheelUnit::Line,
+                        delta,
+                        modifiers,
+                    });
+                }
+                context.wants_pointer_input()
+            }
+            SdlEvent::KeyDown {
+                keycode,
+                scancode,
+                keymod,
+                ..
+            }
+            | SdlEvent::KeyUp {
+                keycode,
+                scancode,
+                keymod,
+                ..
+            } => {
+                let pressed = matches!(event, SdlEvent::KeyDown { .. });
+                egui_input.modifiers = translate_modifiers(*keymod);
+                let Some(key) = keycode.and_then(translate_key) else {
+                    return context.wants_keyboard_input();
+                };
+
+                if pressed {
+                    let modifiers = egui_input.modifiers;
+                    if modifiers.command && key == egui::Key::C {
+                        egui_input.events.push(egui::Event::Copy);
+                    } else if modifiers.command && key == egui::Key::X {
+                        egui_input.events.push(egui::Event::Cut);
+                    } else if modifiers.command && key == egui::Key::V {
+                        if let Ok(contents) = self.clipboard.clipboard_text() {
+                            let contents = contents.replace("\r\n", "\n");
+                            if !contents.is_empty() {
+                                egui_input.events.push(egui::Event::Paste(contents));
+                            }
+                        }
+                    }
+                }
+
+                egui_input.events.push(egui::Event::Key {
+                    key,
+                    physical_key: scancode.and_then(translate_scancode),
+                    pressed,
+                    repeat: false, // egui will fill this in for us!
+                    modifiers: egui_input.modifiers,
+                });
+                // When pressing the Tab key, egui focuses the first focusable element, hence Tab always consumes.
+                context.wants_keyboard_input() || key == egui::Key::Tab
+            }
+            SdlEvent::TextEditing { text, .. } => {
+                // the composition was cancelled or is about to be committed.
+                if text.is_empty() {
+                    if self.ime_enabled {
+                        self.ime_enabled = false;
+                        egui_input
+                            .events
+                            .push(egui::Event::Ime(egui::ImeEvent::Preedit(String::new())));
+                        egui_input
+                            .events
+                            .push(egui::Event::Ime(egui::ImeEvent::Disabled));
+                    }
+                    return context.wants_keyboard_input();
+                }
+                if !self.ime_enabled {
+                    self.ime_enabled = true;
+                    egui_input
+                        .events
+                        .push(egui::Event::Ime(egui::ImeEvent::Enabled));
+                }
+                egui_input
+                    .events
+                    .push(egui::Event::Ime(egui::ImeEvent::Preedit(text.clone())));
+                context.wants_keyboard_input()
+            }
+            SdlEvent::TextInput { text, .. } => {
+                if self.ime_enabled {
+                    self.ime_enabled = false;
+                    egui_input
+                        .events
+                        .push(egui::Event::Ime(egui::ImeEvent::Commit(text.clone())));
+                    egui_input
+                        .events
+                        .push(egui::Event::Ime(egui::ImeEvent::Disabled));
+                } else {
+                    let modifiers = egui_input.modifiers;
+                    let is_cmd = modifiers.ctrl || modifiers.command || modifiers.mac_cmd;
+                    if !is_cmd && !text.chars().any(char::is_control) {
+                        egui_input.events.push(egui::Event::Text(text.clone()));
+                    }
+                }
+                context.wants_keyboard_input()
+            }
+            SdlEvent::FingerDown {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                pressure,
+                ..
+            }
+            | SdlEvent::FingerMotion {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                pressure,
+                ..
+            }
+            | SdlEvent::FingerUp {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                pressure,
+                ..
+            } => {
+                let phase = match event {
+                    SdlEvent::FingerDown { .. } => egui::TouchPhase::Start,
+                    SdlEvent::FingerMotion { .. } => egui::TouchPhase::Move,
+                    _ => egui::TouchPhase::End,
+                };
+                // SDL finger positions are normalized to the window.
+                let size = context.viewport_rect().size();
+                let pos = egui::pos2(*x * size.x, *y * size.y);
+                egui_input.events.push(egui::Event::Touch {
+                    device_id: egui::TouchDeviceId(*touch_id as u64),
+                    id: egui::TouchId(*finger_id as u64),
+                    phase,
+                    pos,
+                    force: Some(*pressure),
+                });
+                // SDL also emits synthesized mouse events for touches, which egui uses as the pointer.
+                match phase {
+                    egui::TouchPhase::Move => context.is_using_pointer(),
+                    _ => context.wants_pointer_input(),
+                }
+            }
+            SdlEvent::DropFile { filename, .. } => {
+                egui_input.hovered_files.clear();
+                egui_input.dropped_files.push(egui::DroppedFile {
+                    path: Some(filename.into()),
+                    ..Default::default()
+                });
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_platform_output(
+        &mut self,
+        context: &egui::Context,
+        egui_input: &mut egui::RawInput,
+        platform_output: egui::PlatformOutput,
+    ) {
+        for command in platform_output.commands {
+            match command {
+                egui::OutputCommand::CopyText(text) => {
+                    if let Err(err) = self.clipboard.set_clipboard_text(&text) {
+                        error!("Failed to set clipboard text: {}", err);
+                    }
+                }
+                egui::OutputCommand::CopyImage(_) => {
+                    warn!("Copying images is not supported by the SDL2 backend.");
+                }
+                egui::OutputCommand::OpenUrl(open_url) => {
+                    if let Err(err) = sdl2::url::open_url(&open_url.url) {
+                        error!("Failed to open url {}: {}", open_url.url, err);
+                    }
+                }
+            }
+        }
+
+        self.set_cursor_icon(platform_output.cursor_icon);
+
+        match platform_output.ime {
+            Some(ime) => {
+                if !self.text_input.is_active() {
+                    self.text_input.start();
+                }
+                let rect = ime.cursor_rect * context.zoom_factor();
+                self.text_input.set_rect(sdl2::rect::Rect::new(
+                    rect.min.x.round() as i32,
+                    rect.min.y.round() as i32,
+                    rect.width().round().max(1.0) as u32,
+                    rect.height().round().max(1.0) as u32,
+                ));
+            }
+            None => {
+                // SDL starts text input on init, stop it so that the IME is off outside of text edits.
+                if self.text_input.is_active() {
+                    self.text_input.stop();
+                }
+                if self.ime_enabled {
+                    self.ime_enabled = false;
+                    egui_input
+                        .events
+                        .push(egui::Event::Ime(egui::ImeEvent::Disabled));
+                }
+            }
+        }
+    }
+
+    fn set_cursor_icon(&mut self, cursor_icon: egui::CursorIcon) {
+        if self.cursor.as_ref().map(|(icon, _)| *icon) == Some(cursor_icon) {
+            return;
+        }
+
+        let Some(system_cursor) = translate_cursor(cursor_icon) else {
+            self.mouse.show_cursor(false);
+            self.cursor = None;
+            return;
+        };
+        self.mouse.show_cursor(true);
+        match Cursor::from_system(system_cursor) {
+            Ok(cursor) => {
+                cursor.set();
+                self.cursor = Some((cursor_icon, cursor));
+            }
+            Err(err) => {
+                error!("Failed to create cursor: {}", err);
+            }
+        }
+    }
+}
+
+fn translate_mouse_button(button: MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        MouseButton::Left => Some(egui::PointerButton::Primary),
+        MouseButton::Right => Some(egui::PointerButton::Secondary),
+        MouseButton::Middle => Some(egui::PointerButton::Middle),
+        MouseButton::X1 => Some(egui::PointerButton::Extra1),
+        MouseButton::X2 => Some(egui::PointerButton::Extra2),
+        MouseButton::Unknown => None,
+    }
+}
+
+fn translate_modifiers(keymod: Mod) -> egui::Modifiers {
+    let alt = keymod.intersects(Mod::LALTMOD | Mod::RALTMOD);
+    let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+    let super_ = keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD);
+    egui::Modifiers {
+        alt,
+        ctrl,
+        shift,
+        mac_cmd: cfg!(target_os = "macos") && super_,
+        command: if cfg!(target_os = "macos") {
+            super_
+        } else {
+            ctrl
+        },
+    }
+}
+
+fn translate_cursor(cursor_icon: egui::CursorIcon) -> Option<SystemCursor> {
+    use egui::CursorIcon;
+
+    match cursor_icon {
+        CursorIcon::None => None,
+        CursorIcon::Text | CursorIcon::VerticalText => Some(SystemCursor::IBeam),
+        CursorIcon::Wait => Some(SystemCursor::Wait),
+        CursorIcon::Progress => Some(SystemCursor::WaitArrow),
+        CursorIcon::Crosshair | CursorIcon::Cell => Some(SystemCursor::Crosshair),
+        CursorIcon::PointingHand => Some(SystemCursor::Hand),
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => Some(SystemCursor::No),
+        CursorIcon::Move | CursorIcon::AllScroll | CursorIcon::Grab | CursorIcon::Grabbing => {
+            Some(SystemCursor::SizeAll)
+        }
+        CursorIcon::ResizeHorizontal
+        | CursorIcon::ResizeEast
+        | CursorIcon::ResizeWest
+        | CursorIcon::ResizeColumn => Some(SystemCursor::SizeWE),
+        CursorIcon::ResizeVertical
+        | CursorIcon::ResizeNorth
+        | CursorIcon::ResizeSouth
+        | CursorIcon::ResizeRow => Some(SystemCursor::SizeNS),
+        CursorIcon::ResizeNeSw | CursorIcon::ResizeNorthEast | CursorIcon::ResizeSouthWest => {
+            Some(SystemCursor::SizeNESW)
+        }
+        CursorIcon::ResizeNwSe | CursorIcon::ResizeNorthWest | CursorIcon::ResizeSouthEast => {
+            Some(SystemCursor::SizeNWSE)
+        }
+        _ => Some(SystemCursor::Arrow),
+    }
+}
+
+fn translate_key(keycode: Keycode) -> Option<egui::Key> {
+    use egui::Key;
+
+    Some(match keycode {
+        Keycode::DOWN => Key::ArrowDown,
+        Keycode::LEFT => Key::ArrowLeft,
+        Keycode::RIGHT => Key::ArrowRight,
+        Keycode::UP => Key::ArrowUp,
+
+        Keycode::ESCAPE => Key::Escape,
+        Keycode::TAB => Key::Tab,
+        Keycode::BACKSPACE => Key::Backspace,
+        Keycode::RETURN | Keycode::KP_ENTER => Key::Enter,
+        Keycode::SPACE => Key::Space,
+
+        Keycode::INSERT => Key::Insert,
+        Keycode::DELETE => Key::Delete,
+        Keycode::HOME => Key::Home,
+        Keycode::END => Key::End,
+        Keycode::PAGEUP => Key::PageUp,
+        Keycode::PAGEDOWN => Key::PageDown,
+
+        Keycode::COPY => Key::Copy,
+        Keycode::CUT => Key::Cut,
+        Keycode::PASTE => Key::Paste,
+
+        Keycode::COLON => Key::Colon,
+        Keycode::COMMA => Key::Comma,
+        Keycode::BACKSLASH => Key::Backslash,
+        Keycode::SLASH | Keycode::KP_DIVIDE => Key::Slash,
+        Keycode::LEFTBRACKET => Key::OpenBracket,
+        Keycode::RIGHTBRACKET => Key::CloseBracket,
+        Keycode::BACKQUOTE => Key::Backtick,
+        Keycode::MINUS | Keycode::KP_MINUS => Key::Minus,
+        Keycode::PERIOD | Keycode::KP_PERIOD => Key::Period,
+        Keycode::PLUS | Keycode::KP_PLUS => Key::Plus,
+        Keycode::EQUALS | Keycode::KP_EQUALS => Key::Equals,
+        Keycode::SEMICOLON => Key::Semicolon,
+        Keycode::QUOTE => Key::Quote,
+        Keycode::QUESTION => Key::Questionmark,
+        Keycode::EXCLAIM => Key::Exclamationmark,
+
+        Keycode::NUM_0 | Keycode::KP_0 => Key::Num0,
+        Keycode::NUM_1 | Keycode::KP_1 => Key::Num1,
+        Keycode::NUM_2 | Keycode::KP_2 => Key::Num2,
+        Keycode::NUM_3 | Keycode::KP_3 => Key::Num3,
+        Keycode::NUM_4 | Keycode::KP_4 => Key::Num4,
+        Keycode::NUM_5 | Keycode::KP_5 => Key::Num5,
+        Keycode::NUM_6 | Keycode::KP_6 => Key::Num6,
+        Keycode::NUM_7 | Keycode::KP_7 => Key::Num7,
+        Keycode::NUM_8 | Keycode::KP_8 => Key::Num8,
+        Keycode::NUM_9 | Keycode::KP_9 => Key::Num9,
+
+        Keycode::A => Key::A,
+        Keycode::B => Key::B,
+        Keycode::C => Key::C,
+        Keycode::D => Key::D,
+        Keycode::E => Key::E,
+        Keycode::F => Key::F,
+        Keycode::G => Key::G,
+        Keycode::H => Key::H,
+        Keycode::I => Key::I,
+        Keycode::J => Key::J,
+        Keycode::K => Key::K,
+        Keycode::L => Key::L,
+        Keycode::M => Key::M,
+        Keycode::N => Key::N,
+        Keycode::O => Key::O,
+        Keycode::P => Key::P,
+        Keycode::Q => Key::Q,
+        Keycode::R => Key::R,
+        Keycode::S => Key::S,
+        Keycode::T => Key::T,
+        Keycode::U => Key::U,
+        Keycode::V => Key::V,
+        Keycode::W => Key::W,
+        Keycode::X => Key::X,
+        Keycode::Y => Key::Y,
+        Keycode::Z => Key::Z,
+
+        Keycode::F1 => Key::F1,
+        Keycode::F2 => Key::F2,
+        Keycode::F3 => Key::F3,
+        Keycode::F4 => Key::F4,
+        Keycode::F5 => Key::F5,
+        Keycode::F6 => Key::F6,
+        Keycode::F7 => Key::F7,
+        Keycode::F8 => Key::F8,
+        Keycode::F9 => Key::F9,
+        Keycode::F10 => Key::F10,
+        Keycode::F11 => Key::F11,
+        Keycode::F12 => Key::F12,
+        Keycode::F13 => Key::F13,
+        Keycode::F14 => Key::F14,
+        Keycode::F15 => Key::F15,
+        Keycode::F16 => Key::F16,
+        Keycode::F17 => Key::F17,
+        Keycode::F18 => Key::F18,
+        Keycode::F19 => Key::F19,
+        Keycode::F20 => Key::F20,
+        Keycode::F21 => Key::F21,
+        Keycode::F22 => Key::F22,
+        Keycode::F23 => Key::F23,
+        Keycode::F24 => Key::F24,
+
+        _ => return None,
+    })
+}
+
+/// translate the key position, independent of the keyboard layout.
+fn translate_scancode(scancode: Scancode) -> Option<egui::Key> {
+    use egui::Key;
+
+    Some(match scancode {
+        Scancode::Down => Key::ArrowDown,
+        Scancode::Left => Key::ArrowLeft,
+        Scancode::Right => Key::ArrowRight,
+        Scancode::Up => Key::ArrowUp,
+
+        Scancode::Escape => Key::Escape,
+        Scancode::Tab => Key::Tab,
+        Scancode::Backspace => Key::Backspace,
+        Scancode::Return | Scancode::KpEnter => Key::Enter,
+        Scancode::Space => Key::Space,
+
+        Scancode::Insert => Key::Insert,
+        Scancode::Delete => Key::Delete,
+        Scancode::Home => Key::Home,
+        Scancode::End => Key::End,
+        Scancode::PageUp => Key::PageUp,
+        Scancode::PageDown => Key::PageDown,
+
+        Scancode::Copy => Key::Copy,
+        Scancode::Cut => Key::Cut,
+        Scancode::Paste => Key::Paste,
+
+        Scancode::Comma => Key::Comma,
+        Scancode::Backslash => Key::Backslash,
+        Scancode::Slash | Scancode::KpDivide => Key::Slash,
+        Scancode::LeftBracket => Key::OpenBracket,
+        Scancode::RightBracket => Key::CloseBracket,
+        Scancode::Grave => Key::Backtick,
+        Scancode::Minus | Scancode::KpMinus => Key::Minus,
+        Scancode::Period | Scancode::KpPeriod => Key::Period,
+        Scancode::KpPlus => Key::Plus,
+        Scancode::Equals | Scancode::KpEquals => Key::Equals,
+        Scancode::Semicolon => Key::Semicolon,
+        Scancode::Apostrophe => Key::Quote,
+
+        Scancode::Num0 | Scancode::Kp0 => Key::Num0,
+        Scancode::Num1 | Scancode::Kp1 => Key::Num1,
+        Scancode::Num2 | Scancode::Kp2 => Key::Num2,
+        Scancode::Num3 | Scancode::Kp3 => Key::Num3,
+        Scancode::Num4 | Scancode::Kp4 => Key::Num4,
+        Scancode::Num5 | Scancode::Kp5 => Key::Num5,
+        Scancode::Num6 | Scancode::Kp6 => Key::Num6,
+        Scancode::Num7 | Scancode::Kp7 => Key::Num7,
+        Scancode::Num8 | Scancode::Kp8 => Key::Num8,
+        Scancode::Num9 | Scancode::Kp9 => Key::Num9,
+
+        Scancode::A => Key::A,
+        Scancode::B => Key::B,
+        Scancode::C => Key::C,
+        Scancode::D => Key::D,
+        Scancode::E => Key::E,
+        Scancode::F => Key::F,
+        Scancode::G => Key::G,
+        Scancode::H => Key::H,
+        Scancode::I => Key::I,
+        Scancode::J => Key::J,
+        Scancode::K => Key::K,
+        Scancode::L => Key::L,
+        Scancode::M => Key::M,
+        Scancode::N => Key::N,
+        Scancode::O => Key::O,
+        Scancode::P => Key::P,
+        Scancode::Q => Key::Q,
+        Scancode::R => Key::R,
+        Scancode::S => Key::S,
+        Scancode::T => Key::T,
+        Scancode::U => Key::U,
+        Scancode::V => Key::V,
+        Scancode::W => Key::W,
+        Scancode::X => Key::X,
+        Scancode::Y => Key::Y,
+        Scancode::Z => Key::Z,
+
+        Scancode::F1 => Key::F1,
+        Scancode::F2 => Key::F2,
+        Scancode::F3 => Key::F3,
+        Scancode::F4 => Key::F4,
+        Scancode::F5 => Key::F5,
+        Scancode::F6 => Key::F6,
+        Scancode::F7 => Key::F7,
+        Scancode::F8 => Key::F8,
+        Scancode::F9 => Key::F9,
+        Scancode::F10 => Key::F10,
+        Scancode::F11 => Key::F11,
+        Scancode::F12 => Key::F12,
+        Scancode::F13 => Key::F13,
+        Scancode::F14 => Key::F14,
+        Scancode::F15 => Key::F15,
+        Scancode::F16 => Key::F16,
+        Scancode::F17 => Key::F17,
+        Scancode::F18 => Key::F18,
+        Scancode::F19 => Key::F19,
+        Scancode::F20 => Key::F20,
+        Scancode::F21 => Key::F21,
+        Scancode::F22 => Key::F22,
+        Scancode::F23 => Key::F23,
+        Scancode::F24 => Key::F24,
+
+        _ => return None,
+    })
+}
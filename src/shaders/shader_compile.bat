@@ -1,2 +1,3 @@
 glslc.exe src/vert.vert -O -o spv/vert.spv
 glslc.exe src/frag.frag -O -o spv/frag.spv
+glslc.exe src/post.frag -O -o spv/post.spv
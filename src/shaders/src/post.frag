@@ -0,0 +1,24 @@
+#version 450
+
+layout(location = 1) in vec2 inUV;
+
+layout(location = 0) out vec4 outColor;
+
+layout(binding = 0, set = 0) uniform sampler2D egui_layer;
+
+layout(push_constant) uniform PushConstants {
+  layout(offset = 16) mat4 color_matrix;
+  vec4 decode_exponent;
+  vec4 encode_exponent;
+}
+pushConstants;
+
+void main() {
+  vec4 color = texture(egui_layer, inUV);
+  // the color transform applies to straight alpha.
+  vec3 rgb = color.rgb * (1.0 / max(color.a, 1e-6));
+  rgb = pow(rgb, pushConstants.decode_exponent.rgb);
+  rgb = (pushConstants.color_matrix * vec4(rgb, 1.0)).rgb;
+  rgb = pow(clamp(rgb, vec3(0.0), vec3(1.0)), pushConstants.encode_exponent.rgb);
+  outColor = vec4(rgb * color.a, color.a);
+}
@@ -0,0 +1,47 @@
+/// when new windows are shown. They are created hidden, so that they do not flash
+/// before their first frame. (winit backend only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ShowPolicy {
+    /// show each window after its first frame was presented.
+    #[default]
+    AfterFirstPresent,
+    /// like [`ShowPolicy::AfterFirstPresent`], but only after the app called [`signal_ready`],
+    /// e.g. once its assets are loaded.
+    AfterReady,
+    /// create the windows visible.
+    Immediately,
+}
+
+fn policy_id() -> egui::Id {
+    egui::Id::new("egui_ash_show_policy")
+}
+
+fn ready_id() -> egui::Id {
+    egui::Id::new("egui_ash_show_ready")
+}
+
+/// let the windows be shown with [`ShowPolicy::AfterReady`].
+pub fn signal_ready(ctx: &egui::Context) {
+    ctx.data_mut(|data| data.insert_temp(ready_id(), true));
+    ctx.request_repaint();
+}
+
+pub(crate) fn set_show_policy(ctx: &egui::Context, policy: ShowPolicy) {
+    ctx.data_mut(|data| data.insert_temp(policy_id(), policy));
+}
+
+pub(crate) fn show_policy(ctx: &egui::Context) -> ShowPolicy {
+    ctx.data(|data| data.get_temp(policy_id()))
+        .unwrap_or_default()
+}
+
+/// whether a window may be shown, `presented` if its first frame is on screen.
+pub(crate) fn can_show(ctx: &egui::Context, presented: bool) -> bool {
+    match show_policy(ctx) {
+        ShowPolicy::AfterFirstPresent => presented,
+        ShowPolicy::AfterReady => {
+            presented && ctx.data(|data| data.get_temp::<bool>(ready_id())) == Some(true)
+        }
+        ShowPolicy::Immediately => true,
+    }
+}
@@ -0,0 +1,254 @@
+//! one instance of the app per user, see [`crate::RunOption::single_instance`].
+//!
+//! The first instance listens on a unix socket only the user can reach, or a loopback TCP port
+//! guarded by a random token on Windows. A second launch connects to it, sends its arguments and
+//! working directory, and exits.
+
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use crate::integration::IntegrationEvent;
+
+/// arguments of a second launch, see [`crate::event::AppEvent::SecondInstance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Forwarded {
+    pub(crate) args: Vec<String>,
+    pub(crate) cwd: PathBuf,
+}
+
+// the name of the socket or port file of the app, with the characters allowed in file names.
+fn file_name(app_id: &str, extension: &str) -> String {
+    let app_id = app_id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    format!("egui-ash-{app_id}-{user}.{extension}")
+}
+
+// arguments separated by NUL, which can not be part of an argument.
+fn encode(token: &str) -> Vec<u8> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    std::iter::once(token.to_owned())
+        .chain(std::iter::once(cwd.to_string_lossy().into_owned()))
+        .chain(
+            std::env::args_os()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join("\0")
+        .into_bytes()
+}
+
+fn decode(message: &[u8], token: &str) -> Option<Forwarded> {
+    let message = String::from_utf8_lossy(message);
+    let mut parts = message.split('\0');
+    if parts.next()? != token {
+        return None;
+    }
+    let cwd = PathBuf::from(parts.next()?);
+    Some(Forwarded {
+        args: parts.map(str::to_owned).collect(),
+        cwd,
+    })
+}
+
+fn send(event_loop_proxy: &EventLoopProxy<IntegrationEvent>, message: &[u8], token: &str) {
+    match decode(message, token) {
+        Some(forwarded) => {
+            let _ = event_loop_proxy.send_event(IntegrationEvent::SecondInstance(forwarded));
+        }
+        None => warn!("Ignored a malformed message of another instance"),
+    }
+}
+
+// directory of the socket: `XDG_RUNTIME_DIR`, or else a directory in the temporary directory only
+// the user can access.
+#[cfg(unix)]
+fn socket_dir() -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    let uid = unsafe { libc::geteuid() };
+    let dir = std::env::temp_dir().join(format!("egui-ash-{uid}"));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+    // another user may have created it first, do not listen where they can connect.
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory of the user", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+// whether the other end of `stream` runs as the same user.
+#[cfg(unix)]
+fn is_same_user(stream: &std::os::unix::net::UnixStream) -> std::io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    let fd = stream.as_raw_fd();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let peer_uid = {
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                (&mut cred as *mut libc::ucred).cast(),
+                &mut len,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        cred.uid
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let peer_uid = {
+        let mut uid = 0;
+        let mut gid = 0;
+        if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        uid
+    };
+    Ok(peer_uid == unsafe { libc::geteuid() })
+}
+
+/// listener of the first instance.
+pub(crate) struct SingleInstance {
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    #[cfg(not(unix))]
+    listener: std::net::TcpListener,
+    #[cfg(not(unix))]
+    token: String,
+}
+impl SingleInstance {
+    /// become the first instance of `app_id`, or forward the arguments to the running one and
+    /// return `None`.
+    #[cfg(unix)]
+    pub(crate) fn acquire(app_id: &str) -> std::io::Result<Option<Self>> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let path = socket_dir()?.join(file_name(app_id, "sock"));
+        for _ in 0..2 {
+            match UnixStream::connect(&path) {
+                Ok(mut stream) => {
+                    if !is_same_user(&stream)? {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::PermissionDenied,
+                            format!("{} is listened by another user", path.display()),
+                        ));
+                    }
+                    stream.write_all(&encode(""))?;
+                    return Ok(None);
+                }
+                // left behind by an instance that crashed.
+                Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    std::fs::remove_file(&path)?;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+            match UnixListener::bind(&path) {
+                Ok(listener) => return Ok(Some(Self { listener })),
+                // another instance started at the same time, connect to it.
+                Err(err) if err.kind() == std::io::ErrorKind::AddrInUse => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(std::io::ErrorKind::AddrInUse.into())
+    }
+
+    /// become the first instance of `app_id`, or forward the arguments to the running one and
+    /// return `None`.
+    #[cfg(not(unix))]
+    pub(crate) fn acquire(app_id: &str) -> std::io::Result<Option<Self>> {
+        use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+        let dir = std::env::var_os("LOCALAPPDATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(file_name(app_id, "port"));
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            if let Some((port, token)) = contents.split_once(' ') {
+                let stream = port
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|port| TcpStream::connect((Ipv4Addr::LOCALHOST, port)).ok());
+                if let Some(mut stream) = stream {
+                    stream.write_all(&encode(token))?;
+                    return Ok(None);
+                }
+            }
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        // only the user can read the port file, other local processes do not know the token.
+        let mut bytes = [0; 16];
+        getrandom::getrandom(&mut bytes)?;
+        let token = bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        let port = listener.local_addr()?.port();
+        std::fs::write(&path, format!("{port} {token}"))?;
+        Ok(Some(Self { listener, token }))
+    }
+
+    /// send the arguments of the next launches to the event loop.
+    pub(crate) fn listen(self, event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+        #[cfg(unix)]
+        let token = String::new();
+        #[cfg(not(unix))]
+        let token = self.token;
+        let listener = self.listener;
+        let spawned = std::thread::Builder::new()
+            .name("egui-ash single instance".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    #[cfg(unix)]
+                    let stream = stream.and_then(|stream| match is_same_user(&stream)? {
+                        true => Ok(stream),
+                        false => Err(std::io::Error::new(
+                            std::io::ErrorKind::PermissionDenied,
+                            "the other instance runs as another user",
+                        )),
+                    });
+                    let mut message = Vec::new();
+                    match stream.and_then(|mut stream| stream.read_to_end(&mut message)) {
+                        Ok(_) => send(&event_loop_proxy, &message, &token),
+                        Err(err) => {
+                            warn!("Failed to receive the arguments of another instance: {err}");
+                        }
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            error!("Failed to start the single instance thread: {err}");
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! golden-image snapshot tests with the [`TestHarness`].
+//!
+//! [`TestHarness::snapshot`] renders frames of an app and compares the last one with a PNG checked in
+//! next to the tests. On a mismatch the test fails and the new image and a diff image are written next
+//! to the snapshot. Set the `UPDATE_SNAPSHOTS` environment variable to accept the new images.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::{testing::TestHarness, Allocator, App};
+
+/// options of [`TestHarness::snapshot`] and [`compare`].
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// directory of the snapshots.
+    pub dir: PathBuf,
+    /// largest difference of a color channel for a pixel to still match, GPUs rasterize slightly differently.
+    pub tolerance: u8,
+    /// number of pixels that may differ by more than the tolerance.
+    pub max_failed_pixels: usize,
+    /// write the new images as the snapshots instead of comparing them.
+    pub update: bool,
+}
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("tests/snapshots"),
+            tolerance: 2,
+            max_failed_pixels: 0,
+            update: std::env::var_os("UPDATE_SNAPSHOTS").is_some(),
+        }
+    }
+}
+
+impl<A: Allocator + 'static> TestHarness<A> {
+    /// step `frames` frames of the app and compare the last one with the snapshot `name`.
+    pub fn snapshot(
+        &mut self,
+        app: &mut impl App,
+        name: &str,
+        frames: usize,
+        options: &SnapshotOptions,
+    ) -> Result<()> {
+        for _ in 0..frames.max(1) {
+            self.step(app)?;
+        }
+        compare(&self.read_pixels()?, name, options)
+    }
+}
+
+/// compare `image` with the snapshot `name`, e.g. "settings_dark" for `settings_dark.png`.
+pub fn compare(image: &egui::ColorImage, name: &str, options: &SnapshotOptions) -> Result<()> {
+    let path = options.dir.join(format!("{name}.png"));
+    let new_path = options.dir.join(format!("{name}.new.png"));
+    let diff_path = options.dir.join(format!("{name}.diff.png"));
+    if options.update {
+        save(image, &path)?;
+        // outputs of the failed runs before
+        let _ = std::fs::remove_file(&new_path);
+        let _ = std::fs::remove_file(&diff_path);
+        return Ok(());
+    }
+
+    if !path.exists() {
+        save(image, &new_path)?;
+        anyhow::bail!(
+            "No snapshot {path:?}, the image was written to {new_path:?}. Set UPDATE_SNAPSHOTS to accept it"
+        );
+    }
+    let expected = load(&path)?;
+    if expected.size != image.size {
+        save(image, &new_path)?;
+        anyhow::bail!(
+            "The snapshot {path:?} is {:?} pixels, the image is {:?}. The image was written to {new_path:?}",
+            expected.size,
+            image.size
+        );
+    }
+
+    let mut failed_pixels = 0;
+    let diff = image
+        .pixels
+        .iter()
+        .zip(&expected.pixels)
+        .map(|(&pixel, &expected_pixel)| {
+            let difference = (0..4)
+                .map(|channel| pixel[channel].abs_diff(expected_pixel[channel]))
+                .max()
+                .unwrap_or(0);
+            if difference > options.tolerance {
+                failed_pixels += 1;
+                egui::Color32::RED
+            } else {
+                // matching pixels are dimmed, so that the failed ones stand out.
+                expected_pixel.gamma_multiply(0.25).to_opaque()
+            }
+        })
+        .collect();
+    if failed_pixels <= options.max_failed_pixels {
+        return Ok(());
+    }
+    save(image, &new_path)?;
+    save(&egui::ColorImage::new(image.size, diff), &diff_path)?;
+    anyhow::bail!(
+        "{failed_pixels} pixels differ from the snapshot {path:?}. See {new_path:?} and {diff_path:?}"
+    )
+}
+
+fn load(path: &Path) -> Result<egui::ColorImage> {
+    let image = image::open(path)
+        .map_err(|err| anyhow::anyhow!("Failed to load {path:?}: {err}"))?
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    // stored premultiplied like the rendered image, so that they compare byte by byte.
+    let pixels = image
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            egui::Color32::from_rgba_premultiplied(r, g, b, a)
+        })
+        .collect();
+    Ok(egui::ColorImage::new(size, pixels))
+}
+
+fn save(image: &egui::ColorImage, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let bytes = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_array())
+        .collect();
+    let buffer = image::RgbaImage::from_raw(image.size[0] as u32, image.size[1] as u32, bytes)
+        .ok_or_else(|| anyhow::anyhow!("The image size does not match its pixels"))?;
+    buffer
+        .save(path)
+        .map_err(|err| anyhow::anyhow!("Failed to save {path:?}: {err}"))
+}
@@ -0,0 +1,318 @@
+use egui_winit::winit;
+use std::{cell::RefCell, collections::HashMap, num::NonZeroU32, rc::Rc};
+
+use crate::theme;
+
+/// options of the splash window shown while [`crate::AppCreator::create`] runs, see [`crate::RunOption::splash`].
+#[derive(Clone)]
+pub struct SplashOptions {
+    /// size of the splash window in points.
+    pub size: egui::Vec2,
+    /// image shown above the progress message, e.g. the logo of the app.
+    pub image: Option<egui::ColorImage>,
+    /// progress message shown until the creator sets another one.
+    pub message: String,
+}
+impl Default for SplashOptions {
+    fn default() -> Self {
+        Self {
+            size: egui::vec2(480.0, 320.0),
+            image: None,
+            message: "Loading...".to_owned(),
+        }
+    }
+}
+
+/// handle of the splash window, passed as [`crate::CreationContext::splash`].
+///
+/// The splash window is painted on the CPU when it is created and on every update, since the event loop
+/// is blocked while the app is created. It is closed when the root window is shown.
+#[derive(Clone)]
+pub struct SplashHandle {
+    splash: Rc<RefCell<Option<Splash>>>,
+}
+impl SplashHandle {
+    /// open the splash window, `None` if it could not be created.
+    pub(crate) fn new(
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        app_context: &egui::Context,
+        app_id: &str,
+        options: SplashOptions,
+    ) -> Option<Self> {
+        let splash = Splash::new(event_loop, app_context, app_id, options)
+            .inspect_err(|err| error!("Failed to create splash window: {err}"))
+            .ok()?;
+        let handle = Self {
+            splash: Rc::new(RefCell::new(Some(splash))),
+        };
+        handle.paint();
+        Some(handle)
+    }
+
+    /// set the progress message and repaint the splash window.
+    pub fn set_message(&self, message: impl Into<String>) {
+        if let Some(splash) = self.splash.borrow_mut().as_mut() {
+            splash.message = message.into();
+        }
+        self.paint();
+    }
+
+    /// set the progress in `0.0..=1.0` shown as progress bar, `None` hides it, and repaint the splash window.
+    pub fn set_progress(&self, progress: Option<f32>) {
+        if let Some(splash) = self.splash.borrow_mut().as_mut() {
+            splash.progress = progress.map(|progress| progress.clamp(0.0, 1.0));
+        }
+        self.paint();
+    }
+
+    pub(crate) fn close(&self) {
+        self.splash.borrow_mut().take();
+    }
+
+    fn paint(&self) {
+        if let Some(splash) = self.splash.borrow_mut().as_mut() {
+            if let Err(err) = splash.paint() {
+                error!("Failed to paint splash window: {err}");
+            }
+        }
+    }
+}
+
+struct Splash {
+    window: Rc<winit::window::Window>,
+    _softbuffer_context: softbuffer::Context<Rc<winit::window::Window>>,
+    surface: softbuffer::Surface<Rc<winit::window::Window>, Rc<winit::window::Window>>,
+    context: egui::Context,
+    textures: HashMap<egui::TextureId, egui::ColorImage>,
+    image: Option<egui::TextureHandle>,
+    message: String,
+    progress: Option<f32>,
+}
+impl Splash {
+    fn new(
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        app_context: &egui::Context,
+        app_id: &str,
+        options: SplashOptions,
+    ) -> anyhow::Result<Self> {
+        let mut window_attributes = winit::window::WindowAttributes::default()
+            .with_title(app_id)
+            .with_inner_size(winit::dpi::LogicalSize::new(options.size.x, options.size.y))
+            .with_decorations(false)
+            .with_resizable(false);
+        if let Some(monitor) = event_loop.primary_monitor() {
+            let size = winit::dpi::LogicalSize::new(options.size.x as f64, options.size.y as f64)
+                .to_physical::<i32>(monitor.scale_factor());
+            let position = monitor.position();
+            window_attributes = window_attributes.with_position(winit::dpi::PhysicalPosition::new(
+                position.x + (monitor.size().width as i32 - size.width) / 2,
+                position.y + (monitor.size().height as i32 - size.height) / 2,
+            ));
+        }
+        let window = Rc::new(event_loop.create_window(window_attributes)?);
+        let softbuffer_context =
+            softbuffer::Context::new(window.clone()).map_err(|err| anyhow::anyhow!("{err}"))?;
+        let surface = softbuffer::Surface::new(&softbuffer_context, window.clone())
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+
+        // same look as the app.
+        let context = egui::Context::default();
+        context.set_theme(theme::theme_preference(app_context));
+        context.set_style_of(egui::Theme::Light, app_context.style_of(egui::Theme::Light));
+        context.set_style_of(egui::Theme::Dark, app_context.style_of(egui::Theme::Dark));
+        let image = options
+            .image
+            .map(|image| context.load_texture("egui_ash_splash_image", image, Default::default()));
+
+        Ok(Self {
+            window,
+            _softbuffer_context: softbuffer_context,
+            surface,
+            context,
+            textures: HashMap::new(),
+            image,
+            message: options.message,
+            progress: None,
+        })
+    }
+
+    fn paint(&mut self) -> anyhow::Result<()> {
+        let size = self.window.inner_size();
+        let (Some(width), Some(height)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            return Ok(());
+        };
+        let pixels_per_point = self.window.scale_factor() as f32;
+
+        let mut raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(size.width as f32, size.height as f32) / pixels_per_point,
+            )),
+            system_theme: self.window.theme().map(theme::egui_theme),
+            ..Default::default()
+        };
+        raw_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(pixels_per_point);
+
+        let (image, message, progress) = (&self.image, &self.message, self.progress);
+        let output = self.context.run(raw_input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    if let Some(image) = image {
+                        let max_height = (ui.available_height() - 48.0).max(0.0);
+                        ui.add(
+                            egui::Image::new(image)
+                                .max_height(max_height)
+                                .shrink_to_fit(),
+                        );
+                    }
+                    ui.label(message);
+                    if let Some(progress) = progress {
+                        ui.add(egui::ProgressBar::new(progress));
+                    }
+                });
+            });
+        });
+
+        for (texture_id, delta) in &output.textures_delta.set {
+            let egui::ImageData::Color(image) = &delta.image;
+            match (delta.pos, self.textures.get_mut(texture_id)) {
+                (Some([x, y]), Some(texture)) => {
+                    for row in 0..image.height() {
+                        let start = (y + row) * texture.width() + x;
+                        texture.pixels[start..start + image.width()].copy_from_slice(
+                            &image.pixels[row * image.width()..(row + 1) * image.width()],
+                        );
+                    }
+                }
+                _ => {
+                    self.textures.insert(*texture_id, (**image).clone());
+                }
+            }
+        }
+
+        self.surface
+            .resize(width, height)
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        let mut buffer = self
+            .surface
+            .buffer_mut()
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        buffer.fill(0);
+        let mut target = Target {
+            pixels: &mut buffer,
+            width: size.width as usize,
+            height: size.height as usize,
+        };
+        for clipped_primitive in self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point)
+        {
+            let egui::epaint::Primitive::Mesh(mesh) = &clipped_primitive.primitive else {
+                continue;
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            target.draw_mesh(
+                mesh,
+                texture,
+                clipped_primitive.clip_rect,
+                output.pixels_per_point,
+            );
+        }
+        buffer.present().map_err(|err| anyhow::anyhow!("{err}"))?;
+
+        for texture_id in &output.textures_delta.free {
+            self.textures.remove(texture_id);
+        }
+        Ok(())
+    }
+}
+
+// software rasterizer for the few meshes of the splash window, the device does not exist yet.
+struct Target<'a> {
+    // 0RGB pixels
+    pixels: &'a mut [u32],
+    width: usize,
+    height: usize,
+}
+impl Target<'_> {
+    fn draw_mesh(
+        &mut self,
+        mesh: &egui::Mesh,
+        texture: &egui::ColorImage,
+        clip_rect: egui::Rect,
+        pixels_per_point: f32,
+    ) {
+        let clip_rect = egui::Rect::from_min_max(
+            (clip_rect.min.to_vec2() * pixels_per_point).to_pos2(),
+            (clip_rect.max.to_vec2() * pixels_per_point).to_pos2(),
+        )
+        .intersect(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(self.width as f32, self.height as f32),
+        ));
+        if !clip_rect.is_positive() {
+            return;
+        }
+
+        for triangle in mesh.indices.as_chunks::<3>().0 {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let [pa, pb, pc] = [a, b, c].map(|vertex| vertex.pos * pixels_per_point);
+            let area = edge(pa, pb, pc);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+            let bounds = egui::Rect::from_points(&[pa, pb, pc]).intersect(clip_rect);
+            if !bounds.is_positive() {
+                continue;
+            }
+
+            for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+                for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+                    let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                    let wa = edge(pb, pc, p) / area;
+                    let wb = edge(pc, pa, p) / area;
+                    let wc = 1.0 - wa - wb;
+                    if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                        continue;
+                    }
+
+                    let uv =
+                        (a.uv.to_vec2() * wa + b.uv.to_vec2() * wb + c.uv.to_vec2() * wc).to_pos2();
+                    let texel = sample(texture, uv);
+                    let color = [0, 1, 2, 3].map(|i| {
+                        (a.color[i] as f32 * wa + b.color[i] as f32 * wb + c.color[i] as f32 * wc)
+                            * texel[i] as f32
+                            / (255.0 * 255.0)
+                    });
+
+                    // premultiplied alpha blending
+                    let dst = &mut self.pixels[y * self.width + x];
+                    let blend = |shift: u32, src: f32| {
+                        let dst = ((*dst >> shift) & 0xff) as f32 / 255.0;
+                        (((src + dst * (1.0 - color[3])) * 255.0).round() as u32).min(255) << shift
+                    };
+                    *dst = blend(16, color[0]) | blend(8, color[1]) | blend(0, color[2]);
+                }
+            }
+        }
+    }
+}
+
+fn edge(a: egui::Pos2, b: egui::Pos2, p: egui::Pos2) -> f32 {
+    (p.x - a.x) * (b.y - a.y) - (p.y - a.y) * (b.x - a.x)
+}
+
+fn sample(texture: &egui::ColorImage, uv: egui::Pos2) -> egui::Color32 {
+    let [width, height] = texture.size;
+    let x = ((uv.x * width as f32) as usize).min(width.saturating_sub(1));
+    let y = ((uv.y * height as f32) as usize).min(height.saturating_sub(1));
+    texture.pixels[y * width + x]
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::allocator::{Allocator, AllocatorReport, MemoryBudget};
+
+// the allocator of the renderer without its allocation types.
+trait ReportAllocator: Send + Sync {
+    fn report(&self) -> Option<AllocatorReport>;
+    fn budget(&self) -> Option<Vec<MemoryBudget>>;
+}
+impl<A: Allocator> ReportAllocator for A {
+    fn report(&self) -> Option<AllocatorReport> {
+        Allocator::report(self)
+    }
+
+    fn budget(&self) -> Option<Vec<MemoryBudget>> {
+        Allocator::budget(self)
+    }
+}
+
+/// metrics of the last frame of a viewport.
+///
+/// The stages update their fields as the frame passes them, so the fields of a frame which is
+/// still recording or presenting may belong to the previous frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    /// time spent handling the window events of the viewport since its previous frame.
+    pub event_handling_time: Duration,
+    /// time of `egui::Context::run`, including [`crate::App::ui`].
+    pub egui_run_time: Duration,
+    /// time tessellating the shapes. Zero if the meshes of the previous frame were reused.
+    pub tessellation_time: Duration,
+    /// time recording the command buffer.
+    pub record_time: Duration,
+    /// time submitting and presenting the frames painted together with this one.
+    pub submit_time: Duration,
+    /// GPU time of the egui render pass, measured with timestamp queries.
+    ///
+    /// It lags behind by the number of frames in flight.
+    /// `None` if the queue does not support timestamps.
+    pub gpu_time: Option<Duration>,
+    /// number of indexed draws.
+    pub draw_calls: u32,
+    /// number of vertices in the vertex buffer.
+    pub vertices: u32,
+    /// number of indices in the index buffer.
+    pub indices: u32,
+    /// memory of the egui managed textures in bytes.
+    pub texture_memory: u64,
+}
+
+/// latest [`FrameStats`] of each viewport.
+///
+/// Passed as [`crate::CreationContext::frame_stats`], e.g. to show them in a perf HUD.
+#[derive(Clone, Default)]
+pub struct FrameStatistics {
+    stats: Arc<Mutex<HashMap<egui::ViewportId, FrameStats>>>,
+    // reports the allocator of the renderer until it is destroyed.
+    allocator: Arc<Mutex<Option<Arc<dyn ReportAllocator>>>>,
+}
+impl FrameStatistics {
+    /// stats of the last frame of `viewport_id`. `None` before its first frame.
+    pub fn get(&self, viewport_id: egui::ViewportId) -> Option<FrameStats> {
+        self.stats.lock().unwrap().get(&viewport_id).copied()
+    }
+
+    /// memory usage of the allocator of the integration, see [`Allocator::report`].
+    ///
+    /// `None` if the allocator does not report it or the integration is destroyed.
+    pub fn allocator_report(&self) -> Option<AllocatorReport> {
+        let allocator = self.allocator.lock().unwrap().clone()?;
+        allocator.report()
+    }
+
+    /// budget of the memory heaps from the allocator of the integration, see
+    /// [`Allocator::budget`].
+    ///
+    /// `None` if the allocator does not query it or the integration is destroyed.
+    pub fn memory_budget(&self) -> Option<Vec<MemoryBudget>> {
+        let allocator = self.allocator.lock().unwrap().clone()?;
+        allocator.budget()
+    }
+
+    pub(crate) fn set_allocator(&self, allocator: impl Allocator + 'static) {
+        *self.allocator.lock().unwrap() = Some(Arc::new(allocator));
+    }
+
+    /// release the allocator, so that it can be dropped with the app.
+    pub(crate) fn clear_allocator(&self) {
+        *self.allocator.lock().unwrap() = None;
+    }
+
+    pub(crate) fn update(&self, viewport_id: egui::ViewportId, f: impl FnOnce(&mut FrameStats)) {
+        f(self.stats.lock().unwrap().entry(viewport_id).or_default());
+    }
+
+    pub(crate) fn remove(&self, viewport_id: egui::ViewportId) {
+        self.stats.lock().unwrap().remove(&viewport_id);
+    }
+}
+impl std::fmt::Debug for FrameStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.stats.lock().unwrap().iter())
+            .finish()
+    }
+}
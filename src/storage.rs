@@ -3,146 +3,362 @@ use egui_winit::WindowSettings;
 use std::{
     collections::HashMap,
     fmt::Debug,
-    path::PathBuf,
+    io::Write as _,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
-struct InnerStorage {
+/// serialization format of the persisted state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageFormat {
+    /// human readable RON.
+    #[default]
+    Ron,
+    /// human readable JSON.
+    ///
+    /// Values that JSON can not represent, like the egui memory with its non-string map keys,
+    /// are stored as RON strings.
+    Json,
+    /// compact binary MessagePack.
+    Binary,
+}
+impl StorageFormat {
+    /// file extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ron => "ron",
+            Self::Json => "json",
+            Self::Binary => "bin",
+        }
+    }
+
+    pub(crate) fn encode<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::Ron => ron::to_string(value)?.into_bytes(),
+            Self::Json => match serde_json::to_vec(value) {
+                Ok(value) => value,
+                Err(_) => serde_json::to_vec(&ron::to_string(value)?)?,
+            },
+            Self::Binary => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    pub(crate) fn decode<T: serde::de::DeserializeOwned>(self, value: &[u8]) -> Result<T> {
+        Ok(match self {
+            Self::Ron => ron::from_str(std::str::from_utf8(value)?)?,
+            Self::Json => match serde_json::from_slice(value) {
+                Ok(value) => value,
+                Err(err) => match serde_json::from_slice::<String>(value) {
+                    Ok(value) => ron::from_str(&value)?,
+                    Err(_) => return Err(err.into()),
+                },
+            },
+            Self::Binary => rmp_serde::from_slice(value)?,
+        })
+    }
+}
+
+/// persistent key-value store behind [`Storage`].
+///
+/// Values are encoded in [`StorageBackend::format`]. Implement this to keep the app state
+/// somewhere else than [`FileStorage`], e.g. in a database or a synced config store,
+/// and pass it as [`crate::RunOption::storage_backend`].
+pub trait StorageBackend: Send {
+    /// get the encoded value of `key`.
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>>;
+
+    /// set the encoded value of `key`.
+    fn set_bytes(&mut self, key: &str, value: Vec<u8>);
+
+    /// write the changed values. Called after [`crate::App::save`].
+    fn flush(&mut self) {}
+
+    /// format of the values.
+    fn format(&self) -> StorageFormat {
+        StorageFormat::Ron
+    }
+
+    /// get the window settings of the viewports.
+    fn get_windows(&self) -> Option<HashMap<egui::ViewportId, WindowSettings>> {
+        get_decoded(self, STORAGE_WINDOWS_KEY)
+    }
+
+    /// set the window settings of the viewports, keeping the settings of closed viewports.
+    fn set_windows(&mut self, windows: &HashMap<egui::ViewportId, WindowSettings>) {
+        let mut prev_windows = self.get_windows().unwrap_or_default();
+        for (id, window) in windows {
+            prev_windows.insert(*id, *window);
+        }
+        set_encoded(self, STORAGE_WINDOWS_KEY, &prev_windows);
+    }
+
+    /// get the egui memory.
+    fn get_egui_memory(&self) -> Option<egui::Memory> {
+        get_decoded(self, STORAGE_EGUI_MEMORY_KEY)
+    }
+
+    /// set the egui memory.
+    fn set_egui_memory(&mut self, egui_memory: &egui::Memory) {
+        set_encoded(self, STORAGE_EGUI_MEMORY_KEY, egui_memory);
+    }
+}
+
+fn get_decoded<B: StorageBackend + ?Sized, T: serde::de::DeserializeOwned>(
+    backend: &B,
+    key: &str,
+) -> Option<T> {
+    let value = backend.get_bytes(key)?;
+    match backend.format().decode(&value) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            error!("failed to deserialize value: {}", err);
+            None
+        }
+    }
+}
+
+fn set_encoded<B: StorageBackend + ?Sized, T: serde::Serialize + ?Sized>(
+    backend: &mut B,
+    key: &str,
+    value: &T,
+) {
+    match backend.format().encode(value) {
+        Ok(value) => backend.set_bytes(key, value),
+        Err(err) => {
+            error!("failed to serialize value: {}", err);
+        }
+    }
+}
+
+/// default [`StorageBackend`] saving a file in the data directory of the app.
+///
+/// RON and JSON files map the keys to encoded strings, binary files map them to encoded bytes.
+pub struct FileStorage {
     filepath: PathBuf,
-    kv: HashMap<String, String>,
+    format: StorageFormat,
+    kv: HashMap<String, Vec<u8>>,
     dirty: bool,
     save_join_handle: Option<std::thread::JoinHandle<()>>,
 }
-impl InnerStorage {
+impl FileStorage {
     fn storage_dir(app_id: &str) -> Option<PathBuf> {
         directories_next::ProjectDirs::from("", "", app_id)
             .map(|project_dirs| project_dirs.data_dir().to_owned())
     }
 
-    fn from_app_id(app_id: &str) -> Result<Self> {
-        if let Some(dir) = Self::storage_dir(app_id) {
-            match std::fs::create_dir_all(&dir) {
-                Ok(_) => {
-                    let filepath = dir.join("app.ron");
-                    match std::fs::File::open(&filepath) {
-                        Ok(file) => {
-                            let kv: HashMap<String, String> = match ron::de::from_reader(file) {
-                                Ok(kv) => kv,
-                                Err(err) => {
-                                    log::error!("Failed to deserialize storage: {}", err);
-                                    HashMap::new()
-                                }
-                            };
-
-                            Ok(Self {
-                                filepath,
-                                kv,
-                                dirty: false,
-                                save_join_handle: None,
-                            })
-                        }
-                        Err(_) => Ok(Self {
-                            filepath,
-                            kv: HashMap::new(),
-                            dirty: false,
-                            save_join_handle: None,
-                        }),
-                    }
-                }
-                Err(err) => {
-                    anyhow::bail!("Failed to create directory {dir:?}: {err}");
-                }
-            }
-        } else {
+    /// open `app.<extension>` in the data directory of `app_id`.
+    pub fn from_app_id(app_id: &str, format: StorageFormat) -> Result<Self> {
+        let Some(dir) = Self::storage_dir(app_id) else {
             anyhow::bail!("Failed to get storage directory");
+        };
+        Self::from_dir(dir, None, format)
+    }
+
+    /// open `file_name` in `dir`. `None` uses `app.<extension>`.
+    pub fn from_dir(
+        dir: impl Into<PathBuf>,
+        file_name: Option<&str>,
+        format: StorageFormat,
+    ) -> Result<Self> {
+        let dir = dir.into();
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            anyhow::bail!("Failed to create directory {dir:?}: {err}");
         }
+        let filepath = match file_name {
+            Some(file_name) => dir.join(file_name),
+            None => dir.join(format!("app.{}", format.extension())),
+        };
+        Ok(Self::from_path(filepath, format))
     }
 
-    fn get_value<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
-        self.kv
-            .get(key)
-            .cloned()
-            .and_then(|value| match ron::from_str(&value) {
-                Ok(value) => Some(value),
-                Err(err) => {
-                    log::error!("failed to deserialize value: {}", err);
-                    None
-                }
-            })
+    /// open the file at `filepath`. A missing file is created on the first flush.
+    ///
+    /// If the file is corrupted, e.g. because the app was killed while writing it,
+    /// the previous file is used instead.
+    pub fn from_path(filepath: impl Into<PathBuf>, format: StorageFormat) -> Self {
+        let filepath = filepath.into();
+        let kv = match Self::load(&filepath, format) {
+            Ok(Some(kv)) => kv,
+            Ok(None) => Self::load_backup(&filepath, format),
+            Err(err) => {
+                error!("Failed to load storage {filepath:?}: {err}");
+                Self::load_backup(&filepath, format)
+            }
+        };
+        Self {
+            filepath,
+            format,
+            kv,
+            dirty: false,
+            save_join_handle: None,
+        }
+    }
+
+    fn backup_path(filepath: &Path) -> PathBuf {
+        let mut file_name = filepath.file_name().unwrap_or_default().to_owned();
+        file_name.push(".bak");
+        filepath.with_file_name(file_name)
+    }
+
+    fn temp_path(filepath: &Path) -> PathBuf {
+        let mut file_name = filepath.file_name().unwrap_or_default().to_owned();
+        file_name.push(".tmp");
+        filepath.with_file_name(file_name)
     }
 
-    fn set_value<T: serde::Serialize>(&mut self, key: &str, value: &T) {
-        match ron::to_string(value) {
-            Ok(value) => {
-                self.kv.insert(key.to_owned(), value);
-                self.dirty = true;
+    /// `None` if the file does not exist.
+    fn load(filepath: &Path, format: StorageFormat) -> Result<Option<HashMap<String, Vec<u8>>>> {
+        let data = match std::fs::read(filepath) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let kv = Self::deserialize(&data, format)?;
+        // files written before the version header are version 0.
+        let version = match kv.get(STORAGE_FORMAT_VERSION_KEY) {
+            Some(version) => format.decode::<u32>(version)?,
+            None => 0,
+        };
+        if version > STORAGE_FORMAT_VERSION {
+            anyhow::bail!("Unsupported storage format version {version}");
+        }
+        Ok(Some(kv))
+    }
+
+    fn load_backup(filepath: &Path, format: StorageFormat) -> HashMap<String, Vec<u8>> {
+        let backup_path = Self::backup_path(filepath);
+        match Self::load(&backup_path, format) {
+            Ok(Some(kv)) => {
+                warn!("Restored storage from {backup_path:?}");
+                kv
             }
+            Ok(None) => HashMap::new(),
             Err(err) => {
-                log::error!("failed to serialize value: {}", err);
+                error!("Failed to load storage {backup_path:?}: {err}");
+                HashMap::new()
             }
         }
     }
 
-    fn save_to_disk(filepath: &PathBuf, kv: HashMap<String, String>) {
+    fn deserialize(data: &[u8], format: StorageFormat) -> Result<HashMap<String, Vec<u8>>> {
+        let kv = match format {
+            StorageFormat::Ron => {
+                let kv: HashMap<String, String> = ron::de::from_bytes(data)?;
+                Self::string_values_to_bytes(kv)
+            }
+            StorageFormat::Json => {
+                let kv: HashMap<String, String> = serde_json::from_slice(data)?;
+                Self::string_values_to_bytes(kv)
+            }
+            StorageFormat::Binary => rmp_serde::from_slice(data)?,
+        };
+        Ok(kv)
+    }
+
+    fn serialize(kv: HashMap<String, Vec<u8>>, format: StorageFormat) -> Result<Vec<u8>> {
+        let data = match format {
+            StorageFormat::Ron => {
+                let kv = Self::bytes_values_to_string(kv)?;
+                let config = ron::ser::PrettyConfig::new();
+                ron::Options::default()
+                    .to_string_pretty(&kv, config)?
+                    .into_bytes()
+            }
+            StorageFormat::Json => serde_json::to_vec_pretty(&Self::bytes_values_to_string(kv)?)?,
+            StorageFormat::Binary => rmp_serde::to_vec(&kv)?,
+        };
+        Ok(data)
+    }
+
+    fn string_values_to_bytes(kv: HashMap<String, String>) -> HashMap<String, Vec<u8>> {
+        kv.into_iter()
+            .map(|(key, value)| (key, value.into_bytes()))
+            .collect()
+    }
+
+    fn bytes_values_to_string(kv: HashMap<String, Vec<u8>>) -> Result<HashMap<String, String>> {
+        kv.into_iter()
+            .map(|(key, value)| Ok((key, String::from_utf8(value)?)))
+            .collect()
+    }
+
+    /// write to a temporary file and rename it over the old file, which becomes the backup.
+    fn save_to_disk(filepath: &Path, kv: HashMap<String, Vec<u8>>, format: StorageFormat) {
         if let Some(parent_dir) = filepath.parent() {
             if !parent_dir.exists() {
                 if let Err(err) = std::fs::create_dir_all(parent_dir) {
-                    log::error!("Failed to create directory {parent_dir:?}: {err}");
+                    error!("Failed to create directory {parent_dir:?}: {err}");
                 }
             }
         }
 
-        match std::fs::File::create(filepath) {
-            Ok(file) => {
-                let config = ron::ser::PrettyConfig::new();
-
-                if let Err(err) = ron::Options::default().to_io_writer_pretty(file, &kv, config) {
-                    log::error!("Failed to serialize app state: {}", err);
-                }
-            }
+        let data = match Self::serialize(kv, format) {
+            Ok(data) => data,
             Err(err) => {
-                log::error!("Failed to create file {filepath:?}: {err}");
+                error!("Failed to serialize app state: {}", err);
+                return;
             }
+        };
+        let temp_path = Self::temp_path(filepath);
+        if let Err(err) = Self::write_synced(&temp_path, &data) {
+            error!("Failed to write file {temp_path:?}: {err}");
+            return;
+        }
+        if filepath.exists() {
+            let backup_path = Self::backup_path(filepath);
+            if let Err(err) = std::fs::rename(filepath, &backup_path) {
+                error!("Failed to rename {filepath:?} to {backup_path:?}: {err}");
+            }
+        }
+        if let Err(err) = std::fs::rename(&temp_path, filepath) {
+            error!("Failed to rename {temp_path:?} to {filepath:?}: {err}");
         }
     }
 
+    fn write_synced(filepath: &Path, data: &[u8]) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(filepath)?;
+        file.write_all(data)?;
+        file.sync_all()
+    }
+}
+impl StorageBackend for FileStorage {
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        self.kv.get(key).cloned()
+    }
+
+    fn set_bytes(&mut self, key: &str, value: Vec<u8>) {
+        self.kv.insert(key.to_owned(), value);
+        self.dirty = true;
+    }
+
     fn flush(&mut self) {
         if self.dirty {
             self.dirty = false;
-            let kv = self.kv.clone();
+            let mut kv = self.kv.clone();
+            match self.format.encode(&STORAGE_FORMAT_VERSION) {
+                Ok(version) => {
+                    kv.insert(STORAGE_FORMAT_VERSION_KEY.to_owned(), version);
+                }
+                Err(err) => error!("Failed to serialize storage version: {}", err),
+            }
             let filepath = self.filepath.clone();
+            let format = self.format;
 
             if let Some(join_handle) = self.save_join_handle.take() {
                 join_handle.join().ok();
             }
 
             self.save_join_handle = Some(std::thread::spawn(move || {
-                Self::save_to_disk(&filepath, kv)
+                Self::save_to_disk(&filepath, kv, format)
             }));
         }
     }
 
-    fn set_egui_memory(&mut self, egui_memory: &egui::Memory) {
-        self.set_value(STORAGE_EGUI_MEMORY_KEY, egui_memory);
-    }
-
-    fn get_egui_memory(&self) -> Option<egui::Memory> {
-        self.get_value(STORAGE_EGUI_MEMORY_KEY)
-    }
-
-    fn set_windows(&mut self, windows: &HashMap<egui::ViewportId, WindowSettings>) {
-        let mut prev_windows = self.get_windows().unwrap_or_default();
-        for (id, window) in windows {
-            prev_windows.insert(*id, *window);
-        }
-        self.set_value(STORAGE_WINDOWS_KEY, &prev_windows);
-    }
-
-    fn get_windows(&self) -> Option<HashMap<egui::ViewportId, WindowSettings>> {
-        self.get_value(STORAGE_WINDOWS_KEY)
+    fn format(&self) -> StorageFormat {
+        self.format
     }
 }
-impl Drop for InnerStorage {
+impl Drop for FileStorage {
     fn drop(&mut self) {
         if let Some(join_handle) = self.save_join_handle.take() {
             join_handle.join().ok();
@@ -150,22 +366,54 @@ impl Drop for InnerStorage {
     }
 }
 
+/// app state storage passed to [`crate::App::save`].
 #[derive(Clone)]
 pub struct Storage {
-    inner: Arc<Mutex<InnerStorage>>,
+    inner: Arc<Mutex<Box<dyn StorageBackend>>>,
 }
 impl Storage {
-    pub(crate) fn from_app_id(app_id: &str) -> Result<Self> {
-        Ok(Self {
-            inner: Arc::new(Mutex::new(InnerStorage::from_app_id(app_id)?)),
-        })
+    pub(crate) fn new(backend: Box<dyn StorageBackend>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(backend)),
+        }
+    }
+
+    /// use the backend of `run_option`, or a [`FileStorage`] of `app_id` with the format and location of `run_option`.
+    pub(crate) fn from_run_option(run_option: &mut crate::RunOption, app_id: &str) -> Result<Self> {
+        let mut backend = match run_option.storage_backend.take() {
+            Some(backend) => backend,
+            None => Box::new(Self::file_storage(run_option, app_id)?),
+        };
+        migrate(
+            backend.as_mut(),
+            run_option.storage_version,
+            run_option.storage_migration.take(),
+        );
+        Ok(Self::new(backend))
+    }
+
+    fn file_storage(run_option: &crate::RunOption, app_id: &str) -> Result<FileStorage> {
+        let Some(dir) =
+            (run_option.storage_dir.clone()).or_else(|| FileStorage::storage_dir(app_id))
+        else {
+            anyhow::bail!("Failed to get storage directory");
+        };
+        FileStorage::from_dir(
+            dir,
+            run_option.storage_file_name.as_deref(),
+            run_option.storage_format,
+        )
     }
 
     pub(crate) fn flush(&self) {
         self.inner.lock().unwrap().flush();
     }
 
-    pub(crate) fn set_egui_memory(&mut self, egui_memory: &egui::Memory) {
+    pub(crate) fn clear_poison(&self) {
+        self.inner.clear_poison();
+    }
+
+    pub(crate) fn set_egui_memory(&self, egui_memory: &egui::Memory) {
         self.inner.lock().unwrap().set_egui_memory(egui_memory);
     }
 
@@ -173,7 +421,7 @@ impl Storage {
         self.inner.lock().unwrap().get_egui_memory()
     }
 
-    pub(crate) fn set_windows(&mut self, windows: &HashMap<egui::ViewportId, WindowSettings>) {
+    pub(crate) fn set_windows(&self, windows: &HashMap<egui::ViewportId, WindowSettings>) {
         self.inner.lock().unwrap().set_windows(windows);
     }
 
@@ -183,12 +431,12 @@ impl Storage {
 
     /// Set value to storage.
     pub fn set_value<T: serde::Serialize>(&mut self, key: &str, value: &T) {
-        self.inner.lock().unwrap().set_value(key, value);
+        set_encoded(self.inner.lock().unwrap().as_mut(), key, value);
     }
 
     /// Get value from storage.
     pub fn get_value<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
-        self.inner.lock().unwrap().get_value(key)
+        get_decoded(self.inner.lock().unwrap().as_ref(), key)
     }
 }
 impl Debug for Storage {
@@ -197,5 +445,144 @@ impl Debug for Storage {
     }
 }
 
+/// migrate the app state from the stored version, see [`crate::RunOption::storage_migration`].
+pub type StorageMigration = Box<dyn FnOnce(u32, &mut dyn StorageBackend)>;
+
+fn migrate(backend: &mut dyn StorageBackend, version: u32, migration: Option<StorageMigration>) {
+    let stored_version = get_decoded(backend, STORAGE_APP_VERSION_KEY).unwrap_or(0);
+    if stored_version >= version {
+        return;
+    }
+    if let Some(migration) = migration {
+        migration(stored_version, backend);
+    }
+    set_encoded(backend, STORAGE_APP_VERSION_KEY, &version);
+}
+
+/// version of the layout of the files written by [`FileStorage`].
+const STORAGE_FORMAT_VERSION: u32 = 1;
+const STORAGE_FORMAT_VERSION_KEY: &str = "egui_ash_format_version";
+const STORAGE_APP_VERSION_KEY: &str = "egui_ash_app_version";
 pub(crate) const STORAGE_EGUI_MEMORY_KEY: &str = "egui_memory";
 pub(crate) const STORAGE_WINDOWS_KEY: &str = "egui_windows";
+pub(crate) const STORAGE_ZOOM_FACTOR_KEY: &str = "egui_ash_zoom_factor";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an empty directory for the files of one test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("egui-ash-storage-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // open the file, set `key` to `value` and wait until the file is written.
+    fn write_value(filepath: &Path, format: StorageFormat, key: &str, value: &str) {
+        let mut storage = FileStorage::from_path(filepath, format);
+        set_encoded(&mut storage, key, value);
+        storage.flush();
+    }
+
+    fn read_value(filepath: &Path, format: StorageFormat, key: &str) -> Option<String> {
+        get_decoded(&FileStorage::from_path(filepath, format), key)
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        // tuple keys are not JSON object keys, JSON stores the value as a RON string.
+        let value = HashMap::from([((1u32, 2u32), "one two".to_owned())]);
+        for format in [
+            StorageFormat::Ron,
+            StorageFormat::Json,
+            StorageFormat::Binary,
+        ] {
+            let encoded = format.encode(&value).unwrap();
+            let decoded: HashMap<(u32, u32), String> = format.decode(&encoded).unwrap();
+            assert_eq!(decoded, value, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let dir = test_dir("round-trip");
+        for format in [
+            StorageFormat::Ron,
+            StorageFormat::Json,
+            StorageFormat::Binary,
+        ] {
+            let filepath = dir.join(format!("app.{}", format.extension()));
+            write_value(&filepath, format, "key", "value");
+            assert_eq!(
+                read_value(&filepath, format, "key").as_deref(),
+                Some("value"),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn corrupted_file_falls_back_to_backup() {
+        let dir = test_dir("backup");
+        let filepath = dir.join("app.ron");
+        write_value(&filepath, StorageFormat::Ron, "key", "first");
+        // the second write keeps the first file as backup.
+        write_value(&filepath, StorageFormat::Ron, "key", "second");
+        std::fs::write(&filepath, b"(\"key\": ").unwrap();
+        assert_eq!(
+            read_value(&filepath, StorageFormat::Ron, "key").as_deref(),
+            Some("first")
+        );
+    }
+
+    #[test]
+    fn failed_write_keeps_file() {
+        let dir = test_dir("failed-write");
+        let filepath = dir.join("app.ron");
+        write_value(&filepath, StorageFormat::Ron, "key", "first");
+        // the temporary file can not be created where a directory is.
+        std::fs::create_dir(FileStorage::temp_path(&filepath)).unwrap();
+        write_value(&filepath, StorageFormat::Ron, "key", "second");
+        assert_eq!(
+            read_value(&filepath, StorageFormat::Ron, "key").as_deref(),
+            Some("first")
+        );
+        assert!(!FileStorage::backup_path(&filepath).exists());
+    }
+
+    #[test]
+    fn migration_runs_once() {
+        let dir = test_dir("migration");
+        let filepath = dir.join("app.ron");
+        let calls = Arc::new(Mutex::new(vec![]));
+        let migration = |calls: &Arc<Mutex<Vec<u32>>>| -> StorageMigration {
+            let calls = calls.clone();
+            Box::new(move |stored_version, backend| {
+                calls.lock().unwrap().push(stored_version);
+                set_encoded(backend, "key", "migrated");
+            })
+        };
+
+        // a store written before the app versioned it is version 0.
+        write_value(&filepath, StorageFormat::Ron, "key", "old");
+        for _ in 0..2 {
+            let mut storage = FileStorage::from_path(&filepath, StorageFormat::Ron);
+            migrate(&mut storage, 2, Some(migration(&calls)));
+            storage.flush();
+        }
+        assert_eq!(*calls.lock().unwrap(), [0]);
+        let storage = FileStorage::from_path(&filepath, StorageFormat::Ron);
+        assert_eq!(get_decoded(&storage, STORAGE_APP_VERSION_KEY), Some(2u32));
+        assert_eq!(get_decoded(&storage, "key"), Some("migrated".to_owned()));
+        drop(storage);
+
+        // the next version migrates from the stored one.
+        let mut storage = FileStorage::from_path(&filepath, StorageFormat::Ron);
+        migrate(&mut storage, 3, Some(migration(&calls)));
+        assert_eq!(*calls.lock().unwrap(), [0, 2]);
+        assert_eq!(get_decoded(&storage, STORAGE_APP_VERSION_KEY), Some(3u32));
+    }
+}
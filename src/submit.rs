@@ -0,0 +1,114 @@
+use ash::vk;
+use std::sync::{Arc, Mutex};
+
+type SubmitHook = Arc<dyn Fn(&SubmitInfo) + Send + Sync>;
+type FinalPassHook = Arc<dyn Fn(&FinalPassInfo) + Send + Sync>;
+
+/// frame of a viewport in a [`SubmitInfo`].
+#[derive(Debug, Clone, Copy)]
+pub struct SubmittedFrame {
+    pub viewport_id: egui::ViewportId,
+    /// command buffer with the egui render pass of the frame.
+    pub command_buffer: vk::CommandBuffer,
+    /// semaphore the submission waits for, signaled when the swapchain image is acquired.
+    pub wait_semaphore: vk::Semaphore,
+    /// semaphore the submission signals, waited for by the present.
+    pub signal_semaphore: vk::Semaphore,
+}
+
+/// queue submission of egui-ash passed to the [`SubmitHooks`].
+#[derive(Debug, Clone, Copy)]
+pub struct SubmitInfo<'a> {
+    pub queue: vk::Queue,
+    /// frames submitted together in one `vkQueueSubmit`.
+    pub frames: &'a [SubmittedFrame],
+    /// fence signaled when the frames finish on the GPU.
+    pub fence: vk::Fence,
+}
+
+/// swapchain image of a frame passed to the final pass of the [`SubmitHooks`].
+///
+/// The image is in `PRESENT_SRC_KHR`, written by egui in the `COLOR_ATTACHMENT_OUTPUT` stage.
+#[derive(Debug, Clone, Copy)]
+pub struct FinalPassInfo {
+    pub viewport_id: egui::ViewportId,
+    /// command buffer with the egui render pass of the frame, recording.
+    pub command_buffer: vk::CommandBuffer,
+    pub image: vk::Image,
+    /// index of the image in the swapchain, e.g. to look up the image views you created for it.
+    pub image_index: usize,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+}
+
+#[derive(Default)]
+struct Hooks {
+    pre_submit: Option<SubmitHook>,
+    post_submit: Option<SubmitHook>,
+    final_pass: Option<FinalPassHook>,
+}
+
+/// callbacks around the queue submissions of egui-ash, e.g. for GPU profilers or to schedule
+/// async compute work.
+///
+/// Passed as [`crate::CreationContext::submit_hooks`]. The hooks run on the thread presenting the
+/// frames, which is the render thread with [`crate::RunOption::render_thread`]. Frames drawn by
+/// [`crate::HandleRedraw::Handle`] are submitted by the app and do not call the hooks.
+#[derive(Clone, Default)]
+pub struct SubmitHooks {
+    hooks: Arc<Mutex<Hooks>>,
+}
+impl SubmitHooks {
+    /// call `hook` right before the frames are submitted, e.g. to submit work on the same queue
+    /// the frames run after.
+    pub fn set_pre_submit(&self, hook: impl Fn(&SubmitInfo) + Send + Sync + 'static) {
+        self.hooks.lock().unwrap().pre_submit = Some(Arc::new(hook));
+    }
+
+    /// call `hook` right after the frames are submitted, before they are presented.
+    pub fn set_post_submit(&self, hook: impl Fn(&SubmitInfo) + Send + Sync + 'static) {
+        self.hooks.lock().unwrap().post_submit = Some(Arc::new(hook));
+    }
+
+    /// call `hook` to record commands on the swapchain image of each frame after egui, before the
+    /// frame is submitted and presented, e.g. a watermark, the redaction of a part of the window
+    /// from screen captures or compositing of your own.
+    ///
+    /// Leave the image in `PRESENT_SRC_KHR`. The hook records the frames of several viewports in
+    /// parallel, each on its own thread. With [`crate::RunOption::skip_unchanged_frames`] unchanged
+    /// frames are not presented again, request a repaint for a final pass that animates.
+    pub fn set_final_pass(&self, hook: impl Fn(&FinalPassInfo) + Send + Sync + 'static) {
+        self.hooks.lock().unwrap().final_pass = Some(Arc::new(hook));
+    }
+
+    /// remove the hooks.
+    pub fn clear(&self) {
+        *self.hooks.lock().unwrap() = Hooks::default();
+    }
+
+    // the hooks are cloned out of the lock, so that they can replace themselves.
+    pub(crate) fn pre_submit(&self, info: &SubmitInfo) {
+        let hook = self.hooks.lock().unwrap().pre_submit.clone();
+        if let Some(hook) = hook {
+            hook(info);
+        }
+    }
+
+    pub(crate) fn post_submit(&self, info: &SubmitInfo) {
+        let hook = self.hooks.lock().unwrap().post_submit.clone();
+        if let Some(hook) = hook {
+            hook(info);
+        }
+    }
+
+    /// record the final pass, returns whether there is one.
+    pub(crate) fn final_pass(&self, info: &FinalPassInfo) -> bool {
+        let hook = self.hooks.lock().unwrap().final_pass.clone();
+        let Some(hook) = hook else {
+            return false;
+        };
+        profile_scope!("final_pass");
+        hook(info);
+        true
+    }
+}
@@ -0,0 +1,129 @@
+//! fonts of the operating system for apps without bundled fonts.
+//!
+//! [`font_definitions`] uses the UI font of the OS, with fallbacks for CJK, Arabic, Hebrew, Thai and
+//! Devanagari text. Set [`crate::RunOption::system_fonts`] to install them before the app is created,
+//! or pass them to [`crate::Fonts::set_base`].
+
+use std::sync::Arc;
+
+#[cfg(target_os = "windows")]
+const UI_FAMILIES: &[&str] = &["Segoe UI"];
+#[cfg(target_os = "macos")]
+const UI_FAMILIES: &[&str] = &[".AppleSystemUIFont", "SF Pro Text", "Helvetica Neue"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const UI_FAMILIES: &[&str] = &["Cantarell", "Noto Sans", "Ubuntu", "DejaVu Sans"];
+
+#[cfg(target_os = "windows")]
+const MONOSPACE_FAMILIES: &[&str] = &["Cascadia Mono", "Consolas"];
+#[cfg(target_os = "macos")]
+const MONOSPACE_FAMILIES: &[&str] = &["SF Mono", "Menlo"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const MONOSPACE_FAMILIES: &[&str] = &["Noto Sans Mono", "Ubuntu Mono", "DejaVu Sans Mono"];
+
+// the first installed family of each group is used, so that only one large CJK font is loaded.
+const FALLBACK_GROUPS: &[&[&str]] = &[
+    // CJK
+    &[
+        "Microsoft YaHei UI",
+        "Microsoft YaHei",
+        "PingFang SC",
+        "Hiragino Sans",
+        "Noto Sans CJK SC",
+        "Noto Sans CJK JP",
+        "Source Han Sans SC",
+        "WenQuanYi Micro Hei",
+    ],
+    // Korean, for the CJK fonts without Hangul
+    &["Malgun Gothic", "Apple SD Gothic Neo", "Noto Sans CJK KR"],
+    // Arabic
+    &["Segoe UI", "Geeza Pro", "Noto Sans Arabic", "DejaVu Sans"],
+    // Hebrew
+    &[
+        "Segoe UI",
+        "Arial Hebrew",
+        "Noto Sans Hebrew",
+        "DejaVu Sans",
+    ],
+    // Thai
+    &["Leelawadee UI", "Thonburi", "Noto Sans Thai"],
+    // Devanagari
+    &["Nirmala UI", "Kohinoor Devanagari", "Noto Sans Devanagari"],
+];
+
+/// egui's default fonts preceded by the UI and monospace fonts of the OS and followed by fallback fonts
+/// for other scripts. Families that are not installed are skipped.
+///
+/// Loading the system fonts reads the font directories and takes a moment, call it once.
+pub fn font_definitions() -> egui::FontDefinitions {
+    profile_function!();
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let mut fonts = egui::FontDefinitions::default();
+    // families already in the chains, they cover their scripts.
+    let mut loaded = vec![];
+    for (family, name, candidates) in [
+        (egui::FontFamily::Proportional, "system-ui", UI_FAMILIES),
+        (
+            egui::FontFamily::Monospace,
+            "system-monospace",
+            MONOSPACE_FAMILIES,
+        ),
+    ] {
+        let Some((family_name, font_data)) = load_first(&db, candidates) else {
+            debug!("No system font of {candidates:?} found");
+            continue;
+        };
+        debug!("Using system font {family_name} for {family:?}");
+        fonts.font_data.insert(name.to_owned(), Arc::new(font_data));
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .insert(0, name.to_owned());
+        loaded.push(family_name);
+    }
+
+    let mut fallbacks = vec![];
+    for candidates in FALLBACK_GROUPS {
+        if candidates
+            .iter()
+            .any(|candidate| loaded.iter().any(|family_name| family_name == candidate))
+        {
+            continue;
+        }
+        let Some((family_name, font_data)) = load_first(&db, candidates) else {
+            continue;
+        };
+        debug!("Using system font {family_name} as fallback");
+        let name = format!("system-fallback-{family_name}");
+        fonts.font_data.insert(name.clone(), Arc::new(font_data));
+        fallbacks.push(name);
+        loaded.push(family_name);
+    }
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .extend(fallbacks.iter().cloned());
+    }
+
+    fonts
+}
+
+/// the regular face of the first installed family of `candidates`.
+fn load_first(db: &fontdb::Database, candidates: &[&str]) -> Option<(String, egui::FontData)> {
+    candidates.iter().find_map(|&family_name| {
+        let id = db.query(&fontdb::Query {
+            families: &[fontdb::Family::Name(family_name)],
+            ..Default::default()
+        })?;
+        let font_data = db.with_face_data(id, |data, index| {
+            let mut font_data = egui::FontData::from_owned(data.to_vec());
+            font_data.index = index;
+            font_data
+        })?;
+        Some((family_name.to_owned(), font_data))
+    })
+}
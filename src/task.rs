@@ -0,0 +1,98 @@
+//! futures spawned from the app, repainting egui when they complete.
+//!
+//! [`Spawner`] runs the futures on the async runtime of the app, egui-ash does not depend on one:
+//! pass it the spawn function of tokio, async-std, smol etc. The result of a future is picked up on
+//! the UI thread from its [`Task`], in the frame that the completion repaints.
+//!
+//! ```ignore
+//! let spawner = Spawner::new(&cc.context, |future| {
+//!     runtime.spawn(future);
+//! });
+//! self.task = Some(spawner.spawn(fetch_report()));
+//!
+//! // in App::ui
+//! if let Some(report) = self.task.as_mut().and_then(Task::poll) {
+//!     ui.label(report);
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{mpsc, Arc},
+};
+
+/// future handed to the spawn function of [`Spawner`].
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// spawns futures on the async runtime of the app, see the [module docs](self).
+#[derive(Clone)]
+pub struct Spawner {
+    context: egui::Context,
+    spawn: Arc<dyn Fn(BoxFuture) + Send + Sync>,
+}
+impl Spawner {
+    /// create a spawner repainting `context`, spawning the futures with `spawn`,
+    /// e.g. `|future| { tokio::spawn(future); }`.
+    pub fn new(context: &egui::Context, spawn: impl Fn(BoxFuture) + Send + Sync + 'static) -> Self {
+        Self {
+            context: context.clone(),
+            spawn: Arc::new(spawn),
+        }
+    }
+
+    /// run `future` on the runtime. Its completion repaints the viewport it was spawned from.
+    ///
+    /// Dropping the task does not cancel the future, only its result is dropped.
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+    ) -> Task<T> {
+        let (sender, receiver) = mpsc::channel();
+        let context = self.context.clone();
+        let viewport_id = context.viewport_id();
+        (self.spawn)(Box::pin(async move {
+            let output = future.await;
+            if sender.send(output).is_ok() {
+                context.request_repaint_of(viewport_id);
+            }
+        }));
+        Task {
+            receiver,
+            output: None,
+        }
+    }
+
+    /// run `future` on the runtime and drop its result, e.g. to save a file in the background.
+    pub fn spawn_detached(&self, future: impl Future<Output = ()> + Send + 'static) {
+        (self.spawn)(Box::pin(future));
+    }
+}
+
+/// result of a future spawned with [`Spawner::spawn`].
+pub struct Task<T> {
+    receiver: mpsc::Receiver<T>,
+    output: Option<T>,
+}
+impl<T> Task<T> {
+    /// the output of the future, once it completed.
+    pub fn poll(&mut self) -> Option<&T> {
+        if self.output.is_none() {
+            self.output = self.receiver.try_recv().ok();
+        }
+        self.output.as_ref()
+    }
+
+    /// take the output of the future, once it completed.
+    pub fn try_take(&mut self) -> Option<T> {
+        self.poll();
+        self.output.take()
+    }
+
+    /// whether the future completed.
+    ///
+    /// `false` forever if the runtime dropped the future, e.g. when it shut down.
+    pub fn is_finished(&mut self) -> bool {
+        self.poll().is_some()
+    }
+}
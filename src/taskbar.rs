@@ -0,0 +1,85 @@
+//! the app icon in the dock or taskbar, e.g. for the unread count of a chat app.
+//!
+//! Attention requests go through winit on every platform. The badge is shown on the macOS dock
+//! icon with the `dock-badge` feature and ignored elsewhere.
+
+fn badge_id() -> egui::Id {
+    egui::Id::new("egui_ash_taskbar_badge")
+}
+
+/// the app icon in the dock or taskbar.
+#[derive(Clone)]
+pub struct Taskbar {
+    context: egui::Context,
+}
+impl Taskbar {
+    pub(crate) fn new(context: egui::Context) -> Self {
+        Self { context }
+    }
+
+    /// set the badge text of the dock icon, `None` removes it. macOS only, needs the `dock-badge` feature.
+    pub fn set_badge(&self, label: Option<&str>) {
+        let label = label.map(str::to_owned);
+        self.context
+            .data_mut(|data| data.insert_temp(badge_id(), Some(label)));
+        self.context.request_repaint_of(egui::ViewportId::ROOT);
+    }
+
+    /// show `count` as badge of the dock icon, `0` removes it. macOS only, needs the `dock-badge` feature.
+    pub fn set_badge_count(&self, count: u64) {
+        let label = (count > 0).then(|| count.to_string());
+        self.set_badge(label.as_deref());
+    }
+
+    /// bounce the dock icon or flash the taskbar button until the app is focused.
+    ///
+    /// `critical` keeps bouncing on macOS, otherwise the icon bounces once.
+    pub fn request_attention(&self, critical: bool) {
+        let attention = if critical {
+            egui::UserAttentionType::Critical
+        } else {
+            egui::UserAttentionType::Informational
+        };
+        self.context.send_viewport_cmd_to(
+            egui::ViewportId::ROOT,
+            egui::ViewportCommand::RequestUserAttention(attention),
+        );
+    }
+
+    /// stop a request of [`Self::request_attention`] before the app is focused.
+    pub fn cancel_attention(&self) {
+        self.context.send_viewport_cmd_to(
+            egui::ViewportId::ROOT,
+            egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Reset),
+        );
+    }
+}
+
+/// apply the badge set since the last frame, on the main thread.
+pub(crate) fn apply_badge(ctx: &egui::Context) {
+    let Some(label) = ctx.data_mut(|data| data.remove_temp::<Option<String>>(badge_id())) else {
+        return;
+    };
+    #[cfg(all(feature = "dock-badge", target_os = "macos"))]
+    set_dock_badge(label.as_deref());
+    #[cfg(not(all(feature = "dock-badge", target_os = "macos")))]
+    let _ = label;
+}
+
+#[cfg(all(feature = "dock-badge", target_os = "macos"))]
+fn set_dock_badge(label: Option<&str>) {
+    use objc2::{class, msg_send, runtime::AnyObject};
+    use objc2_foundation::NSString;
+
+    let label = label.map(NSString::from_str);
+    let label = label
+        .as_deref()
+        .map_or(std::ptr::null(), |label| label as *const NSString);
+    // SAFETY: called on the main thread, `dockTile` returns the tile of the shared application,
+    // and `setBadgeLabel:` takes a nullable NSString.
+    unsafe {
+        let application: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let dock_tile: *mut AnyObject = msg_send![application, dockTile];
+        let _: () = msg_send![dock_tile, setBadgeLabel: label];
+    }
+}
@@ -0,0 +1,567 @@
+//! headless test harness rendering an app into an offscreen image, e.g. for CI with lavapipe.
+//!
+//! A [`TestHarness`] needs no display and no surface: create the device without the swapchain
+//! extension, feed egui input with the helpers, step the frames with a fixed time step and read the
+//! rendered image back with [`TestHarness::read_pixels`].
+
+use anyhow::Result;
+use ash::{vk, Device};
+use egui_winit::winit;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    allocator::{AllocationCreateInfo, MemoryLocation},
+    inspector::Inspector,
+    layer,
+    renderer::{self, EguiFrame, ImageRegistry, Renderer, RendererOptions, SwapchainUpdateInfo},
+    utils, zoom, Allocation, Allocator, App, AshRenderState, FrameInfo, FrameStatistics,
+};
+
+// rendered like a `_UNORM` swapchain, so that the pixels read back are the sRGB colors of egui.
+const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+/// offscreen images egui is rendered into, with the renderer and the command buffer to submit.
+///
+/// The image is created by the target, or the images are owned by someone else, e.g. an OpenXR
+/// swapchain.
+pub(crate) struct OffscreenTarget<A: Allocator + 'static> {
+    device: Device,
+    queue: vk::Queue,
+    queue_family_index: u32,
+    allocator: A,
+    pub(crate) image_registry: ImageRegistry,
+    pub(crate) max_texture_side: usize,
+    renderer: Arc<Mutex<Renderer<A>>>,
+    images: Vec<vk::Image>,
+    format: vk::Format,
+    // `None` for images owned by someone else.
+    image_allocation: Option<A::Allocation>,
+    rendered: bool,
+    command_pool: vk::CommandPool,
+    command_buffer: vk::CommandBuffer,
+    fence: vk::Fence,
+    pub(crate) size: winit::dpi::PhysicalSize<u32>,
+}
+impl<A: Allocator + 'static> OffscreenTarget<A> {
+    pub(crate) fn new(
+        render_state: AshRenderState<A>,
+        size: winit::dpi::PhysicalSize<u32>,
+        inspector: Inspector,
+    ) -> Result<Self> {
+        let mut target = Self::with_images(render_state, vec![], FORMAT, size, inspector)?;
+        target.create_image()?;
+        Ok(target)
+    }
+
+    /// render into `images` of `format`, which have `COLOR_ATTACHMENT` and `TRANSFER_DST` usage.
+    pub(crate) fn with_images(
+        render_state: AshRenderState<A>,
+        images: Vec<vk::Image>,
+        format: vk::Format,
+        size: winit::dpi::PhysicalSize<u32>,
+        inspector: Inspector,
+    ) -> Result<Self> {
+        if size.width == 0 || size.height == 0 {
+            anyhow::bail!("The offscreen image needs a size of at least one pixel");
+        }
+        let limits = unsafe {
+            render_state
+                .instance
+                .get_physical_device_properties(render_state.physical_device)
+                .limits
+        };
+        let max_texture_side = renderer::max_texture_side(&limits);
+        let (image_registry, receiver) = ImageRegistry::new();
+        let renderer = Renderer::new(
+            &render_state,
+            &limits,
+            receiver,
+            RendererOptions::default(),
+            FrameStatistics::default(),
+            inspector,
+        );
+        let device = render_state.device;
+        let allocator = render_state.allocator;
+
+        unsafe {
+            let command_pool = device.create_command_pool(
+                &vk::CommandPoolCreateInfo::default()
+                    .queue_family_index(render_state.queue_family_index)
+                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER),
+                None,
+            )?;
+            let command_buffer = device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::default()
+                    .command_buffer_count(1)
+                    .command_pool(command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY),
+            )?[0];
+            let fence = device.create_fence(&vk::FenceCreateInfo::default(), None)?;
+
+            Ok(Self {
+                device,
+                queue: render_state.queue,
+                queue_family_index: render_state.queue_family_index,
+                allocator,
+                image_registry,
+                max_texture_side,
+                renderer,
+                images,
+                format,
+                image_allocation: None,
+                rendered: false,
+                command_pool,
+                command_buffer,
+                fence,
+                size,
+            })
+        }
+    }
+
+    fn create_image(&mut self) -> Result<()> {
+        unsafe {
+            let image = self.device.create_image(
+                &vk::ImageCreateInfo::default()
+                    .image_type(vk::ImageType::TYPE_2D)
+                    .format(self.format)
+                    .extent(vk::Extent3D {
+                        width: self.size.width,
+                        height: self.size.height,
+                        depth: 1,
+                    })
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .samples(vk::SampleCountFlags::TYPE_1)
+                    .tiling(vk::ImageTiling::OPTIMAL)
+                    .usage(
+                        vk::ImageUsageFlags::COLOR_ATTACHMENT
+                            | vk::ImageUsageFlags::TRANSFER_SRC
+                            | vk::ImageUsageFlags::TRANSFER_DST,
+                    )
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                    .initial_layout(vk::ImageLayout::UNDEFINED),
+                None,
+            )?;
+            let requirements = self.device.get_image_memory_requirements(image);
+            let image_allocation = match self.allocator.allocate(A::AllocationCreateInfo::new(
+                Some("egui-ash offscreen image"),
+                requirements,
+                MemoryLocation::gpu_only(),
+                false,
+            )) {
+                Ok(allocation) => allocation,
+                Err(err) => {
+                    self.device.destroy_image(image, None);
+                    return Err(err);
+                }
+            };
+            if let Err(err) = self.device.bind_image_memory(
+                image,
+                image_allocation.memory(),
+                image_allocation.offset(),
+            ) {
+                self.device.destroy_image(image, None);
+                let _ = self.allocator.free(image_allocation);
+                return Err(err.into());
+            }
+            self.images = vec![image];
+            self.image_allocation = Some(image_allocation);
+        }
+        Ok(())
+    }
+
+    fn destroy_image(&mut self) {
+        let Some(allocation) = self.image_allocation.take() else {
+            return;
+        };
+        for image in self.images.drain(..) {
+            unsafe {
+                self.device.destroy_image(image, None);
+            }
+        }
+        if let Err(err) = self.allocator.free(allocation) {
+            error!("Failed to free the offscreen image: {err}");
+        }
+    }
+
+    /// recreate the image with `size`, the next render updates the renderer to it.
+    #[cfg_attr(not(feature = "kittest"), allow(dead_code))]
+    pub(crate) fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) -> Result<()> {
+        if size == self.size {
+            return Ok(());
+        }
+        if self.image_allocation.is_none() {
+            anyhow::bail!("Images owned by someone else can not be resized");
+        }
+        if size.width == 0 || size.height == 0 {
+            anyhow::bail!("The offscreen image needs a size of at least one pixel");
+        }
+        // the last render was waited for, the image is not in use.
+        self.destroy_image();
+        self.size = size;
+        self.rendered = false;
+        self.create_image()
+    }
+
+    /// render `shapes` into the image, waiting for the GPU.
+    pub(crate) fn render(
+        &mut self,
+        context: &egui::Context,
+        shapes: Vec<egui::epaint::ClippedShape>,
+        pixels_per_point: f32,
+        textures_delta: egui::TexturesDelta,
+    ) -> Result<()> {
+        self.render_image(
+            0,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            context,
+            shapes,
+            pixels_per_point,
+            textures_delta,
+        )
+    }
+
+    /// render `shapes` into the image at `index`, left in `final_layout`, waiting for the GPU.
+    pub(crate) fn render_image(
+        &mut self,
+        index: usize,
+        final_layout: vk::ImageLayout,
+        context: &egui::Context,
+        shapes: Vec<egui::epaint::ClippedShape>,
+        pixels_per_point: f32,
+        textures_delta: egui::TexturesDelta,
+    ) -> Result<()> {
+        profile_function!();
+        let image = self.images[index];
+        let mut egui_cmd = self.renderer.lock().unwrap().create_egui_cmd(
+            egui::ViewportId::ROOT,
+            context,
+            EguiFrame {
+                shapes,
+                pixels_per_point,
+                textures_delta,
+            },
+            pixels_per_point,
+            self.size,
+        );
+        if !self.rendered || egui_cmd.swapchain_recreate_required() {
+            egui_cmd.update_swapchain(SwapchainUpdateInfo {
+                width: self.size.width,
+                height: self.size.height,
+                swapchain_images: self.images.clone(),
+                surface_format: self.format,
+            });
+        }
+        egui_cmd.update_textures();
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .layer_count(1)
+            .level_count(1);
+        let cmd = self.command_buffer;
+        unsafe {
+            self.device
+                .reset_command_buffer(cmd, vk::CommandBufferResetFlags::empty())?;
+            self.device.begin_command_buffer(
+                cmd,
+                &vk::CommandBufferBeginInfo::default()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )?;
+            // like the swapchain images, the image is cleared before egui paints.
+            utils::insert_image_memory_barrier(
+                &self.device,
+                cmd,
+                image,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                subresource_range,
+            );
+            self.device.cmd_clear_color_image(
+                cmd,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: egui_cmd.clear_color(),
+                },
+                &[subresource_range],
+            );
+            utils::insert_image_memory_barrier(
+                &self.device,
+                cmd,
+                image,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                subresource_range,
+            );
+            egui_cmd.record(cmd, index);
+            if final_layout != vk::ImageLayout::PRESENT_SRC_KHR {
+                utils::insert_image_memory_barrier(
+                    &self.device,
+                    cmd,
+                    image,
+                    vk::QUEUE_FAMILY_IGNORED,
+                    vk::QUEUE_FAMILY_IGNORED,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::AccessFlags::empty(),
+                    vk::ImageLayout::PRESENT_SRC_KHR,
+                    final_layout,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    subresource_range,
+                );
+            }
+            self.device.end_command_buffer(cmd)?;
+
+            let command_buffers = [cmd];
+            self.device.queue_submit(
+                self.queue,
+                std::slice::from_ref(&vk::SubmitInfo::default().command_buffers(&command_buffers)),
+                self.fence,
+            )?;
+            self.device.wait_for_fences(&[self.fence], true, u64::MAX)?;
+            self.device.reset_fences(&[self.fence])?;
+        }
+        self.rendered = true;
+        Ok(())
+    }
+
+    /// the image of the last render.
+    pub(crate) fn read_pixels(&self) -> Result<egui::ColorImage> {
+        if !self.rendered {
+            anyhow::bail!("No frame was rendered yet");
+        }
+        // the render pass leaves the image in the present layout of a swapchain image.
+        renderer::copy_to_cpu(
+            &self.device,
+            self.queue,
+            self.queue_family_index,
+            &self.allocator,
+            self.images[0],
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            self.format,
+            [0, 0],
+            [self.size.width, self.size.height],
+        )
+    }
+
+    pub(crate) fn destroy(&mut self) {
+        self.renderer.lock().unwrap().destroy_root();
+        unsafe {
+            self.device.destroy_fence(self.fence, None);
+            self.device.destroy_command_pool(self.command_pool, None);
+        }
+        self.destroy_image();
+    }
+}
+
+/// runs an app headless, rendering egui into an offscreen image.
+///
+/// Frames paint egui only, like [`crate::HandleRedraw::Auto`], and child viewports are embedded.
+pub struct TestHarness<A: Allocator + 'static> {
+    context: egui::Context,
+    target: OffscreenTarget<A>,
+    pixels_per_point: f32,
+    egui_input: egui::RawInput,
+    time_step: Duration,
+    time: Duration,
+    frame_index: u64,
+    repaint_delay: Duration,
+}
+impl<A: Allocator + 'static> TestHarness<A> {
+    /// create a harness rendering into an image of `size` physical pixels.
+    pub fn new(
+        context: egui::Context,
+        render_state: AshRenderState<A>,
+        size: winit::dpi::PhysicalSize<u32>,
+        pixels_per_point: f32,
+    ) -> Result<Self> {
+        if size.width == 0 || size.height == 0 {
+            anyhow::bail!("The test harness needs a size of at least one pixel");
+        }
+        context.set_embed_viewports(true);
+        let inspector = Inspector::install(&context);
+        let target = OffscreenTarget::new(render_state, size, inspector)?;
+        let egui_input = egui::RawInput {
+            focused: true,
+            max_texture_side: Some(target.max_texture_side),
+            ..Default::default()
+        };
+        Ok(Self {
+            context,
+            target,
+            pixels_per_point,
+            egui_input,
+            time_step: Duration::from_secs(1) / 60,
+            time: Duration::ZERO,
+            frame_index: 0,
+            repaint_delay: Duration::ZERO,
+        })
+    }
+
+    /// egui context
+    pub fn context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    /// image registry to register user textures.
+    pub fn image_registry(&self) -> ImageRegistry {
+        self.target.image_registry.clone()
+    }
+
+    /// size of the rendered image in physical pixels.
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.target.size
+    }
+
+    /// time the frames advance by, 1/60 s by default.
+    pub fn set_time_step(&mut self, time_step: Duration) {
+        self.time_step = time_step;
+    }
+
+    /// input for the next frame. (modifiers, dropped files, etc.)
+    pub fn egui_input_mut(&mut self) -> &mut egui::RawInput {
+        &mut self.egui_input
+    }
+
+    /// add an event to the input of the next frame.
+    pub fn push_event(&mut self, event: egui::Event) {
+        self.egui_input.events.push(event);
+    }
+
+    /// move the pointer to `pos` in points.
+    pub fn move_pointer(&mut self, pos: egui::Pos2) {
+        self.push_event(egui::Event::PointerMoved(pos));
+    }
+
+    /// press and release the primary button at `pos` in points.
+    pub fn click(&mut self, pos: egui::Pos2) {
+        self.move_pointer(pos);
+        for pressed in [true, false] {
+            self.push_event(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: self.egui_input.modifiers,
+            });
+        }
+    }
+
+    /// press and release `key`.
+    pub fn press_key(&mut self, key: egui::Key, modifiers: egui::Modifiers) {
+        for pressed in [true, false] {
+            self.push_event(egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers,
+            });
+        }
+    }
+
+    /// type `text` into the focused text edit.
+    pub fn type_text(&mut self, text: &str) {
+        self.push_event(egui::Event::Text(text.to_owned()));
+    }
+
+    /// run and render one frame of the app, waiting for the GPU.
+    pub fn step(&mut self, app: &mut impl App) -> Result<egui::PlatformOutput> {
+        profile_function!();
+        let size = self.target.size;
+        let screen_size_in_points = egui::vec2(size.width as f32, size.height as f32)
+            / (self.pixels_per_point * self.context.zoom_factor());
+        self.egui_input.screen_rect = Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            screen_size_in_points,
+        ));
+        self.egui_input.time = Some(self.time.as_secs_f64());
+        self.egui_input.predicted_dt = self.time_step.as_secs_f32();
+        self.egui_input.viewport_id = egui::ViewportId::ROOT;
+        self.egui_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(self.pixels_per_point);
+        let mut raw_input = self.egui_input.take();
+        app.on_raw_input(egui::ViewportId::ROOT, &mut raw_input);
+
+        let frame_info = FrameInfo {
+            delta_time: if self.frame_index == 0 {
+                Duration::ZERO
+            } else {
+                self.time_step
+            },
+            frame_index: self.frame_index,
+            viewport_id: egui::ViewportId::ROOT,
+            predicted_present_time: None,
+            window_size: size,
+            fixed_update_alpha: crate::fixed_update_alpha(&self.context),
+        };
+        let full_output = self.context.run(raw_input, |ctx| {
+            app.ui(ctx, &frame_info);
+            zoom::handle_zoom(ctx);
+            layer::paint_layer_split(ctx);
+        });
+        app.on_full_output(egui::ViewportId::ROOT, &full_output);
+        let egui::FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            viewport_output,
+        } = full_output;
+        #[cfg(feature = "accesskit")]
+        if let Some(update) = &platform_output.accesskit_update {
+            crate::access_tree::store(&self.context, egui::ViewportId::ROOT, update);
+        }
+        self.repaint_delay = viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(Duration::MAX, |output| output.repaint_delay);
+        self.frame_index += 1;
+        self.time += self.time_step;
+
+        self.target
+            .render(&self.context, shapes, pixels_per_point, textures_delta)?;
+        Ok(platform_output)
+    }
+
+    /// step frames until egui does not repaint right away, e.g. when the animations finished.
+    ///
+    /// Returns the number of frames, at most `max_frames`.
+    pub fn run(&mut self, app: &mut impl App, max_frames: usize) -> Result<usize> {
+        for frame in 1..=max_frames {
+            self.step(app)?;
+            if !self.repaint_delay.is_zero() {
+                return Ok(frame);
+            }
+        }
+        Ok(max_frames)
+    }
+
+    /// the image rendered by the last frame.
+    pub fn read_pixels(&self) -> Result<egui::ColorImage> {
+        self.target.read_pixels()
+    }
+
+    /// destroy the vulkan objects of the harness.
+    ///
+    /// Call this before destroying the device.
+    pub fn destroy(mut self) {
+        self.target.destroy();
+    }
+}
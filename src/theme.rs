@@ -0,0 +1,56 @@
+use egui_winit::winit;
+
+fn pending_id() -> egui::Id {
+    egui::Id::new("egui_ash_theme_preference")
+}
+
+/// switch between following the system theme and a fixed light or dark theme at runtime.
+///
+/// Restyles all viewports and sets the theme of their windows, e.g. the title bars, from the next frame on.
+pub fn set_theme_preference(ctx: &egui::Context, preference: egui::ThemePreference) {
+    ctx.set_theme(preference);
+    ctx.data_mut(|data| data.insert_temp(pending_id(), preference));
+    ctx.request_repaint();
+}
+
+/// the current theme preference, see [`set_theme_preference`].
+pub fn theme_preference(ctx: &egui::Context) -> egui::ThemePreference {
+    ctx.options(|options| options.theme_preference)
+}
+
+/// use the styles of [`crate::RunOption::light_style`] and [`crate::RunOption::dark_style`] instead of egui's defaults.
+///
+/// The styles are shared by all viewports of `ctx`, including the ones created later.
+pub(crate) fn set_styles(
+    ctx: &egui::Context,
+    light_style: Option<&egui::Style>,
+    dark_style: Option<&egui::Style>,
+) {
+    if let Some(style) = light_style {
+        ctx.set_style_of(egui::Theme::Light, style.clone());
+    }
+    if let Some(style) = dark_style {
+        ctx.set_style_of(egui::Theme::Dark, style.clone());
+    }
+}
+
+/// the preference set by [`set_theme_preference`] since the last call, to be applied to the windows.
+pub(crate) fn take_theme_preference(ctx: &egui::Context) -> Option<egui::ThemePreference> {
+    ctx.data_mut(|data| data.remove_temp(pending_id()))
+}
+
+/// the theme of the windows for `preference`, `None` follows the system.
+pub(crate) fn window_theme(preference: egui::ThemePreference) -> Option<winit::window::Theme> {
+    match preference {
+        egui::ThemePreference::System => None,
+        egui::ThemePreference::Light => Some(winit::window::Theme::Light),
+        egui::ThemePreference::Dark => Some(winit::window::Theme::Dark),
+    }
+}
+
+pub(crate) fn egui_theme(theme: winit::window::Theme) -> egui::Theme {
+    match theme {
+        winit::window::Theme::Light => egui::Theme::Light,
+        winit::window::Theme::Dark => egui::Theme::Dark,
+    }
+}
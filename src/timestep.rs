@@ -0,0 +1,331 @@
+use egui_winit::winit;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::integration::IntegrationEvent;
+
+// more steps are dropped after a long stall, so that the simulation catches up instead of hanging.
+const MAX_STEPS_PER_FRAME: u32 = 8;
+
+// sleeping is not precise, the last part of the wait for the next frame is spun.
+const SPIN_DURATION: Duration = Duration::from_millis(2);
+
+fn alpha_id() -> egui::Id {
+    egui::Id::new("egui_ash_fixed_update_alpha")
+}
+
+/// interpolation factor between the previous and the last [`crate::App::fixed_update`] in `0.0..1.0`.
+///
+/// Blend the previous and the current simulation state with it in [`crate::App::ui`] for smooth motion.
+/// `1.0` without [`crate::RunOption::fixed_timestep`].
+pub fn fixed_update_alpha(ctx: &egui::Context) -> f32 {
+    ctx.data(|data| data.get_temp(alpha_id())).unwrap_or(1.0)
+}
+
+/// accumulator that calls [`crate::App::fixed_update`] at a fixed rate.
+pub(crate) struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    last_tick: Instant,
+}
+impl FixedTimestep {
+    /// `None` if `step` is zero, which would never advance the simulation.
+    pub(crate) fn new(step: Duration) -> Option<Self> {
+        if step.is_zero() {
+            warn!("Ignored RunOption::fixed_timestep of zero, App::fixed_update is not called");
+            return None;
+        }
+        Some(Self {
+            step,
+            accumulator: Duration::ZERO,
+            last_tick: Instant::now(),
+        })
+    }
+
+    /// run the steps due since the last tick and store the interpolation factor in `ctx`.
+    pub(crate) fn tick(&mut self, ctx: &egui::Context, app: &mut impl crate::App) {
+        let (steps, alpha) = self.advance(Instant::now());
+        for _ in 0..steps {
+            app.fixed_update(self.step);
+        }
+        ctx.data_mut(|data| data.insert_temp(alpha_id(), alpha));
+    }
+
+    /// the number of steps due at `now` and the interpolation factor after them.
+    fn advance(&mut self, now: Instant) -> (u32, f32) {
+        self.accumulator += now - self.last_tick;
+        self.last_tick = now;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == MAX_STEPS_PER_FRAME {
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        let alpha = self.accumulator.as_secs_f32() / self.step.as_secs_f32();
+        (steps, alpha)
+    }
+
+    /// time of the next step.
+    pub(crate) fn next_step(&self) -> Instant {
+        self.last_tick + (self.step - self.accumulator)
+    }
+}
+
+/// counts the frames of [`crate::App::ui`] for [`crate::FrameInfo`].
+#[derive(Default)]
+pub(crate) struct FrameClock {
+    last_frame: Option<Instant>,
+    frame_index: u64,
+}
+impl FrameClock {
+    /// info of the next frame. `refresh_period` is the frame time of the monitor, if known.
+    pub(crate) fn next_frame(
+        &mut self,
+        ctx: &egui::Context,
+        viewport_id: egui::ViewportId,
+        window_size: egui_winit::winit::dpi::PhysicalSize<u32>,
+        refresh_period: Option<Duration>,
+    ) -> crate::FrameInfo {
+        let now = Instant::now();
+        let delta_time = self
+            .last_frame
+            .map_or(Duration::ZERO, |last_frame| now - last_frame);
+        self.last_frame = Some(now);
+        let frame_index = self.frame_index;
+        self.frame_index += 1;
+
+        crate::FrameInfo {
+            delta_time,
+            frame_index,
+            viewport_id,
+            predicted_present_time: refresh_period.map(|period| now + period),
+            window_size,
+            fixed_update_alpha: fixed_update_alpha(ctx),
+        }
+    }
+}
+
+/// frame rate cap, independent of the present mode.
+///
+/// Starts at [`crate::RunOption::max_fps`] and can be changed while the app runs.
+#[derive(Clone, Default)]
+pub struct FrameLimiter {
+    max_fps: Arc<Mutex<Option<f32>>>,
+}
+impl FrameLimiter {
+    pub(crate) fn new(max_fps: Option<f32>) -> Self {
+        let limiter = Self::default();
+        limiter.set_max_fps(max_fps);
+        limiter
+    }
+
+    /// cap the frame rate at `max_fps` frames per second. `None` removes the cap.
+    pub fn set_max_fps(&self, max_fps: Option<f32>) {
+        *self.max_fps.lock().unwrap() = max_fps.filter(|max_fps| *max_fps > 0.0);
+    }
+
+    /// current frame rate cap.
+    pub fn max_fps(&self) -> Option<f32> {
+        *self.max_fps.lock().unwrap()
+    }
+}
+impl std::fmt::Debug for FrameLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameLimiter")
+            .field("max_fps", &self.max_fps())
+            .finish()
+    }
+}
+
+/// paces the frames of the run loops by [`FrameLimiter`].
+pub(crate) struct FramePacer {
+    limiter: FrameLimiter,
+    last_frame: Option<Instant>,
+}
+impl FramePacer {
+    pub(crate) fn new(limiter: FrameLimiter) -> Self {
+        Self {
+            limiter,
+            last_frame: None,
+        }
+    }
+
+    /// `Some(wake_up_time)` if the next frame is not due yet and the loop should wait for it.
+    /// Otherwise the remaining time is slept precisely and the frame can start.
+    pub(crate) fn defer_frame(&mut self) -> Option<Instant> {
+        if let Some(next_frame) = self.next_frame() {
+            let now = Instant::now();
+            if next_frame > now + SPIN_DURATION {
+                return Some(next_frame - SPIN_DURATION);
+            }
+            sleep_until(next_frame);
+        }
+        self.last_frame = Some(Instant::now());
+        None
+    }
+
+    /// sleep precisely until the next frame is due.
+    #[cfg(feature = "sdl2")]
+    pub(crate) fn wait_for_frame(&mut self) {
+        if let Some(next_frame) = self.next_frame() {
+            sleep_until(next_frame);
+        }
+        self.last_frame = Some(Instant::now());
+    }
+
+    fn next_frame(&self) -> Option<Instant> {
+        let max_fps = self.limiter.max_fps()?;
+        let last_frame = self.last_frame?;
+        Some(last_frame + Duration::from_secs_f32(1.0 / max_fps))
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline <= now {
+        return;
+    }
+    if let Some(sleep) = (deadline - now).checked_sub(SPIN_DURATION) {
+        std::thread::sleep(sleep);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+#[derive(Default)]
+struct StepperState {
+    manual: bool,
+    steps: VecDeque<Duration>,
+    // time of the manual clock, the sum of the stepped frame times.
+    time: Duration,
+}
+
+/// steps the frames by hand, e.g. in tests or a debugger UI. (winit backend only)
+///
+/// With [`crate::RunOption::manual_stepping`] or [`Self::set_manual`], egui-ash never paints on its
+/// own, not even for repaint requests or resizes. Each [`Self::step_frame`] paints one frame of every
+/// viewport, with a clock that only advances by the stepped frame times, so that animations are the
+/// same in every run.
+#[derive(Clone)]
+pub struct FrameStepper {
+    state: Arc<Mutex<StepperState>>,
+    event_loop_proxy: winit::event_loop::EventLoopProxy<IntegrationEvent>,
+}
+impl FrameStepper {
+    pub(crate) fn new(
+        manual: bool,
+        event_loop_proxy: winit::event_loop::EventLoopProxy<IntegrationEvent>,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(StepperState {
+                manual,
+                ..Default::default()
+            })),
+            event_loop_proxy,
+        }
+    }
+
+    /// paint one frame of every viewport, advancing the clock by `dt`. Ignored unless manual.
+    pub fn step_frame(&self, dt: Duration) {
+        self.state.lock().unwrap().steps.push_back(dt);
+        let _ = self
+            .event_loop_proxy
+            .send_event(IntegrationEvent::RequestRepaint);
+    }
+
+    /// switch between manual stepping and painting on repaint requests.
+    pub fn set_manual(&self, manual: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.manual = manual;
+        state.steps.clear();
+        drop(state);
+        let _ = self
+            .event_loop_proxy
+            .send_event(IntegrationEvent::RequestRepaint);
+    }
+
+    /// whether the frames are stepped by hand.
+    pub fn is_manual(&self) -> bool {
+        self.state.lock().unwrap().manual
+    }
+
+    /// time of the manual clock, `None` unless manual.
+    pub fn time(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state.manual.then_some(state.time)
+    }
+
+    /// the frame time of the next step, advancing the clock.
+    pub(crate) fn take_step(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        if !state.manual {
+            return None;
+        }
+        let dt = state.steps.pop_front()?;
+        state.time += dt;
+        Some(dt)
+    }
+}
+impl std::fmt::Debug for FrameStepper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("FrameStepper")
+            .field("manual", &state.manual)
+            .field("pending_steps", &state.steps.len())
+            .field("time", &state.time)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_timestep(step_ms: u64) -> (FixedTimestep, Instant) {
+        let timestep = FixedTimestep::new(Duration::from_millis(step_ms)).unwrap();
+        let start = timestep.last_tick;
+        (timestep, start)
+    }
+
+    #[test]
+    fn zero_step_is_rejected() {
+        assert!(FixedTimestep::new(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn steps_due_since_last_tick() {
+        let (mut timestep, start) = fixed_timestep(10);
+        assert_eq!(timestep.advance(start + Duration::from_millis(5)).0, 0);
+        let (steps, alpha) = timestep.advance(start + Duration::from_millis(35));
+        assert_eq!(steps, 3);
+        assert!((alpha - 0.5).abs() < 1e-3);
+        assert_eq!(timestep.next_step(), start + Duration::from_millis(40));
+    }
+
+    #[test]
+    fn steps_after_a_stall_are_dropped() {
+        let (mut timestep, start) = fixed_timestep(10);
+        let (steps, alpha) = timestep.advance(start + Duration::from_secs(1));
+        assert_eq!(steps, MAX_STEPS_PER_FRAME);
+        assert_eq!(alpha, 0.0);
+        assert_eq!(timestep.advance(start + Duration::from_millis(1010)).0, 1);
+    }
+
+    #[test]
+    fn alpha_is_in_range() {
+        let (mut timestep, start) = fixed_timestep(16);
+        for ms in (0..200).step_by(3) {
+            let (_, alpha) = timestep.advance(start + Duration::from_millis(ms));
+            assert!((0.0..1.0).contains(&alpha), "{alpha} at {ms} ms");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! diagnostics of the integration, emitted through `tracing` with the `tracing` feature
+//! and through `log` otherwise.
+
+macro_rules! error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::error!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        log::error!($($arg)*);
+    }};
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        log::warn!($($arg)*);
+    }};
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        log::debug!($($arg)*);
+    }};
+}
+
+/// enter a `tracing` span for the rest of the enclosing block. Nothing without the `tracing` feature.
+macro_rules! span {
+    ($name:expr) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($name).entered();
+    };
+    ($name:expr, $($field:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($name, $($field)*).entered();
+    };
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use egui_winit::winit::event_loop::EventLoopProxy;
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub use tray_icon::{MouseButton, MouseButtonState, TrayIconEvent};
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::integration::IntegrationEvent;
+use crate::menu::MenuItem;
+
+/// tray icon settings for [`Tray::show`].
+#[derive(Debug, Clone)]
+pub struct TrayOptions {
+    /// RGBA pixels of the icon. `icon_rgba.len()` must be `icon_width * icon_height * 4`.
+    pub icon_rgba: Vec<u8>,
+    pub icon_width: u32,
+    pub icon_height: u32,
+    pub tooltip: Option<String>,
+    /// context menu items, delivered as [`crate::event::Event::MenuActivated`] when clicked.
+    pub menu: Vec<MenuItem>,
+}
+
+struct TrayInner {
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    tray_icon: Option<tray_icon::TrayIcon>,
+    hide_on_close: bool,
+    visibility_request: Option<bool>,
+}
+
+/// system tray icon of the app. (Windows and macOS)
+///
+/// Clicks on the icon are delivered as [`crate::event::Event::TrayIconEvent`].
+/// Hide all viewports with [`Tray::hide_viewports`] to keep the app running only in the tray.
+#[derive(Clone)]
+pub struct Tray {
+    inner: Rc<RefCell<TrayInner>>,
+}
+impl Tray {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(TrayInner {
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                tray_icon: None,
+                hide_on_close: false,
+                visibility_request: None,
+            })),
+        }
+    }
+
+    /// show the tray icon, replacing the current one.
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    pub fn show(&self, options: TrayOptions) -> Result<()> {
+        let icon =
+            tray_icon::Icon::from_rgba(options.icon_rgba, options.icon_width, options.icon_height)?;
+        let menu = muda::Menu::new();
+        for item in crate::menu::build_items(&options.menu, None, &mut vec![])? {
+            menu.append(item.as_ref())?;
+        }
+
+        let mut builder = tray_icon::TrayIconBuilder::new().with_icon(icon);
+        if let Some(tooltip) = options.tooltip {
+            builder = builder.with_tooltip(tooltip);
+        }
+        if !options.menu.is_empty() {
+            builder = builder.with_menu(Box::new(menu));
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        // drop the old icon first, so that it is removed from the tray before the new one appears.
+        inner.tray_icon = None;
+        inner.tray_icon = Some(builder.build()?);
+        Ok(())
+    }
+
+    /// show the tray icon, replacing the current one.
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    pub fn show(&self, _options: TrayOptions) -> Result<()> {
+        anyhow::bail!("Tray icon is not supported on this platform")
+    }
+
+    /// remove the tray icon.
+    pub fn remove(&self) {
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        {
+            self.inner.borrow_mut().tray_icon = None;
+        }
+    }
+
+    /// hide the root window instead of exiting when it is closed.
+    ///
+    /// Use [`crate::ExitSignal`] to exit the app, e.g. from a "Quit" item of the tray menu.
+    pub fn set_hide_on_close(&self, hide_on_close: bool) {
+        self.inner.borrow_mut().hide_on_close = hide_on_close;
+    }
+
+    /// hide all viewports. The app keeps running and receives events while hidden.
+    pub fn hide_viewports(&self) {
+        self.inner.borrow_mut().visibility_request = Some(false);
+    }
+
+    /// show all viewports again and focus the root window.
+    pub fn show_viewports(&self) {
+        self.inner.borrow_mut().visibility_request = Some(true);
+    }
+
+    pub(crate) fn hide_on_close(&self) -> bool {
+        self.inner.borrow().hide_on_close
+    }
+
+    pub(crate) fn take_visibility_request(&self) -> Option<bool> {
+        self.inner.borrow_mut().visibility_request.take()
+    }
+}
+impl std::fmt::Debug for Tray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tray").finish()
+    }
+}
+
+/// send tray icon events to the event loop. The handler can be set only once per process.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn set_event_handler(event_loop_proxy: EventLoopProxy<IntegrationEvent>) {
+    tray_icon::TrayIconEvent::set_event_handler(Some(move |event: tray_icon::TrayIconEvent| {
+        let _ = event_loop_proxy.send_event(IntegrationEvent::TrayIcon(event));
+    }));
+}
@@ -1,6 +1,45 @@
 #[cfg(feature = "persistence")]
 use egui_winit::winit::event_loop::ActiveEventLoop;
 
+use std::sync::Arc;
+
+/// queue families accessing the images and buffers of egui-ash, see
+/// [`crate::AshRenderState::concurrent_queue_family_indices`].
+#[derive(Clone)]
+pub(crate) struct QueueFamilies {
+    // empty if only the queue family of egui-ash uses them.
+    indices: Arc<[u32]>,
+}
+impl QueueFamilies {
+    pub(crate) fn new(queue_family_index: u32, concurrent_queue_family_indices: &[u32]) -> Self {
+        let mut indices = vec![queue_family_index];
+        for &index in concurrent_queue_family_indices {
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        if indices.len() == 1 {
+            indices.clear();
+        }
+        Self {
+            indices: indices.into(),
+        }
+    }
+
+    pub(crate) fn sharing_mode(&self) -> ash::vk::SharingMode {
+        if self.indices.is_empty() {
+            ash::vk::SharingMode::EXCLUSIVE
+        } else {
+            ash::vk::SharingMode::CONCURRENT
+        }
+    }
+
+    /// queue family indices of [`Self::sharing_mode`], empty for exclusive sharing.
+    pub(crate) fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn insert_image_memory_barrier(
     device: &ash::Device,
@@ -61,3 +100,26 @@ pub(crate) fn largest_monitor_point_size(
         max_size
     }
 }
+
+#[cfg(any(feature = "file-dialog", feature = "message-dialog"))]
+struct ThreadWaker(std::thread::Thread);
+#[cfg(any(feature = "file-dialog", feature = "message-dialog"))]
+impl std::task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// run `future` to completion on the current thread, e.g. a native dialog on a thread of its own.
+#[cfg(any(feature = "file-dialog", feature = "message-dialog"))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = std::task::Context::from_waker(&waker);
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
@@ -0,0 +1,158 @@
+//! Vulkan validation layer and debug messenger for the instance created in [`crate::AppCreator::create`].
+//!
+//! Enable [`layer_names`] and [`required_instance_extensions`] on the instance, then create a
+//! [`DebugMessenger`] routing the messages to `log`, or to `tracing` with the `tracing` feature.
+//! Chain [`messenger_create_info`] to the `vk::InstanceCreateInfo` to also receive the messages of
+//! instance creation.
+
+use ash::{ext::debug_utils, prelude::VkResult, vk, Entry, Instance};
+use std::ffi::{c_void, CStr, CString};
+
+/// name of the Khronos validation layer.
+pub const LAYER_NAME: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+/// options of the [`DebugMessenger`].
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationOptions {
+    /// severities of the reported messages.
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    /// types of the reported messages.
+    pub message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    /// panic on error messages, e.g. to fail tests on validation errors.
+    ///
+    /// The panic can not unwind through the Vulkan driver and aborts the process.
+    pub panic_on_error: bool,
+}
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            severity: vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            message_types: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            panic_on_error: false,
+        }
+    }
+}
+
+/// instance extensions required by the [`DebugMessenger`].
+pub fn required_instance_extensions() -> Vec<CString> {
+    vec![debug_utils::NAME.to_owned()]
+}
+
+/// instance layers to enable for validation.
+///
+/// Empty if the validation layer is not installed, e.g. without the Vulkan SDK.
+pub fn layer_names(entry: &Entry) -> Vec<CString> {
+    let layers = unsafe { entry.enumerate_instance_layer_properties() }.unwrap_or_default();
+    let available = layers
+        .iter()
+        .any(|layer| layer.layer_name_as_c_str() == Ok(LAYER_NAME));
+    if !available {
+        warn!("{:?} is not available, validation is disabled", LAYER_NAME);
+        return vec![];
+    }
+    vec![LAYER_NAME.to_owned()]
+}
+
+/// create info of the debug messenger, to chain to `vk::InstanceCreateInfo` or to create a messenger.
+pub fn messenger_create_info(
+    options: &ValidationOptions,
+) -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
+    let callback: vk::PFN_vkDebugUtilsMessengerCallbackEXT = if options.panic_on_error {
+        Some(debug_callback_panicking)
+    } else {
+        Some(debug_callback)
+    };
+    vk::DebugUtilsMessengerCreateInfoEXT::default()
+        .message_severity(options.severity)
+        .message_type(options.message_types)
+        .pfn_user_callback(callback)
+}
+
+/// debug utils messenger routing the messages of the instance to `log` or `tracing`.
+pub struct DebugMessenger {
+    loader: debug_utils::Instance,
+    messenger: vk::DebugUtilsMessengerEXT,
+}
+impl DebugMessenger {
+    /// create the messenger.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must be created with [`required_instance_extensions`],
+    /// and the messenger must be destroyed before `instance`.
+    pub unsafe fn new(
+        entry: &Entry,
+        instance: &Instance,
+        options: &ValidationOptions,
+    ) -> VkResult<Self> {
+        let loader = debug_utils::Instance::new(entry, instance);
+        let messenger =
+            unsafe { loader.create_debug_utils_messenger(&messenger_create_info(options), None) }?;
+        Ok(Self { loader, messenger })
+    }
+
+    /// destroy the messenger.
+    ///
+    /// # Safety
+    ///
+    /// Call this before destroying the instance.
+    pub unsafe fn destroy(self) {
+        unsafe {
+            self.loader
+                .destroy_debug_utils_messenger(self.messenger, None);
+        }
+    }
+}
+impl std::fmt::Debug for DebugMessenger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugMessenger")
+            .field("messenger", &self.messenger)
+            .finish()
+    }
+}
+
+// log the message and return whether it is an error.
+unsafe fn log_message(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+) -> bool {
+    let message = unsafe { callback_data.as_ref() }
+        .and_then(|data| unsafe { data.message_as_c_str() })
+        .map_or_else(|| "".into(), CStr::to_string_lossy);
+    if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        error!("[{:?}] {}", message_types, message);
+        true
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        warn!("[{:?}] {}", message_types, message);
+        false
+    } else {
+        debug!("[{:?}] {}", message_types, message);
+        false
+    }
+}
+
+unsafe extern "system" fn debug_callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    _user_data: *mut c_void,
+) -> vk::Bool32 {
+    unsafe { log_message(severity, message_types, callback_data) };
+    vk::FALSE
+}
+
+unsafe extern "system" fn debug_callback_panicking(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    _user_data: *mut c_void,
+) -> vk::Bool32 {
+    if unsafe { log_message(severity, message_types, callback_data) } {
+        panic!("Vulkan validation error, see the log above");
+    }
+    vk::FALSE
+}
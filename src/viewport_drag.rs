@@ -0,0 +1,85 @@
+//! egui drag and drop between the windows of the viewports.
+//!
+//! While a button is held, the platforms send the pointer events to the window it was pressed in,
+//! also outside of it. During an egui drag and drop they are forwarded to the viewport under the
+//! pointer, so that a payload dragged out of one window can be dropped on another. The release
+//! reaches the source viewport after the target viewport painted, egui drops the payload on the
+//! first release it sees.
+//!
+//! Wayland does not tell the window positions, the payload stays in its window there.
+
+use egui_winit::winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// a release held back until the target viewport painted.
+struct DeferredRelease {
+    source: egui::ViewportId,
+    target: egui::ViewportId,
+    event: egui::Event,
+}
+
+/// the drag of an egui payload out of the window holding the pointer.
+#[derive(Default)]
+pub(crate) struct ViewportDrag {
+    // last cursor position, in the window that received it.
+    cursor: PhysicalPosition<f64>,
+    target: Option<egui::ViewportId>,
+    deferred_release: Option<DeferredRelease>,
+}
+impl ViewportDrag {
+    pub(crate) fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        self.cursor = position;
+    }
+
+    pub(crate) fn cursor(&self) -> PhysicalPosition<f64> {
+        self.cursor
+    }
+
+    /// set the viewport the pointer is forwarded to, returns the previous one if it changed.
+    pub(crate) fn set_target(
+        &mut self,
+        target: Option<egui::ViewportId>,
+    ) -> Option<egui::ViewportId> {
+        if self.target == target {
+            return None;
+        }
+        std::mem::replace(&mut self.target, target)
+    }
+
+    /// hold back the release `event` of `source` until `target` painted.
+    pub(crate) fn defer_release(
+        &mut self,
+        source: egui::ViewportId,
+        target: egui::ViewportId,
+        event: egui::Event,
+    ) {
+        self.deferred_release = Some(DeferredRelease {
+            source,
+            target,
+            event,
+        });
+    }
+
+    /// the release to deliver to its source viewport, once `painted` was the target.
+    pub(crate) fn take_release(
+        &mut self,
+        painted: egui::ViewportId,
+    ) -> Option<(egui::ViewportId, egui::Event)> {
+        if self.deferred_release.as_ref()?.target != painted {
+            return None;
+        }
+        let release = self.deferred_release.take()?;
+        Some((release.source, release.event))
+    }
+}
+
+/// `screen_pos` relative to the window at `position` with `size`, if it is inside of it.
+pub(crate) fn position_in(
+    screen_pos: PhysicalPosition<f64>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+) -> Option<PhysicalPosition<f64>> {
+    let x = screen_pos.x - position.x as f64;
+    let y = screen_pos.y - position.y as f64;
+    (x >= 0.0 && y >= 0.0 && x < size.width as f64 && y < size.height as f64)
+        .then_some(PhysicalPosition::new(x, y))
+}
@@ -0,0 +1,174 @@
+//! [`crate::Allocator`] implementation for `Arc<vk_mem::Allocator>`, the Vulkan Memory Allocator.
+
+use std::{ffi::c_void, ptr::NonNull, sync::Arc};
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::allocator;
+
+/// memory allocated by `vk_mem::Allocator`.
+pub struct Allocation {
+    allocation: vk_mem::Allocation,
+    name: Option<&'static str>,
+    memory: vk::DeviceMemory,
+    offset: u64,
+    size: u64,
+    mapped_ptr: Option<NonNull<c_void>>,
+}
+// the mapped pointer stays valid until the allocation is freed, like the allocation handle itself.
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+impl Allocation {
+    /// name passed to [`AllocationCreateInfo`].
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+}
+impl allocator::Allocation for Allocation {
+    unsafe fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn mapped_ptr(&self) -> Option<NonNull<c_void>> {
+        self.mapped_ptr
+    }
+}
+
+/// memory location of an [`AllocationCreateInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryLocation {
+    GpuOnly,
+    CpuToGpu,
+    GpuToCpu,
+}
+impl allocator::MemoryLocation for MemoryLocation {
+    fn gpu_only() -> Self {
+        Self::GpuOnly
+    }
+
+    fn cpu_to_gpu() -> Self {
+        Self::CpuToGpu
+    }
+
+    fn gpu_to_cpu() -> Self {
+        Self::GpuToCpu
+    }
+}
+
+/// allocation create info for `vk_mem::Allocator`.
+#[derive(Debug, Clone)]
+pub struct AllocationCreateInfo {
+    pub name: Option<&'static str>,
+    pub requirements: vk::MemoryRequirements,
+    pub location: MemoryLocation,
+}
+impl allocator::AllocationCreateInfo for AllocationCreateInfo {
+    type MemoryLocation = MemoryLocation;
+    fn new(
+        name: Option<&'static str>,
+        requirements: vk::MemoryRequirements,
+        location: Self::MemoryLocation,
+        _linear: bool,
+    ) -> Self {
+        // VMA keeps the buffer-image granularity between allocations of unknown resources itself.
+        Self {
+            name,
+            requirements,
+            location,
+        }
+    }
+}
+
+impl allocator::Allocator for Arc<vk_mem::Allocator> {
+    type Allocation = Allocation;
+    type AllocationCreateInfo = AllocationCreateInfo;
+
+    fn allocate(&self, desc: Self::AllocationCreateInfo) -> Result<Self::Allocation> {
+        let (usage, flags) = match desc.location {
+            MemoryLocation::GpuOnly => (
+                vk_mem::MemoryUsage::AutoPreferDevice,
+                vk_mem::AllocationCreateFlags::empty(),
+            ),
+            MemoryLocation::CpuToGpu => (
+                vk_mem::MemoryUsage::Auto,
+                vk_mem::AllocationCreateFlags::MAPPED
+                    | vk_mem::AllocationCreateFlags::HOST_ACCESS_SEQUENTIAL_WRITE,
+            ),
+            MemoryLocation::GpuToCpu => (
+                vk_mem::MemoryUsage::Auto,
+                vk_mem::AllocationCreateFlags::MAPPED
+                    | vk_mem::AllocationCreateFlags::HOST_ACCESS_RANDOM,
+            ),
+        };
+        let create_info = vk_mem::AllocationCreateInfo {
+            flags,
+            usage,
+            ..Default::default()
+        };
+        let allocation = unsafe { self.allocate_memory(&desc.requirements, &create_info) }?;
+        let info = self.get_allocation_info(&allocation);
+        Ok(Allocation {
+            allocation,
+            name: desc.name,
+            memory: info.device_memory,
+            offset: info.offset,
+            size: info.size,
+            mapped_ptr: NonNull::new(info.mapped_data),
+        })
+    }
+
+    fn free(&self, mut allocation: Self::Allocation) -> Result<()> {
+        unsafe { self.free_memory(&mut allocation.allocation) };
+        Ok(())
+    }
+
+    fn report(&self) -> Option<allocator::AllocatorReport> {
+        let budgets = self.get_heap_budgets().ok()?;
+        let heaps = budgets
+            .iter()
+            .enumerate()
+            .map(|(heap_index, budget)| allocator::HeapUsage {
+                heap_index: heap_index as u32,
+                allocated: budget.statistics.allocationBytes,
+                reserved: budget.statistics.blockBytes,
+            })
+            .collect::<Vec<_>>();
+        Some(allocator::AllocatorReport {
+            allocation_count: budgets
+                .iter()
+                .map(|budget| budget.statistics.allocationCount as usize)
+                .sum(),
+            total_allocated: heaps.iter().map(|heap| heap.allocated).sum(),
+            total_reserved: heaps.iter().map(|heap| heap.reserved).sum(),
+            heaps,
+            // VMA only lists its allocations in the JSON of `build_stats_string`.
+            allocations: vec![],
+        })
+    }
+
+    fn budget(&self) -> Option<Vec<allocator::MemoryBudget>> {
+        // VMA falls back to an estimate if the allocator was created without
+        // `AllocatorCreateFlags::EXT_MEMORY_BUDGET`.
+        let budgets = self.get_heap_budgets().ok()?;
+        Some(
+            budgets
+                .iter()
+                .enumerate()
+                .map(|(heap_index, budget)| allocator::MemoryBudget {
+                    heap_index: heap_index as u32,
+                    usage: budget.usage,
+                    budget: budget.budget,
+                })
+                .collect(),
+        )
+    }
+}
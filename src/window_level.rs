@@ -0,0 +1,30 @@
+fn level_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_window_level").with(viewport_id)
+}
+
+/// switch the window of `viewport_id` between normal, always on top and always on bottom at runtime,
+/// e.g. for a "pin window" toggle. Same as sending `egui::ViewportCommand::WindowLevel`.
+///
+/// The SDL2 backend has no always on bottom windows, they stay normal.
+pub fn set_window_level(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    level: egui::WindowLevel,
+) {
+    ctx.send_viewport_cmd_to(viewport_id, egui::ViewportCommand::WindowLevel(level));
+}
+
+/// the window level of `viewport_id`, set by its viewport builder or the last `ViewportCommand::WindowLevel`.
+pub fn window_level(ctx: &egui::Context, viewport_id: egui::ViewportId) -> egui::WindowLevel {
+    ctx.data(|data| data.get_temp(level_id(viewport_id)))
+        .unwrap_or(egui::WindowLevel::Normal)
+}
+
+/// remember the level applied to the window of `viewport_id` for [`window_level`].
+pub(crate) fn store_window_level(
+    ctx: &egui::Context,
+    viewport_id: egui::ViewportId,
+    level: egui::WindowLevel,
+) {
+    ctx.data_mut(|data| data.insert_temp(level_id(viewport_id), level));
+}
@@ -0,0 +1,77 @@
+use egui_winit::winit;
+use raw_window_handle::RawWindowHandle;
+
+/// how the window of a deferred viewport behaves next to the window of its parent viewport,
+/// e.g. for palettes and inspectors that behave like native tool windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WindowRole {
+    /// hide the window from the taskbar and alt-tab. (Windows)
+    pub skip_taskbar: bool,
+    /// the window is owned by the window of the parent viewport, it stays above it and minimizes with it. (Windows, macOS)
+    pub owned_by_parent: bool,
+    /// mark the window as a utility window, e.g. kept out of the task switcher by the window manager. (X11)
+    pub utility: bool,
+}
+impl WindowRole {
+    /// a tool window: hidden from the taskbar, owned by the parent window and marked as utility window.
+    pub const TOOL: Self = Self {
+        skip_taskbar: true,
+        owned_by_parent: true,
+        utility: true,
+    };
+}
+
+fn role_id(viewport_id: egui::ViewportId) -> egui::Id {
+    egui::Id::new("egui_ash_window_role").with(viewport_id)
+}
+
+/// set the [`WindowRole`] of the window of the deferred viewport `viewport_id`.
+///
+/// Call it before the viewport is shown for the first time, the role applies when its window is created.
+pub fn set_window_role(ctx: &egui::Context, viewport_id: egui::ViewportId, role: WindowRole) {
+    ctx.data_mut(|data| data.insert_temp(role_id(viewport_id), role));
+}
+
+pub(crate) fn window_role(ctx: &egui::Context, viewport_id: egui::ViewportId) -> WindowRole {
+    ctx.data(|data| data.get_temp(role_id(viewport_id)))
+        .unwrap_or_default()
+}
+
+/// the window attributes of `role`. `parent` is the window of the parent viewport, if the window is owned by it.
+#[allow(unused_variables, unused_mut)] // for platforms without window roles
+pub(crate) fn apply_window_role(
+    mut window_attributes: winit::window::WindowAttributes,
+    role: WindowRole,
+    parent: Option<RawWindowHandle>,
+) -> winit::window::WindowAttributes {
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::WindowAttributesExtWindows as _;
+        if role.skip_taskbar {
+            window_attributes = window_attributes.with_skip_taskbar(true);
+        }
+        if let Some(RawWindowHandle::Win32(parent)) = parent {
+            window_attributes = window_attributes.with_owner_window(parent.hwnd.get());
+        }
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(parent) = parent {
+        // SAFETY: the parent window outlives its child viewports, they are closed with it.
+        window_attributes = unsafe { window_attributes.with_parent_window(Some(parent)) };
+    }
+    #[cfg(all(
+        feature = "x11",
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )
+    ))]
+    if role.utility {
+        use winit::platform::x11::{WindowAttributesExtX11 as _, WindowType};
+        window_attributes = window_attributes.with_x11_window_type(vec![WindowType::Utility]);
+    }
+    window_attributes
+}
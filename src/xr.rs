@@ -0,0 +1,229 @@
+//! egui in an OpenXR quad layer, e.g. the desktop UI of a VR app shown in the headset.
+//!
+//! [`XrOverlay`] renders the frames of an app into the images of an OpenXR swapchain, like the
+//! offscreen target of [`crate::testing`]. Call [`XrOverlay::render`] after `xrWaitFrame` with its
+//! frame state, and submit a `CompositionLayerQuad` with [`XrOverlay::sub_image`] in `xrEndFrame`.
+//! egui runs at the predicted display time of the frame, so animations are timed to the headset.
+//!
+//! Create the swapchain with `COLOR_ATTACHMENT | TRANSFER_DST` usage and a `_UNORM` format like
+//! `R8G8B8A8_UNORM`, egui outputs sRGB encoded colors. The colors are premultiplied, which is what
+//! OpenXR expects with `CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA`.
+//!
+//! Frames without input or repaint requests are not rendered, the quad keeps the last image.
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use egui_winit::winit;
+use openxr as xr;
+use std::time::Duration;
+
+use crate::{
+    inspector::Inspector, layer, testing::OffscreenTarget, zoom, Allocator, App, AshRenderState,
+    FrameInfo, ImageRegistry,
+};
+
+/// renders an app into an OpenXR swapchain.
+pub struct XrOverlay<A: Allocator + 'static> {
+    context: egui::Context,
+    target: OffscreenTarget<A>,
+    pixels_per_point: f32,
+    egui_input: egui::RawInput,
+    // predicted display time of the first frame, egui time starts at it.
+    start_time: Option<i64>,
+    last_time: Option<i64>,
+    // predicted display time at which egui wants to repaint.
+    repaint_time: i64,
+    frame_index: u64,
+}
+impl<A: Allocator + 'static> XrOverlay<A> {
+    /// create an overlay rendering into the images of `swapchain`, which are `size` physical
+    /// pixels of `format`.
+    pub fn new(
+        context: egui::Context,
+        render_state: AshRenderState<A>,
+        swapchain: &xr::Swapchain<xr::Vulkan>,
+        format: vk::Format,
+        size: winit::dpi::PhysicalSize<u32>,
+        pixels_per_point: f32,
+    ) -> Result<Self> {
+        context.set_embed_viewports(true);
+        let inspector = Inspector::install(&context);
+        let images = swapchain
+            .enumerate_images()?
+            .into_iter()
+            .map(vk::Image::from_raw)
+            .collect();
+        let target = OffscreenTarget::with_images(render_state, images, format, size, inspector)?;
+        let egui_input = egui::RawInput {
+            focused: true,
+            max_texture_side: Some(target.max_texture_side),
+            ..Default::default()
+        };
+        Ok(Self {
+            context,
+            target,
+            pixels_per_point,
+            egui_input,
+            start_time: None,
+            last_time: None,
+            repaint_time: i64::MIN,
+            frame_index: 0,
+        })
+    }
+
+    /// egui context
+    pub fn context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    /// image registry to register user textures.
+    pub fn image_registry(&self) -> ImageRegistry {
+        self.target.image_registry.clone()
+    }
+
+    /// size of the swapchain images in physical pixels.
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.target.size
+    }
+
+    /// input for the next frame.
+    pub fn egui_input_mut(&mut self) -> &mut egui::RawInput {
+        &mut self.egui_input
+    }
+
+    /// add an event to the input of the next frame, e.g. the pointer of a controller ray.
+    pub fn push_event(&mut self, event: egui::Event) {
+        self.egui_input.events.push(event);
+    }
+
+    /// position in points of the point `uv` on the quad, (0, 0) top left and (1, 1) bottom right,
+    /// e.g. where a controller ray hits it.
+    pub fn pos_from_uv(&self, uv: egui::Vec2) -> egui::Pos2 {
+        let size = self.target.size;
+        let size_in_points = egui::vec2(size.width as f32, size.height as f32)
+            / (self.pixels_per_point * self.context.zoom_factor());
+        (uv * size_in_points).to_pos2()
+    }
+
+    /// the swapchain image of the quad layer.
+    pub fn sub_image<'a>(
+        &self,
+        swapchain: &'a xr::Swapchain<xr::Vulkan>,
+    ) -> xr::SwapchainSubImage<'a, xr::Vulkan> {
+        let size = self.target.size;
+        xr::SwapchainSubImage::new()
+            .swapchain(swapchain)
+            .image_array_index(0)
+            .image_rect(xr::Rect2Di {
+                offset: xr::Offset2Di { x: 0, y: 0 },
+                extent: xr::Extent2Di {
+                    width: size.width as i32,
+                    height: size.height as i32,
+                },
+            })
+    }
+
+    /// size of the quad in meters for `meters_per_point`, e.g. 0.001 for a 1 m wide quad of
+    /// 1000 points.
+    pub fn quad_size(&self, meters_per_point: f32) -> xr::Extent2Df {
+        let size = self.pos_from_uv(egui::vec2(1.0, 1.0));
+        xr::Extent2Df {
+            width: size.x * meters_per_point,
+            height: size.y * meters_per_point,
+        }
+    }
+
+    /// run the app for the frame of `frame_state` and render it into the next swapchain image.
+    ///
+    /// Returns `None` if nothing was rendered, because the runtime asked not to render or egui
+    /// does not need to repaint. Submit the quad layer either way.
+    pub fn render(
+        &mut self,
+        app: &mut impl App,
+        swapchain: &mut xr::Swapchain<xr::Vulkan>,
+        frame_state: &xr::FrameState,
+    ) -> Result<Option<egui::PlatformOutput>> {
+        profile_function!();
+        let display_time = frame_state.predicted_display_time.as_nanos();
+        let repaint_due = display_time >= self.repaint_time;
+        let repaint = repaint_due
+            || !self.egui_input.events.is_empty()
+            || self.context.has_requested_repaint();
+        if !frame_state.should_render || !repaint {
+            return Ok(None);
+        }
+        let start_time = *self.start_time.get_or_insert(display_time);
+        let delta_time = self.last_time.map_or(Duration::ZERO, |last_time| {
+            Duration::from_nanos((display_time - last_time).max(0) as u64)
+        });
+        self.last_time = Some(display_time);
+
+        let size = self.target.size;
+        let screen_size_in_points = egui::vec2(size.width as f32, size.height as f32)
+            / (self.pixels_per_point * self.context.zoom_factor());
+        self.egui_input.screen_rect = Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            screen_size_in_points,
+        ));
+        self.egui_input.time = Some((display_time - start_time) as f64 / 1e9);
+        self.egui_input.predicted_dt = frame_state.predicted_display_period.as_nanos() as f32 / 1e9;
+        self.egui_input.viewport_id = egui::ViewportId::ROOT;
+        self.egui_input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(self.pixels_per_point);
+        let mut raw_input = self.egui_input.take();
+        app.on_raw_input(egui::ViewportId::ROOT, &mut raw_input);
+
+        let frame_info = FrameInfo {
+            delta_time,
+            frame_index: self.frame_index,
+            viewport_id: egui::ViewportId::ROOT,
+            predicted_present_time: None,
+            window_size: size,
+            fixed_update_alpha: crate::fixed_update_alpha(&self.context),
+        };
+        let full_output = self.context.run(raw_input, |ctx| {
+            app.ui(ctx, &frame_info);
+            zoom::handle_zoom(ctx);
+            layer::paint_layer_split(ctx);
+        });
+        app.on_full_output(egui::ViewportId::ROOT, &full_output);
+        let egui::FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            viewport_output,
+        } = full_output;
+        let repaint_delay = viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(Duration::MAX, |output| output.repaint_delay);
+        self.repaint_time = display_time
+            .saturating_add(i64::try_from(repaint_delay.as_nanos()).unwrap_or(i64::MAX));
+        self.frame_index += 1;
+
+        let index = swapchain.acquire_image()?;
+        swapchain.wait_image(xr::Duration::INFINITE)?;
+        // OpenXR takes the images back in the color attachment layout.
+        let rendered = self.target.render_image(
+            index as usize,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            &self.context,
+            shapes,
+            pixels_per_point,
+            textures_delta,
+        );
+        swapchain.release_image()?;
+        rendered?;
+        Ok(Some(platform_output))
+    }
+
+    /// destroy the vulkan objects of the overlay, not the swapchain.
+    ///
+    /// Call this before destroying the device.
+    pub fn destroy(mut self) {
+        self.target.destroy();
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "persistence")]
+use crate::storage::{self, Storage};
+
+/// zoom keybindings like eframe's, changing `egui::Context::zoom_factor`.
+///
+/// The zoom factor scales the UI of all viewports on top of the native scale factor of their windows,
+/// so a zoomed UI keeps its size relative to the window when it moves to a monitor with another scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoomOptions {
+    /// zoom with Ctrl+Plus, Ctrl+Minus and Ctrl+0 (Cmd on macOS).
+    pub keyboard: bool,
+    /// zoom with Ctrl+scroll and pinch gestures.
+    pub scroll: bool,
+    /// zoom factor added or removed by the keyboard shortcuts.
+    pub step: f32,
+    /// smallest zoom factor.
+    pub min: f32,
+    /// largest zoom factor.
+    pub max: f32,
+    /// restore the zoom factor of the last run.
+    #[cfg(feature = "persistence")]
+    pub persist: bool,
+}
+impl Default for ZoomOptions {
+    fn default() -> Self {
+        Self {
+            keyboard: true,
+            scroll: true,
+            step: 0.1,
+            min: 0.2,
+            max: 5.0,
+            #[cfg(feature = "persistence")]
+            persist: true,
+        }
+    }
+}
+
+fn options_id() -> egui::Id {
+    egui::Id::new("egui_ash_zoom_options")
+}
+
+pub(crate) fn set_zoom_options(ctx: &egui::Context, options: ZoomOptions) {
+    ctx.data_mut(|data| data.insert_temp(options_id(), options));
+    // the keyboard zoom of egui is replaced by the one of the options.
+    ctx.options_mut(|egui_options| egui_options.zoom_with_keyboard = false);
+}
+
+fn zoom_options(ctx: &egui::Context) -> ZoomOptions {
+    ctx.data(|data| data.get_temp(options_id()))
+        .unwrap_or_default()
+}
+
+/// apply the zoom input of the pass, after the app had the chance to consume it.
+pub(crate) fn handle_zoom(ctx: &egui::Context) {
+    let options = zoom_options(ctx);
+    let zoom_factor = ctx.zoom_factor();
+    let mut new_zoom_factor = zoom_factor;
+    if options.keyboard {
+        let [zoom_in, zoom_out, reset] = ctx.input_mut(|input| {
+            let mut consume = |key| {
+                input.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, key))
+            };
+            // Plus is typed with Shift on many layouts, take the key below it too.
+            let zoom_in = consume(egui::Key::Plus) | consume(egui::Key::Equals);
+            [zoom_in, consume(egui::Key::Minus), consume(egui::Key::Num0)]
+        });
+        if reset {
+            new_zoom_factor = 1.0;
+        }
+        // steps land on multiples of the step, also after zooming with the wheel.
+        let steps = (new_zoom_factor / options.step).round();
+        if zoom_in {
+            new_zoom_factor = (steps + 1.0) * options.step;
+        }
+        if zoom_out {
+            new_zoom_factor = (steps - 1.0) * options.step;
+        }
+    }
+    if options.scroll {
+        new_zoom_factor *= ctx.input(|input| input.zoom_delta());
+    }
+    if new_zoom_factor != zoom_factor {
+        ctx.set_zoom_factor(new_zoom_factor.clamp(options.min, options.max));
+    }
+}
+
+#[cfg(feature = "persistence")]
+pub(crate) fn restore_zoom_factor(ctx: &egui::Context, storage: &Storage) {
+    if !zoom_options(ctx).persist {
+        return;
+    }
+    if let Some(zoom_factor) = storage.get_value::<f32>(storage::STORAGE_ZOOM_FACTOR_KEY) {
+        ctx.set_zoom_factor(zoom_factor);
+    }
+}
+
+#[cfg(feature = "persistence")]
+pub(crate) fn save_zoom_factor(ctx: &egui::Context, storage: &mut Storage) {
+    if zoom_options(ctx).persist {
+        storage.set_value(storage::STORAGE_ZOOM_FACTOR_KEY, &ctx.zoom_factor());
+    }
+}